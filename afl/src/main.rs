@@ -1,27 +1,185 @@
-#[cfg(features = "afl")]
-use azurite_archiver::Packed;
+//! Fuzzes the compiler with arbitrary source code, asserting
+//! it only ever reports errors and never panics
+//!
+//! Built with the `afl` feature this is an AFL target, without
+//! it the inputs are generated by mutating the corpus with a
+//! seeded random number generator so any panic can be
+//! reproduced by running again with the same `--seed`
 
-#[cfg(features = "afl")]
+#[cfg(feature = "afl")]
 #[macro_use]
 extern crate afl;
 
-#[cfg(features = "afl")]
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+#[cfg(not(feature = "afl"))]
+use std::{fs, path::Path, process::ExitCode};
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// Compiles `source`, the result doesn't matter
+/// only that the compiler returns at all
+fn check(source: &str) {
+    let _ = compile::<BytecodeModule>("fuzz.az".to_string(), source.replace('\t', "    "), OptimizationLevel::O2);
+}
+
+
+#[cfg(feature = "afl")]
 fn main() {
     fuzz!(|data: &[u8]| {
         if let Ok(s) = std::str::from_utf8(data) {
-            let (val, _) = azurite_compiler::compile(String::new(), s.replace('\t', "    "));
-            if let Ok((metadata, bytecode, constants, symbol_table)) = val {
-                let constants_bytes = azurite_compiler::convert_constants_to_bytes(constants, &symbol_table);
-                let packed = Packed::new()
-                    .with(azurite_archiver::Data(Vec::from(metadata.to_bytes())))
-                    .with(azurite_archiver::Data(bytecode))
-                    .with(azurite_archiver::Data(constants_bytes));
-
-                azurite_runtime::run_packed(packed).unwrap();
+            check(s);
+        }
+    });
+}
+
+
+#[cfg(not(feature = "afl"))]
+fn main() -> ExitCode {
+    let mut seed = None;
+    let mut iterations = 10_000;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().and_then(|x| x.parse::<u64>().ok());
+
+        match (arg.as_str(), value) {
+            ("--seed", Some(v)) => seed = Some(v),
+            ("--iterations", Some(v)) => iterations = v,
+
+            _ => {
+                println!("usage: afl [--seed <u64>] [--iterations <u64>]");
+                return ExitCode::FAILURE
             }
         }
+    }
+
+    let seed = seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |x| x.as_nanos() as u64)
     });
+
+    let corpus = read_corpus(&Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus"));
+    if corpus.is_empty() {
+        println!("the corpus is empty");
+        return ExitCode::FAILURE
+    }
+
+    println!("fuzzing with seed {seed} for {iterations} iterations");
+
+    // The panic message is printed by the default hook,
+    // the input is saved below so it can be added to the
+    // regressions
+    let mut rng = Rng::new(seed);
+    for iteration in 0..iterations {
+        let input = mutate(&mut rng, &corpus[rng.below(corpus.len())]);
+        let source = String::from_utf8_lossy(&input).into_owned();
+
+        if std::panic::catch_unwind(|| check(&source)).is_err() {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("regressions")
+                .join(format!("{seed}-{iteration}.az"));
+
+            let _ = fs::write(&path, &source);
+            println!("the compiler panicked on iteration {iteration}, the input was written to {}", path.display());
+
+            return ExitCode::FAILURE
+        }
+    }
+
+    println!("no panics");
+    ExitCode::SUCCESS
 }
 
-#[cfg(not(features = "afl"))]
-fn main() {}
+
+#[cfg(not(feature = "afl"))]
+fn read_corpus(path: &Path) -> Vec<Vec<u8>> {
+    let Ok(entries) = fs::read_dir(path) else { return vec![] };
+
+    let mut paths = entries.filter_map(|x| x.ok()).map(|x| x.path()).collect::<Vec<_>>();
+    // Sorted so the same seed picks the same files
+    paths.sort();
+
+    paths.into_iter().filter_map(|x| fs::read(x).ok()).collect()
+}
+
+
+/// A xorshift64* generator, the quality doesn't matter
+/// as much as being the same on every platform
+struct Rng(u64);
+
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+
+    /// Returns a number in `0..max`, `max` must not be zero
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+}
+
+
+/// The tokens most likely to reach an interesting
+/// part of the compiler when inserted somewhere
+const DICTIONARY : &[&str] = &[
+    "fn", "var", "if", "else", "while", "loop", "for", "in", "return", "break", "continue",
+    "struct", "impl", "extern", "using", "namespace", "const", "enum", "match",
+    "{", "}", "(", ")", "[", "]", ":", "::", ",", ".", "..", "=", "==", "+", "-", "*", "/",
+    "\"", "'", "\\u{", "as", "i64", "u8", "float", "str", "bool", "any", "0", "-1", "1.5",
+];
+
+
+/// Applies between one and four random edits to `input`
+fn mutate(rng: &mut Rng, input: &[u8]) -> Vec<u8> {
+    let mut output = input.to_vec();
+
+    for _ in 0..=rng.below(4) {
+        let position = rng.below(output.len() + 1);
+
+        match rng.below(4) {
+            // Insert a token
+            0 => {
+                let token = DICTIONARY[rng.below(DICTIONARY.len())];
+                output.splice(position..position, token.bytes());
+            },
+
+            // Remove a range
+            1 => {
+                let end = (position + rng.below(16)).min(output.len());
+                output.drain(position..end);
+            },
+
+            // Duplicate a range
+            2 => {
+                let end = (position + rng.below(32)).min(output.len());
+                let range = output[position..end].to_vec();
+                output.splice(position..position, range);
+            },
+
+            // Replace a byte
+            _ => {
+                if position < output.len() {
+                    output[position] = rng.next() as u8;
+                }
+            },
+        }
+    }
+
+    output
+}
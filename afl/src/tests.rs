@@ -0,0 +1,54 @@
+use std::{fs, path::Path};
+
+use crate::{check, mutate, Rng};
+
+
+/// Every input in the corpus and every input which
+/// made the compiler panic in the past
+fn inputs() -> Vec<(String, String)> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut inputs = vec![];
+
+    for directory in ["corpus", "regressions"] {
+        let Ok(entries) = fs::read_dir(root.join(directory)) else { continue };
+
+        for entry in entries {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(true, |x| x != "az") {
+                continue
+            }
+
+            let source = String::from_utf8_lossy(&fs::read(&path).unwrap()).into_owned();
+            inputs.push((path.display().to_string(), source));
+        }
+    }
+
+    inputs
+}
+
+
+#[test]
+fn inputs_dont_panic() {
+    for (path, source) in inputs() {
+        let result = std::panic::catch_unwind(|| check(&source));
+        assert!(result.is_ok(), "the compiler panicked on {path}");
+    }
+}
+
+
+#[test]
+fn seeds_are_reproducible() {
+    let input = b"fn main() { var x = 5 }";
+
+    let (mut first, mut second) = (Rng::new(42), Rng::new(42));
+    for _ in 0..100 {
+        assert_eq!(mutate(&mut first, input), mutate(&mut second, input));
+    }
+}
+
+
+#[test]
+fn zero_seed_doesnt_get_stuck() {
+    let mut rng = Rng::new(0);
+    assert_ne!(rng.next(), rng.next());
+}
@@ -8,9 +8,12 @@ use std::{env, path::Path, process::ExitCode};
 
 use azurite_archiver::Packed;
 use azurite_common::{environment, prepare, Bytecode};
-use azurite_compiler::{BytecodeModule, CModule};
+use azurite_compiler::{BytecodeModule, CModule, CodegenModule, CombineIntoError, DebugHashmap, Error, OptimizationLevel, Target, WasmModule};
 use colored::Colorize;
 
+#[cfg(test)]
+mod tests;
+
 #[allow(clippy::too_many_lines)]
 fn main() -> Result<(), ExitCode> {
     let mut args = env::args();
@@ -25,11 +28,8 @@ fn main() -> Result<(), ExitCode> {
             let Some(file) = args.next() else { invalid_usage() };
             parse_environments(args);
 
-            let target = env::var(environment::CODEGEN_MODULE).unwrap_or("bytecode".to_string());
-
-
-            match target.as_str() {
-                "bytecode" => {
+            match target() {
+                Some(Target::Bytecode) => {
                     let data = compile_as_bytecode(&file)?;
 
                     let mut path = PathBuf::from(file);
@@ -39,15 +39,25 @@ fn main() -> Result<(), ExitCode> {
                 },
 
                 
-                "c" => {
-                    let data = compile_as_c(&file)?;
+                Some(Target::C) => {
+                    let data = compile_as_source::<CModule>(&file)?;
                     
                     let mut path = PathBuf::from(file);
                     path.set_extension("c");
                     
                     fs::write(path, data).unwrap();
                 }
-                _ => {
+
+
+                Some(Target::Wasm) => {
+                    let data = compile_as_source::<WasmModule>(&file)?;
+
+                    let mut path = PathBuf::from(file);
+                    path.set_extension("wat");
+
+                    fs::write(path, data).unwrap();
+                }
+                None => {
                     println!("invalid target module");
                     return Err(ExitCode::FAILURE)
                 }
@@ -59,13 +69,21 @@ fn main() -> Result<(), ExitCode> {
             let Some(file) = args.next() else { invalid_usage() };
             parse_environments(args);
 
+            if target() != Some(Target::Bytecode) {
+                eprintln!("only the bytecode target can be run, use 'build' to compile for other targets");
+                return Err(ExitCode::FAILURE)
+            }
+
             let Some(compiled) = (if file.ends_with(".azurite") {
                 let Ok(file_data) = fs::read(&file) else { eprintln!("can't read file {file}"); return Err(ExitCode::FAILURE) };
                 Packed::from_bytes(&file_data)
             } else { Some(compile_as_bytecode(&file)?) }) else { eprintln!("not a valid azurite file"); return Err(ExitCode::FAILURE)};
 
-            println!("{} {file}", "Running..".bright_green().bold());
-            azurite_runtime::run_packed(compiled).unwrap();
+            progress(format!("{} {file}", "Running..".bright_green().bold()));
+            if let Err(e) = azurite_runtime::run_packed(compiled) {
+                eprintln!("{e}");
+                return Err(ExitCode::FAILURE)
+            }
         }
 
         
@@ -86,7 +104,7 @@ fn main() -> Result<(), ExitCode> {
                     compile_as_bytecode(file)?;
                     let file = format!("{file}urite");
 
-                    println!("{} {file}", "Running..".bright_green().bold());
+                    progress(format!("{} {file}", "Running..".bright_green().bold()));
                 }
             }
         }
@@ -98,11 +116,51 @@ fn main() -> Result<(), ExitCode> {
 
             let packed = compile_as_bytecode(&file)?;
 
-            println!("{} {file}", "Disassembling..".bright_green().bold());
+            progress(format!("{} {file}", "Disassembling..".bright_green().bold()));
 
             let mut data: Vec<_> = packed.into();
 
-            disassemble(std::mem::take(&mut data[1].0));
+            let mut lock = std::io::stdout().lock();
+            disassemble(std::mem::take(&mut data[1].0), &mut lock);
+            disassemble_constants(std::mem::take(&mut data[2].0), &mut lock);
+        }
+
+
+        "deps" => {
+            let Some(file) = args.next() else { invalid_usage() };
+            parse_environments(args);
+
+            let Ok(raw_data) = fs::read(&file) else { eprintln!("'{file}' doesn't exist"); return Err(ExitCode::FAILURE)};
+            let file_data = String::from_utf8_lossy(&raw_data).to_string();
+
+            let (result, debug_info) = azurite_compiler::dependencies(file, file_data);
+
+            let dependencies = match result {
+                Ok(v) => v,
+                Err(e) => {
+                    report(vec![e], &debug_info);
+                    return Err(ExitCode::FAILURE)
+                }
+            };
+
+            for (importer, imported) in &dependencies.cycles {
+                eprintln!("{}: {} uses {} which is already being resolved",
+                    "cyclic import".yellow().bold(),
+                    importer.to_string_lossy(),
+                    imported.to_string_lossy(),
+                );
+            }
+
+            for dependency in &dependencies.files {
+                println!("{}", dependency.to_string_lossy());
+            }
+        }
+
+
+        "explain" => {
+            let Some(code) = args.next() else { invalid_usage() };
+
+            print!("{}", azurite_compiler::explain(&code));
         }
         _ => invalid_usage(),
     }
@@ -114,23 +172,72 @@ fn main() -> Result<(), ExitCode> {
 fn parse_environments(mut arguments: Args) {
     while let Some(i) = arguments.next() {
         match i.as_str() {
-            "--raw"        => env::set_var(environment::RAW_MODE, "1"),
+            "--raw"        => env::set_var(environment::OPTIMIZATION_LEVEL, "0"),
+            "-O0" | "-O1" | "-O2" => env::set_var(environment::OPTIMIZATION_LEVEL, &i[2..]),
+            _ if i.starts_with("--optimize=") => {
+                let level = &i["--optimize=".len()..];
+
+                if level.parse().ok().and_then(OptimizationLevel::from_level).is_none() {
+                    println!("invalid optimization level {level}, expected 0, 1 or 2");
+                    std::process::exit(-1);
+                }
+
+                env::set_var(environment::OPTIMIZATION_LEVEL, level)
+            },
+            _ if i.starts_with("--max-nesting-depth=") => {
+                let depth = &i["--max-nesting-depth=".len()..];
+
+                if depth.parse::<usize>().is_err() {
+                    println!("invalid nesting depth {depth}, expected a positive integer");
+                    std::process::exit(-1);
+                }
+
+                env::set_var(environment::MAX_NESTING_DEPTH, depth)
+            },
             "--dump-ir"    => env::set_var(environment::DUMP_IR, "1"),
             "--dump-ir-to" => env::set_var(environment::DUMP_IR_FILE, match arguments.next() {
                 Some(v) => v.to_string(),
                 None => break,
             }),
+            "--emit-bytecode-text" => env::set_var(environment::BYTECODE_TEXT_FILE, match arguments.next() {
+                Some(v) => v.to_string(),
+                None => break,
+            }),
+            "--dump-ir-filter" => {
+                env::set_var(environment::DUMP_IR, "1");
+                env::set_var(environment::DUMP_IR_FILTER, match arguments.next() {
+                    Some(v) => v.to_string(),
+                    None => break,
+                })
+            },
+            _ if i.starts_with("--message-format=") => {
+                let format = &i["--message-format=".len()..];
+
+                if !matches!(format, "human" | "json") {
+                    println!("invalid message format {format}, expected human or json");
+                    std::process::exit(-1);
+                }
+
+                env::set_var(environment::MESSAGE_FORMAT, format)
+            },
+            "--quiet" | "-q" => env::set_var(environment::QUIET, "1"),
+            "--debug" | "-g" => env::set_var(environment::DEBUG_INFO, "1"),
             "--no-std"     => env::set_var(environment::NO_STD, "1"),
             "--panic-log"  => env::set_var(environment::PANIC_LOG, "1"),
-            "--module"     => {
+            "--module" | "--target" => {
                 let next = match arguments.next() {
                     Some(v) => v,
                     None => {
-                        println!("there must be a target after a --module");
+                        println!("there must be a target after a {i}");
                         std::process::exit(-1);
                     },
                 };
 
+                if Target::from_name(&next).is_none() {
+                    println!("invalid target {next}, expected bytecode, c or wasm");
+                    std::process::exit(-1);
+                }
+
 
                 env::set_var(environment::CODEGEN_MODULE, next);
                 
@@ -145,67 +252,140 @@ fn parse_environments(mut arguments: Args) {
 }
 
 fn invalid_usage() -> ! {
-    println!("{}: please provide a sub-command (build, run, disassemble, constants, repl) followed by a file name", "invalid usage".red().bold());
+    println!("{}: please provide a sub-command (build, run, disassemble, deps, constants, repl) followed by a file name, or explain followed by an error code", "invalid usage".red().bold());
     std::process::exit(1)
 }
 
+fn optimization_level() -> OptimizationLevel {
+    env::var(environment::OPTIMIZATION_LEVEL).ok()
+        .and_then(|x| x.parse().ok())
+        .and_then(OptimizationLevel::from_level)
+        .unwrap_or_default()
+}
+
+
+/// Prints the warnings & errors of a compilation in the
+/// format picked with `--message-format`, as one JSON
+/// array for all of them or as the usual coloured text
+fn report(diagnostics: Vec<Error>, debug_info: &DebugHashmap) {
+    if env::var(environment::MESSAGE_FORMAT).map_or(false, |x| x == "json") {
+        if !diagnostics.is_empty() {
+            println!("{}", diagnostics.combine_into_error().build_json(debug_info));
+        }
+
+        return
+    }
+
+    for diagnostic in diagnostics {
+        print!("{}", diagnostic.build(debug_info));
+    }
+}
+
+
+/// Whether `--quiet` was given, in which case only errors
+/// & the output of the program itself are printed
+fn is_quiet() -> bool {
+    env::var(environment::QUIET).map_or(false, |x| x == "1")
+}
+
+
+/// Prints a message about the progress of the
+/// compiler, such as `Compiling..`, unless quiet
+fn progress(message: impl std::fmt::Display) {
+    if !is_quiet() {
+        println!("{message}");
+    }
+}
+
+
+fn target() -> Option<Target> {
+    env::var(environment::CODEGEN_MODULE).map_or(Some(Target::Bytecode), |x| Target::from_name(&x))
+}
+
+
 fn compile_as_bytecode(file: &str) -> Result<Packed, ExitCode> {
-    println!("{} {file}", "Compiling..".bright_green().bold());
+    progress(format!("{} {file}", "Compiling..".bright_green().bold()));
     let instant = Instant::now();
 
     let Ok(raw_data) = fs::read(file) else { eprintln!("'{file}' doesn't exist"); return Err(ExitCode::FAILURE)};
     let file_data = String::from_utf8_lossy(&raw_data).replace('\t', "    ").replace('\r', "");
 
 
-    let (result, debug_info) = azurite_compiler::compile::<BytecodeModule>(file.to_string(), file_data);
-    
-    let (metadata, bytecode, constants, symbol_table) = match result {
-        Ok(v) => v,
+    let (result, mut warnings, debug_info) = azurite_compiler::compile::<BytecodeModule>(file.to_string(), file_data, optimization_level());
+
+    if is_quiet() {
+        warnings.clear();
+    }
+
+    let (metadata, bytecode, constants, symbol_table, structures, debug, lines) = match result {
+        Ok(v) => {
+            report(warnings, &debug_info);
+            v
+        },
         Err(e) => {
-            print!("{}", e.build(&debug_info));
+            report(warnings.into_iter().chain([e]).collect(), &debug_info);
             return Err(ExitCode::FAILURE)
         }
     };
 
     let constants_bytes = azurite_compiler::convert_constants_to_bytes(constants, &symbol_table);
 
+    if let Ok(path) = env::var(environment::BYTECODE_TEXT_FILE) {
+        if let Err(e) = write_disassembly(&path, &bytecode, &constants_bytes) {
+            eprintln!("can't write the disassembly to '{path}': {e}");
+            return Err(ExitCode::FAILURE)
+        }
+    }
+
     
-    println!(
-        "{}",
+    progress(
         format!("Finished in {} seconds!", instant.elapsed().as_secs_f64())
             .bright_green()
             .bold()
     );
 
+    // Only debug builds have a line table, the
+    // section is empty otherwise
     Ok(Packed::new()
         .with(azurite_archiver::Data(Vec::from(metadata.to_bytes())))
         .with(azurite_archiver::Data(bytecode))
         .with(azurite_archiver::Data(constants_bytes))
+        .with(azurite_archiver::Data(structures))
+        .with(azurite_archiver::Data(debug))
+        .with(azurite_archiver::Data(lines))
     )
 }
 
 
-fn compile_as_c(file: &str) -> Result<Vec<u8>, ExitCode> {
-    println!("{} {file}", "Compiling..".bright_green().bold());
+/// Compiles `file` with a codegen module which
+/// produces source code, such as C or wasm text
+fn compile_as_source<T: CodegenModule>(file: &str) -> Result<Vec<u8>, ExitCode> {
+    progress(format!("{} {file}", "Compiling..".bright_green().bold()));
     let instant = Instant::now();
 
     let Ok(raw_data) = fs::read(file) else { eprintln!("'{file}' doesn't exist"); return Err(ExitCode::FAILURE)};
     let file_data = String::from_utf8_lossy(&raw_data).replace('\t', "    ").replace('\r', "");
 
 
-    let (result, debug_info) = azurite_compiler::compile::<CModule>(file.to_string(), file_data);
-    
-    let (_, bytecode, _, _) = match result {
-        Ok(v) => v,
+    let (result, mut warnings, debug_info) = azurite_compiler::compile::<T>(file.to_string(), file_data, optimization_level());
+
+    if is_quiet() {
+        warnings.clear();
+    }
+
+    let (_, bytecode, ..) = match result {
+        Ok(v) => {
+            report(warnings, &debug_info);
+            v
+        },
         Err(e) => {
-            print!("{}", e.build(&debug_info));
+            report(warnings.into_iter().chain([e]).collect(), &debug_info);
             return Err(ExitCode::FAILURE)
         }
     };
 
     
-    println!(
-        "{}",
+    progress(
         format!("Finished in {} seconds!", instant.elapsed().as_secs_f64())
             .bright_green()
             .bold()
@@ -215,15 +395,25 @@ fn compile_as_c(file: &str) -> Result<Vec<u8>, ExitCode> {
     Ok(bytecode)
 }
 
+/// Writes the disassembly of `bytecode` followed by
+/// the constants table to the file at `path`
+fn write_disassembly(path: &str, bytecode: &[u8], constants: &[u8]) -> std::io::Result<()> {
+    let mut buffer = vec![];
+
+    disassemble(bytecode.to_vec(), &mut buffer);
+    disassemble_constants(constants.to_vec(), &mut buffer);
+
+    fs::write(path, buffer)
+}
+
+
 #[allow(clippy::format_push_string)]
 #[allow(clippy::too_many_lines)]
-fn disassemble(v: Vec<u8>) {
+fn disassemble(v: Vec<u8>, lock: &mut impl Write) {
     let mut d = Disassembler {
         code: v,
         top: 0,
     };
-    
-    let mut lock = std::io::stdout().lock();
 
     while d.code.len() > d.top {
         let bytecode = d.bytecode();
@@ -242,6 +432,13 @@ fn disassemble(v: Vec<u8>) {
                 (0..arg_count).for_each(|_| { let _ = write!(lock, " {}", d.next()); });
                 writeln!(lock, " )")
             },
+            Bytecode::TailCall => {
+                let _ = write!(lock, "tailcall {} ", d.u32());
+                let arg_count = d.next();
+                let _ = write!(lock, "{arg_count} (");
+                (0..arg_count).for_each(|_| { let _ = write!(lock, " {}", d.next()); });
+                writeln!(lock, " )")
+            },
             Bytecode::ExtCall => {
                 let _ = write!(lock, "ecall {} {} ", d.u32(), d.next());
                 let arg_count = d.next();
@@ -256,6 +453,15 @@ fn disassemble(v: Vec<u8>) {
                 (0..arg_count).for_each(|_| { let _ = write!(lock, " {}", d.next()); });
                 writeln!(lock, " )")
             },
+            Bytecode::MakeArray => {
+                let _ = write!(lock, "array {} ", d.next());
+                let arg_count = d.next();
+                let _ = write!(lock, "{arg_count} (");
+                (0..arg_count).for_each(|_| { let _ = write!(lock, " {}", d.next()); });
+                writeln!(lock, " )")
+            },
+            Bytecode::IndexGet => writeln!(lock, "indexget {} {} {}", d.next(), d.next(), d.next()),
+            Bytecode::IndexSet => writeln!(lock, "indexset {} {} {}", d.next(), d.next(), d.next()),
             Bytecode::Push => {
                 writeln!(lock, "push {}", d.next())
             },
@@ -265,6 +471,11 @@ fn disassemble(v: Vec<u8>) {
             Bytecode::Multiply => writeln!(lock, "mul {} {} {}", d.next(), d.next(), d.next()),
             Bytecode::Divide => writeln!(lock, "div {} {} {}", d.next(), d.next(), d.next()),
             Bytecode::Modulo => writeln!(lock, "mod {} {} {}", d.next(), d.next(), d.next()),
+            Bytecode::BitAnd => writeln!(lock, "and {} {} {}", d.next(), d.next(), d.next()),
+            Bytecode::BitOr => writeln!(lock, "or {} {} {}", d.next(), d.next(), d.next()),
+            Bytecode::BitXor => writeln!(lock, "xor {} {} {}", d.next(), d.next(), d.next()),
+            Bytecode::ShiftLeft => writeln!(lock, "shl {} {} {}", d.next(), d.next(), d.next()),
+            Bytecode::ShiftRight => writeln!(lock, "shr {} {} {}", d.next(), d.next(), d.next()),
             Bytecode::Equals => writeln!(lock, "eq {} {} {}", d.next(), d.next(), d.next()),
             Bytecode::NotEquals => writeln!(lock, "neq {} {} {}", d.next(), d.next(), d.next()),
             Bytecode::GreaterThan => writeln!(lock, "gt {} {} {}", d.next(), d.next(), d.next()),
@@ -289,6 +500,7 @@ fn disassemble(v: Vec<u8>) {
             },
             Bytecode::UnaryNot => writeln!(lock, "not {} {}", d.next(), d.next()),
             Bytecode::UnaryNeg => writeln!(lock, "neg {} {}", d.next(), d.next()),
+            Bytecode::UnaryBitNot => writeln!(lock, "bnot {} {}", d.next(), d.next()),
 
             
             Bytecode::CastToI8    => writeln!(lock, "castI8 {} {}", d.next(), d.next()),
@@ -306,6 +518,47 @@ fn disassemble(v: Vec<u8>) {
     }
 }
 
+/// Lists the constants in the format written by
+/// `azurite_compiler::convert_constants_to_bytes`
+fn disassemble_constants(v: Vec<u8>, lock: &mut impl Write) {
+    let mut d = Disassembler {
+        code: v,
+        top: 0,
+    };
+
+    let _ = writeln!(lock, "constants:");
+
+    let mut index = 0;
+    while d.code.len() > d.top {
+        let _ = write!(lock, "{index:>4} | ");
+        let _ = match d.next() {
+            0  => writeln!(lock, "float {}", f64::from_le_bytes(d.bytes())),
+            1  => writeln!(lock, "bool {}", d.next() == 1),
+            2  => {
+                let length = u64::from_le_bytes(d.bytes());
+                let bytes = (0..length).map(|_| d.next()).collect::<Vec<_>>();
+                writeln!(lock, "str {:?}", String::from_utf8_lossy(&bytes))
+            },
+            3  => writeln!(lock, "i8 {}", i8::from_le_bytes(d.bytes())),
+            4  => writeln!(lock, "i16 {}", i16::from_le_bytes(d.bytes())),
+            5  => writeln!(lock, "i32 {}", i32::from_le_bytes(d.bytes())),
+            6  => writeln!(lock, "i64 {}", i64::from_le_bytes(d.bytes())),
+            7  => writeln!(lock, "u8 {}", u8::from_le_bytes(d.bytes())),
+            8  => writeln!(lock, "u16 {}", u16::from_le_bytes(d.bytes())),
+            9  => writeln!(lock, "u32 {}", d.u32()),
+            10 => writeln!(lock, "u64 {}", u64::from_le_bytes(d.bytes())),
+            11 => writeln!(lock, "char {:?}", char::from_u32(d.u32()).unwrap_or(char::REPLACEMENT_CHARACTER)),
+            v  => {
+                let _ = writeln!(lock, "unknown constant type {v}");
+                break
+            },
+        };
+
+        index += 1;
+    }
+}
+
+
 struct Disassembler {
     code: Vec<u8>,
     top: usize,
@@ -331,6 +584,10 @@ impl Disassembler {
         self.code[self.top-1]
     }
 
+    fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        std::array::from_fn(|_| self.next())
+    }
+
     fn string(&mut self) -> String {
         let mut bytes = vec![];
 
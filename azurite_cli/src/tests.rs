@@ -0,0 +1,40 @@
+use std::{env, fs};
+
+use azurite_compiler::{compile, convert_constants_to_bytes, BytecodeModule, OptimizationLevel};
+
+use crate::write_disassembly;
+
+
+#[test]
+fn disassembly_is_written_to_a_file() {
+    let source = "
+fn add(a: i64, b: i64): i64 { a + b }
+
+var x = add(40, 2)
+var name = \"azurite\"
+";
+
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O0);
+    let (_, bytecode, constants, symbol_table, ..) = match result {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e.build(&debug_info)),
+    };
+
+    let constants = convert_constants_to_bytes(constants, &symbol_table);
+
+    let path = env::temp_dir().join(format!("azurite_bytecode_text_{}.txt", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    write_disassembly(path, &bytecode, &constants).unwrap();
+
+    let text = fs::read_to_string(path).unwrap();
+    let _ = fs::remove_file(path);
+
+    for mnemonic in ["call", "add", "load", "ret"] {
+        assert!(text.split_whitespace().any(|x| x == mnemonic), "missing {mnemonic} in\n{text}");
+    }
+
+    assert!(text.contains("constants:"), "{text}");
+    assert!(text.contains("i64 40"), "{text}");
+    assert!(text.contains("str \"azurite\""), "{text}");
+}
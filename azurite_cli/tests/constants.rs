@@ -0,0 +1,67 @@
+use std::{env, fs, process::Command};
+
+use azurite_common::environment;
+
+
+/// Runs `source` unoptimized with the cli,
+/// returning what was printed to stdout
+///
+/// The standard library is left out as its runtime
+/// library isn't built next to the test binaries
+fn run(name: &str, source: &str) -> String {
+    let directory = env::temp_dir().join(format!("azurite_cli_{}_{name}", std::process::id()));
+    let _ = fs::create_dir_all(&directory);
+
+    let file = directory.join("main.az");
+    fs::write(&file, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_azurite_cli"))
+        .arg("run")
+        .arg(&file)
+        .args(["--raw", "-O0"])
+        .env(environment::NO_STD, "1")
+        .output()
+        .unwrap();
+
+    let _ = fs::remove_dir_all(&directory);
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+
+#[test]
+fn float_modulo_is_folded_like_at_runtime() {
+    // Without a standard library to print with, the program
+    // divides by zero if both remainders are equal
+    let source = "
+const FOLDED: float = -7.5 % 2.0
+
+var x = -7.5
+var at_runtime = x % 2.0
+
+var zero = 0
+if FOLDED == at_runtime {
+    var error = 1 / zero
+}
+";
+
+    let stdout = run("float_modulo", source);
+    assert!(stdout.contains("division by zero"), "{stdout}");
+}
+
+
+#[test]
+fn float_equality_is_folded_like_at_runtime() {
+    let source = "
+const ZEROS_EQUAL: bool = 0.0 == -0.0
+const NAN_UNEQUAL: bool = 0.0 / 0.0 != 0.0 / 0.0
+
+var zero = 0
+if ZEROS_EQUAL && NAN_UNEQUAL {
+    var error = 1 / zero
+}
+";
+
+    let stdout = run("float_equality", source);
+    assert!(stdout.contains("division by zero"), "{stdout}");
+}
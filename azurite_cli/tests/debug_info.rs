@@ -0,0 +1,56 @@
+use std::{env, fs, process::Command};
+
+use azurite_common::environment;
+
+
+/// Runs `source` with the cli & the given flags,
+/// returning what was printed to stdout
+///
+/// The standard library is left out as its runtime
+/// library isn't built next to the test binaries
+fn run(name: &str, source: &str, flags: &[&str]) -> String {
+    let directory = env::temp_dir().join(format!("azurite_cli_{}_{name}", std::process::id()));
+    let _ = fs::create_dir_all(&directory);
+
+    let file = directory.join("main.az");
+    fs::write(&file, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_azurite_cli"))
+        .arg("run")
+        .arg(&file)
+        .args(flags)
+        .env(environment::NO_STD, "1")
+        .output()
+        .unwrap();
+
+    let _ = fs::remove_dir_all(&directory);
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+
+const DIVISION_BY_ZERO : &str = "fn divide(a: i64, b: i64): i64 {
+    a / b
+}
+
+var x = divide(1, 0)
+";
+
+
+#[test]
+fn runtime_errors_point_at_the_line() {
+    let stdout = run("debug", DIVISION_BY_ZERO, &["--raw", "--debug"]);
+
+    assert!(stdout.contains("division by zero at "), "{stdout}");
+    assert!(stdout.contains("main.az:2"), "{stdout}");
+    assert!(stdout.contains("main.az:5"), "{stdout}");
+}
+
+
+#[test]
+fn no_lines_without_debug_info() {
+    let stdout = run("release", DIVISION_BY_ZERO, &["--raw"]);
+
+    assert!(stdout.contains("division by zero"), "{stdout}");
+    assert!(!stdout.contains("main.az:"), "{stdout}");
+}
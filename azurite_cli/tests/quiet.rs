@@ -0,0 +1,43 @@
+use std::{env, fs, process::Command};
+
+
+/// Builds `source` with the cli & the given flags,
+/// returning what was printed to stdout
+fn build(name: &str, source: &str, flags: &[&str]) -> String {
+    let directory = env::temp_dir().join(format!("azurite_cli_{}_{name}", std::process::id()));
+    let _ = fs::create_dir_all(&directory);
+
+    let file = directory.join("main.az");
+    fs::write(&file, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_azurite_cli"))
+        .arg("build")
+        .arg(&file)
+        .args(flags)
+        .output()
+        .unwrap();
+
+    let _ = fs::remove_dir_all(&directory);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+
+#[test]
+fn quiet_prints_nothing() {
+    // The unused variable would warn without `--quiet`
+    let stdout = build("quiet", "var x = 5", &["--quiet"]);
+
+    assert_eq!(stdout, "");
+}
+
+
+#[test]
+fn progress_is_printed_by_default() {
+    let stdout = build("default", "var x = 5", &[]);
+
+    assert!(stdout.contains("Compiling.."), "{stdout}");
+    assert!(stdout.contains("Finished in"), "{stdout}");
+    assert!(stdout.contains("warning[302]"), "{stdout}");
+}
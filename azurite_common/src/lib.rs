@@ -1,18 +1,51 @@
-use std::{env, fmt::Display, fs, process::ExitCode, vec::IntoIter, mem::size_of};
+use std::{env, fmt::Display, fs, process::ExitCode, vec::IntoIter};
 
 use colored::Colorize;
 
 
 pub mod environment {
-    pub const RAW_MODE : &str = "AZURITE_COMPILER_RAW_MODE";
+    pub const OPTIMIZATION_LEVEL : &str = "AZURITE_COMPILER_OPTIMIZATION_LEVEL";
     
     pub const DUMP_IR      : &str = "AZURITE_COMPILER_DUMP_IR";
     pub const DUMP_IR_FILE : &str = "AZURITE_COMPILER_DUMP_IR_FILE";
+    pub const DUMP_IR_FILTER : &str = "AZURITE_COMPILER_DUMP_IR_FILTER";
+
+    pub const BYTECODE_TEXT_FILE : &str = "AZURITE_BYTECODE_TEXT_FILE";
 
     pub const NO_STD       : &str = "AZURITE_NO_STD";
     pub const PANIC_LOG    : &str = "AZURITE_PANIC_LOG";
 
+    pub const PANIC_LOG_MAX_OBJECTS   : &str = "AZURITE_PANIC_LOG_MAX_OBJECTS";
+    pub const PANIC_LOG_MAX_STACK     : &str = "AZURITE_PANIC_LOG_MAX_STACK";
+    pub const PANIC_LOG_MAX_BYTECODE  : &str = "AZURITE_PANIC_LOG_MAX_BYTECODE";
+
     pub const CODEGEN_MODULE : &str = "AZURITE_CODEGEN_MODULE";
+
+    pub const MAX_NESTING_DEPTH : &str = "AZURITE_COMPILER_MAX_NESTING_DEPTH";
+
+    pub const MESSAGE_FORMAT : &str = "AZURITE_MESSAGE_FORMAT";
+
+    pub const QUIET : &str = "AZURITE_QUIET";
+
+    pub const DEBUG_INFO : &str = "AZURITE_COMPILER_DEBUG_INFO";
+}
+
+
+/// How deep expressions can be nested by default before
+/// the compiler reports an error instead of overflowing
+/// its own stack
+///
+/// Every level goes through each precedence level of the
+/// parser, which takes about 64kb of stack in debug builds
+pub const DEFAULT_MAX_NESTING_DEPTH : usize = 64;
+
+
+/// Returns the nesting limit set through the environment,
+/// or `DEFAULT_MAX_NESTING_DEPTH` if there's none
+pub fn max_nesting_depth() -> usize {
+    env::var(environment::MAX_NESTING_DEPTH).ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(DEFAULT_MAX_NESTING_DEPTH)
 }
 
 
@@ -56,27 +89,94 @@ pub fn prepare() {
 }
 
 
-#[repr(C)]
+/// The kind of artifact a compilation produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Target {
+    #[default]
+    Bytecode,
+    C,
+    Wasm,
+}
+
+
+impl Target {
+    /// Parses a target from its name as given to `--target`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bytecode" => Some(Self::Bytecode),
+            "c"        => Some(Self::C),
+            "wasm"     => Some(Self::Wasm),
+            _          => None,
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bytecode => "bytecode",
+            Self::C        => "c",
+            Self::Wasm     => "wasm",
+        }
+    }
+
+
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Bytecode),
+            1 => Some(Self::C),
+            2 => Some(Self::Wasm),
+            _ => None,
+        }
+    }
+}
+
+
 pub struct CompilationMetadata {
     pub extern_count: u32,
     pub library_count: u32,
+    pub target: Target,
 }
 
 
 impl CompilationMetadata {
-    pub fn to_bytes(self) -> [u8; size_of::<Self>()] {
+    /// The size of the metadata once converted to bytes
+    pub const SIZE : usize = 10;
+
+    /// The version of the file format, written as the first
+    /// byte of the metadata. It's bumped whenever the sections
+    /// of a compiled file change so the runtime can tell files
+    /// from other versions apart instead of misreading them
+    ///
+    /// Version 3 files have the metadata, bytecode, constants,
+    /// structures, debug & line table sections in that order
+    pub const FORMAT_VERSION : u8 = 3;
+
+
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
         let extern_count : [u8; 4] = self.extern_count.to_le_bytes();
         let library_count : [u8; 4] = self.library_count.to_le_bytes();
 
-        [extern_count, library_count].concat().try_into().unwrap()
+        [&[Self::FORMAT_VERSION], &extern_count[..], &library_count[..], &[self.target.as_u8()]].concat().try_into().unwrap()
     }
 
 
-    pub fn from_bytes(bytes: [u8; size_of::<Self>()]) -> Self {
-        Self {
-            extern_count: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
-            library_count: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+    /// Returns `None` if the format version
+    /// or the target is unknown
+    pub fn from_bytes(bytes: [u8; Self::SIZE]) -> Option<Self> {
+        if bytes[0] != Self::FORMAT_VERSION {
+            return None
         }
+
+        Some(Self {
+            extern_count: u32::from_le_bytes(bytes[1..5].try_into().unwrap()),
+            library_count: u32::from_le_bytes(bytes[5..9].try_into().unwrap()),
+            target: Target::from_u8(bytes[9])?,
+        })
     }
 }
 
@@ -290,6 +390,7 @@ pub enum Bytecode : u8 {
 
     Call,
     ExtCall,
+    TailCall,
     Push,
     Pop,
 
@@ -298,6 +399,10 @@ pub enum Bytecode : u8 {
     Struct,
     AccStruct,
     SetField,
+
+    MakeArray,
+    IndexGet,
+    IndexSet,
     
     Add,
     Subtract,
@@ -305,8 +410,15 @@ pub enum Bytecode : u8 {
     Divide,
     Modulo,
 
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+
     UnaryNot,
     UnaryNeg,
+    UnaryBitNot,
 
     Equals,
     NotEquals,
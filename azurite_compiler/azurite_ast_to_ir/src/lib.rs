@@ -3,7 +3,7 @@ pub mod optimizations;
 use std::{mem::replace, fmt::{Display, Write}, collections::{BTreeMap, HashMap}};
 
 use azurite_parser::ast::{Instruction, Expression, BinaryOperator, Statement, InstructionKind, Declaration, UnaryOperator};
-use common::{Data, default, SymbolIndex, SymbolTable, DataType};
+use common::{Data, default, SymbolIndex, SymbolTable, DataType, GENERIC_START_SYMBOL, GENERIC_END_SYMBOL};
 use rayon::prelude::{ParallelIterator, IntoParallelRefMutIterator};
 
 #[derive(Debug, PartialEq)]
@@ -19,6 +19,18 @@ pub struct ConversionState {
     extern_counter: u32,
     
     pub symbol_table: SymbolTable,
+
+    /// The most instructions a function can have
+    /// to be inlined into its callers at `O2`
+    pub inline_threshold: usize,
+
+    /// Whether to emit the `SourcePosition`s
+    /// of a debug build's line table
+    pub debug_info: bool,
+
+    /// The file being converted, `None` for the templates
+    /// as their instructions can come from any file
+    file: Option<SymbolIndex>,
 }
 
 
@@ -36,12 +48,21 @@ pub struct Function {
     pub stack_size: u32,
     block_counter: u32,
 
-    breaks: Vec<BlockIndex>,
-    continues: Vec<BlockIndex>,
+    breaks: Vec<(BlockIndex, Option<SymbolIndex>)>,
+    continues: Vec<(BlockIndex, Option<SymbolIndex>)>,
     explicit_ret: Vec<BlockIndex>,
 
+    /// The `loop.index` counter of each loop being converted,
+    /// innermost last. Only created once the body reads it
+    loop_counters: Vec<Option<Variable>>,
+
     pub blocks: Vec<Block>,
     entry: BlockIndex,
+
+    /// The position of each block in `blocks`, indexed by
+    /// its `BlockIndex`. Checked on every lookup as `blocks`
+    /// can be reordered or pruned by the optimizations
+    block_positions: Vec<usize>,
     
 }
 
@@ -61,11 +82,12 @@ pub struct ExternFunction {
 pub struct Structure {
     pub id: u64,
     pub fields: Vec<DataType>,
+    pub field_names: Vec<SymbolIndex>,
     pub is_used: bool,
 }
 
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Block {
     pub block_index: BlockIndex,
     pub instructions: Vec<IR>,
@@ -134,6 +156,11 @@ pub enum IR {
     Multiply      { dst: Variable, left: Variable, right: Variable },
     Divide        { dst: Variable, left: Variable, right: Variable },
     Modulo        { dst: Variable, left: Variable, right: Variable },
+    BitAnd        { dst: Variable, left: Variable, right: Variable },
+    BitOr         { dst: Variable, left: Variable, right: Variable },
+    BitXor        { dst: Variable, left: Variable, right: Variable },
+    ShiftLeft     { dst: Variable, left: Variable, right: Variable },
+    ShiftRight    { dst: Variable, left: Variable, right: Variable },
     Equals        { dst: Variable, left: Variable, right: Variable },
     NotEquals     { dst: Variable, left: Variable, right: Variable },
     GreaterThan   { dst: Variable, left: Variable, right: Variable },
@@ -143,14 +170,23 @@ pub enum IR {
 
     UnaryNot      { dst: Variable, val:  Variable },
     UnaryNeg      { dst: Variable, val:  Variable },
+    UnaryBitNot   { dst: Variable, val:  Variable },
 
     Call          { dst: Variable, id: FunctionIndex,  args: Vec<Variable> },
     ExtCall       { dst: Variable, id: FunctionIndex,  args: Vec<Variable> },
+
+    /// A call whose result is returned right away, it
+    /// replaces the current frame instead of adding one
+    TailCall      { id: FunctionIndex, args: Vec<Variable> },
     
     Struct        { dst: Variable, id: SymbolIndex, fields: Vec<Variable> },
     AccStruct     { dst: Variable, val: Variable, index: u8 },
     SetField      { dst: Variable, data: Variable, index: u8},
 
+    MakeArray     { dst: Variable, elements: Vec<Variable> },
+    IndexGet      { dst: Variable, val: Variable, index: Variable },
+    IndexSet      { dst: Variable, data: Variable, index: Variable },
+
 
     CastToI8      { dst: Variable, val: Variable },
     CastToI16     { dst: Variable, val: Variable },
@@ -163,6 +199,10 @@ pub enum IR {
     CastToU64     { dst: Variable, val: Variable },
 
     CastToFloat   { dst: Variable, val: Variable },
+
+    /// Marks where the code of a source line starts, only
+    /// emitted for the line table of a debug build
+    SourcePosition { file: SymbolIndex, line: u32 },
     
 
     Noop,
@@ -188,6 +228,10 @@ impl ConversionState {
             // 0..256 is reserved
             structure_counter: 257,
 
+            inline_threshold: 16,
+
+            debug_info: false,
+            file: None,
         }
     }
 
@@ -213,6 +257,8 @@ impl ConversionState {
         function.block_counter = 0;
 
         for file in files {
+            self.file = Some(file.0);
+
             let function = self.functions.get(&file.0).unwrap().function_index;
             let mut function = Function::new(file.0, function, DataType::Empty, vec![]);
 
@@ -222,6 +268,7 @@ impl ConversionState {
         }
 
 
+        self.file = None;
         self.constants.push(Data::I32(0));
 
         let vec = Vec::from([
@@ -233,7 +280,7 @@ impl ConversionState {
 
         function.register_lookup[0] = DataType::I32;
         function.register_lookup.push(self.find_function(root_index).return_type.clone());
-        function.blocks.push(block);
+        function.push_block(block);
 
         self.functions.insert(init_function, function);
 
@@ -251,7 +298,10 @@ impl ConversionState {
 
 
     pub fn sort(&mut self) {
-        self.functions.par_iter_mut().for_each(|x| x.1.blocks.sort_by_key(|x| x.block_index.0));
+        self.functions.par_iter_mut().for_each(|x| {
+            x.1.blocks.sort_by_key(|x| x.block_index.0);
+            x.1.index_blocks();
+        });
     }
 
 
@@ -322,9 +372,9 @@ impl ConversionState {
     }
 
 
-    fn register_structure(&mut self, structure: SymbolIndex, fields: Vec<DataType>) {
+    fn register_structure(&mut self, structure: SymbolIndex, fields: Vec<DataType>, field_names: Vec<SymbolIndex>) {
         if let std::collections::hash_map::Entry::Vacant(e) = self.structures.entry(structure) {
-            e.insert(Structure { id: self.structure_counter, fields, is_used: false });
+            e.insert(Structure { id: self.structure_counter, fields, field_names, is_used: false });
             self.structure_counter += 1;
         }
     }
@@ -348,8 +398,10 @@ impl Function {
             block_counter: 0,
             breaks: vec![],
             continues: vec![],
+            loop_counters: vec![],
             blocks: vec![],
             entry: BlockIndex(0),
+            block_positions: vec![],
             explicit_ret: vec![],
             return_type,
             arguments,
@@ -374,11 +426,16 @@ impl Function {
 
         if self.evaluate(state, &mut block, instructions, &mut final_value) {
             block.ir(IR::Copy { src: final_value, dst: Variable(0) });
+        } else if final_value == return_val {
+            // Nothing in the body produced a value, like
+            // an empty body or one of only statements, so
+            // the return value would be read uninitialised
+            block.ir(IR::Unit { dst: return_val });
         } else {
             block.ir(IR::Copy { src: final_value, dst: return_val });
         }
 
-        self.blocks.push(block);
+        self.push_block(block);
         
     }
 
@@ -389,6 +446,18 @@ impl Function {
         for instruction in instructions {
             let statement = matches!(instruction.instruction_kind, InstructionKind::Statement(_) | InstructionKind::Declaration(_));
 
+            // Declarations don't produce any code of their own
+            let position = state.file
+                .filter(|_| state.debug_info && !matches!(instruction.instruction_kind, InstructionKind::Declaration(_)))
+                .zip(instruction.source_range.position)
+                .map(|(file, (start, _))| IR::SourcePosition { file, line: start.line as u32 });
+
+            if let Some(position) = &position {
+                block.ir(position.clone());
+            }
+
+            let start_block = block.block_index;
+
             if let InstructionKind::Statement(Statement::Return(e)) = instruction.instruction_kind {
                 let val = self.convert(state, block, *e);
                 *final_value = val;
@@ -399,6 +468,15 @@ impl Function {
             
             let variable = self.convert(state, block, instruction);
 
+            // The rest of the statement continues in a new block
+            // after branching, which comes after the branches'
+            // code so it has to be marked again
+            if let Some(position) = position {
+                if block.block_index != start_block {
+                    block.instructions.insert(0, position);
+                }
+            }
+
             *final_value = if statement { *final_value } else { variable }
         }
         false
@@ -433,7 +511,11 @@ impl ConversionState {
                                 return;
                             }
 
-                            self.register_structure(*name, fields.iter().map(|x| x.1.data_type.clone()).collect())
+                            self.register_structure(
+                                *name,
+                                fields.iter().map(|x| x.1.data_type.clone()).collect(),
+                                fields.iter().map(|x| x.0).collect(),
+                            )
                         },
 
                         
@@ -461,6 +543,10 @@ impl ConversionState {
                         Declaration::ImplBlock { body, .. } => {
                             self.declaration_process(body);
                         },
+
+                        // Constants & enum variants are inlined by the analyzer
+                        Declaration::Const { .. } => (),
+                        Declaration::Enum { .. } => (),
                     }
                 },
                 _ => continue,
@@ -491,7 +577,7 @@ impl Function {
 
 
         let index = block.block_index;
-        self.blocks.push(block);
+        self.push_block(block);
         self.variable_counter = var_count;
         self.variable_lookup.resize_with(lookup_len, || panic!());
         
@@ -564,6 +650,10 @@ impl Function {
             Declaration::ImplBlock { body, .. } => {
                 self.convert_block(state, body);
             },
+
+
+            Declaration::Const { .. } => (),
+            Declaration::Enum { .. } => (),
         }
     }
 
@@ -599,42 +689,93 @@ impl Function {
                 block.ir(IR::SetField { dst, data, index: index_to as u8 })
             },
 
+
+            Statement::IndexUpdate { value, index, right } => {
+                let dst = self.convert(state, block, *value);
+                let index = self.convert(state, block, *index);
+                let data = self.convert(state, block, *right);
+
+                block.ir(IR::IndexSet { dst, data, index })
+            },
+
             
-            Statement::Loop { body } => {
+            Statement::Loop { body, label } => {
+                let outer_breaks = std::mem::take(&mut self.breaks);
+                let outer_continues = std::mem::take(&mut self.continues);
+
+                self.loop_counters.push(None);
                 let body_block = self.convert_block(state, body);
-                self.find_block_mut(body_block.1).ending = BlockTerminator::Goto(body_block.0);
+                let counter = self.loop_counters.pop().flatten();
+
+                // The counter starts at zero before entering the loop
+                // and is incremented on every jump back to its start
+                let back_edge = match counter {
+                    Some(counter) => {
+                        block.ir(IR::Load { dst: counter, data: state.constants.len() as u32 });
+                        state.constants.push(Data::I64(0));
+
+                        let one = self.variable(DataType::I64);
+                        let mut increment = Block { block_index: self.block(), instructions: vec![], ending: BlockTerminator::Goto(body_block.0) };
+                        increment.ir(IR::Load { dst: one, data: state.constants.len() as u32 });
+                        state.constants.push(Data::I64(1));
+                        increment.ir(IR::Add { dst: counter, left: counter, right: one });
+
+                        let index = increment.block_index;
+                        self.push_block(increment);
+                        index
+                    },
+
+                    None => body_block.0,
+                };
+
+                self.find_block_mut(body_block.1).ending = BlockTerminator::Goto(back_edge);
                 
                 let mut continue_block = Block { block_index: self.block(), instructions: vec![], ending: BlockTerminator::Return};
                 continue_block.ending = replace(&mut block.ending, BlockTerminator::Goto(body_block.0));
-                self.blocks.push(replace(block, continue_block));
-
-                for break_block in std::mem::take(&mut self.breaks) {
-                    self.find_block_mut(break_block).ending = BlockTerminator::Goto(block.block_index);
+                self.push_block(replace(block, continue_block));
+
+                // Unlabeled jumps always belong to the innermost loop,
+                // labeled ones propagate outwards until the label matches
+                let (breaks, unresolved_breaks) = replace(&mut self.breaks, outer_breaks)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|x| x.1.is_none() || x.1 == label);
+
+                let (continues, unresolved_continues) = replace(&mut self.continues, outer_continues)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|x| x.1.is_none() || x.1 == label);
+
+                self.breaks.extend(unresolved_breaks);
+                self.continues.extend(unresolved_continues);
+
+                for (break_block, _) in breaks {
+                    let ending = &mut self.find_block_mut(break_block).ending;
+                    match ending {
+                        BlockTerminator::SwitchBool { op2, .. } => *op2 = block.block_index,
+                        _ => *ending = BlockTerminator::Goto(block.block_index),
+                    }
                 }
 
-                for continue_block in std::mem::take(&mut self.continues) {
-                    self.find_block_mut(continue_block).ending = BlockTerminator::Goto(body_block.0);
+                for (continue_block, _) in continues {
+                    self.find_block_mut(continue_block).ending = BlockTerminator::Goto(back_edge);
                 }
-                
-                
             },
 
             
-            Statement::Break => {
-                self.breaks.push(block.block_index);
+            Statement::Break(label) => {
+                self.breaks.push((block.block_index, label));
 
                 let mut continue_block = Block { block_index: self.block(), instructions: vec![], ending: BlockTerminator::Return};
                 continue_block.ending = replace(&mut block.ending, BlockTerminator::Goto(BlockIndex(u32::MAX))); // placeholder terminator
-                self.blocks.push(replace(block, continue_block)); 
+                self.push_block(replace(block, continue_block)); 
             },
 
             
-            Statement::Continue => {
-                self.continues.push(block.block_index);
+            Statement::Continue(label) => {
+                self.continues.push((block.block_index, label));
 
                 let mut continue_block = Block { block_index: self.block(), instructions: vec![], ending: BlockTerminator::Return };
                 continue_block.ending = replace(&mut block.ending, BlockTerminator::Goto(BlockIndex(u32::MAX))); // placeholder terminator
-                self.blocks.push(replace(block, continue_block));   
+                self.push_block(replace(block, continue_block));   
                 
             },
             
@@ -693,6 +834,11 @@ impl Function {
                     BinaryOperator::Multiply      => block.ir(IR::Multiply      { dst, left: left_var, right: right_var }),
                     BinaryOperator::Divide        => block.ir(IR::Divide        { dst, left: left_var, right: right_var }),
                     BinaryOperator::Modulo        => block.ir(IR::Modulo        { dst, left: left_var, right: right_var }),
+                    BinaryOperator::BitwiseAnd    => block.ir(IR::BitAnd        { dst, left: left_var, right: right_var }),
+                    BinaryOperator::BitwiseOr     => block.ir(IR::BitOr         { dst, left: left_var, right: right_var }),
+                    BinaryOperator::BitwiseXor    => block.ir(IR::BitXor        { dst, left: left_var, right: right_var }),
+                    BinaryOperator::ShiftLeft     => block.ir(IR::ShiftLeft     { dst, left: left_var, right: right_var }),
+                    BinaryOperator::ShiftRight    => block.ir(IR::ShiftRight    { dst, left: left_var, right: right_var }),
                     BinaryOperator::Equals        => block.ir(IR::Equals        { dst, left: left_var, right: right_var }),
                     BinaryOperator::NotEquals     => block.ir(IR::NotEquals     { dst, left: left_var, right: right_var }),
                     BinaryOperator::GreaterThan   => block.ir(IR::GreaterThan   { dst, left: left_var, right: right_var }),
@@ -712,6 +858,7 @@ impl Function {
                 match operator {
                     UnaryOperator::Not => block.ir(IR::UnaryNot { dst, val }),
                     UnaryOperator::Negate => block.ir(IR::UnaryNeg { dst, val }),
+                    UnaryOperator::BitwiseNot => block.ir(IR::UnaryBitNot { dst, val }),
                 };
 
                 dst
@@ -725,7 +872,7 @@ impl Function {
                 continue_block.ending = replace(&mut block.ending, BlockTerminator::Goto(body.0));
                 self.find_block_mut(body.1).ending = BlockTerminator::Goto(continue_block.block_index);
                 
-                self.blocks.push(replace(block, continue_block));
+                self.push_block(replace(block, continue_block));
 
                 body.2
             },
@@ -737,12 +884,22 @@ impl Function {
                 let body_block_index = self.convert_block(state, body);
                 
                 let mut continue_block = Block { block_index: self.block(), instructions: vec![], ending: BlockTerminator::Return };
+                let else_break = else_part.as_deref().and_then(else_only_breaks);
                 
                 let switch = BlockTerminator::SwitchBool {
                     cond: condition,
                     op1: body_block_index.0, 
-                    op2: match else_part {
-                        Some(else_part) => {
+                    op2: match (else_part, else_break) {
+                        // An else that only breaks (i.e. a desugared `while`)
+                        // jumps straight to the end of the loop instead of
+                        // going through a block of its own. The placeholder
+                        // is patched once the loop is done
+                        (Some(_), Some(label)) => {
+                            self.breaks.push((block.block_index, label));
+                            BlockIndex(u32::MAX)
+                        },
+
+                        (Some(else_part), None) => {
                             let else_body_index = self.convert_block(state, vec![*else_part]);
                             let else_body = self.find_block_mut(else_body_index.1);
                             
@@ -753,18 +910,63 @@ impl Function {
                         
                             else_body_index.0
                         },
-                        None => continue_block.block_index,
+                        (None, _) => continue_block.block_index,
                     }
                 };                
 
                 continue_block.ending = replace(&mut block.ending, switch);
                 self.find_block_mut(body_block_index.1).ending = BlockTerminator::Goto(continue_block.block_index);
-                self.blocks.push(replace(block, continue_block));
+                self.push_block(replace(block, continue_block));
 
                 body_block_index.2
             },
 
             
+            Expression::Match { scrutinee, mut arms, default } => {
+                let scrutinee = self.convert(state, block, *scrutinee);
+                let dst = self.variable(typ);
+
+                let mut continue_block = Block { block_index: self.block(), instructions: vec![], ending: BlockTerminator::Return };
+                continue_block.ending = replace(&mut block.ending, BlockTerminator::Return);
+
+                // Without a `_` arm the analysis made sure the arms
+                // cover every value, so the last one doesn't need
+                // to be compared
+                let default = match default {
+                    Some(v) => v,
+                    None => arms.pop().unwrap().1,
+                };
+
+                // Each arm compares the value with its pattern and
+                // either runs its body or moves on to the next arm
+                for (pattern, body) in arms {
+                    let pattern = self.convert(state, block, pattern);
+                    let condition = self.variable(DataType::Bool);
+                    block.ir(IR::Equals { dst: condition, left: scrutinee, right: pattern });
+
+                    let body = self.convert_block(state, body);
+                    let body_end = self.find_block_mut(body.1);
+                    body_end.ir(IR::Copy { src: body.2, dst });
+                    body_end.ending = BlockTerminator::Goto(continue_block.block_index);
+
+                    let next_arm = Block { block_index: self.block(), instructions: vec![], ending: BlockTerminator::Return };
+                    block.ending = BlockTerminator::SwitchBool { cond: condition, op1: body.0, op2: next_arm.block_index };
+
+                    self.push_block(replace(block, next_arm));
+                }
+
+                let body = self.convert_block(state, default);
+                let body_end = self.find_block_mut(body.1);
+                body_end.ir(IR::Copy { src: body.2, dst });
+                body_end.ending = BlockTerminator::Goto(continue_block.block_index);
+
+                block.ending = BlockTerminator::Goto(body.0);
+                self.push_block(replace(block, continue_block));
+
+                dst
+            },
+
+            
             Expression::Identifier(v) => self.variable_lookup.iter().rev().find(|x| x.0 == v).unwrap().1,
 
             
@@ -811,6 +1013,46 @@ impl Function {
             },
 
             
+            Expression::ArrayCreation { elements } => {
+                let mut variables = Vec::with_capacity(elements.len());
+                for element in elements.into_iter() {
+                    let element_reg = self.convert(state, block, element);
+                    variables.push(element_reg);
+                }
+
+                let dst = self.variable(typ);
+                block.ir(IR::MakeArray { dst, elements: variables });
+
+                dst
+            },
+
+            
+            Expression::Index { value, index } => {
+                let val = self.convert(state, block, *value);
+                let index = self.convert(state, block, *index);
+                let dst = self.variable(typ);
+
+                block.ir(IR::IndexGet { dst, val, index });
+
+                dst
+            },
+
+            
+            Expression::Range { .. } => unreachable!("ranges are replaced during analysis"),
+
+
+            Expression::LoopIndex => match self.loop_counters.last().copied() {
+                Some(Some(counter)) => counter,
+                Some(None) => {
+                    let counter = self.variable(DataType::I64);
+                    *self.loop_counters.last_mut().unwrap() = Some(counter);
+                    counter
+                },
+
+                None => unreachable!("the analysis only allows `loop.index` inside a loop"),
+            },
+
+            
             Expression::AccessStructureData { structure, index_to, .. } => {
                 let struct_at = self.convert(state, block, *structure);
                 let dst = self.variable(typ);
@@ -842,18 +1084,94 @@ impl Function {
     }
 
 
+    fn push_block(&mut self, block: Block) {
+        let index = block.block_index.0 as usize;
+        if self.block_positions.len() <= index {
+            self.block_positions.resize(index + 1, usize::MAX);
+        }
+
+        self.block_positions[index] = self.blocks.len();
+        self.blocks.push(block);
+    }
+
+
+    /// Rebuilds `block_positions` after `blocks` was modified
+    /// without going through `push_block`
+    fn index_blocks(&mut self) {
+        self.block_positions.clear();
+
+        for position in 0..self.blocks.len() {
+            let index = self.blocks[position].block_index.0 as usize;
+            if self.block_positions.len() <= index {
+                self.block_positions.resize(index + 1, usize::MAX);
+            }
+
+            self.block_positions[index] = position;
+        }
+    }
+
+
+    fn block_position(&self, index: BlockIndex) -> Option<usize> {
+        let position = *self.block_positions.get(index.0 as usize)?;
+
+        match self.blocks.get(position) {
+            Some(block) if block.block_index == index => Some(position),
+            _ => None,
+        }
+    }
+
+
     fn find_block_mut(&mut self, index: BlockIndex) -> &mut Block {
-        self.blocks.iter_mut().find(|x| x.block_index == index).unwrap()
+        let position = match self.block_position(index) {
+            Some(v) => v,
+            None => {
+                self.index_blocks();
+                self.block_position(index).unwrap()
+            },
+        };
+
+        &mut self.blocks[position]
     }
 
     fn find_block(&self, index: BlockIndex) -> &Block {
-        self.blocks.iter().find(|x| x.block_index == index).unwrap()
+        match self.block_position(index) {
+            Some(v) => &self.blocks[v],
+            None => self.blocks.iter().find(|x| x.block_index == index).unwrap(),
+        }
     }
 
 }
 
 
+/// Removes every generic argument list from a path,
+/// i.e `file::Pair::method` for `file::Pair::@<::str::>@::method`
+fn without_generics(path: &str) -> String {
+    let mut depth = 0usize;
+    path.split("::")
+        .filter(|x| match *x {
+            GENERIC_START_SYMBOL => { depth += 1; false },
+            GENERIC_END_SYMBOL => { depth -= 1; false },
+            _ => depth == 0,
+        })
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+
 impl Function {
+    /// Whether `filter` names this function, either by its full path,
+    /// its path within its file or just its name. Generic arguments
+    /// are ignored so `foo` matches every instance of `foo[T]`
+    pub fn matches_name(&self, state: &ConversionState, filter: &str) -> bool {
+        let full = without_generics(&state.symbol_table.get(&self.identifier));
+        let within_file = full.split_once("::").map(|x| x.1);
+
+        full == filter
+            || within_file == Some(filter)
+            || full.rsplit("::").next() == Some(filter)
+    }
+
+
     pub fn pretty_print(&self, state: &ConversionState, lock: &mut impl Write) {
         let _ = writeln!(lock, "fn {} ({})", self.function_index, state.symbol_table.get(&self.identifier));
         for block in &self.blocks {
@@ -871,6 +1189,11 @@ impl Function {
                     IR::Multiply { dst, left, right }      => writeln!(lock, "mul {dst} {left} {right}"),
                     IR::Divide { dst, left, right }        => writeln!(lock, "div {dst} {left} {right}"),
                     IR::Modulo { dst, left, right }        => writeln!(lock, "mod {dst} {left} {right}"),
+                    IR::BitAnd { dst, left, right }        => writeln!(lock, "and {dst} {left} {right}"),
+                    IR::BitOr { dst, left, right }         => writeln!(lock, "or {dst} {left} {right}"),
+                    IR::BitXor { dst, left, right }        => writeln!(lock, "xor {dst} {left} {right}"),
+                    IR::ShiftLeft { dst, left, right }     => writeln!(lock, "shl {dst} {left} {right}"),
+                    IR::ShiftRight { dst, left, right }    => writeln!(lock, "shr {dst} {left} {right}"),
                     IR::Copy { src, dst }                  => writeln!(lock, "copy {src} {dst}"),
                     IR::Swap { v1, v2 }                    => writeln!(lock, "swap {v1} {v2}"),
                     IR::Equals { dst, left, right }        => writeln!(lock, "eq {dst} {left} {right}"),
@@ -881,13 +1204,19 @@ impl Function {
                     IR::LesserEquals { dst, left, right }  => writeln!(lock, "le {dst} {left} {right}"),
                     IR::Call { id, dst, args }             => writeln!(lock, "call {id} {dst} ({} )", args.iter().map(|x| format!(" {x}")).collect::<String>()),
                     IR::ExtCall { id: index, dst, args }   => writeln!(lock, "ecall {index} {dst} ({} )", args.iter().map(|x| format!(" {x}")).collect::<String>()),
+                    IR::TailCall { id, args }              => writeln!(lock, "tailcall {id} ({} )", args.iter().map(|x| format!(" {x}")).collect::<String>()),
                     IR::Unit { dst }                       => writeln!(lock, "unit {dst}"),
                     IR::Struct { dst, fields, id }         => writeln!(lock, "struct({}) {dst} ({} )", state.symbol_table.get(id), fields.iter().map(|x| format!(" {x}")).collect::<String>()),
                     IR::AccStruct { dst, val, index }      => writeln!(lock, "accstruct, {dst} {val} {index}"),
                     IR::SetField { dst, data, index }      => writeln!(lock, "setfield {dst} {data} {index}"),
+                    IR::MakeArray { dst, elements }        => writeln!(lock, "array {dst} ({} )", elements.iter().map(|x| format!(" {x}")).collect::<String>()),
+                    IR::IndexGet { dst, val, index }       => writeln!(lock, "indexget {dst} {val} {index}"),
+                    IR::IndexSet { dst, data, index }      => writeln!(lock, "indexset {dst} {data} {index}"),
                     IR::Noop                               => continue,
+                    IR::SourcePosition { file, line }      => writeln!(lock, "line {}:{}", state.symbol_table.get(file), line + 1),
                     IR::UnaryNot { dst, val }              => writeln!(lock, "not {dst} {val}"),
                     IR::UnaryNeg { dst, val }              => writeln!(lock, "neg {dst} {val}"),
+                    IR::UnaryBitNot { dst, val }           => writeln!(lock, "bnot {dst} {val}"),
                     
                     IR::CastToI8 { dst, val }  => writeln!(lock, "castI8 {dst} {val}"),
                     IR::CastToI16 { dst, val } => writeln!(lock, "castI16 {dst} {val}"),
@@ -917,4 +1246,17 @@ impl Block {
     fn ir(&mut self, ir: IR) {
         self.instructions.push(ir);
     }
-}
\ No newline at end of file
+}
+
+
+/// Returns the label of the `break` if the instruction
+/// is a block that contains nothing but a `break`
+fn else_only_breaks(instruction: &Instruction) -> Option<Option<SymbolIndex>> {
+    let InstructionKind::Expression(Expression::Block { body }) = &instruction.instruction_kind else { return None };
+
+    match body.as_slice() {
+        [Instruction { instruction_kind: InstructionKind::Statement(Statement::Break(label)), .. }] => Some(*label),
+        _ => None,
+    }
+}
+
@@ -1,52 +1,68 @@
 mod register_alloc;
+mod constant_folding;
+mod inlining;
+mod tail_calls;
+
+use std::collections::{HashMap, HashSet};
+
+use common::{Data, DataType, SymbolIndex};
+
+use crate::{ConversionState, Function, BlockIndex, BlockTerminator, IR, FunctionIndex, Variable};
+use register_alloc::{is_register_used_later, instruction_used_registers};
+
+/// How aggressively `ConversionState::optimize` transforms the IR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptimizationLevel {
+    /// The IR is left as it was generated, apart from
+    /// removing the blocks that can't be reached
+    O0,
+
+    /// Only passes local to a function are ran. These are
+    /// constant folding, branch folding, dead block
+    /// elimination, copy propagation and register allocation
+    O1,
+
+    /// Every pass is ran until none of them make a change.
+    /// On top of `O1` these are inlining of small functions,
+    /// dead function elimination and merging blocks into
+    /// their only predecessor
+    #[default]
+    O2,
+}
 
-use std::collections::HashMap;
 
-use common::{Data, DataType};
+impl OptimizationLevel {
+    pub fn from_level(level: u8) -> Option<Self> {
+        match level {
+            0 => Some(Self::O0),
+            1 => Some(Self::O1),
+            2 => Some(Self::O2),
+            _ => None,
+        }
+    }
+}
 
-use crate::{ConversionState, Function, Block, BlockIndex, BlockTerminator, IR, FunctionIndex, Variable};
 
 impl ConversionState {
-    pub fn optimize(&mut self) {
+    pub fn optimize(&mut self, level: OptimizationLevel) {
+        if level == OptimizationLevel::O0 {
+            return
+        }
+
+        let is_full = level >= OptimizationLevel::O2;
+
         loop {
             let mut has_changed = false;
-            {
-                let mut used_functions = HashMap::from([(FunctionIndex(0), FunctionIndex(0))]);
-                let mut counter = 1;
-
-                for f in self.functions.iter_mut() {
-                    for b in f.1.blocks.iter_mut() {
-                        for i in b.instructions.iter_mut() {
-                            match i {
-                                IR::Call { id, .. } => {
-                                    let val = match used_functions.entry(*id) {
-                                        std::collections::hash_map::Entry::Occupied(v) => *v.get(),
-                                        std::collections::hash_map::Entry::Vacant(v) => {
-                                            counter += 1;
-                                            *v.insert(FunctionIndex(counter-1))
-                                        }
-                                    };
-
-                                    *id = val;
-                                },
-                                _ => continue,
-                            }
-                        }
-                    }
-                }
+            if is_full && self.inline_functions() {
+                has_changed = true
+            }
 
-                for f in self.functions.iter().map(|x| (*x.0, x.1.function_index)).collect::<Vec<_>>() {
-                    if let Some(mapping) = used_functions.get(&f.1) {
-                        self.functions.get_mut(&f.0).unwrap().function_index = *mapping;
-                    } else {
-                        has_changed = true;
-                        self.functions.remove(&f.0);
-                    }
-                }
+            if is_full && self.eliminate_dead_functions() {
+                has_changed = true
             }
 
 
-            if self.functions.iter_mut().map(|x| x.1.optimize(true)).any(|x| x) {
+            if self.functions.iter_mut().map(|x| x.1.optimize(is_full)).any(|x| x) {
                 has_changed = true
             }
 
@@ -116,37 +132,133 @@ impl ConversionState {
     }
 }
 
-impl Function {
-    pub fn optimize(&mut self, inline: bool) -> bool {
-        let mut has_changed = false;
+impl ConversionState {
+    /// Removes every function that isn't reachable from the
+    /// root function through calls and numbers the remaining
+    /// ones in the order they're found, keeping the root at 0.
+    ///
+    /// Extern functions are numbered separately by
+    /// `take_out_externs` which only looks at the calls
+    /// that are left so they don't need any handling here
+    fn eliminate_dead_functions(&mut self) -> bool {
+        let symbols : HashMap<FunctionIndex, SymbolIndex> = self.functions
+            .iter()
+            .map(|x| (x.1.function_index, *x.0))
+            .collect();
+
+        let mut mapping = HashMap::from([(FunctionIndex(0), FunctionIndex(0))]);
+        let mut stack = vec![FunctionIndex(0)];
+
+        while let Some(index) = stack.pop() {
+            let Some(symbol) = symbols.get(&index) else { continue };
+
+            for b in self.functions[symbol].blocks.iter() {
+                for i in b.instructions.iter() {
+                    let (IR::Call { id, .. } | IR::TailCall { id, .. }) = i else { continue };
+
+                    if !mapping.contains_key(id) {
+                        mapping.insert(*id, FunctionIndex(mapping.len() as u32));
+                        stack.push(*id);
+                    }
+                }
+            }
+        }
 
-        // Dead block analysis
-        {
-            let mut block_stack = vec![self.entry];
-            let mut new_blocks : Vec<Block> = Vec::with_capacity(self.blocks.len());
 
-            while let Some(block_id) = block_stack.pop() {
-                if new_blocks.iter().any(|x| x.block_index == block_id) {
-                    continue
+        let has_changed = mapping.len() != self.functions.len();
+
+        self.functions.retain(|_, f| mapping.contains_key(&f.function_index));
+
+        for f in self.functions.values_mut() {
+            f.function_index = mapping[&f.function_index];
+
+            for b in f.blocks.iter_mut() {
+                for i in b.instructions.iter_mut() {
+                    if let IR::Call { id, .. } | IR::TailCall { id, .. } = i {
+                        *id = mapping[&*id];
+                    }
                 }
+            }
+        }
 
+        has_changed
+    }
+}
 
-                let raw_block_index = self.blocks.iter().enumerate().find(|x| x.1.block_index == block_id).unwrap().0;
-                let block = self.blocks.remove(raw_block_index);
 
-                match &block.ending {
-                    crate::BlockTerminator::Goto(v) => block_stack.push(*v),
-                    crate::BlockTerminator::SwitchBool { op1, op2, .. } => {
-                        block_stack.push(*op1);
-                        block_stack.push(*op2);
-                    },
-                    crate::BlockTerminator::Return => (),
-                };
+impl ConversionState {
+    /// Strips the `Noop`s left after every variable
+    /// declaration & update.
+    ///
+    /// They keep copy propagation from merging an instruction
+    /// into the copy of the next statement, so this has to
+    /// run after every other pass, right before codegen
+    pub fn remove_noops(&mut self) {
+        for f in self.functions.values_mut() {
+            for b in f.blocks.iter_mut() {
+                b.instructions.retain(|x| !matches!(x, IR::Noop));
+            }
+        }
+    }
+}
+
 
-                new_blocks.push(block)
+impl ConversionState {
+    /// Removes the blocks of every function that can't be
+    /// reached from its entry. This runs at every level as
+    /// lowering `break`, `continue` & `return` leaves empty
+    /// blocks behind that codegen would otherwise emit
+    pub fn remove_unreachable_blocks(&mut self) {
+        for f in self.functions.values_mut() {
+            f.remove_unreachable_blocks();
+        }
+    }
+}
+
+
+impl Function {
+    /// Removes the blocks no terminator leads to from the
+    /// entry, keeping the order of the remaining ones
+    ///
+    /// Every placeholder terminator has to be patched by
+    /// the time this runs, a jump to a block which doesn't
+    /// exist panics
+    pub fn remove_unreachable_blocks(&mut self) -> bool {
+        let mut reachable = HashSet::from([self.entry]);
+        let mut block_stack = vec![self.entry];
+
+        while let Some(block_id) = block_stack.pop() {
+            let block = self.blocks.iter().find(|x| x.block_index == block_id)
+                .unwrap_or_else(|| panic!("jump to the block {block_id} which doesn't exist"));
+
+            let successors = match block.ending {
+                BlockTerminator::Goto(v) => vec![v],
+                BlockTerminator::SwitchBool { op1, op2, .. } => vec![op1, op2],
+                BlockTerminator::Return => vec![],
+            };
+
+            for successor in successors {
+                if reachable.insert(successor) {
+                    block_stack.push(successor);
+                }
             }
-            
-            self.blocks = new_blocks;
+        }
+
+        let block_count = self.blocks.len();
+        self.blocks.retain(|x| reachable.contains(&x.block_index));
+        self.index_blocks();
+
+        block_count != self.blocks.len()
+    }
+}
+
+
+impl Function {
+    pub fn optimize(&mut self, inline: bool) -> bool {
+        let mut has_changed = false;
+
+        if self.remove_unreachable_blocks() {
+            has_changed = true
         }
         
 
@@ -196,7 +308,7 @@ impl Function {
 
         {
             let block_ids = self.blocks.iter().map(|x| x.block_index).collect::<Vec<_>>();
-            // let block_used_registers = HashMap::with_capacity(self.blocks.len());
+            let block_map = self.generate_block_map();
 
             for block_id in &block_ids {
                 let block = self.find_block_mut(*block_id);
@@ -204,6 +316,16 @@ impl Function {
                 loop {
                     let mut remove = None;
                     let mut last_instruction = None;
+
+                    // How many times each register is read after the
+                    // current instruction. The source of the copy can
+                    // only be replaced if the copy is its only reader
+                    let mut reads : HashMap<Variable, usize> = HashMap::new();
+                    let mut storage = vec![];
+                    if let BlockTerminator::SwitchBool { cond, .. } = block.ending {
+                        reads.insert(cond, 1);
+                    }
+
                     for (index, instruction) in block.instructions.iter_mut().enumerate().rev() {
                         let val = match instruction {
                             IR::Copy { dst, src } => Some((*dst, *src)),
@@ -212,6 +334,17 @@ impl Function {
 
                         let copy = last_instruction;
                         last_instruction = val;
+
+                        let is_only_read = |reg: Variable| reads.get(&reg) == Some(&1)
+                            && !is_register_used_later(reg, &block.ending, &[].iter(), &block_map);
+
+                        let copy = copy.filter(|x| is_only_read(x.1));
+
+                        storage.clear();
+                        instruction_used_registers(instruction, &mut storage);
+                        for reg in &storage {
+                            *reads.entry(*reg).or_default() += 1;
+                        }
                     
                         let Some((last_dst, last_src)) = copy else { continue };
 
@@ -233,6 +366,11 @@ impl Function {
                             | IR::Multiply { dst, .. } 
                             | IR::Divide { dst, .. } 
                             | IR::Modulo { dst, .. } 
+                            | IR::BitAnd { dst, .. } 
+                            | IR::BitOr { dst, .. } 
+                            | IR::BitXor { dst, .. } 
+                            | IR::ShiftLeft { dst, .. } 
+                            | IR::ShiftRight { dst, .. } 
                             | IR::Equals { dst, .. } 
                             | IR::NotEquals { dst, .. } 
                             | IR::GreaterThan { dst, .. } 
@@ -243,8 +381,11 @@ impl Function {
                             | IR::ExtCall { dst, .. }
                             | IR::UnaryNot { dst, .. }
                             | IR::UnaryNeg { dst, .. }
+                            | IR::UnaryBitNot { dst, .. }
                             | IR::Struct { dst, .. }
                             | IR::AccStruct { dst, ..  } 
+                            | IR::MakeArray { dst, .. }
+                            | IR::IndexGet { dst, .. }
                             | IR::SetField { dst, .. } => {
                                 if *dst == last_src {
                                     *dst = last_dst;
@@ -255,6 +396,9 @@ impl Function {
 
                             
                             | IR::Swap { .. }
+                            | IR::IndexSet { .. }
+                            | IR::TailCall { .. }
+                            | IR::SourcePosition { .. }
                             | IR::Noop => (),
                         }
                     }
@@ -267,6 +411,117 @@ impl Function {
 
 
 
+        // Forward copy propagation
+        //
+        // Reads of the destination of a copy are replaced with
+        // its source until either of them is written to, which
+        // often leaves the copy unused for `register_alloc` to
+        // remove. A copy whose destination is written to before
+        // being read is removed right away.
+        //
+        // The scan continues into the target of a `goto` only if
+        // that block can't be reached any other way, every other
+        // successor is left to `register_alloc`
+        {
+            let mut predecessors : HashMap<BlockIndex, usize> = HashMap::new();
+            for block in &self.blocks {
+                match block.ending {
+                    BlockTerminator::Goto(v) => *predecessors.entry(v).or_default() += 1,
+                    BlockTerminator::SwitchBool { op1, op2, .. } => {
+                        *predecessors.entry(op1).or_default() += 1;
+                        *predecessors.entry(op2).or_default() += 1;
+                    },
+                    BlockTerminator::Return => (),
+                }
+            }
+
+            for block_position in 0..self.blocks.len() {
+                let mut index = 0;
+                while index < self.blocks[block_position].instructions.len() {
+                    let IR::Copy { dst, src } = self.blocks[block_position].instructions[index] else { index += 1; continue };
+                    index += 1;
+
+                    if dst == src {
+                        continue
+                    }
+
+                    let mut is_overwritten = false;
+                    let mut current = block_position;
+                    let mut start = index;
+
+                    loop {
+                        let block = &mut self.blocks[current];
+                        let mut reached_end = true;
+
+                        for instruction in block.instructions[start..].iter_mut() {
+                            // These read their destination in place
+                            let reads_in_place = match instruction {
+                                IR::Swap { v1, v2 } => [dst, src].contains(v1) || [dst, src].contains(v2),
+                                IR::SetField { dst: v, .. } | IR::IndexSet { dst: v, .. } => *v == dst,
+                                _ => false,
+                            };
+
+                            if reads_in_place {
+                                reached_end = false;
+                                break
+                            }
+
+                            for reg in read_registers_mut(instruction) {
+                                if *reg == dst {
+                                    *reg = src;
+                                    has_changed = true;
+                                }
+                            }
+
+                            let written = written_register(instruction);
+                            if written == Some(dst) {
+                                is_overwritten = true;
+                            }
+
+                            if written == Some(dst) || written == Some(src) {
+                                reached_end = false;
+                                break
+                            }
+                        }
+
+                        if !reached_end {
+                            break
+                        }
+
+                        let next = match &mut block.ending {
+                            BlockTerminator::Goto(v) => *v,
+
+                            BlockTerminator::SwitchBool { cond, .. } => {
+                                if *cond == dst {
+                                    *cond = src;
+                                    has_changed = true;
+                                }
+                                break
+                            },
+
+                            BlockTerminator::Return => break,
+                        };
+
+                        if next == self.entry
+                            || next == self.blocks[block_position].block_index
+                            || predecessors.get(&next) != Some(&1) {
+                            break
+                        }
+
+                        current = self.blocks.iter().position(|x| x.block_index == next).unwrap();
+                        start = 0;
+                    }
+
+                    if is_overwritten {
+                        index -= 1;
+                        self.blocks[block_position].instructions.remove(index);
+                        has_changed = true;
+                    }
+                }
+            }
+        }
+
+
         {
             let mut remove = vec![];
             for block in self.blocks.iter_mut() {
@@ -371,7 +626,8 @@ impl Function {
                         | IR::CastToU64 { dst: v1, val: v2 }
                         | IR::CastToFloat { dst: v1, val: v2 }
                         | IR::UnaryNot { dst: v1, val: v2 }
-                        | IR::UnaryNeg { dst: v1, val: v2 } => {
+                        | IR::UnaryNeg { dst: v1, val: v2 }
+                        | IR::UnaryBitNot { dst: v1, val: v2 } => {
                             update_reg(v1, &mut register_mapping, &mut register_counter);
                             update_reg(v2, &mut register_mapping, &mut register_counter);
                         }
@@ -382,12 +638,19 @@ impl Function {
                         | IR::Multiply { dst, left, right }
                         | IR::Divide { dst, left, right }
                         | IR::Modulo { dst, left, right }
+                        | IR::BitAnd { dst, left, right }
+                        | IR::BitOr { dst, left, right }
+                        | IR::BitXor { dst, left, right }
+                        | IR::ShiftLeft { dst, left, right }
+                        | IR::ShiftRight { dst, left, right }
                         | IR::Equals { dst, left, right }
                         | IR::NotEquals { dst, left, right }
                         | IR::GreaterThan { dst, left, right }
                         | IR::LesserThan { dst, left, right }
                         | IR::GreaterEquals { dst, left, right }
-                        | IR::LesserEquals { dst, left, right } => {
+                        | IR::LesserEquals { dst, left, right }
+                        | IR::IndexGet { dst, val: left, index: right }
+                        | IR::IndexSet { dst, data: left, index: right } => {
                             update_reg(dst, &mut register_mapping, &mut register_counter);
                             update_reg(left, &mut register_mapping, &mut register_counter);
                             update_reg(right, &mut register_mapping, &mut register_counter);
@@ -395,6 +658,7 @@ impl Function {
 
                         | IR::ExtCall { dst, args, .. }
                         | IR::Struct { dst, fields: args, .. }
+                        | IR::MakeArray { dst, elements: args }
                         | IR::Call { dst, args, .. } => {
                             update_reg(dst, &mut register_mapping, &mut register_counter);
 
@@ -402,6 +666,12 @@ impl Function {
                                 update_reg(a, &mut register_mapping, &mut register_counter);
                             }
                         },
+
+                        IR::TailCall { args, .. } => {
+                            for a in args.iter_mut() {
+                                update_reg(a, &mut register_mapping, &mut register_counter);
+                            }
+                        },
                     
 
 
@@ -411,7 +681,8 @@ impl Function {
                         }
 
                         
-                        IR::Noop => (),
+                        IR::SourcePosition { .. }
+                        | IR::Noop => (),
                     }
                 }
 
@@ -422,6 +693,8 @@ impl Function {
 
 
             assert_eq!(register_mapping.len(), register_counter as usize);
+            self.stack_size = register_counter;
+
             let old_lookup = std::mem::replace(&mut self.register_lookup, vec![DataType::Empty; register_counter as usize]);
             for i in register_mapping.iter() {
                 self.register_lookup[i.1.0 as usize] = old_lookup[i.0.0 as usize].clone()
@@ -430,3 +703,110 @@ impl Function {
         }
     }
 }
+
+
+/// The register an instruction writes to, if any. `Swap`
+/// writes to both of its registers so it's left out
+fn written_register(instruction: &IR) -> Option<Variable> {
+    match instruction {
+        | IR::Copy { dst, .. }
+        | IR::CastToI8 { dst, .. }
+        | IR::CastToI16 { dst, .. }
+        | IR::CastToI32 { dst, .. }
+        | IR::CastToI64 { dst, .. }
+        | IR::CastToU8 { dst, .. }
+        | IR::CastToU16 { dst, .. }
+        | IR::CastToU32 { dst, .. }
+        | IR::CastToU64 { dst, .. }
+        | IR::CastToFloat { dst, .. }
+        | IR::Unit { dst }
+        | IR::Load { dst, .. }
+        | IR::Add { dst, .. }
+        | IR::Subtract { dst, .. }
+        | IR::Multiply { dst, .. }
+        | IR::Divide { dst, .. }
+        | IR::Modulo { dst, .. }
+        | IR::BitAnd { dst, .. }
+        | IR::BitOr { dst, .. }
+        | IR::BitXor { dst, .. }
+        | IR::ShiftLeft { dst, .. }
+        | IR::ShiftRight { dst, .. }
+        | IR::Equals { dst, .. }
+        | IR::NotEquals { dst, .. }
+        | IR::GreaterThan { dst, .. }
+        | IR::LesserThan { dst, .. }
+        | IR::GreaterEquals { dst, .. }
+        | IR::LesserEquals { dst, .. }
+        | IR::Call { dst, .. }
+        | IR::ExtCall { dst, .. }
+        | IR::UnaryNot { dst, .. }
+        | IR::UnaryNeg { dst, .. }
+        | IR::UnaryBitNot { dst, .. }
+        | IR::Struct { dst, .. }
+        | IR::AccStruct { dst, .. }
+        | IR::MakeArray { dst, .. }
+        | IR::IndexGet { dst, .. }
+        | IR::IndexSet { dst, .. }
+        | IR::SetField { dst, .. } => Some(*dst),
+
+        | IR::Swap { .. }
+        | IR::TailCall { .. }
+        | IR::SourcePosition { .. }
+        | IR::Noop => None,
+    }
+}
+
+
+/// The registers an instruction reads, apart from the
+/// destinations `SetField` & `IndexSet` modify in place
+/// and the registers of a `Swap`
+fn read_registers_mut(instruction: &mut IR) -> Vec<&mut Variable> {
+    match instruction {
+        | IR::Copy { src: val, .. }
+        | IR::AccStruct { val, .. }
+        | IR::SetField { data: val, .. }
+        | IR::CastToI8 { val, .. }
+        | IR::CastToI16 { val, .. }
+        | IR::CastToI32 { val, .. }
+        | IR::CastToI64 { val, .. }
+        | IR::CastToU8 { val, .. }
+        | IR::CastToU16 { val, .. }
+        | IR::CastToU32 { val, .. }
+        | IR::CastToU64 { val, .. }
+        | IR::CastToFloat { val, .. }
+        | IR::UnaryNot { val, .. }
+        | IR::UnaryNeg { val, .. }
+        | IR::UnaryBitNot { val, .. } => vec![val],
+
+        | IR::Add { left, right, .. }
+        | IR::Subtract { left, right, .. }
+        | IR::Multiply { left, right, .. }
+        | IR::Divide { left, right, .. }
+        | IR::Modulo { left, right, .. }
+        | IR::BitAnd { left, right, .. }
+        | IR::BitOr { left, right, .. }
+        | IR::BitXor { left, right, .. }
+        | IR::ShiftLeft { left, right, .. }
+        | IR::ShiftRight { left, right, .. }
+        | IR::Equals { left, right, .. }
+        | IR::NotEquals { left, right, .. }
+        | IR::GreaterThan { left, right, .. }
+        | IR::LesserThan { left, right, .. }
+        | IR::GreaterEquals { left, right, .. }
+        | IR::LesserEquals { left, right, .. }
+        | IR::IndexGet { val: left, index: right, .. }
+        | IR::IndexSet { data: left, index: right, .. } => vec![left, right],
+
+        | IR::Call { args, .. }
+        | IR::ExtCall { args, .. }
+        | IR::TailCall { args, .. }
+        | IR::Struct { fields: args, .. }
+        | IR::MakeArray { elements: args, .. } => args.iter_mut().collect(),
+
+        | IR::Swap { .. }
+        | IR::Load { .. }
+        | IR::Unit { .. }
+        | IR::SourcePosition { .. }
+        | IR::Noop => vec![],
+    }
+}
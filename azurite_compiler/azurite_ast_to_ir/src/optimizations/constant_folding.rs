@@ -0,0 +1,246 @@
+use std::{collections::HashMap, ops::{Add, Sub, Mul, Div}};
+
+use common::Data;
+
+use crate::{ConversionState, IR, Variable, BlockTerminator};
+
+use super::written_register;
+
+impl ConversionState {
+    /// Replaces arithmetic and comparisons whose operands are
+    /// both loaded from constants with a load of the result.
+    ///
+    /// Registers are only tracked within a block, a register
+    /// written by anything else than a `Load` is forgotten.
+    /// The results follow the runtime so integers wrap, and
+    /// divisions by zero are left alone to error at runtime
+    ///
+    /// Casts of a constant, like the widening casts the analyzer
+    /// wraps literals in, are replaced with a load of the constant
+    /// retyped to the width it's cast to if its value fits
+    ///
+    /// A `SwitchBool` on a known condition is replaced with
+    /// a `Goto` to the branch it takes, the other branch is
+    /// then removed along with any block only it reached so
+    /// its registers don't count towards the stack size
+    pub fn fold_constants(&mut self) {
+        let constants = &mut self.constants;
+
+        for function in self.functions.values_mut() {
+            let mut has_folded_branch = false;
+
+            for block in function.blocks.iter_mut() {
+                let mut known : HashMap<Variable, u32> = HashMap::new();
+
+                for instruction in block.instructions.iter_mut() {
+                    if let Some((dst, left, right)) = foldable_operands(instruction) {
+                        let folded = known.get(&left)
+                            .zip(known.get(&right))
+                            .and_then(|(l, r)| fold(instruction, constants[*l as usize].clone(), constants[*r as usize].clone()));
+
+                        if let Some(data) = folded {
+                            constants.push(data);
+                            *instruction = IR::Load { dst, data: constants.len() as u32 - 1 };
+                        }
+                    }
+
+                    if let Some((dst, val)) = cast_operand(instruction) {
+                        let folded = known.get(&val).and_then(|x| cast(instruction, constants[*x as usize].clone()));
+
+                        if let Some(data) = folded {
+                            constants.push(data);
+                            *instruction = IR::Load { dst, data: constants.len() as u32 - 1 };
+                        }
+                    }
+
+                    match instruction {
+                        IR::Load { dst, data } => { known.insert(*dst, *data); },
+
+                        IR::Swap { v1, v2 } => {
+                            known.remove(v1);
+                            known.remove(v2);
+                        },
+
+                        _ => if let Some(dst) = written_register(instruction) {
+                            known.remove(&dst);
+                        },
+                    }
+                }
+
+                if let BlockTerminator::SwitchBool { cond, op1, op2 } = block.ending {
+                    if let Some(Data::Bool(v)) = known.get(&cond).map(|x| &constants[*x as usize]) {
+                        block.ending = BlockTerminator::Goto(if *v { op1 } else { op2 });
+                        has_folded_branch = true;
+                    }
+                }
+            }
+
+            if has_folded_branch {
+                function.remove_unreachable_blocks();
+            }
+        }
+    }
+}
+
+
+fn foldable_operands(instruction: &IR) -> Option<(Variable, Variable, Variable)> {
+    match instruction {
+        | IR::Add { dst, left, right }
+        | IR::Subtract { dst, left, right }
+        | IR::Multiply { dst, left, right }
+        | IR::Divide { dst, left, right }
+        | IR::Modulo { dst, left, right }
+        | IR::GreaterThan { dst, left, right }
+        | IR::LesserThan { dst, left, right }
+        | IR::GreaterEquals { dst, left, right }
+        | IR::LesserEquals { dst, left, right } => Some((*dst, *left, *right)),
+
+        _ => None,
+    }
+}
+
+
+fn cast_operand(instruction: &IR) -> Option<(Variable, Variable)> {
+    match instruction {
+        | IR::CastToI8 { dst, val }
+        | IR::CastToI16 { dst, val }
+        | IR::CastToI32 { dst, val }
+        | IR::CastToI64 { dst, val }
+        | IR::CastToU8 { dst, val }
+        | IR::CastToU16 { dst, val }
+        | IR::CastToU32 { dst, val }
+        | IR::CastToU64 { dst, val }
+        | IR::CastToFloat { dst, val } => Some((*dst, *val)),
+
+        _ => None,
+    }
+}
+
+
+/// Casts a constant if its value fits the type it's cast
+/// to unchanged, like the widening casts of literals
+///
+/// Casts which would truncate, round or change the sign of
+/// the value are left to the runtime so its checks still run
+fn cast(instruction: &IR, value: Data) -> Option<Data> {
+    enum Number {
+        Integer(i128),
+        Float(f64),
+    }
+
+    let value = match value {
+        Data::I8 (v) => Number::Integer(v.into()),
+        Data::I16(v) => Number::Integer(v.into()),
+        Data::I32(v) => Number::Integer(v.into()),
+        Data::I64(v) => Number::Integer(v.into()),
+        Data::U8 (v) => Number::Integer(v.into()),
+        Data::U16(v) => Number::Integer(v.into()),
+        Data::U32(v) => Number::Integer(v.into()),
+        Data::U64(v) => Number::Integer(v.into()),
+        Data::Float(v) => Number::Float(v),
+
+        _ => return None,
+    };
+
+    if let IR::CastToFloat { .. } = instruction {
+        return match value {
+            Number::Float(v) => Some(Data::Float(v)),
+            Number::Integer(v) => {
+                let float = v as f64;
+                (float as i128 == v).then_some(Data::Float(float))
+            },
+        }
+    }
+
+    let integer = match value {
+        Number::Integer(v) => v,
+        Number::Float(v) if v.fract() == 0.0 && (v as i128) as f64 == v => v as i128,
+        Number::Float(_) => return None,
+    };
+
+    Some(match instruction {
+        IR::CastToI8 { .. }  => Data::I8 (integer.try_into().ok()?),
+        IR::CastToI16 { .. } => Data::I16(integer.try_into().ok()?),
+        IR::CastToI32 { .. } => Data::I32(integer.try_into().ok()?),
+        IR::CastToI64 { .. } => Data::I64(integer.try_into().ok()?),
+        IR::CastToU8 { .. }  => Data::U8 (integer.try_into().ok()?),
+        IR::CastToU16 { .. } => Data::U16(integer.try_into().ok()?),
+        IR::CastToU32 { .. } => Data::U32(integer.try_into().ok()?),
+        IR::CastToU64 { .. } => Data::U64(integer.try_into().ok()?),
+
+        _ => return None,
+    })
+}
+
+
+/// Computes `instruction` on two constants the same way
+/// the runtime would, if it can't fail
+fn fold(instruction: &IR, left: Data, right: Data) -> Option<Data> {
+    macro_rules! integers {
+        ($f: ident) => {
+            match (left, right) {
+                (Data::I8 (l), Data::I8 (r)) => Data::I8 (l.$f(r)),
+                (Data::I16(l), Data::I16(r)) => Data::I16(l.$f(r)),
+                (Data::I32(l), Data::I32(r)) => Data::I32(l.$f(r)),
+                (Data::I64(l), Data::I64(r)) => Data::I64(l.$f(r)),
+                (Data::U8 (l), Data::U8 (r)) => Data::U8 (l.$f(r)),
+                (Data::U16(l), Data::U16(r)) => Data::U16(l.$f(r)),
+                (Data::U32(l), Data::U32(r)) => Data::U32(l.$f(r)),
+                (Data::U64(l), Data::U64(r)) => Data::U64(l.$f(r)),
+
+                _ => return None,
+            }
+        }
+    }
+
+    macro_rules! arithmetic {
+        ($f: ident, $float: expr) => {
+            match (&left, &right) {
+                (Data::Float(l), Data::Float(r)) => Data::Float($float(*l, *r)),
+                _ => integers!($f),
+            }
+        }
+    }
+
+    macro_rules! comparison {
+        ($op: tt) => {
+            Data::Bool(match (left, right) {
+                (Data::I8   (l), Data::I8   (r)) => l $op r,
+                (Data::I16  (l), Data::I16  (r)) => l $op r,
+                (Data::I32  (l), Data::I32  (r)) => l $op r,
+                (Data::I64  (l), Data::I64  (r)) => l $op r,
+                (Data::U8   (l), Data::U8   (r)) => l $op r,
+                (Data::U16  (l), Data::U16  (r)) => l $op r,
+                (Data::U32  (l), Data::U32  (r)) => l $op r,
+                (Data::U64  (l), Data::U64  (r)) => l $op r,
+                (Data::Float(l), Data::Float(r)) => l $op r,
+
+                _ => return None,
+            })
+        }
+    }
+
+    let is_zero = match right {
+        | Data::I8(0) | Data::I16(0) | Data::I32(0) | Data::I64(0)
+        | Data::U8(0) | Data::U16(0) | Data::U32(0) | Data::U64(0) => true,
+        Data::Float(v) => v == 0.0,
+        _ => false,
+    };
+
+    Some(match instruction {
+        IR::Add { .. }      => arithmetic!(wrapping_add, f64::add),
+        IR::Subtract { .. } => arithmetic!(wrapping_sub, f64::sub),
+        IR::Multiply { .. } => arithmetic!(wrapping_mul, f64::mul),
+
+        IR::Divide { .. } | IR::Modulo { .. } if is_zero => return None,
+        IR::Divide { .. }   => arithmetic!(wrapping_div, f64::div),
+        IR::Modulo { .. }   => arithmetic!(wrapping_rem, f64::rem_euclid),
+
+        IR::GreaterThan { .. }   => comparison!(>),
+        IR::LesserThan { .. }    => comparison!(<),
+        IR::GreaterEquals { .. } => comparison!(>=),
+        IR::LesserEquals { .. }  => comparison!(<=),
+
+        _ => return None,
+    })
+}
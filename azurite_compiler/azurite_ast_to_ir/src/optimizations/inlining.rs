@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use common::DataType;
+
+use crate::{ConversionState, Function, FunctionIndex, Block, BlockIndex, BlockTerminator, IR, Variable};
+
+use super::read_registers_mut;
+
+
+/// A copy of the parts of a function needed
+/// to inline it into one of its callers
+struct Inlinee {
+    blocks: Vec<Block>,
+    register_lookup: Vec<DataType>,
+    entry: BlockIndex,
+}
+
+
+impl ConversionState {
+    /// Replaces the calls to leaf functions with at most
+    /// `inline_threshold` instructions with their body.
+    ///
+    /// Only functions which don't call any other function
+    /// are inlined, which rules out recursion, but a caller
+    /// can become a leaf itself once its calls are inlined
+    pub(super) fn inline_functions(&mut self) -> bool {
+        let inlinees : HashMap<FunctionIndex, Inlinee> = self.functions
+            .values()
+            .filter(|f| f.is_inlinable(self.inline_threshold))
+            .map(|f| (f.function_index, Inlinee {
+                blocks: f.blocks.clone(),
+                register_lookup: f.register_lookup.clone(),
+                entry: f.entry,
+            }))
+            .collect();
+
+        if inlinees.is_empty() {
+            return false
+        }
+
+
+        let mut has_changed = false;
+        for f in self.functions.values_mut() {
+            while let Some((block, index, id)) = f.find_call(&inlinees) {
+                f.inline_call(block, index, &inlinees[&id]);
+                has_changed = true;
+            }
+        }
+
+        has_changed
+    }
+}
+
+
+impl Function {
+    fn is_inlinable(&self, threshold: usize) -> bool {
+        let mut instructions = self.blocks.iter()
+            .flat_map(|x| x.instructions.iter())
+            .filter(|x| !matches!(x, IR::Noop | IR::SourcePosition { .. }));
+
+        instructions.clone().count() <= threshold
+            && !instructions.any(|x| matches!(x, IR::Call { .. } | IR::TailCall { .. }))
+    }
+
+
+    /// The position of the first call to one of `inlinees`
+    /// as the index of its block and the instruction's
+    /// index within it
+    fn find_call(&self, inlinees: &HashMap<FunctionIndex, Inlinee>) -> Option<(usize, usize, FunctionIndex)> {
+        for (block_index, block) in self.blocks.iter().enumerate() {
+            for (index, instruction) in block.instructions.iter().enumerate() {
+                let IR::Call { id, .. } = instruction else { continue };
+
+                if *id != self.function_index && inlinees.contains_key(id) {
+                    return Some((block_index, index, *id))
+                }
+            }
+        }
+
+        None
+    }
+
+
+    /// Splits the block at the call and jumps into a copy
+    /// of the inlinee's blocks, every `Return` of which
+    /// copies the return value into the call's destination
+    /// and continues with the rest of the block.
+    ///
+    /// The inlinee's registers are given new registers in
+    /// this function, its arguments are copied into them
+    /// before jumping to its entry
+    ///
+    /// The inlined code is reported at the line of the call
+    /// so the inlinee's source positions are replaced with it
+    fn inline_call(&mut self, block_index: usize, index: usize, inlinee: &Inlinee) {
+        let register_mapping : Vec<Variable> = inlinee.register_lookup
+            .iter()
+            .map(|x| self.variable(x.clone()))
+            .collect();
+
+        let block_mapping : HashMap<BlockIndex, BlockIndex> = inlinee.blocks
+            .iter()
+            .map(|x| (x.block_index, self.block()))
+            .collect();
+
+        let continuation_index = self.block();
+
+
+        let block = &mut self.blocks[block_index];
+        let mut rest = block.instructions.split_off(index + 1);
+
+        // The blocks are emitted after the caller's so each
+        // of them has to start with the call's position
+        let position = block.instructions.iter().rev().find(|x| matches!(x, IR::SourcePosition { .. })).cloned();
+        rest.splice(0..0, position.clone());
+
+        let Some(IR::Call { dst, args, .. }) = block.instructions.pop() else { unreachable!() };
+
+        for (i, arg) in args.into_iter().enumerate() {
+            block.instructions.push(IR::Copy { dst: register_mapping[i + 1], src: arg });
+        }
+
+        let ending = std::mem::replace(&mut block.ending, BlockTerminator::Goto(block_mapping[&inlinee.entry]));
+        let continuation = Block { block_index: continuation_index, instructions: rest, ending };
+
+
+        for inlined in inlinee.blocks.iter() {
+            let mut inlined = inlined.clone();
+            inlined.block_index = block_mapping[&inlined.block_index];
+            inlined.instructions.retain(|x| !matches!(x, IR::SourcePosition { .. }));
+            inlined.instructions.splice(0..0, position.clone());
+
+            for instruction in inlined.instructions.iter_mut() {
+                remap_registers(instruction, &register_mapping);
+            }
+
+            inlined.ending = match inlined.ending {
+                BlockTerminator::Goto(v) => BlockTerminator::Goto(block_mapping[&v]),
+
+                BlockTerminator::SwitchBool { cond, op1, op2 } => BlockTerminator::SwitchBool {
+                    cond: register_mapping[cond.0 as usize],
+                    op1: block_mapping[&op1],
+                    op2: block_mapping[&op2],
+                },
+
+                BlockTerminator::Return => {
+                    inlined.instructions.push(IR::Copy { dst, src: register_mapping[0] });
+                    BlockTerminator::Goto(continuation_index)
+                },
+            };
+
+            self.blocks.push(inlined);
+        }
+
+        self.blocks.push(continuation);
+    }
+}
+
+
+fn remap_registers(instruction: &mut IR, mapping: &[Variable]) {
+    let remap = |reg: &mut Variable| *reg = mapping[reg.0 as usize];
+
+    match instruction {
+        IR::Swap { v1, v2 } => {
+            remap(v1);
+            remap(v2);
+            return
+        },
+
+        | IR::Copy { dst, .. }
+        | IR::CastToI8 { dst, .. }
+        | IR::CastToI16 { dst, .. }
+        | IR::CastToI32 { dst, .. }
+        | IR::CastToI64 { dst, .. }
+        | IR::CastToU8 { dst, .. }
+        | IR::CastToU16 { dst, .. }
+        | IR::CastToU32 { dst, .. }
+        | IR::CastToU64 { dst, .. }
+        | IR::CastToFloat { dst, .. }
+        | IR::Unit { dst }
+        | IR::Load { dst, .. }
+        | IR::Add { dst, .. }
+        | IR::Subtract { dst, .. }
+        | IR::Multiply { dst, .. }
+        | IR::Divide { dst, .. }
+        | IR::Modulo { dst, .. }
+        | IR::BitAnd { dst, .. }
+        | IR::BitOr { dst, .. }
+        | IR::BitXor { dst, .. }
+        | IR::ShiftLeft { dst, .. }
+        | IR::ShiftRight { dst, .. }
+        | IR::Equals { dst, .. }
+        | IR::NotEquals { dst, .. }
+        | IR::GreaterThan { dst, .. }
+        | IR::LesserThan { dst, .. }
+        | IR::GreaterEquals { dst, .. }
+        | IR::LesserEquals { dst, .. }
+        | IR::Call { dst, .. }
+        | IR::ExtCall { dst, .. }
+        | IR::UnaryNot { dst, .. }
+        | IR::UnaryNeg { dst, .. }
+        | IR::UnaryBitNot { dst, .. }
+        | IR::Struct { dst, .. }
+        | IR::AccStruct { dst, .. }
+        | IR::MakeArray { dst, .. }
+        | IR::IndexGet { dst, .. }
+        | IR::IndexSet { dst, .. }
+        | IR::SetField { dst, .. } => remap(dst),
+
+        | IR::TailCall { .. }
+        | IR::SourcePosition { .. }
+        | IR::Noop => (),
+    }
+
+    for reg in read_registers_mut(instruction) {
+        remap(reg);
+    }
+}
@@ -27,6 +27,8 @@ impl Function {
                         | IR::Copy { dst, .. }
                         | IR::Struct { dst, .. }
                         | IR::AccStruct { dst, .. }
+                        | IR::MakeArray { dst, .. }
+                        | IR::IndexGet { dst, .. }
                         | IR::SetField { dst, .. }
                         | IR::CastToI8 { dst, .. }
                         | IR::CastToI16 { dst, .. }
@@ -42,6 +44,11 @@ impl Function {
                         | IR::Multiply { dst, .. }
                         | IR::Divide { dst, .. }
                         | IR::Modulo { dst, .. }
+                        | IR::BitAnd { dst, .. }
+                        | IR::BitOr { dst, .. }
+                        | IR::BitXor { dst, .. }
+                        | IR::ShiftLeft { dst, .. }
+                        | IR::ShiftRight { dst, .. }
                         | IR::Equals { dst, .. }
                         | IR::NotEquals { dst, .. }
                         | IR::GreaterThan { dst, .. }
@@ -50,6 +57,7 @@ impl Function {
                         | IR::LesserEquals { dst, .. }
                         | IR::UnaryNot { dst, .. }
                         | IR::UnaryNeg { dst, .. }
+                        | IR::UnaryBitNot { dst, .. }
                         | IR::Load { dst, .. } => {
                             !(is_register_used_later(*dst, &b.ending, &iterator, &block_map))
                         },
@@ -74,7 +82,7 @@ impl Function {
     }
 }
 
-fn is_register_used_later(reg: Variable, term: &BlockTerminator, iter: &Iter<IR>, block_map: &HashMap<BlockIndex, (Box<[Variable]>, BlockTerminator)>) -> bool {
+pub(super) fn is_register_used_later(reg: Variable, term: &BlockTerminator, iter: &Iter<IR>, block_map: &HashMap<BlockIndex, (Box<[Variable]>, BlockTerminator)>) -> bool {
     // Function return register
     if reg == Variable(0) {
         return true
@@ -151,7 +159,7 @@ fn is_register_used_later(reg: Variable, term: &BlockTerminator, iter: &Iter<IR>
 
 
 impl Function {
-    fn generate_block_map(&self) -> HashMap<BlockIndex, (Box<[Variable]>, BlockTerminator)> {
+    pub(super) fn generate_block_map(&self) -> HashMap<BlockIndex, (Box<[Variable]>, BlockTerminator)> {
         let mut block_map : HashMap<BlockIndex, (Box<[Variable]>, BlockTerminator)> = HashMap::new();
         {
             let mut storage = vec![];
@@ -169,7 +177,7 @@ impl Function {
 }
 
 
-fn instruction_used_registers(i: &IR, storage: &mut Vec<Variable>) {
+pub(super) fn instruction_used_registers(i: &IR, storage: &mut Vec<Variable>) {
     match i {
         crate::IR::Copy { src, .. } => {
             storage.push(*src);
@@ -186,6 +194,11 @@ fn instruction_used_registers(i: &IR, storage: &mut Vec<Variable>) {
         | crate::IR::Multiply { left, right, .. }
         | crate::IR::Divide { left, right, .. }
         | crate::IR::Modulo { left, right, .. }
+        | crate::IR::BitAnd { left, right, .. }
+        | crate::IR::BitOr { left, right, .. }
+        | crate::IR::BitXor { left, right, .. }
+        | crate::IR::ShiftLeft { left, right, .. }
+        | crate::IR::ShiftRight { left, right, .. }
         | crate::IR::Equals { left, right, .. }
         | crate::IR::NotEquals { left, right, .. }
         | crate::IR::GreaterThan { left, right, .. }
@@ -207,6 +220,19 @@ fn instruction_used_registers(i: &IR, storage: &mut Vec<Variable>) {
             storage.push(*dst);
         },
 
+
+        crate::IR::IndexGet { val, index, .. } => {
+            storage.push(*val);
+            storage.push(*index);
+        },
+
+
+        crate::IR::IndexSet { dst, data, index } => {
+            storage.push(*data);
+            storage.push(*dst);
+            storage.push(*index);
+        },
+
     
         | crate::IR::CastToI8  { val, .. }
         | crate::IR::CastToI16 { val, .. }
@@ -222,7 +248,9 @@ fn instruction_used_registers(i: &IR, storage: &mut Vec<Variable>) {
 
     
         | crate::IR::ExtCall { args, .. }
+        | crate::IR::TailCall { args, .. }
         | crate::IR::Struct { fields: args, .. }
+        | crate::IR::MakeArray { elements: args, .. }
         | crate::IR::Call { args, .. } => {
             args.iter().copied().for_each(|x| storage.push(x))
         },
@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+
+use crate::{ConversionState, Function, BlockTerminator, IR, Variable};
+
+
+impl ConversionState {
+    /// Replaces every call whose result is returned right
+    /// away with a `TailCall`, which reuses the frame of the
+    /// caller so tail recursion doesn't grow the call stack.
+    ///
+    /// The blocks after a call are followed through `Goto`s
+    /// as long as they only copy its result around, which
+    /// is what the branches of an `if` expression lower to
+    pub fn lower_tail_calls(&mut self) {
+        for f in self.functions.values_mut() {
+            f.lower_tail_calls();
+        }
+    }
+}
+
+
+impl Function {
+    fn lower_tail_calls(&mut self) {
+        for index in 0..self.blocks.len() {
+            let Some(position) = self.tail_call_position(index) else { continue };
+
+            let block = &mut self.blocks[index];
+            block.instructions.truncate(position + 1);
+
+            let Some(IR::Call { id, args, .. }) = block.instructions.pop() else { unreachable!() };
+
+            block.instructions.push(IR::TailCall { id, args });
+            block.ending = BlockTerminator::Return;
+        }
+    }
+
+
+    /// The index of the last call in the block if its
+    /// result ends up in the return register without
+    /// anything else happening in between
+    fn tail_call_position(&self, index: usize) -> Option<usize> {
+        let block = &self.blocks[index];
+
+        let position = block.instructions.iter().rposition(|x| matches!(x, IR::Call { .. }))?;
+        let IR::Call { dst, .. } = block.instructions[position] else { unreachable!() };
+
+        let mut value = dst;
+        let mut instructions = block.instructions[position + 1..].iter();
+        let mut ending = &block.ending;
+        let mut visited = HashSet::from([block.block_index]);
+
+        loop {
+            for instruction in instructions {
+                match instruction {
+                    IR::Noop | IR::SourcePosition { .. } => (),
+                    IR::Copy { dst, src } if *src == value => value = *dst,
+                    _ => return None,
+                }
+            }
+
+            match ending {
+                BlockTerminator::Return => return (value == Variable(0)).then_some(position),
+
+                BlockTerminator::Goto(next) => {
+                    if !visited.insert(*next) {
+                        return None
+                    }
+
+                    let next = self.find_block(*next);
+                    instructions = next.instructions.iter();
+                    ending = &next.ending;
+                },
+
+                BlockTerminator::SwitchBool { .. } => return None,
+            }
+        }
+    }
+}
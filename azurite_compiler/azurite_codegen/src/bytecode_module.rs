@@ -1,10 +1,10 @@
 use std::collections::{HashMap, BTreeMap};
 
 use azurite_ast_to_ir::{FunctionIndex, IR, Function, BlockTerminator, ExternFunction};
-use azurite_common::Bytecode;
+use azurite_common::{Bytecode, Target};
 use common::{Data, SymbolIndex};
 
-use crate::{CodegenModule, CodeGen};
+use crate::{CodegenModule, CodeGen, Unsupported};
 
 pub struct BytecodeModule {
     bytecode: Vec<u8>,
@@ -15,13 +15,17 @@ pub struct BytecodeModule {
 
 
 impl CodegenModule for BytecodeModule {
+    const TARGET: Target = Target::Bytecode;
+
     fn codegen(
-        mut state: crate::CodeGen<Self>,
+        state: &mut crate::CodeGen<Self>,
         symbol_table: &mut common::SymbolTable, 
         externs: BTreeMap<SymbolIndex, Vec<ExternFunction>>, 
         functions: Vec<azurite_ast_to_ir::Function>,
         _: &[Data],
-    ) -> Vec<u8> {
+    ) -> Result<Vec<u8>, Unsupported> {
+        check_operand_limits(&functions)?;
+
         let mut codegen = BytecodeModule {
             function_starts: HashMap::with_capacity(functions.len()),
             function_calls: Vec::new(),
@@ -45,7 +49,7 @@ impl CodegenModule for BytecodeModule {
 
         
         for function in functions {
-            codegen.codegen_blocks(&mut state, function);
+            codegen.codegen_blocks(state, function);
         }
 
         for (function_index, start) in codegen.function_calls.iter() {
@@ -57,8 +61,29 @@ impl CodegenModule for BytecodeModule {
             codegen.bytecode[start + 4] = value[3];
         }
 
-        codegen.bytecode
+        Ok(codegen.bytecode)
+    }
+}
+
+
+/// Registers and the length of an array literal are
+/// encoded as a single byte so anything larger can't
+/// be represented in the bytecode
+fn check_operand_limits(functions: &[Function]) -> Result<(), Unsupported> {
+    if functions.iter().any(|f| f.register_lookup.len() > usize::from(u8::MAX) + 1) {
+        return Err(Unsupported("functions which need more than 256 registers"))
     }
+
+    let is_long_array = |x: &IR| matches!(x, IR::MakeArray { elements, .. } if elements.len() > usize::from(u8::MAX));
+    let has_long_array = functions.iter()
+        .flat_map(|f| f.blocks.iter())
+        .any(|b| b.instructions.iter().any(is_long_array));
+
+    if has_long_array {
+        return Err(Unsupported("array literals with more than 255 elements"))
+    }
+
+    Ok(())
 }
 
 
@@ -99,10 +124,18 @@ impl BytecodeModule {
 
     fn codegen_blocks<T: CodegenModule>(&mut self, codegen: &mut CodeGen<T>, function: Function) {
         self.function_starts.insert(function.function_index, self.bytecode.len() as u32);
-        self.emit_bytecode(Bytecode::Push);
+        codegen.function_starts.push((self.bytecode.len() as u32, function.identifier));
 
-        let temp = function.register_lookup.len() - function.arguments.len();
-        self.emit_byte(temp.try_into().unwrap());
+        // `Call` already pushes the return register and the
+        // arguments so only the locals are left to push. An
+        // empty `Push` or `Pop` is left out entirely
+        let locals = function.register_lookup.len() - function.arguments.len() - 1;
+        let frame_size = function.register_lookup.len() - 1;
+
+        if locals != 0 {
+            self.emit_bytecode(Bytecode::Push);
+            self.emit_byte(locals.try_into().unwrap());
+        }
 
 
         let mut block_starts = HashMap::with_capacity(function.blocks.len() * 2);
@@ -117,8 +150,10 @@ impl BytecodeModule {
                 BlockTerminator::Goto(_) => vec![255; 5],
                 BlockTerminator::SwitchBool { .. } => vec![255; 10],
                 BlockTerminator::Return => {
-                    self.emit_bytecode(Bytecode::Pop);
-                    self.emit_byte((function.register_lookup.len() - 1).try_into().unwrap());
+                    if frame_size != 0 {
+                        self.emit_bytecode(Bytecode::Pop);
+                        self.emit_byte(frame_size.try_into().unwrap());
+                    }
                     vec![255]
                 },
             };
@@ -220,6 +255,19 @@ impl BytecodeModule {
             },
 
             
+            IR::TailCall { id, args } => {
+                self.function_calls.push((id, self.bytecode.len()));
+
+                self.emit_bytecode(Bytecode::TailCall);
+                self.emit_u32(u32::MAX);
+                self.emit_byte(args.len() as u8);
+
+                for i in args {
+                    self.emit_byte(i.0 as u8);
+                }
+            },
+
+            
             IR::ExtCall { id: index, dst, args } => {
                 self.emit_bytecode(Bytecode::ExtCall);
                 self.emit_u32(index.0);
@@ -273,6 +321,46 @@ impl BytecodeModule {
             },
             
             
+            IR::BitAnd { dst, left, right } => {
+                self.emit_bytecode(Bytecode::BitAnd);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(left.0 as u8);
+                self.emit_byte(right.0 as u8);
+            },
+            
+            
+            IR::BitOr { dst, left, right } => {
+                self.emit_bytecode(Bytecode::BitOr);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(left.0 as u8);
+                self.emit_byte(right.0 as u8);
+            },
+            
+            
+            IR::BitXor { dst, left, right } => {
+                self.emit_bytecode(Bytecode::BitXor);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(left.0 as u8);
+                self.emit_byte(right.0 as u8);
+            },
+            
+            
+            IR::ShiftLeft { dst, left, right } => {
+                self.emit_bytecode(Bytecode::ShiftLeft);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(left.0 as u8);
+                self.emit_byte(right.0 as u8);
+            },
+            
+            
+            IR::ShiftRight { dst, left, right } => {
+                self.emit_bytecode(Bytecode::ShiftRight);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(left.0 as u8);
+                self.emit_byte(right.0 as u8);
+            },
+            
+            
             IR::Equals { dst, left, right } => {
                 self.emit_bytecode(Bytecode::Equals);
                 self.emit_byte(dst.0 as u8);
@@ -355,8 +443,46 @@ impl BytecodeModule {
                 self.emit_byte(index);
             },
 
+            IR::MakeArray { dst, elements } => {
+                self.emit_bytecode(Bytecode::MakeArray);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(elements.len().try_into().expect("checked by check_operand_limits"));
+                
+                for i in elements {
+                    self.emit_byte(i.0 as u8);
+                }
+            },
+
+            IR::IndexGet { dst, val, index } => {
+                self.emit_bytecode(Bytecode::IndexGet);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(val.0 as u8);
+                self.emit_byte(index.0 as u8);
+            },
+
+            
+            IR::IndexSet { dst, data, index } => {
+                self.emit_bytecode(Bytecode::IndexSet);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(data.0 as u8);
+                self.emit_byte(index.0 as u8);
+            },
+
             IR::Noop => (),
 
+
+            IR::SourcePosition { file, line } => {
+                let offset = self.bytecode.len() as u32;
+
+                // A line without any code of its own is
+                // covered by the next one at the same offset
+                match state.line_table.last_mut() {
+                    Some(last) if (last.1, last.2) == (file, line) => (),
+                    Some(last) if last.0 == offset => *last = (offset, file, line),
+                    _ => state.line_table.push((offset, file, line)),
+                }
+            },
+
             
             IR::UnaryNot { dst, val } => {
                 self.emit_bytecode(Bytecode::UnaryNot);
@@ -372,6 +498,13 @@ impl BytecodeModule {
             },
 
             
+            IR::UnaryBitNot { dst, val } => {
+                self.emit_bytecode(Bytecode::UnaryBitNot);
+                self.emit_byte(dst.0 as u8);
+                self.emit_byte(val.0 as u8);
+            },
+
+            
             IR::CastToI8  { dst, val } => cast_to!(CastToI8,  dst, val),
             IR::CastToI16 { dst, val } => cast_to!(CastToI16, dst, val),
             IR::CastToI32 { dst, val } => cast_to!(CastToI32, dst, val),
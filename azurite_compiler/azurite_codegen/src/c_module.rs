@@ -1,9 +1,10 @@
 use std::{fmt::Write, collections::{HashMap, BTreeMap}, sync::Arc};
 
 use azurite_ast_to_ir::{Function, Variable, IR, Block, FunctionIndex, ExternFunction};
+use azurite_common::Target;
 use common::{SymbolTable, DataType, GENERIC_START_SYMBOL, GENERIC_END_SYMBOL, SymbolIndex, Data};
 
-use crate::{CodegenModule, CodeGen};
+use crate::{CodegenModule, CodeGen, Unsupported};
 
 pub struct CModule<'a> {
     string: String,
@@ -18,13 +19,32 @@ pub struct CModule<'a> {
 
 
 impl CodegenModule for CModule<'_> {
+    const TARGET: Target = Target::C;
+
     fn codegen(
-        state: crate::CodeGen<Self>,
+        state: &mut crate::CodeGen<Self>,
         symbol_table: &mut common::SymbolTable, 
         externs: BTreeMap<SymbolIndex, Vec<ExternFunction>>, 
         functions: Vec<azurite_ast_to_ir::Function>,
         constants: &[Data],
-    ) -> Vec<u8> {
+    ) -> Result<Vec<u8>, Unsupported> {
+        // Only the standard library can declare externs
+        // which take `any` and C has no type for it
+        let takes_any = |f: &ExternFunction| f.return_type == DataType::Any || f.args.contains(&DataType::Any);
+        if externs.values().flatten().any(takes_any) {
+            return Err(Unsupported("values of any type"))
+        }
+
+        // There's no array object in the C runtime yet
+        let is_array = |x: &DataType| matches!(x, DataType::Array(_));
+        let uses_arrays = functions.iter().any(|f| f.register_lookup.iter().any(is_array))
+            || externs.values().flatten().any(|f| is_array(&f.return_type) || f.args.iter().any(is_array))
+            || state.structures.values().any(|s| s.fields.iter().any(is_array));
+
+        if uses_arrays {
+            return Err(Unsupported("arrays"))
+        }
+
         let mut codegen = CModule {
             string: String::new(),
             symbol_table,
@@ -32,7 +52,7 @@ impl CodegenModule for CModule<'_> {
             constants,
             function_map: HashMap::with_capacity(functions.len()),
             extern_function_map: Vec::with_capacity(externs.iter().map(|x| x.1.len()).sum()),
-            state: &state,
+            state,
         };
 
         let _ = writeln!(codegen.string, r#"#include <stdint.h>"#);
@@ -119,7 +139,7 @@ impl CodegenModule for CModule<'_> {
         }
         
 
-        codegen.string.into_bytes()
+        Ok(codegen.string.into_bytes())
     }
 }
 
@@ -299,6 +319,7 @@ impl CModule<'_> {
 
                             format!("new_string(\"{}\", {len})", string)
                         },
+                        Data::Char(v) => (v as u32).to_string(),
                         Data::Bool(v) => v.to_string(),
                         Data::Empty => "void".to_string(),
                     }
@@ -314,6 +335,14 @@ impl CModule<'_> {
             IR::Multiply { dst, left, right } => infix_operation!(dst, left, right, "*"),
             IR::Divide   { dst, left, right } => infix_operation!(dst, left, right, "/"),
             IR::Modulo   { dst, left, right } => infix_operation!(dst, left, right, "%"),
+            IR::BitAnd   { dst, left, right } => infix_operation!(dst, left, right, "&"),
+            IR::BitOr    { dst, left, right } => infix_operation!(dst, left, right, "|"),
+            IR::BitXor   { dst, left, right } => infix_operation!(dst, left, right, "^"),
+
+            // Shift amounts are masked to the bit width like the
+            // VM does since shifting past it is undefined in C
+            IR::ShiftLeft  { dst, left, right } => writeln!(self.string, "{}{dst} = {left} << ({right} & (sizeof({left}) * 8 - 1));", self.indentation()),
+            IR::ShiftRight { dst, left, right } => writeln!(self.string, "{}{dst} = {left} >> ({right} & (sizeof({left}) * 8 - 1));", self.indentation()),
             IR::Equals   { dst, left, right } => infix_operation!(dst, left, right, "=="),
             IR::NotEquals { dst, left, right }     => infix_operation!(dst, left, right, "!="),
             IR::GreaterThan { dst, left, right }   => infix_operation!(dst, left, right, ">"),
@@ -324,6 +353,7 @@ impl CModule<'_> {
             
             IR::UnaryNot { dst, val } => write!(self.string, "{}{dst} = !{val};", self.indentation()),
             IR::UnaryNeg { dst, val } => write!(self.string, "{}{dst} = -{val};", self.indentation()),
+            IR::UnaryBitNot { dst, val } => write!(self.string, "{}{dst} = ~{val};", self.indentation()),
             
             
             IR::Call { dst, id, args } => {
@@ -337,6 +367,14 @@ impl CModule<'_> {
             },
 
             
+            // C compilers reuse the frame for a call in tail
+            // position themselves so this is a plain call
+            IR::TailCall { id, args } => {
+                self.ir(f, IR::Call { dst: Variable(0), id, args });
+                return
+            },
+
+            
             IR::ExtCall { dst, id, args } => {
                 writeln!(
                     self.string,
@@ -378,6 +416,25 @@ impl CModule<'_> {
             },
 
 
+            IR::MakeArray { .. }
+            | IR::IndexSet { .. } => unreachable!("arrays are rejected before generating code"),
+
+
+            IR::IndexGet { dst, val, index } => {
+                let indent = self.indentation();
+                match &f.register_lookup[val.0 as usize] {
+                    DataType::String => {
+                        let _ = writeln!(self.string, "{}{dst} = string_char_at({val}, {index});", indent);
+                    },
+
+                    _ => unreachable!("arrays are rejected before generating code")
+                }
+
+                self.rc_inc(f, dst);
+                return
+            },
+
+
             IR::SetField { dst, data, index } => {
                 let structure = &f.register_lookup[dst.0 as usize];
                 let typ = match structure {
@@ -404,7 +461,7 @@ impl CModule<'_> {
             IR::CastToU64 { dst, val }   => cast_operation!(dst, val, "uint64_t"),
             IR::CastToFloat { dst, val } => cast_operation!(dst, val, "float"),
 
-            IR::Noop => return,
+            IR::Noop | IR::SourcePosition { .. } => return,
         };
     }
 }
@@ -423,9 +480,11 @@ impl CModule<'_> {
             DataType::U64 => "uint64_t".to_string(),
             DataType::Float => "float".to_string(),
             DataType::String => "string*".to_string(),
+            DataType::Char => "uint32_t".to_string(),
             DataType::Bool => "bool".to_string(),
             DataType::Empty => "unit".to_string(),
-            DataType::Any => panic!("uh oh"),
+            DataType::Any => unreachable!("externs which take any are rejected before generating code"),
+            DataType::Array(_) => unreachable!("arrays are rejected before generating code"),
             DataType::Struct(_, _) => format!("struct {}*", datatype.to_string(self.symbol_table).replace("::", "_").replace(GENERIC_START_SYMBOL, "🚀").replace(GENERIC_END_SYMBOL, "🥓")),
         }
     }
@@ -2,16 +2,28 @@
 
 pub mod bytecode_module;
 pub mod c_module;
+pub mod wasm_module;
 
 use std::{collections::{HashMap, BTreeMap}, marker::PhantomData};
 
 use azurite_ast_to_ir::{Function, Structure, ExternFunction};
+use azurite_common::Target;
 use common::{SymbolTable, SymbolIndex, Data};
 
 #[derive(Debug)]
 pub struct CodeGen<T: CodegenModule> {
     pub bytecode: Vec<u8>,
 
+    /// The bytecode offset each function starts at with
+    /// its name, in order. Only the bytecode module fills
+    /// it in as the other targets have their own symbols
+    pub function_starts: Vec<(u32, SymbolIndex)>,
+
+    /// The bytecode offset each source line starts at with
+    /// its file, in order. Only filled in by the bytecode
+    /// module for debug builds
+    pub line_table: Vec<(u32, SymbolIndex, u32)>,
+
     structures: HashMap<SymbolIndex, Structure>,
 
     phantom_data: PhantomData<T>,
@@ -19,12 +31,12 @@ pub struct CodeGen<T: CodegenModule> {
 
 impl<T: CodegenModule> CodeGen<T> {
     pub fn codegen(
-        self,
+        &mut self,
         symbol_table: &mut SymbolTable, 
         externs: BTreeMap<SymbolIndex, Vec<ExternFunction>>, 
         functions: Vec<Function>, 
         constants: &[Data],
-        ) -> Vec<u8> {
+        ) -> Result<Vec<u8>, Unsupported> {
             T::codegen(self, symbol_table, externs, functions, constants)
         }
         
@@ -33,6 +45,8 @@ impl<T: CodegenModule> CodeGen<T> {
     pub fn new(structures: HashMap<SymbolIndex, Structure>) -> Self {
         Self {
             bytecode: Vec::new(),
+            function_starts: Vec::new(),
+            line_table: Vec::new(),
             structures,
             phantom_data: PhantomData,
         }
@@ -41,11 +55,24 @@ impl<T: CodegenModule> CodeGen<T> {
 
 
 pub trait CodegenModule: Sized {
+    /// The target recorded in the metadata of the artifact
+    const TARGET: Target;
+
+    /// # Errors
+    /// - If the program uses a feature the target
+    ///   can't generate code for
     fn codegen(
-        state: CodeGen<Self>,
+        state: &mut CodeGen<Self>,
         symbol_table: &mut SymbolTable, 
         externs: BTreeMap<SymbolIndex, Vec<ExternFunction>>, 
         functions: Vec<Function>,
         constants: &[Data],
-    ) -> Vec<u8>;
+    ) -> Result<Vec<u8>, Unsupported>;
 }
+
+
+/// A feature used by the program which the target
+/// can't generate code for, named in the plural
+/// like `values of any type`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unsupported(pub &'static str);
@@ -0,0 +1,433 @@
+use std::{fmt::Write, collections::{HashMap, HashSet, BTreeMap}};
+
+use azurite_ast_to_ir::{Function, FunctionIndex, Variable, IR, BlockTerminator, ExternFunction};
+use azurite_common::Target;
+use common::{SymbolTable, DataType, SymbolIndex, Data};
+
+use crate::{CodegenModule, CodeGen, Unsupported};
+
+
+/// Lowers the IR to the WebAssembly text format.
+///
+/// Only programs which don't allocate are supported for now,
+/// programs which use structures, arrays or strings are
+/// rejected before any code is generated. Functions that
+/// are never called aren't emitted so the unused parts of
+/// the standard library don't count
+pub struct WasmModule<'a> {
+    string: String,
+    symbol_table: &'a SymbolTable,
+    constants: &'a [Data],
+    indent: usize,
+}
+
+
+impl CodegenModule for WasmModule<'_> {
+    const TARGET: Target = Target::Wasm;
+
+    fn codegen(
+        _: &mut CodeGen<Self>,
+        symbol_table: &mut SymbolTable,
+        externs: BTreeMap<SymbolIndex, Vec<ExternFunction>>,
+        functions: Vec<Function>,
+        constants: &[Data],
+    ) -> Result<Vec<u8>, Unsupported> {
+        let functions = reachable(functions);
+
+        // There's no heap on the wasm target yet
+        let unsupported = functions.iter()
+            .flat_map(|f| &f.blocks)
+            .flat_map(|b| &b.instructions)
+            .find_map(|ir| unsupported(ir, constants));
+
+        if let Some(what) = unsupported {
+            return Err(what)
+        }
+
+        let mut codegen = WasmModule {
+            string: String::new(),
+            symbol_table,
+            constants,
+            indent: 0,
+        };
+
+        codegen.line("(module");
+        codegen.indent += 1;
+
+        for (file, functions) in externs {
+            for f in functions {
+                let signature = codegen.signature(&f.args, &f.return_type, false);
+
+                codegen.line(&format!(
+                    "(import \"{}\" \"{}\" (func $e{}{signature}))",
+                    codegen.symbol_table.get(&file),
+                    codegen.symbol_table.get(&f.path),
+                    f.function_index,
+                ));
+            }
+        }
+
+        codegen.rem_euclid();
+
+        for f in functions {
+            codegen.codegen_function(f);
+        }
+
+        codegen.indent -= 1;
+        codegen.line(")");
+
+        Ok(codegen.string.into_bytes())
+    }
+}
+
+
+impl WasmModule<'_> {
+    /// Every function is a loop around a `br_table` on the
+    /// index of the next block, the code of each block comes
+    /// right after the end of the wasm block it branches to
+    fn codegen_function(&mut self, f: Function) {
+        let export = if f.function_index.0 == 0 { " (export \"main\")" } else { "" };
+        let signature = self.signature(&f.arguments, &f.return_type, true);
+
+        self.line(&format!(";; {}", self.symbol_table.get(&f.identifier)));
+        self.line(&format!("(func $f{}{export}{signature}", f.function_index));
+        self.indent += 1;
+
+        for (index, typ) in f.register_lookup.iter().enumerate() {
+            if index != 0 && index <= f.arguments.len() {
+                continue
+            }
+
+            self.line(&format!("(local {} {})", Variable(index as u32).wasm(), value_type(typ)));
+        }
+
+        self.line("(local $block i32)");
+        self.line("(local $swap_i32 i32) (local $swap_i64 i64) (local $swap_f64 f64)");
+
+
+        let positions : HashMap<_, _> = f.blocks.iter().enumerate().map(|x| (x.1.block_index, x.0)).collect();
+
+        self.line("(loop $dispatch");
+        self.indent += 1;
+
+        for position in (0..f.blocks.len()).rev() {
+            self.line(&format!("(block $b{position}"));
+            self.indent += 1;
+        }
+
+        let labels = (0..f.blocks.len()).map(|x| format!(" $b{x}")).collect::<String>();
+        self.line(&format!("(br_table{labels} (local.get $block))"));
+
+        for block in &f.blocks {
+            self.indent -= 1;
+            self.line(&format!(") ;; {}", block.block_index));
+
+            for ir in &block.instructions {
+                self.ir(&f, ir);
+            }
+
+            let goto = |position: usize| format!("(local.set $block (i32.const {position}))");
+            match block.ending {
+                BlockTerminator::Goto(v) => {
+                    self.line(&goto(positions[&v]));
+                    self.line("(br $dispatch)");
+                },
+
+                BlockTerminator::SwitchBool { cond, op1, op2 } => {
+                    self.line(&format!(
+                        "(if {} (then {}) (else {}))",
+                        cond.get(), goto(positions[&op1]), goto(positions[&op2]),
+                    ));
+                    self.line("(br $dispatch)");
+                },
+
+                BlockTerminator::Return => self.line(&format!("(return {})", Variable(0).get())),
+            }
+        }
+
+        self.indent -= 1;
+        self.line(")");
+
+        self.line("(unreachable)");
+        self.indent -= 1;
+        self.line(")");
+    }
+
+
+    #[allow(clippy::too_many_lines)]
+    fn ir(&mut self, f: &Function, ir: &IR) {
+        let typ = |v: &Variable| &f.register_lookup[v.0 as usize];
+
+        macro_rules! set {
+            ($dst: expr, $value: expr) => {
+                self.line(&format!("(local.set {} {})", $dst.wasm(), $value))
+            }
+        }
+
+        macro_rules! infix_operation {
+            ($dst: expr, $left: expr, $right: expr, $op: literal) => {{
+                let value = format!("({}.{} {} {})", value_type(typ($left)), $op, $left.get(), $right.get());
+                set!($dst, wrap(typ($dst), value))
+            }}
+        }
+
+        // Operations which depend on the signedness
+        // of the operands, floats don't have a suffix
+        macro_rules! signed_operation {
+            ($dst: expr, $left: expr, $right: expr, $op: literal) => {{
+                let suffix = sign_suffix(typ($left));
+                let value = format!("({}.{}{suffix} {} {})", value_type(typ($left)), $op, $left.get(), $right.get());
+                set!($dst, wrap(typ($dst), value))
+            }}
+        }
+
+        macro_rules! cast {
+            ($dst: expr, $val: expr) => {
+                set!($dst, cast(typ($val), typ($dst), $val.get()))
+            }
+        }
+
+        match ir {
+            IR::Copy { dst, src } => set!(dst, src.get()),
+
+            IR::Swap { v1, v2 } => {
+                let temp = format!("$swap_{}", value_type(typ(v1)));
+                self.line(&format!("(local.set {temp} {})", v1.get()));
+                set!(v1, v2.get());
+                set!(v2, format!("(local.get {temp})"));
+            },
+
+            IR::Load { dst, data } => match self.constants[*data as usize].clone() {
+                Data::I8(v)  => set!(dst, format!("(i32.const {v})")),
+                Data::I16(v) => set!(dst, format!("(i32.const {v})")),
+                Data::I32(v) => set!(dst, format!("(i32.const {v})")),
+                Data::I64(v) => set!(dst, format!("(i64.const {v})")),
+                Data::U8(v)  => set!(dst, format!("(i32.const {v})")),
+                Data::U16(v) => set!(dst, format!("(i32.const {v})")),
+                Data::U32(v) => set!(dst, format!("(i32.const {v})")),
+                Data::U64(v) => set!(dst, format!("(i64.const {v})")),
+                Data::Float(v) if v.is_nan() => set!(dst, "(f64.const nan)"),
+                Data::Float(v) => set!(dst, format!("(f64.const {v:?})")),
+                Data::Char(v) => set!(dst, format!("(i32.const {})", v as u32)),
+                Data::Bool(v) => set!(dst, format!("(i32.const {})", u8::from(v))),
+
+                Data::String(_) => unreachable!("strings are rejected before lowering"),
+                Data::Empty => set!(dst, "(i32.const 0)"),
+            },
+
+            IR::Unit { dst } => set!(dst, "(i32.const 0)"),
+
+            IR::Add { dst, left, right } => infix_operation!(dst, left, right, "add"),
+            IR::Subtract { dst, left, right } => infix_operation!(dst, left, right, "sub"),
+            IR::Multiply { dst, left, right } => infix_operation!(dst, left, right, "mul"),
+            IR::BitAnd { dst, left, right } => infix_operation!(dst, left, right, "and"),
+            IR::BitOr { dst, left, right } => infix_operation!(dst, left, right, "or"),
+            IR::BitXor { dst, left, right } => infix_operation!(dst, left, right, "xor"),
+            IR::ShiftLeft { dst, left, right } => infix_operation!(dst, left, right, "shl"),
+            IR::Equals { dst, left, right } => infix_operation!(dst, left, right, "eq"),
+            IR::NotEquals { dst, left, right } => infix_operation!(dst, left, right, "ne"),
+
+            IR::Divide { dst, left, right } => signed_operation!(dst, left, right, "div"),
+            IR::ShiftRight { dst, left, right } => signed_operation!(dst, left, right, "shr"),
+            IR::GreaterThan { dst, left, right } => signed_operation!(dst, left, right, "gt"),
+            IR::LesserThan { dst, left, right } => signed_operation!(dst, left, right, "lt"),
+            IR::GreaterEquals { dst, left, right } => signed_operation!(dst, left, right, "ge"),
+            IR::LesserEquals { dst, left, right } => signed_operation!(dst, left, right, "le"),
+
+            IR::Modulo { dst, left, right } => {
+                if *typ(left) == DataType::Float {
+                    set!(dst, format!("(call $rem_euclid_f64 {} {})", left.get(), right.get()))
+                } else {
+                    signed_operation!(dst, left, right, "rem")
+                }
+            },
+
+            IR::UnaryNot { dst, val } => set!(dst, format!("(i32.eqz {})", val.get())),
+
+            IR::UnaryNeg { dst, val } => match value_type(typ(val)) {
+                "f64" => set!(dst, format!("(f64.neg {})", val.get())),
+                t => set!(dst, wrap(typ(dst), format!("({t}.sub ({t}.const 0) {})", val.get()))),
+            },
+
+            IR::UnaryBitNot { dst, val } => {
+                let t = value_type(typ(val));
+                set!(dst, wrap(typ(dst), format!("({t}.xor {} ({t}.const -1))", val.get())))
+            },
+
+            IR::Call { dst, id, args } => {
+                set!(dst, format!("(call $f{id}{})", args.iter().map(|x| format!(" {}", x.get())).collect::<String>()))
+            },
+
+            IR::ExtCall { dst, id, args } => {
+                set!(dst, format!("(call $e{id}{})", args.iter().map(|x| format!(" {}", x.get())).collect::<String>()))
+            },
+
+            IR::TailCall { id, args } => {
+                self.line(&format!("(return_call $f{id}{})", args.iter().map(|x| format!(" {}", x.get())).collect::<String>()))
+            },
+
+            IR::CastToI8 { dst, val }
+            | IR::CastToI16 { dst, val }
+            | IR::CastToI32 { dst, val }
+            | IR::CastToI64 { dst, val }
+            | IR::CastToU8 { dst, val }
+            | IR::CastToU16 { dst, val }
+            | IR::CastToU32 { dst, val }
+            | IR::CastToU64 { dst, val }
+            | IR::CastToFloat { dst, val } => cast!(dst, val),
+
+            IR::Struct { .. }
+            | IR::AccStruct { .. }
+            | IR::SetField { .. }
+            | IR::MakeArray { .. }
+            | IR::IndexGet { .. }
+            | IR::IndexSet { .. } => unreachable!("allocations are rejected before lowering"),
+
+            IR::Noop | IR::SourcePosition { .. } => (),
+        }
+    }
+
+
+    /// The `param` & `result` of a function's type, the
+    /// parameters are named after their registers when
+    /// `named` is set
+    fn signature(&self, arguments: &[DataType], return_type: &DataType, named: bool) -> String {
+        let mut string = String::new();
+
+        for (index, argument) in arguments.iter().enumerate() {
+            let name = if named { format!("{} ", Variable(index as u32 + 1).wasm()) } else { String::new() };
+            let _ = write!(string, " (param {name}{})", value_type(argument));
+        }
+
+        let _ = write!(string, " (result {})", value_type(return_type));
+        string
+    }
+
+
+    /// The runtime's float modulo is `f64::rem_euclid`
+    /// which wasm has no instruction for
+    fn rem_euclid(&mut self) {
+        self.line("(func $rem_euclid_f64 (param $a f64) (param $b f64) (result f64)");
+        self.indent += 1;
+        self.line("(local $r f64)");
+        self.line("(local.set $r (f64.sub (local.get $a) (f64.mul (local.get $b) (f64.trunc (f64.div (local.get $a) (local.get $b))))))");
+        self.line("(if (result f64) (f64.lt (local.get $r) (f64.const 0))");
+        self.line("    (then (f64.add (local.get $r) (f64.abs (local.get $b))))");
+        self.line("    (else (local.get $r)))");
+        self.indent -= 1;
+        self.line(")");
+    }
+
+
+    fn line(&mut self, line: &str) {
+        let _ = writeln!(self.string, "{}{line}", "  ".repeat(self.indent));
+    }
+}
+
+
+trait WasmVariable {
+    fn wasm(&self) -> String;
+    fn get(&self) -> String;
+}
+
+
+impl WasmVariable for Variable {
+    fn wasm(&self) -> String {
+        format!("${self}")
+    }
+
+
+    fn get(&self) -> String {
+        format!("(local.get ${self})")
+    }
+}
+
+
+/// Integers up to 32 bits, booleans, characters and the
+/// placeholders for unit & heap values are all `i32`s
+fn value_type(datatype: &DataType) -> &'static str {
+    match datatype {
+        DataType::I64 | DataType::U64 => "i64",
+        DataType::Float => "f64",
+        _ => "i32",
+    }
+}
+
+
+fn sign_suffix(datatype: &DataType) -> &'static str {
+    match datatype {
+        DataType::Float => "",
+        _ if datatype.is_signed_integer() => "_s",
+        _ => "_u",
+    }
+}
+
+
+/// Integers narrower than 32 bits are kept sign or zero
+/// extended in their `i32` so they wrap like the runtime
+fn wrap(datatype: &DataType, value: String) -> String {
+    match datatype {
+        DataType::I8  => format!("(i32.extend8_s {value})"),
+        DataType::I16 => format!("(i32.extend16_s {value})"),
+        DataType::U8  => format!("(i32.and {value} (i32.const 255))"),
+        DataType::U16 => format!("(i32.and {value} (i32.const 65535))"),
+        _ => value,
+    }
+}
+
+
+fn cast(from: &DataType, to: &DataType, value: String) -> String {
+    let value = match (value_type(from), value_type(to)) {
+        ("i32", "i64") => format!("(i64.extend_i32{} {value})", sign_suffix(from)),
+        ("i64", "i32") => format!("(i32.wrap_i64 {value})"),
+        (t @ ("i32" | "i64"), "f64") => format!("(f64.convert_{t}{} {value})", sign_suffix(from)),
+        ("f64", t @ ("i32" | "i64")) => format!("({t}.trunc_sat_f64{} {value})", sign_suffix(to)),
+        _ => value,
+    };
+
+    wrap(to, value)
+}
+
+
+/// The functions called directly or indirectly by
+/// the entry function, in their original order
+fn reachable(functions: Vec<Function>) -> Vec<Function> {
+    let positions : HashMap<_, _> = functions.iter().enumerate().map(|x| (x.1.function_index, x.0)).collect();
+
+    let mut reached = HashSet::from([FunctionIndex(0)]);
+    let mut stack = vec![FunctionIndex(0)];
+
+    while let Some(index) = stack.pop() {
+        let Some(&position) = positions.get(&index) else { continue };
+
+        for b in &functions[position].blocks {
+            for i in &b.instructions {
+                let (IR::Call { id, .. } | IR::TailCall { id, .. }) = i else { continue };
+
+                if reached.insert(*id) {
+                    stack.push(*id);
+                }
+            }
+        }
+    }
+
+    functions.into_iter().filter(|f| reached.contains(&f.function_index)).collect()
+}
+
+
+/// The feature an instruction needs the heap for, if any
+fn unsupported(ir: &IR, constants: &[Data]) -> Option<Unsupported> {
+    match ir {
+        IR::Load { data, .. } if matches!(constants[*data as usize], Data::String(_)) => Some(Unsupported("strings")),
+
+        IR::Struct { .. }
+        | IR::AccStruct { .. }
+        | IR::SetField { .. } => Some(Unsupported("structures")),
+
+        IR::MakeArray { .. }
+        | IR::IndexGet { .. }
+        | IR::IndexSet { .. } => Some(Unsupported("arrays")),
+
+        _ => None,
+    }
+}
@@ -0,0 +1,691 @@
+//! Longer explanations of every error and warning code,
+//! printed by `azurite explain <code>`
+
+use std::fmt::Write;
+
+pub struct Explanation {
+    pub code: u16,
+    pub title: &'static str,
+    pub description: &'static str,
+
+    /// A program which causes the error
+    pub example: Option<&'static str>,
+
+    /// How the error is usually fixed
+    pub fix: &'static str,
+}
+
+
+/// Every explanation, sorted by code
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: 1,
+        title: "Invalid Character",
+        description: "This error occurs if the lexer encounters an unknown character",
+        example: Some("var x = 5 $ 3"),
+        fix: "remove the character or put it inside a string",
+    },
+    Explanation {
+        code: 2,
+        title: "Unterminated String",
+        description: "This error occurs while lexer is working on a string and reaches the end of the file",
+        example: Some("var name = \"azurite"),
+        fix: "close the string with a `\"`",
+    },
+    Explanation {
+        code: 3,
+        title: "Corrupt Unicode Escape",
+        description: "This error occurs when encountered a unicode escape sequence without a '{'",
+        example: Some("var x = \"\\u0041\""),
+        fix: "wrap the code point in braces, e.g. `\"\\u{0041}\"`",
+    },
+    Explanation {
+        code: 4,
+        title: "Invalid Unicode Value",
+        description: "This error occurs while parsing a unicode escape sequence and the value within the brackets is not a valid base-16 character",
+        example: Some("var x = \"\\u{zz}\""),
+        fix: "only use hexadecimal digits inside the braces, e.g. `\"\\u{7a}\"`",
+    },
+    Explanation {
+        code: 5,
+        title: "Number Too Large",
+        description: "This error occurs while converting a string representation of a number to a 64 bit value. It happens if the string representation can not fit in the 64 bit space",
+        example: Some("var x = 99999999999999999999"),
+        fix: "use a smaller number or a float",
+    },
+    Explanation {
+        code: 6,
+        title: "Invalid Number For Base",
+        description: "This error occurs while parsing a number. If the provided characters are not a valid character for the given base",
+        example: Some("var x = 0b102"),
+        fix: "only use digits that are valid for the base of the number",
+    },
+    Explanation {
+        code: 7,
+        title: "Invalid Unicode Character",
+        description: "This error occurs if the value of the given unicode escape sequences does not map to an existing unicode value",
+        example: Some("var x = \"\\u{d800}\""),
+        fix: "use a code point that is a valid unicode scalar value",
+    },
+    Explanation {
+        code: 8,
+        title: "Too Many Dots",
+        description: "This error occurs if a number string has more than 1 dot",
+        example: Some("var x = 1.2.3"),
+        fix: "remove the extra dots",
+    },
+    Explanation {
+        code: 9,
+        title: "Unterminated Block Comment",
+        description: "This error occurs when the lexer reaches the end of the file while inside a block comment. Block comments can be nested so each `/*` needs its own `*/`",
+        example: Some("/* a comment"),
+        fix: "close every `/*` with a `*/`, nested comments need one each",
+    },
+    Explanation {
+        code: 10,
+        title: "Unterminated Character Literal",
+        description: "This error occurs when a character literal isn't closed with a `'` before the end of the line",
+        example: Some("var c = 'a"),
+        fix: "close the character literal with a `'`",
+    },
+    Explanation {
+        code: 11,
+        title: "Invalid Character Literal",
+        description: "This error occurs when a character literal doesn't contain exactly one character",
+        example: Some("var c = 'ab'"),
+        fix: "use a single character or a `str` for more than one",
+    },
+    Explanation {
+        code: 12,
+        title: "Invalid Number Suffix",
+        description: "This error occurs when a number is followed by a suffix that isn't one of `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64` or `f`, or when an integer suffix is used on a float",
+        example: Some("var x = 10u7"),
+        fix: "use one of `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64` or `f`",
+    },
+    Explanation {
+        code: 13,
+        title: "Number Doesn't Fit In Its Suffix Type",
+        description: "This error occurs when a number with a type suffix is out of range for that type",
+        example: Some("var x = 1_000u8"),
+        fix: "use a smaller number or a wider type, e.g. `1_000u16`",
+    },
+    Explanation {
+        code: 101,
+        title: "Unexpected Token",
+        description: "This error occurs when the parser encounters an unexpected token without syntatic structure",
+        example: Some("var x = 5 5"),
+        fix: "remove the token or add what's missing before it",
+    },
+    Explanation {
+        code: 102,
+        title: "Unexpected Token",
+        description: "This error occurs when the parser encounters an unexpected token but unlike `err#101` this comes from syntatic structure",
+        example: Some("fn foo( { }"),
+        fix: "complete the construct the token is part of",
+    },
+    Explanation {
+        code: 103,
+        title: "Invalid Assignment Value",
+        description: "This error occurs when the left side of an assignment isn't a variable, a field or an index",
+        example: Some("5 = 10"),
+        fix: "only assign to variables, fields and indexes",
+    },
+    Explanation {
+        code: 104,
+        title: "Invalid data type token",
+        description: "This error occurs when parsing a data type but encounters a non-datatype token",
+        example: Some("var x : 5 = 5"),
+        fix: "write a type like `i64`, `str` or `[bool]`",
+    },
+    Explanation {
+        code: 105,
+        title: "Invalid statement in a namespace",
+        description: "This error occurs when there is a statement that is not allowed inside a namespace",
+        example: Some("namespace math { var x = 5 }"),
+        fix: "move the statement out of the namespace, only declarations are allowed in one",
+    },
+    Explanation {
+        code: 106,
+        title: "Invalid namespaced expression",
+        description: "This error occurs when trying to do something inside a namespace",
+        example: Some("math::(5 + 5)"),
+        fix: "only access functions, structures and constants through a namespace",
+    },
+    Explanation {
+        code: 107,
+        title: "Invalid extern block",
+        description: "This error occurs when the value after the `extern` keyword isn't a string",
+        example: Some("extern foo { }"),
+        fix: "give the path of the library as a string, e.g. `extern \"foo\" { }`",
+    },
+    Explanation {
+        code: 108,
+        title: "Empty generic arguments",
+        description: "This error occurs when a type or a declaration is written with empty generic brackets, e.g. `List[]` or `fn foo[]()`. Either remove the brackets or provide the types",
+        example: Some("fn foo[]() { }"),
+        fix: "remove the brackets or list the generics",
+    },
+    Explanation {
+        code: 109,
+        title: "Duplicate generic parameter",
+        description: "This error occurs when a function or a structure declares the same generic twice, e.g. `struct Pair[T, T]`",
+        example: Some("struct Pair[T, T] { a: T, b: T }"),
+        fix: "give every generic a different name, e.g. `struct Pair[A, B]`",
+    },
+    Explanation {
+        code: 110,
+        title: "Expression nesting too deep",
+        description: "This error occurs when an expression is nested deeper than the compiler allows, e.g. thousands of parentheses or a very long chain of operators. The limit can be changed with `--max-nesting-depth=<n>`",
+        example: None,
+        fix: "split the expression into variables or raise the limit with `--max-nesting-depth=<n>`",
+    },
+    Explanation {
+        code: 111,
+        title: "Unknown loop property",
+        description: "This error occurs when `loop.` is followed by anything else than `index`, the iteration counter of the innermost loop",
+        example: Some("loop { var x = loop.count }"),
+        fix: "use `loop.index` or count the iterations in a variable",
+    },
+    Explanation {
+        code: 201,
+        title: "Invalid Type Arithmetic Operation",
+        description: "This error occurs when you try to perform an arithmetic operation between invalid types. Structs can support `+`, `-` & `*` by defining `add`, `sub` & `mul` methods which take another value of the same struct and return it",
+        example: Some("var x = 5 + \"five\""),
+        fix: "convert one side with `as` so both sides have the same type",
+    },
+    Explanation {
+        code: 202,
+        title: "Comparisson types are not the same",
+        description: "This error occurs when you try to perform a comparisson operation between values of differing types",
+        example: Some("var x = 5 == 5.0"),
+        fix: "convert one side with `as` so both sides have the same type",
+    },
+    Explanation {
+        code: 203,
+        title: "If condition is not a boolean",
+        description: "This error occurs when the condition of an if expression is not a boolean",
+        example: Some("if 5 { }"),
+        fix: "compare the value to get a `bool`, e.g. `if x != 0 { }`",
+    },
+    Explanation {
+        code: 204,
+        title: "If expression branches differ in type",
+        description: "This error occurs when the branches of an if expression return values of differing types",
+        example: Some("var x = if true { 5 } else { \"five\" }"),
+        fix: "make every branch result in the same type",
+    },
+    Explanation {
+        code: 205,
+        title: "Variable does not exist in scope",
+        description: "This error occurs when mentioning a variable which is not declared yet",
+        example: Some("var x = y"),
+        fix: "declare the variable with `var` before using it",
+    },
+    Explanation {
+        code: 206,
+        title: "Can't update a non existent variable",
+        description: "This error occurs when trying to update a variable which does not exist",
+        example: Some("y = 5"),
+        fix: "declare the variable with `var` before assigning to it",
+    },
+    Explanation {
+        code: 207,
+        title: "Variable is of differnet type",
+        description: "This error occurs when the assigned value and assigned data is of differing types",
+        example: Some("var x = 5\nx = \"five\""),
+        fix: "assign a value of the variable's type, or declare a new variable",
+    },
+    Explanation {
+        code: 208,
+        title: "Break outside of loop",
+        description: "This error occurs when there is a `break` statement outside of a loop",
+        example: Some("break"),
+        fix: "only use `break` inside `loop`, `while` or `for`",
+    },
+    Explanation {
+        code: 209,
+        title: "Continue outside of loop",
+        description: "This error occurs when there is a `continue` statement outside of a loop",
+        example: Some("continue"),
+        fix: "only use `continue` inside `loop`, `while` or `for`",
+    },
+    Explanation {
+        code: 210,
+        title: "Variable value type differs from the type hint",
+        description: "This error occurs when the declared variables assigned value differs from the type given",
+        example: Some("var x : str = 5"),
+        fix: "change the type hint or the value so they match",
+    },
+    Explanation {
+        code: 211,
+        title: "Function return value differs",
+        description: "This error occurs when the functions body returns a value that is not the functions return type",
+        example: Some("fn five(): str { 5 }"),
+        fix: "change the return type or the value the body ends with",
+    },
+    Explanation {
+        code: 212,
+        title: "Function isn't declared",
+        description: "This error occurs when trying to call a function that hasn't been declared",
+        example: Some("foo()"),
+        fix: "declare the function or `using` the file it's declared in",
+    },
+    Explanation {
+        code: 213,
+        title: "Function argument is of different type",
+        description: "This error occurs when calling a function with invalid argument types",
+        example: Some("fn double(x: i64): i64 { x * 2 }\ndouble(\"two\")"),
+        fix: "pass values of the types the function takes",
+    },
+    Explanation {
+        code: 214,
+        title: "Type doesn't exist",
+        description: "This error occurs when a type is used which isn't declared yet",
+        example: Some("var x : Foo = 5"),
+        fix: "declare the type or fix its name",
+    },
+    Explanation {
+        code: 215,
+        title: "Structure isn't declared",
+        description: "This error occurs when trying to create a structure that isn't declared",
+        example: Some("var x = Foo { a: 5 }"),
+        fix: "declare the structure or fix its name",
+    },
+    Explanation {
+        code: 216,
+        title: "Structure field doesn't exist",
+        description: "This error occurs when giving a field to a structure that doesn't have said field",
+        example: Some("struct Foo { a: i64 }\nvar x = Foo { b: 5 }"),
+        fix: "only give the fields the structure declares",
+    },
+    Explanation {
+        code: 217,
+        title: "Structure field is not of valid type",
+        description: "This error occurs when the given type and the value is not matching",
+        example: Some("struct Foo { a: i64 }\nvar x = Foo { a: \"five\" }"),
+        fix: "give every field a value of its declared type",
+    },
+    Explanation {
+        code: 218,
+        title: "Structure fields invalid",
+        description: "This error occurs when there's a field on a struct creation but that field does not exist on the struct",
+        example: Some("struct Foo { a: i64 }\nvar x = Foo { a: 5, b: 6 }"),
+        fix: "remove the fields the structure doesn't declare",
+    },
+    Explanation {
+        code: 219,
+        title: "Structure fields missing",
+        description: "This error occurs when there's a field missing",
+        example: Some("struct Foo { a: i64, b: i64 }\nvar x = Foo { a: 5 }"),
+        fix: "give a value to every field of the structure",
+    },
+    Explanation {
+        code: 220,
+        title: "Structure field doesn't exist",
+        description: "This error occurs when trying to access a field when the type of the value doesn't have said field",
+        example: Some("struct Foo { a: i64 }\nvar x = Foo { a: 5 }\nx.b"),
+        fix: "only access fields the structure declares",
+    },
+    Explanation {
+        code: 221,
+        title: "Return in main scope",
+        description: "This error occurs when there is a return statement in the root scope",
+        example: Some("return 5"),
+        fix: "only use `return` inside a function",
+    },
+    Explanation {
+        code: 222,
+        title: "Invalid return type",
+        description: "This error occurs when the return value is of a different type than the expected type",
+        example: Some("fn five(): i64 { return \"five\" }"),
+        fix: "return a value of the function's return type",
+    },
+    Explanation {
+        code: 223,
+        title: "File doesn't exist",
+        description: "This error occurs when using a file that doesn't exist",
+        example: Some("using missing_file"),
+        fix: "fix the path of the file",
+    },
+    Explanation {
+        code: 224,
+        title: "Invalid Type Order Operation",
+        description: "This error occurs when you try to perform an order operation between invalid types",
+        example: Some("var x = \"a\" < \"b\""),
+        fix: "only order integers, floats and characters of the same type",
+    },
+    Explanation {
+        code: 225,
+        title: "Invalid Type Unary Operation",
+        description: "This error occursh when you try to perform a unary operation on a type that doens't support the specific operation",
+        example: Some("var x = -true"),
+        fix: "use `!` on `bool`s, `-` on numbers and `~` on integers",
+    },
+    Explanation {
+        code: 226,
+        title: "Can only cast between primitives",
+        description: "This error occurs when you try to cast a value by using `as` that is not a primitive and whose type doesn't define a conversion with `fn as <type>(self): <type>`",
+        example: Some("struct Foo { a: i64 }\nvar x = Foo { a: 5 } as i64"),
+        fix: "cast between primitives or declare `fn as i64(self): i64` on the type",
+    },
+    Explanation {
+        code: 227,
+        title: "Duplicate function definition",
+        description: "This error occurs when you have functions of the same name inside the same scope",
+        example: Some("fn foo() { }\nfn foo() { }"),
+        fix: "rename one of the functions",
+    },
+    Explanation {
+        code: 228,
+        title: "Duplicate structure definition",
+        description: "This error occurs when you have structures of the same name inside the same scope",
+        example: Some("struct Foo { }\nstruct Foo { }"),
+        fix: "rename one of the structures",
+    },
+    Explanation {
+        code: 229,
+        title: "Structure has no generic parameters",
+        description: "This error occurs when generic arguments are given to a structure that doesn't declare any",
+        example: Some("struct Foo { a: i64 }\nvar x : Foo[i64] = Foo { a: 5 }"),
+        fix: "remove the generic arguments",
+    },
+    Explanation {
+        code: 230,
+        title: "Structure exists but it has generic parameters",
+        description: "This error occurs when a structure that declares generics is used without giving them",
+        example: Some("struct Box[T] { value: T }\nvar x : Box = Box { value: 5 }"),
+        fix: "give the structure its generic arguments, e.g. `Box[i64]`",
+    },
+    Explanation {
+        code: 231,
+        title: "Function has no generic parameters",
+        description: "This error occurs when generic arguments are given to a function that doesn't declare any",
+        example: Some("fn foo() { }\nfoo[i64]()"),
+        fix: "remove the generic arguments",
+    },
+    Explanation {
+        code: 232,
+        title: "Function exists but it has generic parameters",
+        description: "This error occurs when a function that declares generics is used without its generics being given or inferred",
+        example: Some("fn id[T](x: T): T { x }\nvar y = id"),
+        fix: "give the function its generic arguments or call it so they can be inferred",
+    },
+    Explanation {
+        code: 233,
+        title: "Label doesn't exist",
+        description: "This error occurs when a `break` or a `continue` refers to a label that no enclosing loop has",
+        example: Some("loop { break 'outer }"),
+        fix: "label an enclosing loop with the name, e.g. `'outer: loop { }`",
+    },
+    Explanation {
+        code: 234,
+        title: "Can't infer the type of an empty array",
+        description: "This error occurs when an empty array literal `[]` is used where its element type isn't known, e.g. `var x = []`. Give it a type hint like `var x : [i64] = []`",
+        example: Some("var x = []"),
+        fix: "give the array a type hint, e.g. `var x : [i64] = []`",
+    },
+    Explanation {
+        code: 235,
+        title: "Array elements are of different types",
+        description: "This error occurs when the elements of an array literal don't all have the same type as the first element",
+        example: Some("var x = [1, \"two\"]"),
+        fix: "make every element the same type as the first one",
+    },
+    Explanation {
+        code: 236,
+        title: "Value can't be indexed",
+        description: "This error occurs when using `value[index]` on a value that is neither an array nor a `str`",
+        example: Some("var x = 5\nx[0]"),
+        fix: "only index arrays and `str`s",
+    },
+    Explanation {
+        code: 237,
+        title: "Index must be an integer",
+        description: "This error occurs when the index in `value[index]` isn't of an integer type",
+        example: Some("var x = [1, 2]\nx[1.0]"),
+        fix: "index with an integer, e.g. `x[1]`",
+    },
+    Explanation {
+        code: 238,
+        title: "Can't assign to an index of this value",
+        description: "This error occurs when using `value[index] = x` on a value that isn't an array. Strings can be indexed but not assigned to",
+        example: Some("var x = \"abc\"\nx[0] = 'b'"),
+        fix: "only assign to indexes of arrays, build a new `str` instead",
+    },
+    Explanation {
+        code: 239,
+        title: "Invalid entry result type",
+        description: "This error occurs when the root of the program results in a value that is neither `()` nor an integer exit code",
+        example: Some("\"done\""),
+        fix: "end the program with `()` or an integer exit code",
+    },
+    Explanation {
+        code: 240,
+        title: "Range bounds must be integers",
+        description: "This error occurs when the bounds of a range like `0..10` in a `for` loop aren't of an integer type",
+        example: Some("for i in 0.0..10.0 { }"),
+        fix: "use integers as the bounds of the range",
+    },
+    Explanation {
+        code: 241,
+        title: "Range bounds are of different types",
+        description: "This error occurs when the start and the end of a range have different integer types, e.g. `0u8..n` where `n` is an `i64`",
+        example: Some("var n = 10\nfor i in 0u8..n { }"),
+        fix: "cast one of the bounds so both have the same type",
+    },
+    Explanation {
+        code: 242,
+        title: "Can only match on integers and bools",
+        description: "This error occurs when the value of a `match` isn't an integer or a `bool`",
+        example: Some("match \"a\" { _ => 0 }"),
+        fix: "match on an integer or a `bool`, or use `if` for other values",
+    },
+    Explanation {
+        code: 243,
+        title: "Match pattern is of a different type",
+        description: "This error occurs when a pattern of a `match` arm doesn't have the type of the matched value, e.g. `true` when matching on an `i64`",
+        example: Some("match 5 { true => 0, _ => 1 }"),
+        fix: "use patterns of the matched value's type",
+    },
+    Explanation {
+        code: 244,
+        title: "Match arms don't return the same type",
+        description: "This error occurs when the arms of a `match` result in values of different types. The first arm decides the type",
+        example: Some("match 5 { 0 => 0, _ => \"many\" }"),
+        fix: "make every arm result in the same type as the first one",
+    },
+    Explanation {
+        code: 245,
+        title: "Match doesn't cover every value",
+        description: "This error occurs when a `match` has no `_` arm. Only a `match` on a `bool` with both a `true` and a `false` arm can leave it out",
+        example: Some("match 5 { 0 => 0, 1 => 1 }"),
+        fix: "add a `_` arm",
+    },
+    Explanation {
+        code: 246,
+        title: "Match pattern is already covered",
+        description: "This error occurs when two arms of a `match` have the same pattern, the second one could never run",
+        example: Some("match 5 { 0 => 0, 0 => 1, _ => 2 }"),
+        fix: "remove or change the repeated pattern",
+    },
+    Explanation {
+        code: 247,
+        title: "Invalid type bitwise operation",
+        description: "This error occurs when the operands of `&`, `|` or `^` aren't integers of the same type",
+        example: Some("var x = 5 & 5u8"),
+        fix: "cast one side so both sides are integers of the same type",
+    },
+    Explanation {
+        code: 248,
+        title: "Expression nesting too deep",
+        description: "This error occurs when the analyzed code, including the code the compiler generates from loops & generic functions, is nested deeper than the compiler allows. The limit can be changed with `--max-nesting-depth=<n>`",
+        example: None,
+        fix: "split the code into smaller functions or raise the limit with `--max-nesting-depth=<n>`",
+    },
+    Explanation {
+        code: 249,
+        title: "Invalid type shift operation",
+        description: "This error occurs when either side of `<<` or `>>` isn't an integer. The result has the type of the left side and the shift amount is masked to its bit width, so `1u8 << 9` is `2u8`",
+        example: Some("var x = 1.0 << 2"),
+        fix: "only shift integers by integers",
+    },
+    Explanation {
+        code: 250,
+        title: "Constant initializer isn't constant",
+        description: "This error occurs when the value of a `const` uses anything other than literals, other constants, casts & operators, e.g. a function call or a variable",
+        example: Some("var y = 5\nconst X = y"),
+        fix: "only use literals, other constants, casts and operators in a `const`",
+    },
+    Explanation {
+        code: 251,
+        title: "Constant evaluation failed",
+        description: "This error occurs when evaluating the value of a `const` overflows its type or divides by zero",
+        example: Some("const X : u8 = 255u8 + 1u8"),
+        fix: "change the value so it fits its type and doesn't divide by zero",
+    },
+    Explanation {
+        code: 252,
+        title: "Duplicate constant definition",
+        description: "This error occurs when a constant with the same name is already defined in the same scope",
+        example: Some("const X = 5\nconst X = 6"),
+        fix: "rename one of the constants",
+    },
+    Explanation {
+        code: 253,
+        title: "Integer literal out of range",
+        description: "This error occurs when an integer literal is used as a type it doesn't fit in, e.g. `var x : u8 = 300`. A negated literal is checked as a whole so `var x : i8 = -128` is fine",
+        example: Some("var x : u8 = 300"),
+        fix: "use a value that fits the type or a wider type",
+    },
+    Explanation {
+        code: 254,
+        title: "Recursive structure",
+        description: "This error occurs when a structure contains itself by value, either directly like `struct Node { next: Node }` or through other structures. Such a value could never be created. Storing the recursive field in an array, e.g. `children: [Node]`, breaks the cycle",
+        example: Some("struct Node { value: i64, next: Node }"),
+        fix: "store the recursive field in an array, e.g. `next: [Node]`",
+    },
+    Explanation {
+        code: 255,
+        title: "Duplicate enum definition",
+        description: "This error occurs when an enum has the same name as another enum or structure in the same scope",
+        example: Some("enum Colour { Red }\nenum Colour { Blue }"),
+        fix: "rename one of the enums",
+    },
+    Explanation {
+        code: 256,
+        title: "Duplicate enum variant",
+        description: "This error occurs when an enum has two variants with the same name",
+        example: Some("enum Colour { Red, Red }"),
+        fix: "rename one of the variants",
+    },
+    Explanation {
+        code: 257,
+        title: "Duplicate enum discriminant",
+        description: "This error occurs when two variants of an enum have the same value. A variant without an explicit `= N` is one more than the previous variant, the first one being `0`, so `enum E { A = 1, B = 0, C }` is an error since `C` is `1` too",
+        example: Some("enum E { A = 1, B = 0, C }"),
+        fix: "give the variants different values",
+    },
+    Explanation {
+        code: 258,
+        title: "Match pattern isn't constant",
+        description: "This error occurs when a pattern of a `match` arm names something other than a constant or an enum variant, e.g. a variable",
+        example: Some("var x = 5\nmatch 5 { x => 0, _ => 1 }"),
+        fix: "use a literal, a constant or an enum variant, or an `if` to compare with a variable",
+    },
+    Explanation {
+        code: 259,
+        title: "Conflicting return types",
+        description: "This error occurs when a function without a return type returns values of different types, e.g. an `i64` from a `return` and a `str` from the end of its body. The return type is inferred from the first `return` or, if there's none, from the end of the body",
+        example: Some("fn foo(x: bool) { if x { return 5 }\n\"five\" }"),
+        fix: "return the same type everywhere or write the return type out",
+    },
+    Explanation {
+        code: 260,
+        title: "Recursive function without a return type",
+        description: "This error occurs when a function without a return type calls itself, directly or through other functions without a return type. Its return type can't be inferred so it has to be written out",
+        example: Some("fn count(n: i64) { if n == 0 { 0 } else { count(n - 1) } }"),
+        fix: "write the return type out, e.g. `fn count(n: i64): i64`",
+    },
+    Explanation {
+        code: 261,
+        title: "Loop index outside of loop",
+        description: "This error occurs when `loop.index` is used outside of a `loop`, `while` or `for`. A function declared inside a loop doesn't see the loop's counter either",
+        example: Some("var x = loop.index"),
+        fix: "only use `loop.index` inside a loop or pass it to the function as an argument",
+    },
+    Explanation {
+        code: 301,
+        title: "Label shadows an outer label",
+        description: "This warning occurs when a loop is labeled with the same label as a loop it is nested in. `break` and `continue` will refer to the innermost loop",
+        example: Some("'a: loop { 'a: loop { break 'a } }"),
+        fix: "give the inner loop a different label",
+    },
+    Explanation {
+        code: 302,
+        title: "Unused variable",
+        description: "This warning occurs when a variable declared with `var` is never read. Prefix the name with an underscore, e.g. `_value`, to silence it",
+        example: Some("var x = 5"),
+        fix: "use the variable, remove it or prefix its name with an underscore",
+    },
+    Explanation {
+        code: 303,
+        title: "Comparing floats for equality",
+        description: "This warning occurs when `==` or `!=` is used on two floats. Rounding errors can make floats that should be equal differ slightly so `approx_eq(a, b, epsilon)` is usually what you want",
+        example: Some("var x = 0.1 + 0.2 == 0.3"),
+        fix: "compare within an epsilon with `approx_eq(a, b, epsilon)`",
+    },
+    Explanation {
+        code: 304,
+        title: "Unreachable code",
+        description: "This warning occurs when code follows a `return`, `break` or `continue` in the same block, or an `if` whose every branch ends in one, so it can never run",
+        example: Some("fn foo(): i64 { return 5\n6 }"),
+        fix: "remove the code or the statement that makes it unreachable",
+    },
+    Explanation {
+        code: 401,
+        title: "Unsupported by the target",
+        description: "This error occurs when the program uses a feature which the target it's compiled for can't generate code for yet, like values of any type or arrays on the C target, strings, structures & arrays on the wasm target or array literals with more than 255 elements on the bytecode target",
+        example: None,
+        fix: "compile for the bytecode target or avoid using the feature",
+    },
+];
+
+
+/// Returns the explanation of `code`
+pub fn explanation(code: u16) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .binary_search_by_key(&code, |x| x.code)
+        .ok()
+        .map(|x| &EXPLANATIONS[x])
+}
+
+
+/// Renders the explanation of a code written either as
+/// a number like `211` or prefixed like `E0211`
+pub fn explain(code: &str) -> String {
+    let number = code.strip_prefix(['E', 'e', 'W', 'w']).unwrap_or(code);
+
+    let Some(explanation) = number.parse().ok().and_then(explanation) else {
+        return format!("no such error code '{code}'\n")
+    };
+
+    let kind = if (300..400).contains(&explanation.code) { "warning" } else { "error" };
+
+    let mut string = String::new();
+    let _ = writeln!(string, "{kind}[{:>03}]: {}", explanation.code, explanation.title);
+    let _ = writeln!(string);
+    let _ = writeln!(string, "{}", explanation.description);
+
+    if let Some(example) = explanation.example {
+        let _ = writeln!(string);
+        let _ = writeln!(string, "for example:");
+        for line in example.lines() {
+            let _ = writeln!(string, "    {line}");
+        }
+    }
+
+    let _ = writeln!(string);
+    let _ = writeln!(string, "to fix it, {}", explanation.fix);
+
+    string
+}
@@ -1,4 +1,5 @@
 mod utils;
+pub mod explanations;
 
 use std::{fmt::Write, collections::HashMap};
 
@@ -25,6 +26,84 @@ impl Error {
     pub fn build(self, files: &HashMap<SymbolIndex, (String, String)>) -> String {
         self.body.into_iter().map(|x| x.build(files)).collect()
     }
+
+
+    /// The most serious severity of the diagnostics
+    /// in the error, `None` if there are none
+    pub fn severity(&self) -> Option<Severity> {
+        self.body.iter()
+            .filter_map(|x| match x {
+                ErrorOption::Title { severity, .. } => Some(*severity),
+                _ => None,
+            })
+            .max()
+    }
+
+
+    /// Whether the error should stop the compilation
+    pub fn is_fatal(&self) -> bool {
+        self.severity() == Some(Severity::Error)
+    }
+
+
+    /// Builds the error as a JSON array for tools, with an object
+    /// for each diagnostic holding its id, message & highlights.
+    /// Highlights are given as byte offsets into their file
+    ///
+    /// ```json
+    /// [{"severity":"error","id":205,"message":"variable doesn't exist",
+    ///   "highlights":[{"file":"main.az","start":4,"end":7,"note":null}]}]
+    /// ```
+    pub fn build_json(&self, files: &HashMap<SymbolIndex, (String, String)>) -> String {
+        let mut string = String::from("[");
+        let mut in_diagnostic = false;
+        let mut first_highlight = true;
+
+        for option in &self.body {
+            match option {
+                ErrorOption::Title { id, message, severity } => {
+                    if in_diagnostic {
+                        string.push_str("]},");
+                    }
+
+                    let _ = write!(string, "{{\"severity\":\"{}\",\"id\":{id},\"message\":{},\"highlights\":[", severity.name(), utils::json_string(message));
+
+                    in_diagnostic = true;
+                    first_highlight = true;
+                },
+
+
+                ErrorOption::Highlight { range, note, file, .. } => {
+                    // Highlights always follow the title
+                    // of the diagnostic they belong to
+                    if !in_diagnostic {
+                        continue
+                    }
+
+                    if !first_highlight {
+                        string.push(',');
+                    }
+
+                    let file_name = files.get(file).map_or("", |x| x.0.as_str());
+                    let note = note.as_deref().map_or_else(|| String::from("null"), utils::json_string);
+
+                    let _ = write!(string, "{{\"file\":{},\"start\":{},\"end\":{},\"note\":{note}}}", utils::json_string(file_name), range.start, range.end);
+
+                    first_highlight = false;
+                },
+
+
+                ErrorOption::Text(_) => (),
+            }
+        }
+
+        if in_diagnostic {
+            string.push_str("]}");
+        }
+
+        string.push(']');
+        string
+    }
 }
 
 pub trait CombineIntoError {
@@ -53,6 +132,15 @@ impl CombineIntoError for Vec<Error> {
 #[derive(Debug, PartialEq)]
 pub enum ErrorOption {
     Text(String),
+
+    /// The first line of a diagnostic, such as
+    /// `error[205] variable doesn't exist`
+    Title {
+        id: usize,
+        message: String,
+        severity: Severity,
+    },
+
     Highlight {
         range: SourceRange,
         note: Option<String>,
@@ -62,16 +150,50 @@ pub enum ErrorOption {
     }
 }
 
+/// How serious a diagnostic is, only errors
+/// stop the compilation
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// The name used in the title of a diagnostic
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Note    => "note",
+            Severity::Warning => "warning",
+            Severity::Error   => "error",
+        }
+    }
+
+
+    /// The colour of the title & of the highlights
+    /// unless one is explicitly given
+    pub fn colour(self) -> Color {
+        match self {
+            Severity::Note    => Color::BrightCyan,
+            Severity::Warning => Color::BrightYellow,
+            Severity::Error   => Color::BrightRed,
+        }
+    }
+}
+
+
 pub trait ErrorBuilder {
     fn highlight(self, range: SourceRange) -> Highlight<Self> 
     where
         Self: Sized
     {
+        let colour = self.default_colour();
+
         Highlight {
             parent: self,
             range,
             note: None,
-            colour: Color::BrightRed,
+            colour,
         }
     }
 
@@ -115,6 +237,13 @@ pub trait ErrorBuilder {
 
 
     fn file(&self) -> SymbolIndex;
+
+
+    /// The colour highlights use unless
+    /// one is explicitly given
+    fn default_colour(&self) -> Color {
+        Color::BrightRed
+    }
 }
 
 impl ErrorOption {
@@ -123,18 +252,38 @@ impl ErrorOption {
             ErrorOption::Text(text) => text,
 
 
+            ErrorOption::Title { id, message, severity } => {
+                let mut string = String::new();
+
+                let title = format!("{}[{id:>03}]", severity.name());
+                let _ = write!(string, "{}", title.color(severity.colour()).bold());
+                let _ = writeln!(string, " {}", message.white().bold());
+
+                string
+            },
+
+
             ErrorOption::Highlight { range, note, colour, file } => {
                 let mut string = String::new();
 
                 let (file_name, source) = files.get(&file).unwrap();
 
-                let start_line = utils::line_at_index(source, range.start).unwrap().1;
-                let end_line   = utils::line_at_index(source, range.end - 1).unwrap().1;
+                // Ranges built from tokens already know their lines,
+                // only the ones made by hand need to scan the source
+                let (start_line, start_of_line, end_line, start_of_end) = match range.position {
+                    Some((start, end)) => (start.line, range.start - start.column, end.line, range.end - end.column),
+                    None => {
+                        let start_line = utils::line_at_index(source, range.start).unwrap().1;
+                        let end_line   = utils::line_at_index(source, range.end - 1).unwrap().1;
+
+                        (start_line, utils::start_of_line(source, start_line), end_line, utils::start_of_line(source, end_line))
+                    },
+                };
                 let line_size  = end_line.to_string().len();
 
                 
                 {
-                    let _ = writeln!(string, "{}{} {}:{}:{}", " ".repeat(line_size), "-->".color(ORANGE), file_name, start_line, range.start - utils::start_of_line(source, start_line));
+                    let _ = writeln!(string, "{}{} {}:{}:{}", " ".repeat(line_size), "-->".color(ORANGE), file_name, start_line, range.start - start_of_line);
                     let _ = write!(string, "{} {}", " ".repeat(line_size), "|".color(ORANGE));
                 }
 
@@ -142,14 +291,13 @@ impl ErrorOption {
                 // println!("{}", source.as_bytes().len());
                 // writeln!(string, "{}", &source[range.start..range.end]);
                 
-               for (line_number, line) in source.lines().enumerate().take(end_line + 1).skip(start_line) {
+               let lines = source[start_of_line..].lines().take((end_line + 1).saturating_sub(start_line));
+               for (line_number, line) in lines.enumerate().map(|(index, line)| (start_line + index, line)) {
                     let _ = writeln!(string);
 
                     let _ = writeln!(string, "{:>w$} {} {}", line_number.to_string().color(ORANGE), "|".color(ORANGE), line, w = line_size);
 
                     if line_number == start_line {
-                        let start_of_line = utils::start_of_line(source, line_number);
-
                         let _ = write!(string, "{:>w$} {} ",
                             " ".repeat(line_number.to_string().len()),
                             "|".color(ORANGE),
@@ -182,10 +330,7 @@ impl ErrorOption {
                         
                     } else if line_number == end_line {
                         let _ = write!(string, "{}",
-                            "^".repeat({
-                                let start_of_end = utils::start_of_line(source, end_line);
-                                range.end - start_of_end
-                            }).color(colour),
+                            "^".repeat(range.end - start_of_end).color(colour),
                         );
 
                        
@@ -232,6 +377,11 @@ impl<T: ErrorBuilder> ErrorBuilder for Highlight<T> {
     fn file(&self) -> SymbolIndex {
         self.parent.file()
     }
+
+
+    fn default_colour(&self) -> Color {
+        self.parent.default_colour()
+    }
 }
 
 impl<T: ErrorBuilder> Highlight<T> {
@@ -265,34 +415,38 @@ impl<T: ErrorBuilder> ErrorBuilder for Text<T> {
     fn file(&self) -> SymbolIndex {
         self.parent.file()
     }
+
+
+    fn default_colour(&self) -> Color {
+        self.parent.default_colour()
+    }
 }
 
 
-pub struct CompilerError<'a>(usize, &'a str, SymbolIndex);
+/// A diagnostic, which only stops the compilation
+/// if its severity is `Severity::Error`
+pub struct CompilerError<'a>(usize, &'a str, SymbolIndex, Severity);
 
 
 impl CompilerError<'_> {
-    pub fn new(file: SymbolIndex, id: usize, text: &str) -> CompilerError {
-        CompilerError(id, text, file)
+    pub fn new(file: SymbolIndex, id: usize, text: &str, severity: Severity) -> CompilerError {
+        CompilerError(id, text, file, severity)
     }
 }
 
 
 impl ErrorBuilder for CompilerError<'_> {
     fn flatten(self, vec: &mut Vec<ErrorOption>) {
-        let mut string = String::new();
-
-        let _ = write!(string, "error[{:>03}]", self.0);
-
-        string = string.red().bold().to_string();
-                
-        let _ = writeln!(string, " {}", self.1.white().bold());
-        
-        vec.push(ErrorOption::Text(string))
+        vec.push(ErrorOption::Title { id: self.0, message: self.1.to_string(), severity: self.3 })
     }
 
     
     fn file(&self) -> SymbolIndex {
         self.2
     }
-}
\ No newline at end of file
+
+
+    fn default_colour(&self) -> Color {
+        self.3.colour()
+    }
+}
@@ -28,4 +28,26 @@ pub fn start_of_line(value: &str, line_number: usize) -> usize {
     }
 
     counter
-}
\ No newline at end of file
+}
+
+
+/// Quotes & escapes `value` as a JSON string
+pub fn json_string(value: &str) -> String {
+    let mut string = String::with_capacity(value.len() + 2);
+    string.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"'  => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            c if (c as u32) < 0x20 => string.push_str(&format!("\\u{:04x}", c as u32)),
+            c => string.push(c),
+        }
+    }
+
+    string.push('"');
+    string
+}
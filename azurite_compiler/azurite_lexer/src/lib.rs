@@ -1,7 +1,7 @@
 use std::str::Chars;
 
-use azurite_errors::{CompilerError, ErrorBuilder, Error, CombineIntoError};
-use common::{SymbolTable, SymbolIndex, SourceRange};
+use azurite_errors::{CompilerError, ErrorBuilder, Error, CombineIntoError, Severity};
+use common::{SymbolTable, SymbolIndex, SourceRange, Position};
 
 mod tests;
 
@@ -33,17 +33,21 @@ pub enum TokenKind {
     Minus,
     Star,
     Caret,
+    Ampersand,
+    Pipe,
     Colon,
     DoubleColon,
     Comma,
     Dot,
     Bang,
+    Tilde,
     Equals,
     Underscore,
 
     Literal(Literal),
     Keyword(Keyword),
     Identifier(SymbolIndex),
+    Label(SymbolIndex),
 
     LesserEquals,
     GreaterEquals,
@@ -56,6 +60,14 @@ pub enum TokenKind {
     SubEquals,
     MulEquals,
     DivEquals,
+    ModEquals,
+
+    DoubleLeftAngle,
+    DoubleRightAngle,
+
+    DoubleDot,
+    DoubleDotEquals,
+    FatArrow,
     
     EndOfFile,
 }
@@ -65,7 +77,19 @@ pub enum TokenKind {
 pub enum Literal {
     Integer(i64),
     Float(f64),
+
+    // Integers with a type suffix, e.g `10u8`
+    I8 (i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8 (u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+
     String(SymbolIndex),
+    Char(char),
     Bool(bool),
 }
 
@@ -82,6 +106,7 @@ pub enum Keyword {
     Else,
     While,
     For,
+    In,
     Loop,
     Break,
     Continue,
@@ -89,6 +114,8 @@ pub enum Keyword {
     Return,
     As,
     Const,
+    Match,
+    Enum,
 }
 
 
@@ -101,6 +128,10 @@ struct Lexer<'a> {
     current: Option<char>,
     stale: bool,
 
+    // The byte offset every line starts at, a new
+    // line is added each time `advance` passes a '\n'
+    line_starts: Vec<usize>,
+
     string_storage: String,
     symbol_table: &'a mut SymbolTable,
     file: SymbolIndex,
@@ -141,6 +172,7 @@ pub fn lex(
         stale: false,
         string_storage: String::with_capacity(128),
         character_index: 0,
+        line_starts: vec![0],
         symbol_table,
         file,
     };
@@ -186,6 +218,12 @@ pub fn lex(
                     }
                     continue;
                 }
+                Some('*') => {
+                    if let Err(error) = lexer.block_comment() {
+                        errors.push(error);
+                    }
+                    continue;
+                }
                 Some('=') => {
                     lexer.advance();
                     TokenKind::DivEquals
@@ -195,32 +233,54 @@ pub fn lex(
 
             'a'..='z' | 'A'..='Z' => lexer.identifier(),
 
+            '\'' if matches!(lexer.peek(), Some('a'..='z' | 'A'..='Z' | '_'))
+                && lexer.peek_nth(1) != Some('\'') => lexer.label(),
+
+            '\'' => match lexer.character() {
+                Ok(value) => TokenKind::Literal(value),
+                Err(mut error) => {
+                    errors.append(&mut error);
+                    continue;
+                }
+            },
+
             '(' => TokenKind::LeftParenthesis,
             ')' => TokenKind::RightParenthesis,
-            '<' => lexer.next_matches('=', TokenKind::LesserEquals, TokenKind::LeftAngle),
-            '>' => lexer.next_matches('=', TokenKind::GreaterEquals, TokenKind::RightAngle),
-            '&' if lexer.peek() == Some('&') => {
+            '<' if lexer.peek() == Some('<') => {
                 lexer.advance();
-                TokenKind::LogicalAnd
+                TokenKind::DoubleLeftAngle
             },
-            '|' if lexer.peek() == Some('|') => {
+            '>' if lexer.peek() == Some('>') => {
                 lexer.advance();
-                TokenKind::LogicalOr
+                TokenKind::DoubleRightAngle
             },
+            '<' => lexer.next_matches('=', TokenKind::LesserEquals, TokenKind::LeftAngle),
+            '>' => lexer.next_matches('=', TokenKind::GreaterEquals, TokenKind::RightAngle),
+            '&' => lexer.next_matches('&', TokenKind::LogicalAnd, TokenKind::Ampersand),
+            '|' => lexer.next_matches('|', TokenKind::LogicalOr, TokenKind::Pipe),
             '{' => TokenKind::LeftBracket,
             '}' => TokenKind::RightBracket,
             '[' => TokenKind::LeftSquare,
             ']' => TokenKind::RightSquare,
-            '%' => TokenKind::Percent,
+            '%' => lexer.next_matches('=', TokenKind::ModEquals, TokenKind::Percent),
             '+' => lexer.next_matches('=', TokenKind::AddEquals, TokenKind::Plus),
             '-' => lexer.next_matches('=', TokenKind::SubEquals, TokenKind::Minus),
             '*' => lexer.next_matches('=', TokenKind::MulEquals, TokenKind::Star),
             '^' => TokenKind::Caret,
             ',' => TokenKind::Comma,
+            '.' if lexer.peek() == Some('.') => {
+                lexer.advance();
+                lexer.next_matches('=', TokenKind::DoubleDotEquals, TokenKind::DoubleDot)
+            },
             '.' => TokenKind::Dot,
             ':' => lexer.next_matches(':', TokenKind::DoubleColon, TokenKind::Colon),
+            '=' if lexer.peek() == Some('>') => {
+                lexer.advance();
+                TokenKind::FatArrow
+            },
             '=' => lexer.next_matches('=', TokenKind::EqualsTo, TokenKind::Equals),
             '!' => lexer.next_matches('=', TokenKind::NotEqualsTo, TokenKind::Bang),
+            '~' => TokenKind::Tilde,
 
             
             '_' => {
@@ -237,7 +297,7 @@ pub fn lex(
             
             
             _ => {
-                errors.push(CompilerError::new(lexer.file, 1, "invalid character")
+                errors.push(CompilerError::new(lexer.file, 1, "invalid character", Severity::Error)
                     .highlight(SourceRange::new(start, start))
                         .note(format!("{value:?}"))
                     .build());
@@ -249,7 +309,7 @@ pub fn lex(
 
         let token = Token {
             token_kind,
-            source_range: SourceRange { start, end },
+            source_range: lexer.range(start, end),
         };
 
         tokens.push(token);
@@ -259,10 +319,7 @@ pub fn lex(
 
     tokens.push(Token {
         token_kind: TokenKind::EndOfFile,
-        source_range: SourceRange {
-            start: end,
-            end,
-        },
+        source_range: lexer.range(end, end),
     });
 
     if errors.is_empty() {
@@ -285,6 +342,10 @@ impl Lexer<'_> {
 
         if let Some(v) = self.current {
             self.character_index += v.len_utf8();
+
+            if v == '\n' {
+                self.line_starts.push(self.character_index);
+            }
         }
         
         self.current = self.characters.next();
@@ -297,11 +358,31 @@ impl Lexer<'_> {
     }
 
 
+    fn position(&self, index: usize) -> Position {
+        let line = self.line_starts.partition_point(|x| *x <= index) - 1;
+
+        Position {
+            line,
+            column: index - self.line_starts[line],
+        }
+    }
+
+
+    fn range(&self, start: usize, end: usize) -> SourceRange {
+        SourceRange::with_position(start, end, self.position(start), self.position(end))
+    }
+
+
     pub(crate) fn peek(&mut self) -> Option<char> {
         self.characters.clone().next()
     }
 
 
+    pub(crate) fn peek_nth(&mut self, n: usize) -> Option<char> {
+        self.characters.clone().nth(n)
+    }
+
+
     // # Safety:
     //   - It is the responsibility of the caller to
     //     properly call `Lexer::return_string_storage`
@@ -328,6 +409,41 @@ impl Lexer<'_> {
 }
 
 impl Lexer<'_> {
+    /// Skips a block comment, starting at its opening `/`
+    /// and ending at its closing `/`. Block comments can
+    /// be nested
+    fn block_comment(&mut self) -> Result<(), Error> {
+        let start = self.character_index;
+        self.advance();
+
+        let mut depth = 1;
+        loop {
+            match self.advance() {
+                Some('*') if self.peek() == Some('/') => {
+                    self.advance();
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return Ok(())
+                    }
+                },
+
+                Some('/') if self.peek() == Some('*') => {
+                    self.advance();
+                    depth += 1;
+                },
+
+                Some(_) => (),
+
+                None => return Err(CompilerError::new(self.file, 9, "unterminated block comment", Severity::Error)
+                    .highlight(SourceRange::new(start, start + 1))
+                        .note(format!("consider adding {} at the end of the file", "*/".repeat(depth)))
+                    .build()),
+            }
+        }
+    }
+
+    
     fn identifier(&mut self) -> TokenKind {
         let mut string = self.borrow_string_storage();
 
@@ -355,6 +471,7 @@ impl Lexer<'_> {
             "else" => TokenKind::Keyword(Keyword::Else),
             "while" => TokenKind::Keyword(Keyword::While),
             "for" => TokenKind::Keyword(Keyword::For),
+            "in" => TokenKind::Keyword(Keyword::In),
             "loop" => TokenKind::Keyword(Keyword::Loop),
             "continue" => TokenKind::Keyword(Keyword::Continue),
             "break" => TokenKind::Keyword(Keyword::Break),
@@ -362,6 +479,8 @@ impl Lexer<'_> {
             "var" => TokenKind::Keyword(Keyword::Var),
             "as" => TokenKind::Keyword(Keyword::As),
             "const" => TokenKind::Keyword(Keyword::Const),
+            "match" => TokenKind::Keyword(Keyword::Match),
+            "enum" => TokenKind::Keyword(Keyword::Enum),
 
             _ => {
                 let index = self.symbol_table.add(String::from(&string));
@@ -375,6 +494,24 @@ impl Lexer<'_> {
         token
     }
 
+
+    fn label(&mut self) -> TokenKind {
+        let mut string = self.borrow_string_storage();
+
+        while let Some(value) = self.advance() {
+            match value {
+                'a'..='z' | 'A'..='Z' | '_' | '0'..='9' => string.push(value),
+                _ => break,
+            }
+        }
+        self.stale = true;
+
+        let index = self.symbol_table.add(String::from(&string));
+        self.return_string_storage(string);
+
+        TokenKind::Label(index)
+    }
+
     
     fn string(&mut self) -> Result<Literal, Vec<Error>> {
         let mut string = String::new();
@@ -385,22 +522,9 @@ impl Lexer<'_> {
         let mut is_in_escape = false;
         while let Some(value) = self.advance() {
             if is_in_escape {
-                match value {
-                    'n' => string.push('\n'),
-                    'r' => string.push('\r'),
-                    't' => string.push('\t'),
-                    '\\' => string.push('\\'),
-                    '0' => string.push('\0'),
-                    '"' => string.push('"'),
-
-                    'u' => match self.unicode_escape_character() {
-                        Ok(val) => string.push(val),
-                        Err(err) => {
-                            errors.push(err);
-                        },
-                    },
-
-                    _ => string.push(value),
+                match self.escape_character(value) {
+                    Ok(val) => string.push(val),
+                    Err(err) => errors.push(err),
                 }
 
                 is_in_escape = false;
@@ -416,7 +540,7 @@ impl Lexer<'_> {
         }
 
         if self.current_character() != Some('"') {
-            errors.push(CompilerError::new(self.file, 2, "unterminated string")
+            errors.push(CompilerError::new(self.file, 2, "unterminated string", Severity::Error)
                 .highlight(SourceRange::new(start, self.character_index))
                     .note("consider adding a quotation mark here".to_string())
 
@@ -433,10 +557,79 @@ impl Lexer<'_> {
     }
 
 
+    fn character(&mut self) -> Result<Literal, Vec<Error>> {
+        let start = self.character_index;
+
+        let mut errors = vec![];
+        let mut character = None;
+        let mut count = 0;
+
+        let mut is_in_escape = false;
+        while let Some(value) = self.advance() {
+            if is_in_escape {
+                match self.escape_character(value) {
+                    Ok(val) => {
+                        character.get_or_insert(val);
+                        count += 1;
+                    },
+                    Err(err) => errors.push(err),
+                }
+
+                is_in_escape = false;
+
+                continue;
+            }
+
+            match value {
+                '\\' => is_in_escape = true,
+                '\'' | '\n' => break,
+                _ => {
+                    character.get_or_insert(value);
+                    count += 1;
+                },
+            }
+        }
+
+        if self.current_character() != Some('\'') {
+            errors.push(CompilerError::new(self.file, 10, "unterminated character literal", Severity::Error)
+                .highlight(SourceRange::new(start, self.character_index))
+                    .note("consider adding a single quote here".to_string())
+
+                .build()
+            );
+        } else if errors.is_empty() && count != 1 {
+            errors.push(CompilerError::new(self.file, 11, "invalid character literal", Severity::Error)
+                .highlight(SourceRange::new(start, self.character_index))
+                    .note(format!("a character literal must contain exactly one character but found {count}"))
+
+                .build()
+            );
+        }
+
+        match character {
+            Some(value) if errors.is_empty() => Ok(Literal::Char(value)),
+            _ => Err(errors),
+        }
+    }
+
+
+    fn escape_character(&mut self, value: char) -> Result<char, Error> {
+        Ok(match value {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => self.unicode_escape_character()?,
+
+            _ => value,
+        })
+    }
+
+
     fn unicode_escape_character(&mut self) -> Result<char, Error> {
         if self.advance() != Some('{') {
             self.stale = true;
-            return Err(CompilerError::new(self.file, 3, "corrupt unicode escape")
+            return Err(CompilerError::new(self.file, 3, "corrupt unicode escape", Severity::Error)
                 .highlight(SourceRange::new(self.character_index, self.character_index))
                     .note("unicode escapes are formatted like \\u{..}".to_string())
 
@@ -455,7 +648,7 @@ impl Lexer<'_> {
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | 'A' | 'B' | 'C'
                 | 'D' | 'E' | 'F' => unicode.push(value),
 
-                _ => return Err(CompilerError::new(self.file, 4, "invalid unicode value")
+                _ => return Err(CompilerError::new(self.file, 4, "invalid unicode value", Severity::Error)
                     .highlight(SourceRange::new(self.character_index, self.character_index))
                         .note("unicode escape values must be written in base-16 (0-1-2-3-4-5-6-7-8-9-A-B-C-D-E-F)".to_string())
                     
@@ -470,7 +663,7 @@ impl Lexer<'_> {
 
         match char::from_u32(number as u32) {
             Some(value) => Ok(value),
-            None => Err(CompilerError::new(self.file, 7, "isn't a valid unicode character")
+            None => Err(CompilerError::new(self.file, 7, "isn't a valid unicode character", Severity::Error)
                     .highlight(SourceRange::new(start, self.character_index))
                     .build()
                 ),
@@ -519,7 +712,7 @@ impl Lexer<'_> {
         while let Some(value) = self.current_character() {
             match map_to_hex(value) {
                 Some(n) if base < n as u32 + 1 => 
-                    return Err(CompilerError::new(self.file, 6, "invalid number for base")
+                    return Err(CompilerError::new(self.file, 6, "invalid number for base", Severity::Error)
                         .highlight(SourceRange::new(self.character_index, self.character_index))
                             .note(format!("the value {value} is too big for a base-{base} number"))
 
@@ -527,6 +720,8 @@ impl Lexer<'_> {
 
                 Some(_) => (),
                 _ => match value {
+                    // `0..10` is a range, not a number
+                    '.' if self.peek() == Some('.') => break,
                     '.' => dot_count += 1,
                     '_' => {
                         self.advance();
@@ -543,7 +738,7 @@ impl Lexer<'_> {
         if dot_count > 1 {
             self.return_string_storage(number_string);
 
-            return Err(CompilerError::new(self.file, 8, "too many dots")
+            return Err(CompilerError::new(self.file, 8, "too many dots", Severity::Error)
                 .highlight(SourceRange::new(start, self.character_index-1))
                 .build()
             );
@@ -553,9 +748,10 @@ impl Lexer<'_> {
             .split_once('.')
             .unwrap_or((&number_string, ""));
 
-        let number = self.base_n_number_conversion(base, full_number)?;
+        let number = self.base_n_number_conversion(base, full_number);
+        let is_float = !decimals.is_empty();
 
-        if !decimals.is_empty() {
+        let decimal = {
             let mut decimal = 0.0;
             for (index, value) in decimals.chars().enumerate() {
                 let digit = value.to_digit(base).expect("unreachable") as f64;
@@ -564,12 +760,68 @@ impl Lexer<'_> {
                 decimal += (base as f64).powi(power) * digit;
             }
 
-            self.return_string_storage(number_string);
-            return Ok(Literal::Float(number as f64 + decimal));
-        }
+            decimal
+        };
 
         self.return_string_storage(number_string);
-        Ok(Literal::Integer(number))
+
+        let number = number?;
+        let suffix_start = self.character_index;
+        let mut suffix = self.borrow_string_storage();
+
+        while let Some(value @ ('a'..='z' | 'A'..='Z' | '0'..='9' | '_')) = self.current_character() {
+            suffix.push(value);
+            self.advance();
+        }
+
+        macro_rules! suffixed {
+            ($variant: ident, $ty: ty) => {
+                if is_float {
+                    Err(None)
+                } else {
+                    <$ty>::try_from(number)
+                        .map(Literal::$variant)
+                        .map_err(|_| Some(stringify!($ty)))
+                }
+            }
+        }
+
+        let literal = match suffix.as_str() {
+            "" if is_float => Ok(Literal::Float(number as f64 + decimal)),
+            "" => Ok(Literal::Integer(number)),
+
+            "f" => Ok(Literal::Float(number as f64 + decimal)),
+
+            "i8"  => suffixed!(I8 , i8 ),
+            "i16" => suffixed!(I16, i16),
+            "i32" => suffixed!(I32, i32),
+            "i64" if is_float => Err(None),
+            "i64" => Ok(Literal::I64(number)),
+            "u8"  => suffixed!(U8 , u8 ),
+            "u16" => suffixed!(U16, u16),
+            "u32" => suffixed!(U32, u32),
+            "u64" => suffixed!(U64, u64),
+
+            _ => Err(None),
+        };
+
+        let result = match literal {
+            Ok(v) => Ok(v),
+
+            Err(None) => Err(CompilerError::new(self.file, 12, "invalid number suffix", Severity::Error)
+                .highlight(SourceRange::new(suffix_start, self.character_index-1))
+                    .note(format!("{suffix:?} isn't a valid suffix for {}", if is_float { "a float" } else { "an integer" }))
+                    .note("valid suffixes are i8, i16, i32, i64, u8, u16, u32, u64 and f".to_string())
+                .build()),
+
+            Err(Some(ty)) => Err(CompilerError::new(self.file, 13, "number doesn't fit in its suffix type", Severity::Error)
+                .highlight(SourceRange::new(start, self.character_index-1))
+                    .note(format!("{number} is out of range for {ty}"))
+                .build()),
+        };
+
+        self.return_string_storage(suffix);
+        result
     }
 }
 
@@ -586,7 +838,7 @@ impl Lexer<'_> {
 
             let power = match (base as i64).checked_pow(power) {
                 Some(value) => value,
-                None => return Err(CompilerError::new(self.file, 5, "number is too large")
+                None => return Err(CompilerError::new(self.file, 5, "number is too large", Severity::Error)
                     .highlight(SourceRange::new(start, self.character_index-1))
                     .build()
                 ),
@@ -594,14 +846,14 @@ impl Lexer<'_> {
 
             let result : i64 = match power.checked_mul(digit) {
                 Some(value) => value,
-                None => return Err(CompilerError::new(self.file, 5, "number is too large")
+                None => return Err(CompilerError::new(self.file, 5, "number is too large", Severity::Error)
                     .highlight(SourceRange::new(start, self.character_index-1))
                     .build()),
             };
 
             number = match number.checked_add(result) {
                 Some(value) => value,
-                None => return Err(CompilerError::new(self.file, 5, "number is too large")
+                None => return Err(CompilerError::new(self.file, 5, "number is too large", Severity::Error)
                     .highlight(SourceRange::new(start, self.character_index-1))
                     .build()),
             };
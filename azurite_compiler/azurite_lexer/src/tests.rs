@@ -1,9 +1,9 @@
 #![allow(unused)]
 use std::fmt::Debug;
 
-use common::{SymbolTable, SourceRange};
+use common::{SymbolTable, SourceRange, Position};
 
-use crate::{lex, Token, TokenKind, Literal};
+use crate::{lex, Token, TokenKind, Literal, Keyword};
 
 
 #[test]
@@ -77,6 +77,156 @@ fn numbers() {
 }
 
 
+#[test]
+fn number_suffixes() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "10u8 1_000i16 3.5f";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    compare_individually(&tokens, &vec![
+        Token {
+            token_kind: TokenKind::Literal(Literal::U8(10)),
+            source_range: SourceRange::new(0, 3),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::I16(1000)),
+            source_range: SourceRange::new(5, 12),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::Float(3.5)),
+            source_range: SourceRange::new(14, 17),
+        },
+        Token {
+            token_kind: TokenKind::EndOfFile,
+            source_range: SourceRange::new(17, 17),
+        }
+    ]);
+}
+
+
+#[test]
+fn ranges() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "0..10 0..=10";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    compare_individually(&tokens, &vec![
+        Token {
+            token_kind: TokenKind::Literal(Literal::Integer(0)),
+            source_range: SourceRange::new(0, 0),
+        },
+        Token {
+            token_kind: TokenKind::DoubleDot,
+            source_range: SourceRange::new(1, 2),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::Integer(10)),
+            source_range: SourceRange::new(3, 4),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::Integer(0)),
+            source_range: SourceRange::new(6, 6),
+        },
+        Token {
+            token_kind: TokenKind::DoubleDotEquals,
+            source_range: SourceRange::new(7, 9),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::Integer(10)),
+            source_range: SourceRange::new(10, 11),
+        },
+        Token {
+            token_kind: TokenKind::EndOfFile,
+            source_range: SourceRange::new(11, 11),
+        }
+    ]);
+}
+
+
+#[test]
+fn bitwise_operators() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "& && | || ^ ~";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    let kinds = tokens.iter().map(|x| x.token_kind).collect::<Vec<_>>();
+
+    assert_eq!(kinds, vec![
+        TokenKind::Ampersand,
+        TokenKind::LogicalAnd,
+        TokenKind::Pipe,
+        TokenKind::LogicalOr,
+        TokenKind::Caret,
+        TokenKind::Tilde,
+        TokenKind::EndOfFile,
+    ]);
+}
+
+
+#[test]
+fn shift_operators() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "<< >> < > <= >= <<= >>>";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    let kinds = tokens.iter().map(|x| x.token_kind).collect::<Vec<_>>();
+
+    assert_eq!(kinds, vec![
+        TokenKind::DoubleLeftAngle,
+        TokenKind::DoubleRightAngle,
+        TokenKind::LeftAngle,
+        TokenKind::RightAngle,
+        TokenKind::LesserEquals,
+        TokenKind::GreaterEquals,
+        TokenKind::DoubleLeftAngle,
+        TokenKind::Equals,
+        TokenKind::DoubleRightAngle,
+        TokenKind::RightAngle,
+        TokenKind::EndOfFile,
+    ]);
+}
+
+
+#[test]
+fn invalid_number_suffixes() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    assert!(lex("1_000u8", file, &mut symbol_table).is_err());
+    assert!(lex("256u8", file, &mut symbol_table).is_err());
+    assert!(lex("10u7", file, &mut symbol_table).is_err());
+    assert!(lex("3.5u8", file, &mut symbol_table).is_err());
+}
+
+
+#[test]
+fn positions() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "var x\n  = \"é\"\n";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    let positions = tokens.iter().map(|x| x.source_range.position.unwrap()).collect::<Vec<_>>();
+
+    compare_individually(&positions, &vec![
+        (Position { line: 0, column: 0 }, Position { line: 0, column: 2 }),
+        (Position { line: 0, column: 4 }, Position { line: 0, column: 4 }),
+        (Position { line: 1, column: 2 }, Position { line: 1, column: 2 }),
+        (Position { line: 1, column: 4 }, Position { line: 1, column: 7 }),
+        (Position { line: 1, column: 8 }, Position { line: 1, column: 8 }),
+    ]);
+}
+
+
 #[test]
 fn identifiers() {
     let mut symbol_table = SymbolTable::new();
@@ -123,6 +273,118 @@ fn string() {
 }
 
 
+#[test]
+fn labels() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "'outer: loop";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    compare_individually(&tokens, &vec![
+        Token {
+            token_kind: TokenKind::Label(symbol_table.add(String::from("outer"))),
+            source_range: SourceRange::new(0, 5),
+        },
+        Token {
+            token_kind: TokenKind::Colon,
+            source_range: SourceRange::new(6, 6),
+        },
+        Token {
+            token_kind: TokenKind::Keyword(Keyword::Loop),
+            source_range: SourceRange::new(8, 11),
+        },
+        Token {
+            token_kind: TokenKind::EndOfFile,
+            source_range: SourceRange::new(11, 11),
+        },
+    ])
+}
+
+
+#[test]
+fn characters() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "'a' '\\n' '\\u{41}' '\\\\'";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    compare_individually(&tokens, &vec![
+        Token {
+            token_kind: TokenKind::Literal(Literal::Char('a')),
+            source_range: SourceRange::new(0, 2),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::Char('\n')),
+            source_range: SourceRange::new(4, 7),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::Char('A')),
+            source_range: SourceRange::new(9, 16),
+        },
+        Token {
+            token_kind: TokenKind::Literal(Literal::Char('\\')),
+            source_range: SourceRange::new(18, 21),
+        },
+        Token {
+            token_kind: TokenKind::EndOfFile,
+            source_range: SourceRange::new(21, 21),
+        },
+    ])
+}
+
+
+#[test]
+fn invalid_characters() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    assert!(lex("'ab'", file, &mut symbol_table).is_err());
+    assert!(lex("''", file, &mut symbol_table).is_err());
+    assert!(lex("'\\n", file, &mut symbol_table).is_err());
+}
+
+
+#[test]
+fn block_comments() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    let data = "< /* a /* b */ c */ >/**/{";
+    let tokens = lex(data, file, &mut symbol_table).unwrap();
+
+    compare_individually(&tokens, &vec![
+        Token {
+            token_kind: TokenKind::LeftAngle,
+            source_range: SourceRange::new(0, 0),
+        },
+        Token {
+            token_kind: TokenKind::RightAngle,
+            source_range: SourceRange::new(20, 20),
+        },
+        Token {
+            token_kind: TokenKind::LeftBracket,
+            source_range: SourceRange::new(25, 25),
+        },
+        Token {
+            token_kind: TokenKind::EndOfFile,
+            source_range: SourceRange::new(25, 25),
+        },
+    ])
+}
+
+
+#[test]
+fn unterminated_block_comment() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(String::from("test"));
+
+    assert!(lex("/* a /* b */", file, &mut symbol_table).is_err());
+    assert!(lex("/*", file, &mut symbol_table).is_err());
+}
+
+
 fn compare_individually<T: PartialEq + Debug>(list1: &Vec<T>, list2: &Vec<T>) {
     assert_eq!(list1.len(), list2.len());
     for (index, (v1, v2)) in list1.iter().zip(list2.iter()).enumerate() {
@@ -59,13 +59,20 @@ pub enum Statement {
         identifier: SymbolIndex,
         index_to: usize,
     },
+
+    IndexUpdate {
+        value: Box<Instruction>,
+        index: Box<Instruction>,
+        right: Box<Instruction>,
+    },
     
     Loop {
         body: Vec<Instruction>,
+        label: Option<SymbolIndex>,
     },
 
-    Break,
-    Continue,
+    Break(Option<SymbolIndex>),
+    Continue(Option<SymbolIndex>),
     Return(Box<Instruction>),
 }
 
@@ -99,6 +106,14 @@ pub enum Expression {
         condition: Box<Instruction>,
         else_part: Option<Box<Instruction>>,
     },
+
+    /// Every pattern is a constant `Expression::Data`, the
+    /// `default` is the body of the `_` arm
+    Match {
+        scrutinee: Box<Instruction>,
+        arms: Vec<(Instruction, Vec<Instruction>)>,
+        default: Option<Vec<Instruction>>,
+    },
     
     Identifier(SymbolIndex),
     
@@ -117,6 +132,27 @@ pub enum Expression {
         generics: Arc<[SourcedDataType]>,
     },
 
+    ArrayCreation {
+        elements: Vec<Instruction>,
+    },
+
+    Index {
+        value: Box<Instruction>,
+        index: Box<Instruction>,
+    },
+
+    /// Only produced by the desugaring of a `for` loop,
+    /// the analysis replaces it with `start`
+    Range {
+        start: Box<Instruction>,
+        end: Box<Instruction>,
+        inclusive: bool,
+    },
+
+    /// `loop.index`, how many times the innermost
+    /// loop has jumped back to its start
+    LoopIndex,
+
     AccessStructureData {
         structure: Box<Instruction>,
         identifier: SymbolIndex,
@@ -141,6 +177,10 @@ pub enum Declaration {
         generics: Vec<SymbolIndex>,
         
         source_range_declaration: SourceRange,
+
+        /// Set if the declaration has no `: Type`, the
+        /// analyzer then infers `return_type` from the body
+        infer_return_type: bool,
     },
 
 
@@ -171,7 +211,22 @@ pub enum Declaration {
 
     UseFile {
         file_name: SymbolIndex,
-    }
+    },
+
+
+    Const {
+        name: SymbolIndex,
+        data_type: SourcedDataType,
+        value: Box<Instruction>,
+    },
+
+
+    /// Every variant is an `i64` constant, `None`
+    /// if it has no explicit discriminant
+    Enum {
+        name: SymbolIndex,
+        variants: Vec<(SymbolIndex, Option<i64>, SourceRange)>,
+    },
 }
 
 
@@ -192,6 +247,12 @@ pub enum BinaryOperator {
     Divide,
     Modulo,
 
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+
     Equals,
     NotEquals,
     GreaterThan,
@@ -209,6 +270,12 @@ impl Display for BinaryOperator {
             BinaryOperator::Divide   => "division",
             BinaryOperator::Modulo   => "modulo",
 
+            BinaryOperator::BitwiseAnd => "bitwise and",
+            BinaryOperator::BitwiseOr  => "bitwise or",
+            BinaryOperator::BitwiseXor => "bitwise xor",
+            BinaryOperator::ShiftLeft  => "shift left",
+            BinaryOperator::ShiftRight => "shift right",
+
             BinaryOperator::Equals => "equals",
             BinaryOperator::NotEquals => "not equals",
             BinaryOperator::GreaterThan => "greater than",
@@ -229,6 +296,12 @@ impl BinaryOperator {
             TokenKind::Slash   => Some(BinaryOperator::Divide),
             TokenKind::Percent => Some(BinaryOperator::Modulo),
 
+            TokenKind::Ampersand => Some(BinaryOperator::BitwiseAnd),
+            TokenKind::Pipe      => Some(BinaryOperator::BitwiseOr),
+            TokenKind::Caret     => Some(BinaryOperator::BitwiseXor),
+            TokenKind::DoubleLeftAngle  => Some(BinaryOperator::ShiftLeft),
+            TokenKind::DoubleRightAngle => Some(BinaryOperator::ShiftRight),
+
             TokenKind::RightAngle => Some(BinaryOperator::GreaterThan),
             TokenKind::LeftAngle => Some(BinaryOperator::LesserThan),
             TokenKind::GreaterEquals => Some(BinaryOperator::GreaterEquals),
@@ -245,6 +318,7 @@ impl BinaryOperator {
 pub enum UnaryOperator {
     Not,
     Negate,
+    BitwiseNot,
 }
 
 impl Display for UnaryOperator {
@@ -252,6 +326,7 @@ impl Display for UnaryOperator {
         write!(f, "{}", match self {
             UnaryOperator::Not => "not",
             UnaryOperator::Negate => "negate",
+            UnaryOperator::BitwiseNot => "bitwise not",
         })
     }
     
@@ -262,6 +337,7 @@ impl UnaryOperator {
         match token {
             TokenKind::Minus => Some(UnaryOperator::Negate),
             TokenKind::Bang  => Some(UnaryOperator::Not),
+            TokenKind::Tilde => Some(UnaryOperator::BitwiseNot),
             _ => None
         }
     }
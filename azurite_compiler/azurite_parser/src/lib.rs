@@ -2,8 +2,8 @@ pub mod ast;
 
 use ast::{Instruction, BinaryOperator, InstructionKind, Expression, Statement, Declaration, ExternFunctionAST, UnaryOperator};
 use azurite_lexer::{Token, TokenKind, Keyword, Literal};
-use azurite_errors::{Error, CompilerError, ErrorBuilder, CombineIntoError};
-use common::{default, DataType, Data, SymbolTable, SourcedData, SourceRange, SymbolIndex, SourcedDataType};
+use azurite_errors::{Error, CompilerError, ErrorBuilder, CombineIntoError, Severity};
+use common::{default, conversion_symbol, DataType, Data, SymbolTable, SourcedData, SourceRange, SymbolIndex, SourcedDataType};
 
 type ParseResult = Result<Instruction, Error>;
 
@@ -15,6 +15,14 @@ struct Parser<'a> {
 
     symbol_table: &'a mut SymbolTable,
     file: SymbolIndex,
+
+    /// How deep the expression currently being parsed is
+    depth: usize,
+    max_depth: usize,
+
+    /// Whether `any` can be used as a type, it's only
+    /// meant for the standard library's declarations
+    allow_any: bool,
 }
 
 
@@ -44,12 +52,37 @@ pub fn parse(
     file: SymbolIndex, 
     symbol_table: &mut SymbolTable,
 ) -> Result<Vec<Instruction>, Error> {
+    parse_file(tokens, file, symbol_table, false)
+}
+
+
+/// Same as `parse` but `any` can be used as a type
+/// so the standard library can declare externs which
+/// take a value of any type
+pub fn parse_std(
+    tokens: Vec<Token>, 
+    file: SymbolIndex, 
+    symbol_table: &mut SymbolTable,
+) -> Result<Vec<Instruction>, Error> {
+    parse_file(tokens, file, symbol_table, true)
+}
+
+
+fn parse_file(
+    tokens: Vec<Token>, 
+    file: SymbolIndex, 
+    symbol_table: &mut SymbolTable,
+    allow_any: bool,
+) -> Result<Vec<Instruction>, Error> {
 
     let mut parser = Parser {
         tokens,
         symbol_table,
         file,
         index: 0,
+        depth: 0,
+        max_depth: azurite_common::max_nesting_depth(),
+        allow_any,
     };
 
     parser.advance();
@@ -153,7 +186,7 @@ impl Parser<'_> {
         };
 
         if &token.token_kind != token_kind {
-            return Err(CompilerError::new(self.file, 102, "unexpected token")
+            return Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
                 .highlight(token.source_range)
                     .note(format!("expected {token_kind:?}"))
                 .build())
@@ -173,7 +206,7 @@ impl Parser<'_> {
             return Ok(v)
         }
 
-        return Err(CompilerError::new(self.file, 102, "unexpected token")
+        return Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
             .highlight(token.source_range)
                 .note("expected identifier".to_string())
             .build())
@@ -184,6 +217,19 @@ impl Parser<'_> {
         let current_token = self.current_token().unwrap();
         let source = current_token.source_range;
 
+        if current_token.token_kind == TokenKind::LeftSquare {
+            self.advance();
+            let element_type = self.parse_type()?;
+            
+            self.advance();
+            self.expect(&TokenKind::RightSquare)?;
+
+            return Ok(SourcedDataType::new(
+                SourceRange::combine(source, self.current_range()),
+                DataType::Array(Box::new(element_type.data_type)),
+            ))
+        }
+
         // PERF: Obviously, cache this vec somewhere so it doesn't constantly realloc
         let mut string = vec![];
         loop {
@@ -220,13 +266,13 @@ impl Parser<'_> {
             "float" => DataType::Float,
             "bool" => DataType::Bool,
             "str" => DataType::String,
+            "char" => DataType::Char,
+            "any" if self.allow_any => DataType::Any,
             
             _ => {
                 let g = if self.peek().map(|x| x.token_kind) == Some(TokenKind::LeftSquare) {
                     self.advance();
-                    let v = self.parse_generics_for_expression()?;
-                    self.index -= 1;
-                    v
+                    self.parse_type_arguments()?
                 } else { vec![] };
                 let index = self.symbol_table.add_generics(built_string, &g);
 
@@ -234,87 +280,140 @@ impl Parser<'_> {
             }
         };
 
-        Ok(SourcedDataType::new(SourceRange::new(source.start, self.current_token().unwrap().source_range.end), data_type))
+        Ok(SourcedDataType::new(SourceRange::combine(source, self.current_range()), data_type))
     }
 
 
-    fn parse_generics_for_declaration(&mut self) -> Result<Vec<SymbolIndex>, Error> {
-        if self.expect(&TokenKind::LeftSquare).is_ok() {
+    /// Parses the generic arguments of a type, i.e. the
+    /// `[str, List[i32]]` in `Map[str, List[i32]]`, ending
+    /// on the closing bracket
+    fn parse_type_arguments(&mut self) -> Result<Vec<SourcedDataType>, Error> {
+        self.expect(&TokenKind::LeftSquare)?;
+        let start = self.current_range();
+        self.advance();
+
+        if self.current_kind() == TokenKind::RightSquare {
+            return Err(CompilerError::new(self.file, 108, "empty generic arguments", Severity::Error)
+                .highlight(SourceRange::combine(start, self.current_range()))
+                    .note("either remove the brackets or provide the types".to_string())
+                .build())
+        }
+
+        let mut generics = vec![];
+        loop {
+            generics.push(self.parse_type()?);
             self.advance();
 
-            let mut generics_vec = vec![];
-            loop {
-                if self.expect(&TokenKind::RightSquare).is_ok() {
-                    break
-                }
-            
-                if !generics_vec.is_empty() {
-                    self.expect(&TokenKind::Comma)?;
-                    self.advance();
-                }
+            if self.current_kind() != TokenKind::Comma {
+                self.expect(&TokenKind::RightSquare)?;
+                break
+            }
 
-                if self.expect(&TokenKind::RightSquare).is_ok() {
-                    break
-                }
-            
+            self.advance();
 
-                let identifier = match self.expect_identifier() {
-                    Ok(v) => v,
-                    Err(_) => break,
-                };
+            // Trailing comma
+            if self.current_kind() == TokenKind::RightSquare {
+                break
+            }
+        }
 
-                self.advance();
+        Ok(generics)
+    }
+
+
+    /// Parses the generic parameters of a function or a
+    /// structure declaration, i.e. the `[A, B]` in
+    /// `struct Pair[A, B]`, ending after the closing bracket
+    fn parse_generics_for_declaration(&mut self) -> Result<Vec<SymbolIndex>, Error> {
+        if self.current_kind() != TokenKind::LeftSquare {
+            return Ok(vec![])
+        }
+
+        let start = self.current_range();
+        self.advance();
 
-                generics_vec.push(identifier);
+        if self.current_kind() == TokenKind::RightSquare {
+            return Err(CompilerError::new(self.file, 108, "empty generic arguments", Severity::Error)
+                .highlight(SourceRange::combine(start, self.current_range()))
+                    .note("either remove the brackets or provide the names of the generics".to_string())
+                .build())
+        }
+
+        let mut generics_vec : Vec<SymbolIndex> = vec![];
+        loop {
+            let identifier = self.expect_identifier()?;
+
+            if generics_vec.contains(&identifier) {
+                return Err(CompilerError::new(self.file, 109, "duplicate generic parameter", Severity::Error)
+                    .highlight(self.current_range())
+                        .note("a generic with the same name is already declared".to_string())
+                    .build())
             }
 
-            self.expect(&TokenKind::RightSquare)?;
+            generics_vec.push(identifier);
             self.advance();
 
-            Ok(generics_vec)
-            
-        } else { Ok(vec![]) }
-        
+            if self.current_kind() != TokenKind::Comma {
+                self.expect(&TokenKind::RightSquare)?;
+                break
+            }
+
+            self.advance();
+
+            // Trailing comma
+            if self.current_kind() == TokenKind::RightSquare {
+                break
+            }
+        }
+
+        self.advance();
+
+        Ok(generics_vec)
     }
 
     
+    /// Parses the generic arguments of a function call or
+    /// a structure creation, ending after the closing bracket
     fn parse_generics_for_expression(&mut self) -> Result<Vec<SourcedDataType>, Error> {
-        if self.expect(&TokenKind::LeftSquare).is_ok() {
-            self.advance();
+        if self.current_kind() != TokenKind::LeftSquare {
+            return Ok(vec![])
+        }
 
-            let mut generics_vec = vec![];
-            loop {
-                if self.expect(&TokenKind::RightSquare).is_ok() {
-                    break
-                }
-            
-                if !generics_vec.is_empty() {
-                    self.expect(&TokenKind::Comma)?;
-                    self.advance();
-                }
+        let generics = self.parse_type_arguments()?;
+        self.advance();
 
-                if self.expect(&TokenKind::RightSquare).is_ok() {
-                    break
-                }
-            
+        Ok(generics)
+    }
 
-                let identifier = match self.parse_type() {
-                    Ok(v) => v,
-                    Err(_) => break,
-                };
 
-                self.advance();
+    /// Returns the kind of the token after the identifier
+    /// at the current token, skipping over the brackets if
+    /// they are generic arguments
+    ///
+    /// The brackets are either generics or an index into
+    /// the value, they're only generics if they are followed
+    /// by a call or a structure creation
+    fn peek_past_generics(&mut self) -> Option<TokenKind> {
+        let peek = self.peek_kind()?;
+        if peek != TokenKind::LeftSquare {
+            return Some(peek)
+        }
 
-                generics_vec.push(identifier);
-            }
+        let index = self.index;
 
-            self.expect(&TokenKind::RightSquare)?;
-            self.advance();
+        self.advance();
+        let is_empty = self.peek_kind() == Some(TokenKind::RightSquare);
+        let kind = match self.parse_generics_for_expression() {
+            Ok(_) if matches!(self.current_kind(), TokenKind::LeftParenthesis | TokenKind::LeftBracket) => self.current_kind(),
+
+            // Empty brackets can't be an index so they're
+            // treated as generics to report them as such
+            Err(_) if is_empty => self.tokens.get(index + 2).map_or(peek, |x| x.token_kind),
+            _ => peek,
+        };
 
-            Ok(generics_vec)
-            
-        } else { Ok(vec![]) }
-        
+        self.index = index;
+        Some(kind)
     }
 }
 
@@ -329,55 +428,125 @@ impl Parser<'_> {
         match &current_token.token_kind {
             TokenKind::Keyword(keyword) => match keyword {
                 Keyword::Var => self.var_declaration(),
+                Keyword::Const => self.const_declaration(),
+                Keyword::Loop if self.peek_kind() == Some(TokenKind::Dot) => self.var_update(),
                 Keyword::Loop => self.loop_statement(),
                 Keyword::While => self.while_statement(),
+                Keyword::For => self.for_statement(),
 
                 Keyword::Namespace => self.namespace_declaration(),
                 Keyword::Fn => self.function_declaration(None),
                 Keyword::Struct => self.struct_declaration(),
+                Keyword::Enum => self.enum_declaration(),
                 Keyword::Impl => self.impl_block(),
 
                 Keyword::Using => self.using_declaration(),
                 Keyword::Extern => self.extern_block(None),
 
                 Keyword::Return => {
-                    let start = current_token.source_range.start;
+                    let start = current_token.source_range;
                     self.advance();
 
                     let expression = self.expression(default())?;
                     
                     Ok(Instruction {
-                        source_range: SourceRange::new(start, expression.source_range.end),
+                        source_range: SourceRange::combine(start, expression.source_range),
                         instruction_kind: InstructionKind::Statement(Statement::Return(Box::new(expression))),
                         ..default()
                     })
                 },
 
-                Keyword::Break => Ok(Instruction {
-                    instruction_kind: InstructionKind::Statement(Statement::Break),
-                    source_range: self.current_token().unwrap().source_range,
-                    ..default()
-                }),
+                Keyword::Break => {
+                    let (label, source_range) = self.optional_label();
 
-                Keyword::Continue => Ok(Instruction {
-                    instruction_kind: InstructionKind::Statement(Statement::Continue),
-                    source_range: self.current_token().unwrap().source_range,
-                    ..default()
-                }),
+                    Ok(Instruction {
+                        instruction_kind: InstructionKind::Statement(Statement::Break(label)),
+                        source_range,
+                        ..default()
+                    })
+                },
+
+                Keyword::Continue => {
+                    let (label, source_range) = self.optional_label();
+
+                    Ok(Instruction {
+                        instruction_kind: InstructionKind::Statement(Statement::Continue(label)),
+                        source_range,
+                        ..default()
+                    })
+                },
 
 
                 
                 _ => self.expression(default()),
             },
 
+            TokenKind::Label(label) => {
+                let label = *label;
+                self.labeled_loop(label)
+            },
+
             _ => self.var_update(),
         }
     }
 
 
+    /// Parses an optional label following a `break` or a `continue`
+    /// and returns it with the source range of the whole statement
+    fn optional_label(&mut self) -> (Option<SymbolIndex>, SourceRange) {
+        let start = self.current_range();
+
+        let label = match self.peek_kind() {
+            Some(TokenKind::Label(label)) => {
+                self.advance();
+                Some(label)
+            },
+
+            _ => None,
+        };
+
+        (label, SourceRange::combine(start, self.current_range()))
+    }
+
+
+    fn labeled_loop(&mut self, label: SymbolIndex) -> ParseResult {
+        let start = self.current_range();
+        self.advance();
+
+        self.expect(&TokenKind::Colon)?;
+        self.advance();
+
+        let mut instruction = match self.current_kind() {
+            TokenKind::Keyword(Keyword::Loop) => self.loop_statement()?,
+            TokenKind::Keyword(Keyword::While) => self.while_statement()?,
+            TokenKind::Keyword(Keyword::For) => self.for_statement()?,
+
+            _ => return Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
+                .highlight(self.current_range())
+                    .note("labels can only be used on a `loop`, a `while` or a `for`".to_string())
+                .build())
+        };
+
+        // A `for` is a block that ends with the loop it desugars into
+        let loop_instruction = match &mut instruction.instruction_kind {
+            InstructionKind::Expression(Expression::Block { body }) => body.last_mut().unwrap(),
+            _ => &mut instruction,
+        };
+
+        if let InstructionKind::Statement(Statement::Loop { label: loop_label, .. }) = &mut loop_instruction.instruction_kind {
+            *loop_label = Some(label);
+        }
+
+        loop_instruction.source_range = SourceRange::combine(start, loop_instruction.source_range);
+
+        instruction.source_range = SourceRange::combine(start, instruction.source_range);
+        Ok(instruction)
+    }
+
+
     fn struct_declaration(&mut self) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::Struct))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
         
         let identifier = self.expect_identifier()?;
@@ -424,7 +593,7 @@ impl Parser<'_> {
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Declaration(Declaration::StructDeclaration { name: identifier, fields, generics }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
         
@@ -433,10 +602,16 @@ impl Parser<'_> {
 
     fn function_declaration(&mut self, impl_type: Option<SourcedDataType>) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::Fn))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
-        let identifier = self.expect_identifier()?;
+        let identifier = if self.expect(&TokenKind::Keyword(Keyword::As)).is_ok() {
+            self.advance();
+            let target = self.parse_type()?;
+            conversion_symbol(self.symbol_table, &target.data_type)
+        } else {
+            self.expect_identifier()?
+        };
         self.advance();
 
         let generics = self.parse_generics_for_declaration()?;
@@ -492,17 +667,18 @@ impl Parser<'_> {
 
         self.advance();
 
-        let return_type = if self.expect(&TokenKind::Colon).is_ok() {
+        let infer_return_type = self.expect(&TokenKind::Colon).is_err();
+        let return_type = if !infer_return_type {
             self.advance();
             let return_type = self.parse_type()?;
             
             self.advance();
             return_type
         } else {
-            SourcedDataType::new(SourceRange::new(start, self.current_token().unwrap().source_range.end), DataType::Empty)
+            SourcedDataType::new(SourceRange::combine(start, self.current_range()), DataType::Empty)
         };
 
-        let declaration_end = self.current_token().unwrap().source_range.end;
+        let declaration_end = self.current_range();
 
         self.expect(&TokenKind::LeftBracket)?;
         self.advance();
@@ -516,9 +692,10 @@ impl Parser<'_> {
                 return_type,
                 body,
                 generics,
-                source_range_declaration: SourceRange::new(start, declaration_end),
+                source_range_declaration: SourceRange::combine(start, declaration_end),
+                infer_return_type,
             }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
@@ -526,7 +703,7 @@ impl Parser<'_> {
 
     fn var_declaration(&mut self) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::Var))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         
         self.advance();
 
@@ -549,16 +726,114 @@ impl Parser<'_> {
         let expression = self.expression(default())?;
         
         Ok(Instruction {
-            source_range: SourceRange::new(start, expression.source_range.end),
+            source_range: SourceRange::combine(start, expression.source_range),
             instruction_kind: InstructionKind::Statement(Statement::DeclareVar { identifier, type_hint, data: Box::new(expression) }),
             ..default()
         })
     }
 
 
+    fn const_declaration(&mut self) -> ParseResult {
+        self.expect(&TokenKind::Keyword(Keyword::Const))?;
+        let start = self.current_range();
+        
+        self.advance();
+
+        let name = self.expect_identifier()?;
+        self.advance();
+
+        self.expect(&TokenKind::Colon)?;
+        self.advance();
+
+        let data_type = self.parse_type()?;
+        self.advance();
+
+        self.expect(&TokenKind::Equals)?;
+        self.advance();
+
+        let value = self.expression(default())?;
+        
+        Ok(Instruction {
+            source_range: SourceRange::combine(start, value.source_range),
+            instruction_kind: InstructionKind::Declaration(Declaration::Const { name, data_type, value: Box::new(value) }),
+            ..default()
+        })
+    }
+
+
+    fn enum_declaration(&mut self) -> ParseResult {
+        self.expect(&TokenKind::Keyword(Keyword::Enum))?;
+        let start = self.current_range();
+        self.advance();
+
+        let name = self.expect_identifier()?;
+        self.advance();
+
+        self.expect(&TokenKind::LeftBracket)?;
+        self.advance();
+
+        let mut variants = vec![];
+        loop {
+            if self.expect(&TokenKind::RightBracket).is_ok() {
+                break
+            }
+
+            if !variants.is_empty() {
+                self.expect(&TokenKind::Comma)?;
+                self.advance();
+            }
+
+            if self.expect(&TokenKind::RightBracket).is_ok() {
+                break
+            }
+
+            let variant_start = self.current_range();
+            let variant = self.expect_identifier()?;
+
+            let discriminant = if self.peek().map(|x| x.token_kind) == Some(TokenKind::Equals) {
+                self.advance();
+                self.advance();
+
+                Some(self.enum_discriminant()?)
+            } else { None };
+
+            variants.push((variant, discriminant, SourceRange::combine(variant_start, self.current_range())));
+            self.advance();
+        }
+
+        self.expect(&TokenKind::RightBracket)?;
+
+        Ok(Instruction {
+            instruction_kind: InstructionKind::Declaration(Declaration::Enum { name, variants }),
+            source_range: SourceRange::combine(start, self.current_range()),
+            ..default()
+        })
+    }
+
+
+    /// Parses an explicit discriminant of an enum
+    /// variant, i.e. an integer or a negative integer
+    fn enum_discriminant(&mut self) -> Result<i64, Error> {
+        let negative = self.current_kind() == TokenKind::Minus;
+
+        if negative {
+            self.advance();
+        }
+
+        match self.current_kind() {
+            TokenKind::Literal(Literal::Integer(i) | Literal::I64(i)) => Ok(if negative { -i } else { i }),
+
+            _ => Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
+                .highlight(self.current_range())
+                    .note("expected an integer discriminant".to_string())
+                .build())
+        }
+    }
+
+
     fn loop_statement(&mut self) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::Loop))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
         
         self.expect(&TokenKind::LeftBracket)?;
@@ -567,8 +842,35 @@ impl Parser<'_> {
         let body = self.parse_till(&TokenKind::RightBracket)?;
 
         Ok(Instruction {
-            instruction_kind: InstructionKind::Statement(Statement::Loop { body }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            instruction_kind: InstructionKind::Statement(Statement::Loop { body, label: None }),
+            source_range: SourceRange::combine(start, self.current_range()),
+            ..default()
+        })
+    }
+
+
+    /// Parses `loop.index`, the iteration counter
+    /// of the innermost loop
+    fn loop_index(&mut self) -> ParseResult {
+        self.expect(&TokenKind::Keyword(Keyword::Loop))?;
+        let start = self.current_range();
+        self.advance();
+
+        self.expect(&TokenKind::Dot)?;
+        self.advance();
+
+        let property = self.expect_identifier()?;
+
+        if self.symbol_table.get(&property) != "index" {
+            return Err(CompilerError::new(self.file, 111, "unknown loop property", Severity::Error)
+                .highlight(self.current_range())
+                    .note("the only property of a loop is `index`".to_string())
+                .build())
+        }
+
+        Ok(Instruction {
+            instruction_kind: InstructionKind::Expression(Expression::LoopIndex),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
@@ -576,81 +878,378 @@ impl Parser<'_> {
 
     fn while_statement(&mut self) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::While))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
-        let condition = self.comparison_expression(ParserSettings { can_parse_struct_creation: false })?;
-        self.advance();
+        let condition = self.expression(ParserSettings { can_parse_struct_creation: false })?;
+        self.advance();
+
+        self.expect(&TokenKind::LeftBracket)?;
+        self.advance();
+
+        let body = self.parse_till(&TokenKind::RightBracket)?;
+
+        let source_range = SourceRange::combine(start, self.current_range());
+
+        
+        // This converts the usual while statement into a loop
+        // i.e.
+        // 
+        // while x > 15 {
+        //    do_stuff()
+        // }
+        //
+        // into:
+        //
+        // loop {
+        //     if x > 15 {
+        //        do_stuff()
+        //     } else {
+        //        break
+        // }
+        //     }
+        
+        // The synthesized nodes get the span of the tokens they
+        // stand in for instead of the whole loop: the `if` spans
+        // the condition and the body, and the `else { break }`
+        // points at the `while` keyword
+        let if_statement = Instruction {
+            source_range: SourceRange::combine(condition.source_range, source_range),
+            instruction_kind: InstructionKind::Expression(Expression::IfExpression {
+                body,
+                condition: Box::new(condition),
+                else_part: Some(Box::new(Instruction {
+                    instruction_kind: InstructionKind::Expression(Expression::Block {
+                        body: vec![Instruction {
+                            instruction_kind: InstructionKind::Statement(Statement::Break(None)),
+                            source_range: start,
+                            ..default()
+                        }]
+                    }),
+                    source_range: start,
+                    ..default()
+                })),
+            }),
+            ..default()
+        };
+        
+        Ok(Instruction {
+            instruction_kind: InstructionKind::Statement(Statement::Loop { body: vec![if_statement], label: None }),
+            source_range,
+            ..default()
+        })
+    }
+    
+
+    fn for_statement(&mut self) -> ParseResult {
+        self.expect(&TokenKind::Keyword(Keyword::For))?;
+        let start = self.current_range();
+        self.advance();
+
+        let identifier = self.expect_identifier()?;
+        let identifier_range = self.current_range();
+        self.advance();
+
+        self.expect(&TokenKind::Keyword(Keyword::In))?;
+        self.advance();
+
+        let range = self.range_expression()?;
+        self.advance();
+
+        self.expect(&TokenKind::LeftBracket)?;
+        self.advance();
+
+        let body = self.parse_till(&TokenKind::RightBracket)?;
+
+        let source_range = SourceRange::combine(start, self.current_range());
+        let range_range = range.source_range;
+
+        let (end, inclusive) = match &range.instruction_kind {
+            InstructionKind::Expression(Expression::Range { end, inclusive, .. }) => ((**end).clone(), *inclusive),
+            _ => unreachable!(),
+        };
+
+
+        // This converts the for loop into a loop
+        // i.e.
+        //
+        // for i in 0..10 {
+        //     do_stuff()
+        // }
+        //
+        // into:
+        //
+        // {
+        //     var #index = 0..10
+        //     var #end = #index
+        //     #end = 10
+        //
+        //     loop {
+        //         if #index < #end {
+        //             var i = #index
+        //             #index = #index + 1
+        //             do_stuff()
+        //         } else {
+        //             break
+        //         }
+        //     }
+        // }
+        //
+        // Inclusive ranges can end on the largest value of their
+        // type, where `#index + 1` wraps around and `#index <= #end`
+        // would hold forever. They check whether there's another
+        // iteration before incrementing instead. `#more` is set at
+        // the start of the body so `continue` doesn't skip it
+        //
+        // {
+        //     var #index = 0..=10
+        //     var #end = #index
+        //     #end = 10
+        //     var #more = #index <= #end
+        //
+        //     loop {
+        //         if #more {
+        //             var i = #index
+        //             #more = #index != #end
+        //             #index = #index + 1
+        //             do_stuff()
+        //         } else {
+        //             break
+        //         }
+        //     }
+        // }
+        //
+        // The range itself is replaced with its start during
+        // analysis, after the types of the bounds are unified.
+        // `#end` is assigned separately so that it's evaluated
+        // only once but still has the type of the range.
+        //
+        // The names of the hidden variables aren't valid
+        // identifiers so they can't clash with user code.
+        //
+        // Every synthesized node gets the span of the tokens it
+        // stands in for: the bookkeeping of `#index` points at the
+        // loop variable, the bounds check at the range and the
+        // `else { break }` at the `for` keyword
+
+        let index_identifier = self.symbol_table.add(String::from("#index"));
+        let end_identifier = self.symbol_table.add(String::from("#end"));
+        let more_identifier = self.symbol_table.add(String::from("#more"));
+
+        let instruction = |instruction_kind, source_range| Instruction {
+            instruction_kind,
+            source_range,
+            ..default()
+        };
+
+        let index = |source_range| instruction(InstructionKind::Expression(Expression::Identifier(index_identifier)), source_range);
+        let end_of = |source_range| instruction(InstructionKind::Expression(Expression::Identifier(end_identifier)), source_range);
+        let more = |source_range| instruction(InstructionKind::Expression(Expression::Identifier(more_identifier)), source_range);
+        let compare = |operator, source_range| instruction(InstructionKind::Expression(Expression::BinaryOp {
+            operator,
+            left: Box::new(index(source_range)),
+            right: Box::new(end_of(source_range)),
+        }), source_range);
+
+        let mut if_body = vec![
+            instruction(InstructionKind::Statement(Statement::DeclareVar {
+                identifier,
+                type_hint: None,
+                data: Box::new(index(identifier_range)),
+            }), identifier_range),
+        ];
+
+        if inclusive {
+            if_body.push(instruction(InstructionKind::Statement(Statement::VariableUpdate {
+                left: Box::new(more(range_range)),
+                right: Box::new(compare(BinaryOperator::NotEquals, range_range)),
+            }), range_range));
+        }
+
+        if_body.push(
+            instruction(InstructionKind::Statement(Statement::VariableUpdate {
+                left: Box::new(index(identifier_range)),
+                right: Box::new(instruction(InstructionKind::Expression(Expression::BinaryOp {
+                    operator: BinaryOperator::Add,
+                    left: Box::new(index(identifier_range)),
+                    right: Box::new(instruction(InstructionKind::Expression(Expression::Data(SourcedData::new(identifier_range, Data::I64(1)))), identifier_range)),
+                }), identifier_range)),
+            }), identifier_range),
+        );
+
+        if_body.extend(body);
+
+        let condition = if inclusive { more(range_range) } else { compare(BinaryOperator::LesserThan, range_range) };
+
+        let if_statement = instruction(InstructionKind::Expression(Expression::IfExpression {
+            body: if_body,
+            condition: Box::new(condition),
+            else_part: Some(Box::new(instruction(InstructionKind::Expression(Expression::Block {
+                body: vec![instruction(InstructionKind::Statement(Statement::Break(None)), start)]
+            }), start))),
+        }), SourceRange::combine(range_range, source_range));
+
+        let mut block = vec![
+            instruction(InstructionKind::Statement(Statement::DeclareVar {
+                identifier: index_identifier,
+                type_hint: None,
+                data: Box::new(range),
+            }), range_range),
+
+            instruction(InstructionKind::Statement(Statement::DeclareVar {
+                identifier: end_identifier,
+                type_hint: None,
+                data: Box::new(index(range_range)),
+            }), range_range),
+
+            instruction(InstructionKind::Statement(Statement::VariableUpdate {
+                left: Box::new(end_of(range_range)),
+                right: Box::new(end),
+            }), range_range),
+        ];
+
+        if inclusive {
+            block.push(instruction(InstructionKind::Statement(Statement::DeclareVar {
+                identifier: more_identifier,
+                type_hint: None,
+                data: Box::new(compare(BinaryOperator::LesserEquals, range_range)),
+            }), range_range));
+        }
+
+        block.push(instruction(InstructionKind::Statement(Statement::Loop { body: vec![if_statement], label: None }), source_range));
+
+        Ok(instruction(InstructionKind::Expression(Expression::Block { body: block }), source_range))
+    }
+
+
+    /// Parses a range like `0..10` or `0..=10`. Ranges can
+    /// only be used as the iterator of a `for` loop
+    fn range_expression(&mut self) -> ParseResult {
+        let settings = ParserSettings { can_parse_struct_creation: false };
+
+        let start = self.arithmetic_expression(settings)?;
+        self.advance();
+
+        let inclusive = match self.current_kind() {
+            TokenKind::DoubleDot => false,
+            TokenKind::DoubleDotEquals => true,
+
+            _ => return Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
+                .highlight(self.current_range())
+                    .note("expected a range like `0..10` or `0..=10`".to_string())
+                .build())
+        };
+        self.advance();
+
+        let end = self.arithmetic_expression(settings)?;
+
+        Ok(Instruction {
+            source_range: SourceRange::combine(start.source_range, end.source_range),
+            instruction_kind: InstructionKind::Expression(Expression::Range {
+                start: Box::new(start),
+                end: Box::new(end),
+                inclusive,
+            }),
+            ..default()
+        })
+    }
+    
+
+    fn var_update(&mut self) -> ParseResult {
+        let left = self.expression(default())?;
+
+        let operator = match self.peek_kind() {
+            Some(TokenKind::Equals) => None,
+            Some(TokenKind::AddEquals) => Some(BinaryOperator::Add),
+            Some(TokenKind::SubEquals) => Some(BinaryOperator::Subtract),
+            Some(TokenKind::MulEquals) => Some(BinaryOperator::Multiply),
+            Some(TokenKind::DivEquals) => Some(BinaryOperator::Divide),
+            Some(TokenKind::ModEquals) => Some(BinaryOperator::Modulo),
+
+            _ => return Ok(left),
+        };
 
-        self.expect(&TokenKind::LeftBracket)?;
+        self.advance(); // = or the compound assignment
         self.advance();
 
-        let body = self.parse_till(&TokenKind::RightBracket)?;
+        let right = self.expression(default())?;
+        let source_range = SourceRange::combine(left.source_range, right.source_range);
 
-        let source_range = SourceRange::new(start, self.current_token().unwrap().source_range.end);
+        let Some(operator) = operator else { return self.assignment(left, right, source_range) };
 
-        
-        // This converts the usual while statement into a loop
+
+        // This converts a compound assignment into an assignment
+        // i.e. `x += 5` into `x = x + 5`
+        //
+        // Anything the target is accessed through is stored in
+        // a hidden variable first so it's only evaluated once
         // i.e.
-        // 
-        // while x > 15 {
-        //    do_stuff()
-        // }
+        //
+        // foo().values[bar()] += 5
         //
         // into:
         //
-        // loop {
-        //     if x > 15 {
-        //        do_stuff()
-        //     } else {
-        //        break
+        // {
+        //     var #target = foo().values
+        //     var #index = bar()
+        //     #target[#index] = #target[#index] + 5
         // }
-        //     }
-        
-        let if_statement = Instruction {
-            instruction_kind: InstructionKind::Expression(Expression::IfExpression {
-                body,
-                condition: Box::new(condition),
-                else_part: Some(Box::new(Instruction {
-                    instruction_kind: InstructionKind::Expression(Expression::Block {
-                        body: vec![Instruction {
-                            instruction_kind: InstructionKind::Statement(Statement::Break),
-                            source_range,
-                            ..default()
-                        }]
-                    }),
-                    source_range,
-                    ..default()
-                })),
+
+        let mut hoisted = vec![];
+        let left = match left.instruction_kind {
+            InstructionKind::Expression(Expression::AccessStructureData { structure, identifier, index_to }) => {
+                let structure = self.hoist("#target", *structure, &mut hoisted);
+
+                Instruction {
+                    instruction_kind: InstructionKind::Expression(Expression::AccessStructureData { structure: Box::new(structure), identifier, index_to }),
+                    ..left
+                }
+            },
+
+            InstructionKind::Expression(Expression::Index { value, index }) => {
+                let value = self.hoist("#target", *value, &mut hoisted);
+                let index = self.hoist("#index", *index, &mut hoisted);
+
+                Instruction {
+                    instruction_kind: InstructionKind::Expression(Expression::Index { value: Box::new(value), index: Box::new(index) }),
+                    ..left
+                }
+            },
+
+            instruction_kind => Instruction { instruction_kind, ..left },
+        };
+
+        let right = Instruction {
+            instruction_kind: InstructionKind::Expression(Expression::BinaryOp {
+                operator,
+                left: Box::new(left.clone()),
+                right: Box::new(right),
             }),
             source_range,
             ..default()
         };
-        
+
+        let assignment = self.assignment(left, right, source_range)?;
+
+        if hoisted.is_empty() {
+            return Ok(assignment)
+        }
+
+        hoisted.push(assignment);
+
         Ok(Instruction {
-            instruction_kind: InstructionKind::Statement(Statement::Loop { body: vec![if_statement] }),
+            instruction_kind: InstructionKind::Expression(Expression::Block { body: hoisted }),
             source_range,
             ..default()
         })
     }
-    
-
-    fn var_update(&mut self) -> ParseResult {
-        let left = self.expression(default())?;
-
-        if self.peek().is_none() || self.peek().unwrap().token_kind != TokenKind::Equals {
-            return Ok(left)
-        }
-
-        self.advance(); // =
-        self.advance();
 
-        let right = self.expression(default())?;
 
+    fn assignment(&self, left: Instruction, right: Instruction, source_range: SourceRange) -> ParseResult {
         match left.instruction_kind {
             InstructionKind::Expression(Expression::Identifier(_)) => {
                 Ok(Instruction {
-                    source_range: SourceRange::new(left.source_range.start, right.source_range.end), 
+                    source_range, 
                     instruction_kind: InstructionKind::Statement(Statement::VariableUpdate { 
                         left: Box::new(left), 
                         right: Box::new(right)
@@ -662,7 +1261,7 @@ impl Parser<'_> {
 
             InstructionKind::Expression(Expression::AccessStructureData { structure, identifier, index_to }) => {
                 Ok(Instruction {
-                    source_range: SourceRange::new(left.source_range.start, right.source_range.end), 
+                    source_range, 
                     instruction_kind: InstructionKind::Statement(Statement::FieldUpdate {
                         structure,
                         right: Box::new(right),
@@ -674,20 +1273,66 @@ impl Parser<'_> {
             }
 
             
-            _ => Err(CompilerError::new(self.file, 103, "invalid assignment value")
+            InstructionKind::Expression(Expression::Index { value, index }) => {
+                Ok(Instruction {
+                    source_range, 
+                    instruction_kind: InstructionKind::Statement(Statement::IndexUpdate {
+                        value,
+                        index,
+                        right: Box::new(right),
+                    }),
+                    ..default()
+                })
+            }
+
+            
+            _ => Err(CompilerError::new(self.file, 103, "invalid assignment value", Severity::Error)
                     .highlight(left.source_range)
-                        .note("this is not one of the following: identifier, field access".to_string())
+                        .note("this is not one of the following: identifier, field access, index".to_string())
                     .build()
             )
         }
+    }
+
+
+    /// Declares a hidden variable named `name` holding `value`
+    /// unless it's already free to evaluate again, returning
+    /// an instruction that can be used in place of `value`
+    fn hoist(&mut self, name: &str, value: Instruction, hoisted: &mut Vec<Instruction>) -> Instruction {
+        if matches!(value.instruction_kind, InstructionKind::Expression(Expression::Identifier(_) | Expression::Data(_))) {
+            return value
+        }
 
+        let identifier = self.symbol_table.add(String::from(name));
+        let source_range = value.source_range;
+
+        hoisted.push(Instruction {
+            instruction_kind: InstructionKind::Statement(Statement::DeclareVar {
+                identifier,
+                type_hint: None,
+                data: Box::new(value),
+            }),
+            source_range,
+            ..default()
+        });
+
+        Instruction {
+            instruction_kind: InstructionKind::Expression(Expression::Identifier(identifier)),
+            source_range,
+            ..default()
+        }
     }
 
 
     fn namespace_declaration(&mut self) -> ParseResult {
         fn namespace_rename(symbol_table: &mut SymbolTable, namespace: SymbolIndex, i: &mut Instruction) {
             match &mut i.instruction_kind {
-                InstructionKind::Declaration(Declaration::FunctionDeclaration { name, .. } | Declaration::StructDeclaration { name, .. }) => {
+                InstructionKind::Declaration(
+                    | Declaration::FunctionDeclaration { name, .. }
+                    | Declaration::StructDeclaration { name, .. }
+                    | Declaration::Const { name, .. }
+                    | Declaration::Enum { name, .. }
+                ) => {
                     *name = symbol_table.add_combo(namespace, *name);
                 }
                 
@@ -709,7 +1354,7 @@ impl Parser<'_> {
         }
         
         self.expect(&TokenKind::Keyword(Keyword::Namespace))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
         let identifier = self.expect_identifier()?;
@@ -736,11 +1381,13 @@ impl Parser<'_> {
                 TokenKind::Keyword(Keyword::Fn) => self.function_declaration(None),
                 TokenKind::Keyword(Keyword::Struct) => self.struct_declaration(),
                 TokenKind::Keyword(Keyword::Extern) => self.extern_block(None),
+                TokenKind::Keyword(Keyword::Const) => self.const_declaration(),
+                TokenKind::Keyword(Keyword::Enum) => self.enum_declaration(),
 
                 
-                _ => Err(CompilerError::new(self.file, 105, "invalid statement in namespace")
+                _ => Err(CompilerError::new(self.file, 105, "invalid statement in namespace", Severity::Error)
                     .highlight(token.source_range)
-                        .note("only the following are allowed: function declarations, namespaces, structure declarations".to_string())
+                        .note("only the following are allowed: function declarations, namespaces, structure declarations, constants, enums".to_string())
                     .build())
             };
 
@@ -764,20 +1411,20 @@ impl Parser<'_> {
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Declaration(Declaration::Namespace { body, identifier }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
 
 
-    fn extern_block(&mut self, mut impl_type: Option<SourcedDataType>) -> ParseResult {
+    fn extern_block(&mut self, impl_type: Option<SourcedDataType>) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::Extern))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
         let path = match self.current_token().map(|x| x.token_kind).unwrap() {
             TokenKind::Literal(Literal::String(v)) => v,
-            _ => return Err(CompilerError::new(self.file, 107, "expected a constant string")
+            _ => return Err(CompilerError::new(self.file, 107, "expected a constant string", Severity::Error)
                     .highlight(self.current_token().unwrap().source_range)
                         .note("..because of the `extern` keyword before".to_string())
                     .build())
@@ -811,6 +1458,8 @@ impl Parser<'_> {
             self.advance();
 
             
+            // Only the first `self` of each function gets replaced
+            let mut self_type = impl_type.clone();
             let mut arguments = vec![];
             loop {
                 if self.expect(&TokenKind::RightParenthesis).is_ok() {
@@ -829,11 +1478,11 @@ impl Parser<'_> {
                 let mut data_type = self.parse_type()?;
                 self.advance();
 
-                if impl_type.is_some() {
+                if self_type.is_some() {
                     let symbol_index = data_type.data_type.symbol_index(self.symbol_table);
                     if symbol_index == self_kw {
-                        data_type = impl_type.unwrap();
-                        impl_type = None;
+                        data_type = self_type.unwrap();
+                        self_type = None;
                     }
                 }
 
@@ -847,7 +1496,7 @@ impl Parser<'_> {
                 self.advance();
                 self.advance();
                 self.parse_type()?
-            } else { SourcedDataType::new(SourceRange::new(start, self.current_token().unwrap().source_range.end), DataType::Empty) };
+            } else { SourcedDataType::new(SourceRange::combine(start, self.current_range()), DataType::Empty) };
 
             self.advance();
 
@@ -863,7 +1512,7 @@ impl Parser<'_> {
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Declaration(Declaration::Extern { file: path, functions }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
@@ -871,14 +1520,14 @@ impl Parser<'_> {
 
     fn using_declaration(&mut self) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::Using))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
         let string = self.expect_identifier()?;
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Declaration(Declaration::UseFile { file_name: string }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
@@ -887,7 +1536,12 @@ impl Parser<'_> {
     fn impl_block(&mut self) -> ParseResult {
         fn namespace_rename(symbol_table: &mut SymbolTable, namespace: SymbolIndex, i: &mut Instruction) {
             match &mut i.instruction_kind {
-                InstructionKind::Declaration(Declaration::FunctionDeclaration { name, .. } | Declaration::StructDeclaration { name, .. }) => {
+                InstructionKind::Declaration(
+                    | Declaration::FunctionDeclaration { name, .. }
+                    | Declaration::StructDeclaration { name, .. }
+                    | Declaration::Const { name, .. }
+                    | Declaration::Enum { name, .. }
+                ) => {
                     *name = symbol_table.add_combo(namespace, *name);
                 }
                 
@@ -910,7 +1564,7 @@ impl Parser<'_> {
 
 
         self.expect(&TokenKind::Keyword(Keyword::Impl))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
         let impl_type = self.parse_type()?;
@@ -939,7 +1593,7 @@ impl Parser<'_> {
                 TokenKind::Keyword(Keyword::Extern) => self.extern_block(Some(impl_type.clone())),
 
                 
-                _ => Err(CompilerError::new(self.file, 105, "invalid statement in impl block")
+                _ => Err(CompilerError::new(self.file, 105, "invalid statement in impl block", Severity::Error)
                     .highlight(token.source_range)
                         .note("only the following are allowed: function declarations, namespaces, structure declarations".to_string())
                     .build())
@@ -965,7 +1619,7 @@ impl Parser<'_> {
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Declaration(Declaration::ImplBlock { body, datatype: impl_type }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
         
@@ -974,94 +1628,58 @@ impl Parser<'_> {
 
 impl Parser<'_> {
     fn expression(&mut self, settings: ParserSettings) -> ParseResult {
-        macro_rules! def_binary_assign {
-            ($start: expr, $left: expr, $token: expr, $operator: expr) => {
-                if self.current_kind() == $token {
-                    let token_pos = self.current_range();
-                    self.advance();
+        self.nested(|parser| parser.logical_or_expression(settings))
+    }
 
-                    let right = self.expression(default())?;
 
-                    let expr = Instruction {
-                        instruction_kind: InstructionKind::Expression(Expression::BinaryOp { 
-                            operator: BinaryOperator::from_token(&$operator).unwrap(), 
-                            left: Box::new($left.clone()),
-                            right: Box::new(right)
-                        }),
-                        source_range: SourceRange::new($start.start, self.current_range().end),
-                        ..default()
-                    };
-                    
-                    return Ok(Instruction {
-                        source_range: expr.source_range,
-                        instruction_kind: InstructionKind::Statement(Statement::VariableUpdate {
-                            left: Box::new($left),
-                            right: Box::new(expr)
-                        }),
-                        ..default()
-                    })
-                }
-            };
-        }
+    /// Runs `func` one level of nesting deeper, erroring
+    /// instead if that goes past the nesting limit so
+    /// pathological input can't overflow the stack
+    fn nested(&mut self, func: impl FnOnce(&mut Self) -> ParseResult) -> ParseResult {
+        self.enter_nesting()?;
+        let result = func(self);
+        self.depth -= 1;
 
-        
-        let start = self.current_range();
-        let left_val = self.logical_or_expression(settings)?;
-
-        const ASSIGN_TOKENS : [TokenKind; 4] = [
-            TokenKind::AddEquals,
-            TokenKind::SubEquals,
-            TokenKind::MulEquals,
-            TokenKind::DivEquals,
-            // TokenKind::ModEquals,
-            // TokenKind::BitOrAssign,
-            // TokenKind::BitAndAssign,
-            // TokenKind::BitXORAssign,
-            // TokenKind::LeftShiftAssign,
-            // TokenKind::RightShiftAssign,
-        ];
+        result
+    }
 
-        if self.peek_kind().map(|x| ASSIGN_TOKENS.contains(&x)).unwrap_or(false) {
-            self.advance();
-            def_binary_assign!(start, left_val, TokenKind::AddEquals, TokenKind::Plus);
-            def_binary_assign!(start, left_val, TokenKind::SubEquals, TokenKind::Minus);
-            def_binary_assign!(start, left_val, TokenKind::MulEquals, TokenKind::Star);
-            def_binary_assign!(start, left_val, TokenKind::DivEquals, TokenKind::Slash);
-            // def_binary_assign!(start, left_val, TokenKind::ModEquals, TokenKind::Percent);
-            // def_binary_assign!(start, left_val, TokenKind::BitOrAssign, TokenKind::BitwiseOR);
-            // def_binary_assign!(start, left_val, TokenKind::BitAndAssign, TokenKind::BitwiseAND);
-            // def_binary_assign!(start, left_val, TokenKind::BitXORAssign, TokenKind::BitwiseXOR);
-            // def_binary_assign!(start, left_val, TokenKind::LeftShiftAssign, TokenKind::LeftShift);
-            // def_binary_assign!(start, left_val, TokenKind::RightShiftAssign, TokenKind::RightShift);
-
-            unreachable!()
 
-        } else {
-            Ok(left_val)
+    fn enter_nesting(&mut self) -> Result<(), Error> {
+        if self.depth >= self.max_depth {
+            return Err(CompilerError::new(self.file, 110, "expression nesting too deep", Severity::Error)
+                .highlight(self.current_range())
+                    .note(format!("expressions can only be nested {} levels deep", self.max_depth))
+                .build())
         }
+
+        self.depth += 1;
+        Ok(())
     }
 
 
     fn logical_and_expression(&mut self, settings: ParserSettings) -> ParseResult {
-        let expr = self.logical_or_expression(settings)?;
+        let expr = self.comparison_expression(settings)?;
         if self.peek().map(|x| x.token_kind) != Some(TokenKind::LogicalAnd) {
             return Ok(expr)
         }
 
         self.advance();
+        let operator_range = self.current_range();
         self.advance();
 
-        let oth_expr = self.logical_and_expression(settings)?;
+        let oth_expr = self.nested(|parser| parser.logical_and_expression(settings))?;
         let source_range = SourceRange::combine(expr.source_range, oth_expr.source_range);
 
+        // The synthesized `false` points at the `&&` itself so that
+        // errors about it don't highlight the operands as well
         Ok(Instruction { 
             source_range,
             instruction_kind: InstructionKind::Expression(Expression::IfExpression {
                 body: vec![oth_expr],
                 condition: Box::new(expr),
                 else_part: Some(Box::new(Instruction {
-                    instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(source_range, Data::Bool(false)))),
-                    source_range,
+                    instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(operator_range, Data::Bool(false)))),
+                    source_range: operator_range,
                     ..default()
                 }))
             }),
@@ -1071,23 +1689,24 @@ impl Parser<'_> {
 
     
     fn logical_or_expression(&mut self, settings: ParserSettings) -> ParseResult {
-        let expr = self.comparison_expression(settings)?;
+        let expr = self.logical_and_expression(settings)?;
         if self.peek().map(|x| x.token_kind) != Some(TokenKind::LogicalOr) {
             return Ok(expr)
         }
 
         self.advance();
+        let operator_range = self.current_range();
         self.advance();
 
-        let oth_expr = self.logical_or_expression(settings)?;
+        let oth_expr = self.nested(|parser| parser.logical_or_expression(settings))?;
         let source_range = SourceRange::combine(expr.source_range, oth_expr.source_range);
 
         Ok(Instruction { 
             source_range,
             instruction_kind: InstructionKind::Expression(Expression::IfExpression {
                 body: vec![Instruction {
-                    instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(source_range, Data::Bool(true)))),
-                    source_range,
+                    instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(operator_range, Data::Bool(true)))),
+                    source_range: operator_range,
                     ..default()
                 }],
                 condition: Box::new(expr),
@@ -1100,8 +1719,8 @@ impl Parser<'_> {
 
     fn comparison_expression(&mut self, settings: ParserSettings) -> ParseResult {
         self.binary_operation(
-            Parser::arithmetic_expression,
-            Parser::arithmetic_expression,
+            Parser::bitwise_or_expression,
+            Parser::bitwise_or_expression,
             settings,
             &[
                 TokenKind::LeftAngle,
@@ -1114,6 +1733,45 @@ impl Parser<'_> {
         )
     }
 
+    fn bitwise_or_expression(&mut self, settings: ParserSettings) -> ParseResult {
+        self.binary_operation(
+            Parser::bitwise_xor_expression,
+            Parser::bitwise_xor_expression,
+            settings,
+            &[TokenKind::Pipe],
+        )
+    }
+
+    fn bitwise_xor_expression(&mut self, settings: ParserSettings) -> ParseResult {
+        self.binary_operation(
+            Parser::bitwise_and_expression,
+            Parser::bitwise_and_expression,
+            settings,
+            &[TokenKind::Caret],
+        )
+    }
+
+    fn bitwise_and_expression(&mut self, settings: ParserSettings) -> ParseResult {
+        self.binary_operation(
+            Parser::shift_expression,
+            Parser::shift_expression,
+            settings,
+            &[TokenKind::Ampersand],
+        )
+    }
+
+    fn shift_expression(&mut self, settings: ParserSettings) -> ParseResult {
+        self.binary_operation(
+            Parser::arithmetic_expression,
+            Parser::arithmetic_expression,
+            settings,
+            &[
+                TokenKind::DoubleLeftAngle,
+                TokenKind::DoubleRightAngle,
+            ],
+        )
+    }
+
     fn arithmetic_expression(&mut self, settings: ParserSettings) -> ParseResult {
         self.binary_operation(
             Parser::product_expression, 
@@ -1153,7 +1811,7 @@ impl Parser<'_> {
         let cast_type = self.parse_type()?;
 
         Ok(Instruction { 
-            source_range: SourceRange::new(unary.source_range.start, cast_type.source_range.end),
+            source_range: SourceRange::combine(unary.source_range, cast_type.source_range),
             instruction_kind: InstructionKind::Expression(Expression::AsCast {
                 value: Box::new(unary),
                 cast_type,
@@ -1164,11 +1822,11 @@ impl Parser<'_> {
 
 
     fn unary_expression(&mut self, settings: ParserSettings) -> ParseResult {
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         let (op, val) = match self.current_token().unwrap().token_kind {
             TokenKind::Bang => {
                 self.advance();
-                let val = self.unary_expression(settings)?;
+                let val = self.nested(|parser| parser.unary_expression(settings))?;
 
                 (UnaryOperator::Not, val)
             },
@@ -1176,18 +1834,26 @@ impl Parser<'_> {
             
             TokenKind::Minus => {
                 self.advance();
-                let val = self.unary_expression(settings)?;
+                let val = self.nested(|parser| parser.unary_expression(settings))?;
 
                 (UnaryOperator::Negate, val)
             }
 
             
+            TokenKind::Tilde => {
+                self.advance();
+                let val = self.nested(|parser| parser.unary_expression(settings))?;
+
+                (UnaryOperator::BitwiseNot, val)
+            }
+
+            
             _ => return self.accessor(settings)
         };
 
 
         Ok(Instruction {
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             instruction_kind: InstructionKind::Expression(Expression::UnaryOp { operator: op, value: Box::new(val) }),
             ..default()
         })
@@ -1197,13 +1863,36 @@ impl Parser<'_> {
     fn accessor(&mut self, settings: ParserSettings) -> ParseResult {
         let mut atom = self.atom(settings)?;
 
-        while let Some(TokenKind::Dot) = self.peek().map(|x| x.token_kind) {
+        loop {
+            let peek = self.peek().map(|x| x.token_kind);
+
+            if peek == Some(TokenKind::LeftSquare) {
+                self.advance();
+                self.advance();
+
+                let index = self.expression(default())?;
+                self.advance();
+                self.expect(&TokenKind::RightSquare)?;
+
+                atom = Instruction {
+                    source_range: SourceRange::combine(atom.source_range, self.current_range()),
+                    instruction_kind: InstructionKind::Expression(Expression::Index { value: Box::new(atom), index: Box::new(index) }),
+                    ..default()
+                };
+
+                continue
+            }
+
+            if peek != Some(TokenKind::Dot) {
+                break
+            }
+
             self.advance();
             self.advance();
             
             let identifier = self.expect_identifier()?;
 
-            if self.peek().map(|x| x.token_kind) != Some(TokenKind::LeftParenthesis) {
+            if self.peek_past_generics() != Some(TokenKind::LeftParenthesis) {
                 atom = Instruction {
                     source_range: SourceRange::combine(atom.source_range, self.current_token().unwrap().source_range),
                     instruction_kind: InstructionKind::Expression(Expression::AccessStructureData { structure: Box::new(atom), identifier, index_to: usize::MAX }),
@@ -1247,7 +1936,16 @@ impl Parser<'_> {
                 let data = match literal {
                     Literal::Integer(i) => Data::I64(i),
                     Literal::Float(f) => Data::Float(f),
+                    Literal::I8 (i) => Data::I8 (i),
+                    Literal::I16(i) => Data::I16(i),
+                    Literal::I32(i) => Data::I32(i),
+                    Literal::I64(i) => Data::I64(i),
+                    Literal::U8 (i) => Data::U8 (i),
+                    Literal::U16(i) => Data::U16(i),
+                    Literal::U32(i) => Data::U32(i),
+                    Literal::U64(i) => Data::U64(i),
                     Literal::String(s) => Data::String(s),
+                    Literal::Char(c) => Data::Char(c),
                     Literal::Bool(b) => Data::Bool(b),
                 };
 
@@ -1260,6 +1958,8 @@ impl Parser<'_> {
             
             
             TokenKind::Keyword(Keyword::If) => self.if_expression(),
+            TokenKind::Keyword(Keyword::Match) => self.match_expression(),
+            TokenKind::Keyword(Keyword::Loop) => self.loop_index(),
             
             
             TokenKind::Identifier(_) => {
@@ -1271,18 +1971,7 @@ impl Parser<'_> {
                 };
 
                 
-                if let Some(mut v) = self.peek().map(|x| x.token_kind) {
-                    if v == TokenKind::LeftSquare {
-                        let index = self.index;
-
-                        self.advance();
-                        self.parse_generics_for_expression()?;
-                        v = self.current_token().unwrap().token_kind;
-
-                        self.index = index;
-                    }
-
-
+                if let Some(v) = self.peek_past_generics() {
                     if v == TokenKind::LeftParenthesis {
                         return self.function_call()
                     }
@@ -1308,11 +1997,11 @@ impl Parser<'_> {
 
 
             TokenKind::LeftParenthesis => {
-                let start = token.source_range.start;
+                let start = token.source_range;
                 self.advance();
 
                 if self.expect(&TokenKind::RightParenthesis).is_ok() {
-                    let source_range = SourceRange::new(start, self.current_token().unwrap().source_range.end);
+                    let source_range = SourceRange::combine(start, self.current_range());
                     return Ok(Instruction {
                         instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(source_range, Data::Empty))),
                         source_range,
@@ -1331,6 +2020,8 @@ impl Parser<'_> {
             
             TokenKind::LeftBracket => self.block_expression(),
 
+            TokenKind::LeftSquare => self.array_creation(),
+
             TokenKind::Underscore => Ok(Instruction {
                 instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(token.source_range, Data::Empty))),
                 source_range: token.source_range,
@@ -1340,7 +2031,7 @@ impl Parser<'_> {
 
             _ => {
                 let return_val = Err(
-                    CompilerError::new(self.file, 101, "expected an expression")
+                    CompilerError::new(self.file, 101, "expected an expression", Severity::Error)
                         .highlight(token.source_range)
                         .build()
                 );
@@ -1361,6 +2052,10 @@ impl<'a> Parser<'a> {
     ) -> ParseResult {
         let mut base = left_func(self, settings)?;
 
+        // Each operator in a chain like `a + a + a` nests the
+        // previous operations one level deeper
+        let depth = self.depth;
+
         loop {
             if self.peek().is_none() || !operators.contains(&self.peek().unwrap().token_kind) {
                 break
@@ -1372,10 +2067,16 @@ impl<'a> Parser<'a> {
 
             self.advance();
 
-            let right = right_func(self, settings)?;
+            let right = match self.enter_nesting().and_then(|()| right_func(self, settings)) {
+                Ok(v) => v,
+                Err(e) => {
+                    self.depth = depth;
+                    return Err(e)
+                }
+            };
 
             base = Instruction {
-                source_range: SourceRange::new(base.source_range.start, right.source_range.end),
+                source_range: SourceRange::combine(base.source_range, right.source_range),
                 instruction_kind: InstructionKind::Expression(Expression::BinaryOp {
                     operator,
                     left: Box::new(base),
@@ -1385,6 +2086,8 @@ impl<'a> Parser<'a> {
             }
         }
 
+        self.depth = depth;
+
         Ok(base)
     }
 }
@@ -1392,7 +2095,7 @@ impl<'a> Parser<'a> {
 impl Parser<'_> {
     fn block_expression(&mut self) -> ParseResult {
         self.expect(&TokenKind::LeftBracket)?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         
         self.advance();
 
@@ -1400,7 +2103,7 @@ impl Parser<'_> {
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Expression(Expression::Block { body }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
@@ -1408,7 +2111,7 @@ impl Parser<'_> {
 
     fn if_expression(&mut self) -> ParseResult {
         self.expect(&TokenKind::Keyword(Keyword::If))?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
         
         let condition = self.expression(ParserSettings { can_parse_struct_creation: false })?;
@@ -1419,7 +2122,7 @@ impl Parser<'_> {
         
         let block = self.parse_till(&TokenKind::RightBracket)?;
 
-        let if_end = self.current_token().unwrap().source_range.end;
+        let if_end = self.current_range();
 
         if self.peek().is_some() && self.peek().unwrap().token_kind == TokenKind::Keyword(Keyword::Else) {
             self.advance();
@@ -1432,7 +2135,7 @@ impl Parser<'_> {
             };
 
             return Ok(Instruction {
-                source_range: SourceRange::new(start, if_end),
+                source_range: SourceRange::combine(start, if_end),
                 instruction_kind: InstructionKind::Expression(Expression::IfExpression { body: block, condition: Box::new(condition), else_part: Some(Box::new(else_part)) }),
                 ..default()
             })
@@ -1440,16 +2143,120 @@ impl Parser<'_> {
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Expression(Expression::IfExpression { body: block, condition: Box::new(condition), else_part: None }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
         
     }
 
 
+    fn match_expression(&mut self) -> ParseResult {
+        self.expect(&TokenKind::Keyword(Keyword::Match))?;
+        let start = self.current_range();
+        self.advance();
+
+        let scrutinee = self.expression(ParserSettings { can_parse_struct_creation: false })?;
+        self.advance();
+
+        self.expect(&TokenKind::LeftBracket)?;
+        self.advance();
+
+        let mut arms = vec![];
+        let mut default_arm = None;
+
+        while self.current_kind() != TokenKind::RightBracket {
+            if default_arm.is_some() {
+                return Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
+                    .highlight(self.current_range())
+                        .note("the `_` arm has to be the last one".to_string())
+                    .build())
+            }
+
+            let pattern = match self.current_kind() {
+                TokenKind::Underscore => None,
+                _ => Some(self.match_pattern()?),
+            };
+            self.advance();
+
+            self.expect(&TokenKind::FatArrow)?;
+            self.advance();
+
+            let body = if self.current_kind() == TokenKind::LeftBracket {
+                self.advance();
+                self.parse_till(&TokenKind::RightBracket)?
+            } else {
+                vec![self.statement()?]
+            };
+            self.advance();
+
+            match pattern {
+                Some(pattern) => arms.push((pattern, body)),
+                None => default_arm = Some(body),
+            }
+
+            if self.current_kind() == TokenKind::Comma {
+                self.advance();
+            } else {
+                self.expect(&TokenKind::RightBracket)?;
+            }
+        }
+
+        Ok(Instruction {
+            instruction_kind: InstructionKind::Expression(Expression::Match { scrutinee: Box::new(scrutinee), arms, default: default_arm }),
+            source_range: SourceRange::combine(start, self.current_range()),
+            ..default()
+        })
+    }
+
+
+    /// Parses a constant pattern of a match arm, i.e. an
+    /// integer, a negative integer, a bool or a path to a
+    /// constant like an enum variant
+    fn match_pattern(&mut self) -> ParseResult {
+        let start = self.current_range();
+        let negative = self.current_kind() == TokenKind::Minus;
+
+        if negative {
+            self.advance();
+        }
+
+        let literal = match self.current_kind() {
+            TokenKind::Literal(literal) => literal,
+
+            // Whether it names a constant is up to the analyzer
+            TokenKind::Identifier(_) if !negative => return self.atom(default()),
+
+            _ => return Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
+                .highlight(self.current_range())
+                    .note("expected an integer, a bool, a constant or `_`".to_string())
+                .build())
+        };
+
+        let data = match (literal, negative) {
+            (Literal::Integer(i) | Literal::I64(i), true) => Data::I64(-i),
+            (Literal::I8 (i), true) => Data::I8 (-i),
+            (Literal::I16(i), true) => Data::I16(-i),
+            (Literal::I32(i), true) => Data::I32(-i),
+
+            (_, true) => return Err(CompilerError::new(self.file, 102, "unexpected token", Severity::Error)
+                .highlight(SourceRange::combine(start, self.current_range()))
+                    .note("only signed integers can be negated".to_string())
+                .build()),
+
+            (_, false) => return self.atom(default()),
+        };
+
+        Ok(Instruction {
+            instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(SourceRange::combine(start, self.current_range()), data))),
+            source_range: SourceRange::combine(start, self.current_range()),
+            ..default()
+        })
+    }
+
+
     fn function_call(&mut self) -> ParseResult {
         let identifier = self.expect_identifier()?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
         let generics = self.parse_generics_for_expression()?;
@@ -1488,7 +2295,7 @@ impl Parser<'_> {
                 created_by_accessing: false,
                 generics: generics.into(),
             }),
-            source_range: SourceRange::new(start, self.current_token().unwrap().source_range.end),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
@@ -1496,7 +2303,7 @@ impl Parser<'_> {
 
     fn structure_creation(&mut self) -> ParseResult {
         let identifier = self.expect_identifier()?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         let identifier_range = self.current_token().unwrap().source_range;
         self.advance();
 
@@ -1537,7 +2344,44 @@ impl Parser<'_> {
 
         Ok(Instruction {
             instruction_kind: InstructionKind::Expression(Expression::StructureCreation { identifier, fields, identifier_range, generics: generics.into() }),
-            source_range: SourceRange { start, end: self.current_token().unwrap().source_range.end },
+            source_range: SourceRange::combine(start, self.current_range()),
+            ..default()
+        })
+    }
+
+
+    fn array_creation(&mut self) -> ParseResult {
+        self.expect(&TokenKind::LeftSquare)?;
+        let start = self.current_range();
+        self.advance();
+
+        let mut elements = vec![];
+        loop {
+            if self.expect(&TokenKind::RightSquare).is_ok() {
+                break
+            }
+            
+            if !elements.is_empty() {
+                self.expect(&TokenKind::Comma)?;
+                self.advance();
+            }
+
+            if self.expect(&TokenKind::RightSquare).is_ok() {
+                break
+            }
+            
+            let expression = self.expression(default())?;
+
+            self.advance();
+            
+            elements.push(expression);
+        }
+        
+        self.expect(&TokenKind::RightSquare)?;
+
+        Ok(Instruction {
+            instruction_kind: InstructionKind::Expression(Expression::ArrayCreation { elements }),
+            source_range: SourceRange::combine(start, self.current_range()),
             ..default()
         })
     }
@@ -1545,7 +2389,7 @@ impl Parser<'_> {
 
     fn do_within_namespace(&mut self) -> ParseResult {
         let namespace = self.expect_identifier()?;
-        let start = self.current_token().unwrap().source_range.start;
+        let start = self.current_range();
         self.advance();
 
         self.expect(&TokenKind::DoubleColon)?;
@@ -1553,7 +2397,7 @@ impl Parser<'_> {
 
         let mut expression = self.expression(default())?;
 
-        expression.source_range.start = start;
+        expression.source_range = SourceRange::combine(start, expression.source_range);
         match &mut expression.instruction_kind {
             InstructionKind::Expression(v) => match v {
                 | Expression::StructureCreation { identifier, .. }
@@ -1561,9 +2405,13 @@ impl Parser<'_> {
                     *identifier = self.symbol_table.add_combo(namespace, *identifier)
                 },
 
-                _ => return Err(CompilerError::new(self.file, 105, "invalid expression in namespace")
+                Expression::Identifier(identifier) => {
+                    *identifier = self.symbol_table.add_combo(namespace, *identifier)
+                },
+
+                _ => return Err(CompilerError::new(self.file, 105, "invalid expression in namespace", Severity::Error)
                     .highlight(expression.source_range)
-                        .note("only function calls are allowed".to_string())
+                        .note("only function calls, structure creations & constants are allowed".to_string())
                     .build())
             },
             _ => unreachable!()
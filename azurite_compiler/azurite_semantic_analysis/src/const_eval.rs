@@ -0,0 +1,174 @@
+use azurite_parser::ast::{Instruction, InstructionKind, Expression, BinaryOperator, UnaryOperator};
+use common::{Data, DataType};
+
+
+/// Folds the analyzed initializer of a `const` into a literal
+///
+/// Returns `None` if the expression contains anything other than
+/// literals, casts & operators on them or if the evaluation
+/// overflows or divides by zero
+pub fn evaluate(instruction: &Instruction) -> Option<Data> {
+    let InstructionKind::Expression(expression) = &instruction.instruction_kind else { return None };
+
+    match expression {
+        Expression::Data(v) => Some(v.data.clone()),
+        Expression::UnaryOp { operator, value } => unary(operator, evaluate(value)?),
+        Expression::BinaryOp { operator, left, right } => binary(operator, evaluate(left)?, evaluate(right)?),
+        Expression::AsCast { value, cast_type } => cast(evaluate(value)?, &cast_type.data_type),
+
+        _ => None,
+    }
+}
+
+
+macro_rules! integer_operation {
+    ($left: expr, $right: expr, |$a: ident, $b: ident| $body: expr) => {
+        match ($left, $right) {
+            (Data::I8 ($a), Data::I8 ($b)) => $body.map(Data::I8),
+            (Data::I16($a), Data::I16($b)) => $body.map(Data::I16),
+            (Data::I32($a), Data::I32($b)) => $body.map(Data::I32),
+            (Data::I64($a), Data::I64($b)) => $body.map(Data::I64),
+            (Data::U8 ($a), Data::U8 ($b)) => $body.map(Data::U8),
+            (Data::U16($a), Data::U16($b)) => $body.map(Data::U16),
+            (Data::U32($a), Data::U32($b)) => $body.map(Data::U32),
+            (Data::U64($a), Data::U64($b)) => $body.map(Data::U64),
+
+            _ => None,
+        }
+    }
+}
+
+
+macro_rules! comparison {
+    ($left: expr, $right: expr, |$a: ident, $b: ident| $body: expr) => {
+        match ($left, $right) {
+            (Data::I8 ($a), Data::I8 ($b)) => Some(Data::Bool($body)),
+            (Data::I16($a), Data::I16($b)) => Some(Data::Bool($body)),
+            (Data::I32($a), Data::I32($b)) => Some(Data::Bool($body)),
+            (Data::I64($a), Data::I64($b)) => Some(Data::Bool($body)),
+            (Data::U8 ($a), Data::U8 ($b)) => Some(Data::Bool($body)),
+            (Data::U16($a), Data::U16($b)) => Some(Data::Bool($body)),
+            (Data::U32($a), Data::U32($b)) => Some(Data::Bool($body)),
+            (Data::U64($a), Data::U64($b)) => Some(Data::Bool($body)),
+
+            (Data::Float($a), Data::Float($b)) => Some(Data::Bool($body)),
+            (Data::Char ($a), Data::Char ($b)) => Some(Data::Bool($body)),
+            (Data::Bool ($a), Data::Bool ($b)) => Some(Data::Bool($body)),
+
+            _ => None,
+        }
+    }
+}
+
+
+fn binary(operator: &BinaryOperator, left: Data, right: Data) -> Option<Data> {
+    if let (Data::Float(a), Data::Float(b)) = (&left, &right) {
+        let (a, b) = (*a, *b);
+
+        match operator {
+            BinaryOperator::Add      => return Some(Data::Float(a + b)),
+            BinaryOperator::Subtract => return Some(Data::Float(a - b)),
+            BinaryOperator::Multiply => return Some(Data::Float(a * b)),
+            BinaryOperator::Divide   => return Some(Data::Float(a / b)),
+            // The runtime uses the euclidean remainder for floats
+            BinaryOperator::Modulo   => return Some(Data::Float(a.rem_euclid(b))),
+
+            _ => (),
+        }
+    }
+
+    match operator {
+        BinaryOperator::Add      => integer_operation!(left, right, |a, b| a.checked_add(b)),
+        BinaryOperator::Subtract => integer_operation!(left, right, |a, b| a.checked_sub(b)),
+        BinaryOperator::Multiply => integer_operation!(left, right, |a, b| a.checked_mul(b)),
+        BinaryOperator::Divide   => integer_operation!(left, right, |a, b| a.checked_div(b)),
+        BinaryOperator::Modulo   => integer_operation!(left, right, |a, b| a.checked_rem(b)),
+
+        BinaryOperator::BitwiseAnd => integer_operation!(left, right, |a, b| Some(a & b)),
+        BinaryOperator::BitwiseOr  => integer_operation!(left, right, |a, b| Some(a | b)),
+        BinaryOperator::BitwiseXor => integer_operation!(left, right, |a, b| Some(a ^ b)),
+
+        // The shift amount is masked to the bit width of
+        // the left side just like at runtime
+        BinaryOperator::ShiftLeft  => {
+            let amount = shift_amount(right)?;
+            integer_operation!(left.clone(), left, |a, _b| Some(a.wrapping_shl(amount)))
+        },
+
+        BinaryOperator::ShiftRight => {
+            let amount = shift_amount(right)?;
+            integer_operation!(left.clone(), left, |a, _b| Some(a.wrapping_shr(amount)))
+        },
+
+        // `Data` compares floats by their bits, which would make
+        // `0.0 == -0.0` false & `NaN == NaN` true unlike at runtime
+        BinaryOperator::Equals    => comparison!(&left, &right, |a, b| a == b).or(Some(Data::Bool(left == right))),
+        BinaryOperator::NotEquals => comparison!(&left, &right, |a, b| a != b).or(Some(Data::Bool(left != right))),
+
+        BinaryOperator::GreaterThan   => comparison!(left, right, |a, b| a > b),
+        BinaryOperator::LesserThan    => comparison!(left, right, |a, b| a < b),
+        BinaryOperator::GreaterEquals => comparison!(left, right, |a, b| a >= b),
+        BinaryOperator::LesserEquals  => comparison!(left, right, |a, b| a <= b),
+    }
+}
+
+
+/// The shift amount can be of any integer type
+fn shift_amount(amount: Data) -> Option<u32> {
+    match cast(amount, &DataType::U32)? {
+        Data::U32(v) => Some(v),
+        _ => None,
+    }
+}
+
+
+fn unary(operator: &UnaryOperator, value: Data) -> Option<Data> {
+    match (operator, value) {
+        (UnaryOperator::Not, Data::Bool(v)) => Some(Data::Bool(!v)),
+
+        (UnaryOperator::Negate, Data::Float(v)) => Some(Data::Float(-v)),
+        (UnaryOperator::Negate, Data::I8 (v)) => v.checked_neg().map(Data::I8),
+        (UnaryOperator::Negate, Data::I16(v)) => v.checked_neg().map(Data::I16),
+        (UnaryOperator::Negate, Data::I32(v)) => v.checked_neg().map(Data::I32),
+        (UnaryOperator::Negate, Data::I64(v)) => v.checked_neg().map(Data::I64),
+
+        (UnaryOperator::BitwiseNot, v) => integer_operation!(v.clone(), v, |a, _b| Some(!a)),
+
+        _ => None,
+    }
+}
+
+
+fn cast(value: Data, to: &DataType) -> Option<Data> {
+    macro_rules! cast_to {
+        ($v: expr) => {
+            match to {
+                DataType::I8  => Some(Data::I8 ($v as i8)),
+                DataType::I16 => Some(Data::I16($v as i16)),
+                DataType::I32 => Some(Data::I32($v as i32)),
+                DataType::I64 => Some(Data::I64($v as i64)),
+                DataType::U8  => Some(Data::U8 ($v as u8)),
+                DataType::U16 => Some(Data::U16($v as u16)),
+                DataType::U32 => Some(Data::U32($v as u32)),
+                DataType::U64 => Some(Data::U64($v as u64)),
+                DataType::Float => Some(Data::Float($v as f64)),
+
+                _ => None,
+            }
+        }
+    }
+
+    match value {
+        Data::I8 (v) => cast_to!(v),
+        Data::I16(v) => cast_to!(v),
+        Data::I32(v) => cast_to!(v),
+        Data::I64(v) => cast_to!(v),
+        Data::U8 (v) => cast_to!(v),
+        Data::U16(v) => cast_to!(v),
+        Data::U32(v) => cast_to!(v),
+        Data::U64(v) => cast_to!(v),
+        Data::Float(v) => cast_to!(v),
+
+        _ => None,
+    }
+}
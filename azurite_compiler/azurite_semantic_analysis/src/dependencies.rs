@@ -0,0 +1,117 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, fs};
+
+use azurite_errors::{Error, CompilerError, ErrorBuilder, Severity};
+use azurite_parser::ast::{Instruction, InstructionKind, Declaration};
+use common::{SymbolTable, SymbolIndex, SourceRange};
+
+use crate::read_use_file;
+
+
+/// The import graph of a file
+#[derive(Debug, PartialEq)]
+pub struct Dependencies {
+    /// Every file that is reachable through `using` declarations
+    /// in the order they were first found. The root file is not
+    /// included
+    pub files: Vec<PathBuf>,
+
+    /// Every `using` declaration that refers back to a file which
+    /// is still being resolved, stored as `(importer, imported)`
+    pub cycles: Vec<(PathBuf, PathBuf)>,
+}
+
+
+struct DependencyResolver<'a> {
+    symbol_table: &'a mut SymbolTable,
+    sources: HashMap<SymbolIndex, (String, String)>,
+    dependencies: Dependencies,
+    resolving: Vec<PathBuf>,
+}
+
+
+/// Lexes & parses `file` and every file it uses, without analysing
+/// them, to find the transitive list of files it depends on
+///
+/// The returned hashmap contains the source of every file that was
+/// read so the error can be displayed
+pub fn resolve_dependencies(
+    file: SymbolIndex,
+    path: &Path,
+    data: String,
+    symbol_table: &mut SymbolTable
+) -> (Result<Dependencies, Error>, HashMap<SymbolIndex, (String, String)>) {
+    let mut resolver = DependencyResolver {
+        symbol_table,
+        sources: HashMap::new(),
+        dependencies: Dependencies { files: vec![], cycles: vec![] },
+        resolving: vec![],
+    };
+
+    let path = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    let result = resolver.resolve(file, path, data);
+
+    (result.map(|_| resolver.dependencies), resolver.sources)
+}
+
+
+impl DependencyResolver<'_> {
+    fn resolve(&mut self, file: SymbolIndex, path: PathBuf, data: String) -> Result<(), Error> {
+        let data = data.replace('\t', "    ").replace('\r', "");
+        let tokens = azurite_lexer::lex(&data, file, self.symbol_table);
+        self.sources.insert(file, (self.symbol_table.get(&file), data));
+
+        let instructions = azurite_parser::parse(tokens?, file, self.symbol_table)?;
+
+        let mut used_files = vec![];
+        find_used_files(&instructions, &mut used_files);
+
+        self.resolving.push(path.clone());
+
+        for (file_name, source_range) in used_files {
+            let name = self.symbol_table.get(&file_name);
+            let (used_path, used_data) = match read_use_file(&path, &name) {
+                Ok(v) => v,
+                Err([path_local_to_file, new_path]) => return Err(CompilerError::new(file, 223, "file doesn't exist", Severity::Error)
+                    .highlight(source_range)
+                        .note(format!("can't find a file named {} at any of the following paths: {}, {}",
+                            name,
+                            path_local_to_file.to_string_lossy(),
+                            new_path.to_string_lossy(),
+                    ))
+                    .build())
+            };
+
+            let used_path = fs::canonicalize(&used_path).unwrap_or(used_path);
+
+            if self.resolving.contains(&used_path) {
+                self.dependencies.cycles.push((path.clone(), used_path));
+                continue
+            }
+
+            if self.dependencies.files.contains(&used_path) {
+                continue
+            }
+
+            self.dependencies.files.push(used_path.clone());
+
+            let used_file = self.symbol_table.add(used_path.to_string_lossy().to_string());
+            self.resolve(used_file, used_path, used_data)?;
+        }
+
+        self.resolving.pop();
+
+        Ok(())
+    }
+}
+
+
+fn find_used_files(instructions: &[Instruction], used_files: &mut Vec<(SymbolIndex, SourceRange)>) {
+    for instruction in instructions {
+        match &instruction.instruction_kind {
+            InstructionKind::Declaration(Declaration::UseFile { file_name }) => used_files.push((*file_name, instruction.source_range)),
+            InstructionKind::Declaration(Declaration::Namespace { body, .. }) => find_used_files(body, used_files),
+
+            _ => (),
+        }
+    }
+}
@@ -2,12 +2,15 @@
 #![feature(hash_extract_if)]
 #![feature(iter_intersperse)]
 pub mod variable_stack;
+pub mod dependencies;
+mod const_eval;
+mod suggestions;
 
-use std::{collections::HashMap, fs, path::{PathBuf, Path}, env};
+use std::{collections::HashMap, fs, path::{PathBuf, Path}, sync::Arc};
 
-use azurite_errors::{Error, CompilerError, ErrorBuilder, CombineIntoError};
+use azurite_errors::{Error, CompilerError, ErrorBuilder, CombineIntoError, Severity};
 use azurite_parser::ast::{Instruction, InstructionKind, Statement, Expression, BinaryOperator, Declaration, UnaryOperator};
-use common::{DataType, SymbolTable, SymbolIndex, Data, SourceRange, SourcedDataType, default};
+use common::{conversion_symbol, DataType, SymbolTable, SymbolIndex, Data, SourcedData, SourceRange, SourcedDataType, default};
 use variable_stack::VariableStack;
 
 const STD_LIBRARY : &str = include_str!("../../../builtin_libraries/azurite_api_files/std.az");
@@ -17,27 +20,49 @@ const STD_LIBRARY : &str = include_str!("../../../builtin_libraries/azurite_api_
 pub struct GlobalState<'a> {
     pub symbol_table: &'a mut SymbolTable,
     pub files: HashMap<SymbolIndex, (AnalysisState, Vec<Instruction>, String)>,
+    pub warnings: Vec<Error>,
 
     functions: HashMap<SymbolIndex, Function>,
     pub template_functions: HashMap<SymbolIndex, TemplateFunction>,
     template_structures: HashMap<SymbolIndex, TemplateStructure>,
     structures: HashMap<SymbolIndex, Structure>,
+    constants: HashMap<SymbolIndex, Data>,
+    enums: HashMap<SymbolIndex, Vec<(SymbolIndex, i64)>>,
+
+    /// Functions without a return type are moved out of their
+    /// block so they can be analyzed the first time they're
+    /// called and are put back once the block is analyzed
+    pending_functions: HashMap<SymbolIndex, Instruction>,
+    inferred_functions: HashMap<SymbolIndex, Instruction>,
+
+    /// The functions whose return type is being inferred,
+    /// calling one of them is recursion
+    inferring: Vec<SymbolIndex>,
+
+    /// How deep the instruction currently being analyzed is
+    nesting: usize,
+    max_nesting: usize,
 }
 
 
 #[derive(Debug, PartialEq)]
 pub struct AnalysisState {
     pub variable_stack: VariableStack,
-    loop_depth: usize,
+    loop_labels: Vec<Option<(SymbolIndex, SourceRange)>>,
 
     functions: HashMap<SymbolIndex, (SymbolIndex, usize)>,
     structures: HashMap<SymbolIndex, (SymbolIndex, usize)>,
+    constants: HashMap<SymbolIndex, (SymbolIndex, usize)>,
+    enums: HashMap<SymbolIndex, (SymbolIndex, usize)>,
     // generics: Vec<SymbolIndex>,
 
     available_files: HashMap<SymbolIndex, SymbolIndex>,
     
     explicit_return: Option<SourcedDataType>,
 
+    /// If set the first `return` decides `explicit_return`
+    infers_return: bool,
+
     depth: usize,
     file: SymbolIndex,
     custom_path: SymbolIndex,
@@ -60,6 +85,7 @@ pub struct TemplateFunction {
     name: SymbolIndex,
     
     return_type: SourcedDataType,
+    infer_return_type: bool,
     arguments: Vec<(SymbolIndex, SourcedDataType)>,
     instructions: Vec<Instruction>,
     generics: Vec<SymbolIndex>,
@@ -91,10 +117,20 @@ impl<'a> GlobalState<'a> {
         Self {
             symbol_table, 
             files: HashMap::new(),
+            warnings: vec![],
             functions: HashMap::new(),
             structures: HashMap::new(),
+            constants: HashMap::new(),
+            enums: HashMap::new(),
+            pending_functions: HashMap::new(),
+            inferred_functions: HashMap::new(),
+            inferring: vec![],
             template_functions: HashMap::new(),
             template_structures: HashMap::new(),
+            nesting: 0,
+            // Statements, blocks, desugared code & the bodies of
+            // instantiated templates count towards the depth too
+            max_nesting: azurite_common::max_nesting_depth() * 4,
         }
     }
 }
@@ -104,11 +140,14 @@ impl AnalysisState {
     pub fn new(file: SymbolIndex) -> Self {
         Self {
             variable_stack: VariableStack::new(),
-            loop_depth: 0,
+            loop_labels: vec![],
             depth: 0,
             explicit_return: None,
+            infers_return: false,
             functions: HashMap::new(),
             structures: HashMap::new(),
+            constants: HashMap::new(),
+            enums: HashMap::new(),
             available_files: HashMap::new(),
             file,
             custom_path: file,
@@ -119,12 +158,40 @@ impl AnalysisState {
     }
 
 
-    pub fn start_analysis(&mut self, global: &mut GlobalState, instructions: &mut [Instruction]) -> Result<(), Error> {
-        #[cfg(features = "afl")]
+    /// Analyzes the root file of the program
+    ///
+    /// On top of `start_analysis` this checks that the
+    /// root results in either `()` or an integer which
+    /// is used as the exit code
+    pub fn analyze_entry(&mut self, global: &mut GlobalState, instructions: &mut [Instruction]) -> Result<(), Error> {
+        let result = self.start_analysis(global, instructions)?;
+
+        match result.data_type {
+            | DataType::Empty
+            | DataType::Any
+            | DataType::I8
+            | DataType::I16
+            | DataType::I32
+            | DataType::I64
+            | DataType::U8
+            | DataType::U16
+            | DataType::U32
+            | DataType::U64 => Ok(()),
+
+            _ => Err(CompilerError::new(self.file, 239, "invalid entry result type", Severity::Error)
+                .highlight(instructions.last().map_or(result.source_range, |x| x.source_range))
+                    .note(format!("is of type {} but the program can only result in '()' or an integer exit code", global.to_string(&result.data_type)))
+                .build())
+        }
+    }
+
+
+    pub fn start_analysis(&mut self, global: &mut GlobalState, instructions: &mut [Instruction]) -> Result<SourcedDataType, Error> {
+        #[cfg(feature = "afl")]
         let no_std = false;
 
-        #[cfg(not(features = "afl"))]
-        let no_std = env::var(azurite_common::environment::NO_STD).unwrap_or("0".to_string()) == "1";
+        #[cfg(not(feature = "afl"))]
+        let no_std = std::env::var(azurite_common::environment::NO_STD).unwrap_or("0".to_string()) == "1";
 
         if !no_std {
             let file_name = global.symbol_table.add(String::from("std"));
@@ -137,7 +204,7 @@ impl AnalysisState {
                 global.files.insert(file_name, (AnalysisState::new(file_name), vec![], file));
 
                 let tokens = tokens?;
-                let mut instructions = azurite_parser::parse(tokens, file_name, global.symbol_table)?;
+                let mut instructions = azurite_parser::parse_std(tokens, file_name, global.symbol_table)?;
                 let mut analysis = AnalysisState::new(file_name);
                 analysis.start_analysis(global, &mut instructions)?;
 
@@ -149,15 +216,29 @@ impl AnalysisState {
             }
         }
         
-        self.analyze_block(global, instructions, false, true, None)?;
-
-        Ok(())
+        self.analyze_block(global, instructions, false, true, None)
     }
 }
 
 
 impl AnalysisState {
     fn analyze(&mut self, global: &mut GlobalState, instruction: &mut Instruction, expected: Option<&DataType>) -> Result<SourcedDataType, Error> {
+        if global.nesting >= global.max_nesting {
+            return Err(CompilerError::new(self.file, 248, "expression nesting too deep", Severity::Error)
+                .highlight(instruction.source_range)
+                    .note(format!("expressions can only be nested {} levels deep", global.max_nesting))
+                .build())
+        }
+
+        global.nesting += 1;
+        let result = self.analyze_instruction(global, instruction, expected);
+        global.nesting -= 1;
+
+        result
+    }
+
+
+    fn analyze_instruction(&mut self, global: &mut GlobalState, instruction: &mut Instruction, expected: Option<&DataType>) -> Result<SourcedDataType, Error> {
         match &mut instruction.instruction_kind {
             InstructionKind::Statement(s) => {
                 self.analyze_statement(global, s, &instruction.source_range)?;
@@ -209,6 +290,12 @@ impl AnalysisState {
                 }
             }
 
+            if errors.is_empty() {
+                if let Err(e) = self.check_recursive_structures(global, instructions) {
+                    errors.push(e);
+                }
+            }
+
             if !errors.is_empty() {
                 return Err(errors.combine_into_error())
             }
@@ -216,6 +303,21 @@ impl AnalysisState {
         
         
         let mut errors = vec![];
+
+        // Functions without a return type are analyzed before
+        // the rest of the block, the ones called by others
+        // are analyzed during the call
+        let mut pending = vec![];
+        if pre_declaration {
+            take_pending_functions(global, instructions, &mut vec![], &mut pending);
+
+            for (_, name) in pending.iter() {
+                if let Err(e) = self.analyze_pending_function(global, *name) {
+                    errors.push(e);
+                }
+            }
+        }
+
         let size = instructions.len();
         instructions.iter_mut().take(size.max(1)-1).for_each(|x| if let Err(e) = self.analyze(global, x, None) {
             errors.push(e);
@@ -232,11 +334,22 @@ impl AnalysisState {
             }
         }
 
+        for (path, name) in pending {
+            *pending_function_slot(instructions, &path) = global.inferred_functions.remove(&name).unwrap();
+        }
+
+        if errors.is_empty() {
+            self.warn_unreachable_code(global, instructions);
+            self.warn_unused_variables(global, top);
+        }
+
         self.variable_stack.pop(self.variable_stack.len() - top);
 
         if reset {
             self.functions.retain(|_, y| self.depth > y.1);
             self.structures.retain(|_, y| self.depth > y.1);
+            self.constants.retain(|_, y| self.depth > y.1);
+            self.enums.retain(|_, y| self.depth > y.1);
             self.depth -= 1;
         }
         
@@ -249,13 +362,142 @@ impl AnalysisState {
     }
 
 
+    /// Analyzes a function without a return type which was
+    /// moved out of its block, inferring its return type
+    fn analyze_pending_function(&mut self, global: &mut GlobalState, name: SymbolIndex) -> Result<(), Error> {
+        let Some(mut instruction) = global.pending_functions.remove(&name) else { return Ok(()) };
+
+        let result = self.analyze(global, &mut instruction, None);
+        global.inferred_functions.insert(name, instruction);
+
+        result.map(|_| ())
+    }
+
+
+    /// Unifies the type of the tail expression of a function
+    /// without a return type with the type of its first `return`
+    fn infer_return_type(&mut self, global: &mut GlobalState, body: &mut [Instruction], body_type: SourcedDataType) -> Result<DataType, Error> {
+        let first_return = self.explicit_return.take();
+
+        // A diverging tail never produces a value
+        let Some(last) = body.last_mut().filter(|x| !diverges(x)) else {
+            return Ok(first_return.map_or(DataType::Empty, |x| x.data_type))
+        };
+
+        let Some(first_return) = first_return else { return Ok(body_type.data_type) };
+
+        if !self.is_of_type(global, (&body_type, last), &first_return)? {
+            let tail = SourcedDataType::new(last.source_range, body_type.data_type);
+            return Err(conflicting_return_types(self.file, global, &first_return, &tail))
+        }
+
+        Ok(first_return.data_type)
+    }
+
+
+    /// Errors on structures which contain themselves by value,
+    /// directly or through other structures, as a value of one
+    /// could never be created. Arrays can be empty so they break
+    /// the cycle
+    fn check_recursive_structures(&self, global: &mut GlobalState, instructions: &[Instruction]) -> Result<(), Error> {
+        let mut fields = HashMap::new();
+        collect_structure_fields(instructions, &mut fields);
+
+        let mut errors = vec![];
+        let mut reported = vec![];
+
+        for instruction in instructions {
+            let InstructionKind::Declaration(Declaration::StructDeclaration { name, generics, .. }) = &instruction.instruction_kind else { continue };
+
+            if !generics.is_empty() || reported.contains(name) {
+                continue
+            }
+
+            let mut path = vec![*name];
+            if !find_structure_cycle(&fields, &mut path) {
+                continue
+            }
+
+            let cycle = path
+                .iter()
+                .map(|x| format!("'{}'", global.symbol_table.get(&global.symbol_table.last_piece(*x))))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            errors.push(CompilerError::new(self.file, 254, "recursive structure", Severity::Error)
+                .highlight(instruction.source_range)
+                    .note(format!("contains itself through {cycle}, store the recursive field in an array to break the cycle"))
+                .build());
+
+            reported.extend(path);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors.combine_into_error())
+        }
+
+        Ok(())
+    }
+
+
+    /// Warns about the first instruction following one which
+    /// always diverges, declarations are hoisted so they don't
+    /// count as unreachable
+    fn warn_unreachable_code(&self, global: &mut GlobalState, instructions: &[Instruction]) {
+        let Some(index) = instructions.iter().position(diverges) else { return };
+
+        let unreachable = instructions[index+1..]
+            .iter()
+            .find(|x| !matches!(x.instruction_kind, InstructionKind::Declaration(_)));
+
+        let Some(unreachable) = unreachable else { return };
+
+        let warning = CompilerError::new(self.file, 304, "unreachable code", Severity::Warning)
+            .highlight(instructions[index].source_range)
+                .note("any code following this is unreachable".to_string())
+            .highlight(unreachable.source_range)
+                .note("this will never be executed".to_string())
+            .build();
+
+        if !global.warnings.contains(&warning) {
+            global.warnings.push(warning);
+        }
+    }
+
+
+    /// Warns about the variables declared above `top` which were
+    /// never read. Variables starting with an underscore & the
+    /// hidden variables the parser generates are ignored
+    fn warn_unused_variables(&self, global: &mut GlobalState, top: usize) {
+        for (identifier, declaration) in self.variable_stack.unused(top) {
+            let name = global.symbol_table.get(&identifier);
+            if name.starts_with('_') || name.starts_with('#') {
+                continue
+            }
+
+            let warning = CompilerError::new(self.file, 302, "unused variable", Severity::Warning)
+                .highlight(declaration)
+                    .note(format!("{name} is never read, prefix it with an underscore to silence this"))
+                .build();
+
+            // The bodies of generic functions are analyzed
+            // once for every instance
+            if !global.warnings.contains(&warning) {
+                global.warnings.push(warning);
+            }
+        }
+    }
+
+
     fn analyze_declaration(&mut self, global: &mut GlobalState, declaration: &mut Declaration, source_range: &SourceRange) -> Result<(), Error> {
         match declaration {
-            Declaration::FunctionDeclaration { arguments, return_type, body, source_range_declaration, generics, name } => {
+            Declaration::FunctionDeclaration { arguments, return_type, body, source_range_declaration, generics, name, infer_return_type } => {
                 let mut analysis_state = AnalysisState::new(self.file);
 
                 analysis_state.functions = std::mem::take(&mut self.functions);
                 analysis_state.structures = std::mem::take(&mut self.structures);
+                analysis_state.constants = std::mem::take(&mut self.constants);
+                analysis_state.enums = std::mem::take(&mut self.enums);
                 analysis_state.available_files = std::mem::take(&mut self.available_files);
                 // analysis_state.generics = std::mem::take(generics);
                 analysis_state.custom_path = *name;
@@ -272,6 +514,8 @@ impl AnalysisState {
                 if let Err(e) = analysis_state.update_type(return_type, global) {
                     self.functions = std::mem::take(&mut analysis_state.functions);
                     self.structures = std::mem::take(&mut analysis_state.structures);
+                    self.constants = std::mem::take(&mut analysis_state.constants);
+                    self.enums = std::mem::take(&mut analysis_state.enums);
                     self.available_files = std::mem::take(&mut analysis_state.available_files);
                     // *generics = std::mem::take(&mut analysis_state.generics);
 
@@ -279,7 +523,11 @@ impl AnalysisState {
                 }
                 
 
-                analysis_state.explicit_return = Some(return_type.clone());
+                if *infer_return_type {
+                    analysis_state.infers_return = true;
+                } else {
+                    analysis_state.explicit_return = Some(return_type.clone());
+                }
 
                 {
 
@@ -298,6 +546,8 @@ impl AnalysisState {
                     if !errors.is_empty() {
                         self.functions = std::mem::take(&mut analysis_state.functions);
                         self.structures = std::mem::take(&mut analysis_state.structures);
+                        self.constants = std::mem::take(&mut analysis_state.constants);
+                        self.enums = std::mem::take(&mut analysis_state.enums);
                         self.available_files = std::mem::take(&mut analysis_state.available_files);
                         // *generics = std::mem::take(&mut analysis_state.generics);
 
@@ -307,12 +557,31 @@ impl AnalysisState {
                 }
 
                 
-                let body_return_type = analysis_state.analyze_block(global, body, true, true, Some(&return_type.data_type));
+                let expected = if *infer_return_type { None } else { Some(&return_type.data_type) };
+
+                if *infer_return_type {
+                    global.inferring.push(*name);
+                }
+
+                let body_return_type = analysis_state.analyze_block(global, body, true, true, expected);
+
+                if *infer_return_type {
+                    global.inferring.pop();
+                }
+
                 let body_return_type = match body_return_type {
                     Ok(v) => v,
                     Err(e) => {
+                        if *infer_return_type {
+                            return_type.data_type = DataType::Any;
+                            global.functions.get_mut(name).unwrap().return_type = return_type.clone();
+                        }
+
+
                         self.functions = std::mem::take(&mut analysis_state.functions);
                         self.structures = std::mem::take(&mut analysis_state.structures);
+                        self.constants = std::mem::take(&mut analysis_state.constants);
+                        self.enums = std::mem::take(&mut analysis_state.enums);
                         self.available_files = std::mem::take(&mut analysis_state.available_files);
                         // *generics = std::mem::take(&mut analysis_state.generics);
 
@@ -322,17 +591,33 @@ impl AnalysisState {
                 };
 
 
-                let return_type_is_not_same_as_body_type = (body.last().is_none() && return_type.data_type != DataType::Empty) ||
-                    (body.last().is_some() && !analysis_state.is_of_type(global, (&body_return_type, body.last_mut().unwrap()), return_type).unwrap_or(false)); 
+                let inferred = if *infer_return_type {
+                    Some(analysis_state.infer_return_type(global, body, body_return_type.clone()))
+                } else { None };
+
+                let return_type_is_not_same_as_body_type = inferred.is_none() && (
+                    (body.last().is_none() && return_type.data_type != DataType::Empty) ||
+                    (body.last().is_some() && !analysis_state.is_of_type(global, (&body_return_type, body.last_mut().unwrap()), return_type).unwrap_or(false))
+                );
                 
 
                 self.functions = std::mem::take(&mut analysis_state.functions);
                 self.structures = std::mem::take(&mut analysis_state.structures);
+                self.constants = std::mem::take(&mut analysis_state.constants);
+                self.enums = std::mem::take(&mut analysis_state.enums);
                 self.available_files = std::mem::take(&mut analysis_state.available_files);
 
 
+                if let Some(inferred) = inferred {
+                    return_type.data_type = inferred.as_ref().map_or(DataType::Any, |x| x.clone());
+                    global.functions.get_mut(name).unwrap().return_type = return_type.clone();
+
+                    return inferred.map(|_| ())
+                }
+
+
                 if return_type_is_not_same_as_body_type {
-                    return Err(CompilerError::new(self.file, 211, "function body returns a different type")
+                    return Err(CompilerError::new(self.file, 211, "function body returns a different type", Severity::Error)
                         .highlight(*source_range_declaration)
                             .note(format!("function returns {}", global.to_string(&return_type.data_type)))
 
@@ -387,6 +672,10 @@ impl AnalysisState {
 
             
             Declaration::UseFile { .. } => Ok(()),
+
+
+            Declaration::Const { .. } => Ok(()),
+            Declaration::Enum { .. } => Ok(()),
         }
     }
     
@@ -428,11 +717,32 @@ impl AnalysisState {
                         
                     ) => Ok(cast_type.clone()),
 
-                    _ => Err(CompilerError::new(self.file, 226, "can only cast beteen primitives")
-                            .highlight(*source_range)
-                                .note(format!("value is of type {}", global.to_string(&value_type.data_type)))
-                            .build()
-                    ),
+                    _ => {
+                        self.update_type(cast_type, global)?;
+
+                        let Some(conversion) = self.get_conversion(global, &value_type.data_type, &cast_type.data_type) else {
+                            return Err(CompilerError::new(self.file, 226, "can only cast beteen primitives", Severity::Error)
+                                    .highlight(*source_range)
+                                        .note(format!("value is of type {}", global.to_string(&value_type.data_type)))
+                                        .note(format!("and {} doesn't define a conversion into {}",
+                                            global.to_string(&value_type.data_type),
+                                            global.to_string(&cast_type.data_type),
+                                        ))
+                                    .build())
+                        };
+
+                        let cast_type = cast_type.clone();
+                        let value = std::mem::take(&mut **value);
+
+                        *expression = Expression::FunctionCall {
+                            identifier: conversion,
+                            arguments: vec![value],
+                            created_by_accessing: false,
+                            generics: Vec::new().into(),
+                        };
+
+                        Ok(cast_type)
+                    },
                 }
             }
 
@@ -446,29 +756,36 @@ impl AnalysisState {
                 macro_rules! conversion {
                     ($i: ident) => {
                         match (&v.data, &expected) {
-                            (Data::$i(n), DataType::I8)  => if let Ok(val) = i8 ::try_from(*n) { v.data = Data::I8 (val); },
-                            (Data::$i(n), DataType::I16) => if let Ok(val) = i16::try_from(*n) { v.data = Data::I16(val); },
-                            (Data::$i(n), DataType::I32) => if let Ok(val) = i32::try_from(*n) { v.data = Data::I32(val); },
-                            (Data::$i(n), DataType::I64) => if let Ok(val) = i64::try_from(*n) { v.data = Data::I64(val); },
-                            (Data::$i(n), DataType::U8)  => if let Ok(val) = u8 ::try_from(*n) { v.data = Data::U8 (val); },
-                            (Data::$i(n), DataType::U16) => if let Ok(val) = u16::try_from(*n) { v.data = Data::U16(val); },
-                            (Data::$i(n), DataType::U32) => if let Ok(val) = u32::try_from(*n) { v.data = Data::U32(val); },
-                            (Data::$i(n), DataType::U64) => if let Ok(val) = u64::try_from(*n) { v.data = Data::U64(val); },
-
-                            _ => (),
+                            (Data::$i(n), DataType::I8)  => Some(i8 ::try_from(*n).map(Data::I8 ).map_err(|_| *n)),
+                            (Data::$i(n), DataType::I16) => Some(i16::try_from(*n).map(Data::I16).map_err(|_| *n)),
+                            (Data::$i(n), DataType::I32) => Some(i32::try_from(*n).map(Data::I32).map_err(|_| *n)),
+                            (Data::$i(n), DataType::I64) => Some(i64::try_from(*n).map(Data::I64).map_err(|_| *n)),
+                            (Data::$i(n), DataType::U8)  => Some(u8 ::try_from(*n).map(Data::U8 ).map_err(|_| *n)),
+                            (Data::$i(n), DataType::U16) => Some(u16::try_from(*n).map(Data::U16).map_err(|_| *n)),
+                            (Data::$i(n), DataType::U32) => Some(u32::try_from(*n).map(Data::U32).map_err(|_| *n)),
+                            (Data::$i(n), DataType::U64) => Some(u64::try_from(*n).map(Data::U64).map_err(|_| *n)),
+
+                            _ => None,
                             
                         }
                     }
                 }
 
-                conversion!(I8);
-                conversion!(I16);
-                conversion!(I32);
-                conversion!(I64);
-                conversion!(U8);
-                conversion!(U16);
-                conversion!(U32);
-                conversion!(U64);
+                // Only unsuffixed integer literals are coerced, a
+                // suffixed literal like `10u8` keeps its type.
+                // Expecting a non-integer type isn't handled here
+                // so a genuine type mismatch still gets reported
+                // by whoever expected the type
+                match conversion!(I64) {
+                    Some(Ok(data)) => v.data = data,
+
+                    Some(Err(n)) => return Err(CompilerError::new(self.file, 253, "integer literal out of range", Severity::Error)
+                        .highlight(v.source_range)
+                            .note(format!("literal {n} does not fit in {}", global.to_string(expected)))
+                        .build()),
+
+                    None => (),
+                }
 
                 Ok(SourcedDataType::from(v))
             },
@@ -477,6 +794,32 @@ impl AnalysisState {
                 let left_type  = self.analyze(global, left, expected)?;
                 let right_type = self.analyze(global, right, Some(&left_type.data_type))?;
 
+                if let Some((method, return_type)) = self.get_operator_overload(global, operator, &left_type.data_type, &right_type.data_type) {
+                    let left  = std::mem::take(&mut **left);
+                    let right = std::mem::take(&mut **right);
+
+                    let call = Expression::FunctionCall {
+                        identifier: method,
+                        arguments: vec![left, right],
+                        created_by_accessing: false,
+                        generics: Vec::new().into(),
+                    };
+
+                    // `a != b` is `!a.eq(b)`
+                    *expression = if *operator == BinaryOperator::NotEquals {
+                        Expression::UnaryOp {
+                            operator: UnaryOperator::Not,
+                            value: Box::new(Instruction {
+                                instruction_kind: InstructionKind::Expression(call),
+                                source_range: *source_range,
+                                result_type: DataType::Bool,
+                            }),
+                        }
+                    } else { call };
+
+                    return Ok(return_type)
+                }
+
                 let data_type = match *operator {
                     | BinaryOperator::Add
                     | BinaryOperator::Subtract
@@ -501,22 +844,71 @@ impl AnalysisState {
                             (DataType::Any, DataType::Any) => DataType::Any,
                             
                             _ => {
-                                return Err(CompilerError::new(self.file, 201, "invalid type arithmetic operation")
+                                return Err(CompilerError::new(self.file, 201, "invalid type arithmetic operation", Severity::Error)
+                                    .highlight(SourceRange::combine(left.source_range, right.source_range))
+                                        .note(format!(
+                                            "left side is of type {} while the right side is of type {}", 
+                                            global.to_string(&left_type.data_type),
+                                            global.to_string(&right_type.data_type)))
+                                    .build())
+                            }
+                        }
+                    }
+
+
+                    | BinaryOperator::BitwiseAnd
+                    | BinaryOperator::BitwiseOr
+                    | BinaryOperator::BitwiseXor => {
+                        match (&left_type.data_type, &right_type.data_type) {
+                            match_macro!(I8) => DataType::I8,
+                            match_macro!(I16) => DataType::I16,
+                            match_macro!(I32) => DataType::I32,
+                            match_macro!(I64) => DataType::I64,
+
+                            match_macro!(U8) => DataType::U8,
+                            match_macro!(U16) => DataType::U16,
+                            match_macro!(U32) => DataType::U32,
+                            match_macro!(U64) => DataType::U64,
+
+                            (DataType::Any, DataType::Any) => DataType::Any,
+                            
+                            _ => {
+                                return Err(CompilerError::new(self.file, 247, "invalid type bitwise operation", Severity::Error)
                                     .highlight(SourceRange::combine(left.source_range, right.source_range))
                                         .note(format!(
                                             "left side is of type {} while the right side is of type {}", 
                                             global.to_string(&left_type.data_type),
                                             global.to_string(&right_type.data_type)))
+                                        .note("bitwise operations only work on integers of the same type".to_string())
                                     .build())
                             }
                         }
                     }
 
 
+                    | BinaryOperator::ShiftLeft
+                    | BinaryOperator::ShiftRight => {
+                        let is_integer = |data_type: &DataType| matches!(data_type, DataType::Any) || data_type.is_integer();
+
+                        if !is_integer(&left_type.data_type) || !is_integer(&right_type.data_type) {
+                            return Err(CompilerError::new(self.file, 249, "invalid type shift operation", Severity::Error)
+                                .highlight(SourceRange::combine(left.source_range, right.source_range))
+                                    .note(format!(
+                                        "left side is of type {} while the right side is of type {}", 
+                                        global.to_string(&left_type.data_type),
+                                        global.to_string(&right_type.data_type)))
+                                    .note("shifts only work on integers, the shift amount is masked to the bit width of the left side".to_string())
+                                .build())
+                        }
+
+                        left_type.data_type.clone()
+                    }
+
+
                     | BinaryOperator::Equals
                     | BinaryOperator::NotEquals => {
                         if !self.is_of_type(global, (&left_type, left), &right_type)? {
-                            return Err(CompilerError::new(self.file, 202, "comparisson types differ")
+                            return Err(CompilerError::new(self.file, 202, "comparisson types differ", Severity::Error)
                                 .highlight(SourceRange::combine(left.source_range, right.source_range))
                                     .note(format!(
                                         "left side is of type {} while the right side is of type {}",
@@ -524,6 +916,14 @@ impl AnalysisState {
                                         global.to_string(&right_type.data_type)))
                                 .build())
                         }
+
+                        if left_type.data_type == DataType::Float && right_type.data_type == DataType::Float {
+                            global.warnings.push(CompilerError::new(self.file, 303, "comparing floats for equality", Severity::Warning)
+                                .highlight(SourceRange::combine(left.source_range, right.source_range))
+                                    .note("rounding errors can make floats that should be equal differ slightly".to_string())
+                                    .note("consider comparing them within an epsilon with `approx_eq(a, b, epsilon)`".to_string())
+                                .build())
+                        }
             
                         DataType::Bool
                     }
@@ -542,13 +942,14 @@ impl AnalysisState {
                             | match_macro!(U16)
                             | match_macro!(U32)
                             | match_macro!(U64)
+                            | match_macro!(Char)
                             | (DataType::Any, DataType::Float)
                             | (DataType::Float, DataType::Any)
                             | (DataType::Float, DataType::Float)
                             | (DataType::Any, DataType::Any) => DataType::Bool,
                             
                             _ => {
-                                return Err(CompilerError::new(self.file, 224, "invalid type order operation")
+                                return Err(CompilerError::new(self.file, 224, "invalid type order operation", Severity::Error)
                                     .highlight(SourceRange::combine(left.source_range, right.source_range))
                                         .note(format!(
                                             "left side is of type {} while the right side is of type {}", 
@@ -566,15 +967,26 @@ impl AnalysisState {
 
             
             Expression::UnaryOp { operator, value } => {
+                // A negated literal is folded into a negative literal
+                // so its range is checked as a whole, e.g. `-128` fits
+                // in an `i8` while `128` doesn't
+                if let (UnaryOperator::Negate, InstructionKind::Expression(Expression::Data(SourcedData { data: Data::I64(n), .. }))) = (&*operator, &value.instruction_kind) {
+                    let data = SourcedData::new(*source_range, Data::I64(n.wrapping_neg()));
+
+                    *expression = Expression::Data(data);
+                    return self.analyze_expression(global, expression, source_range, expected)
+                }
+
                 let value_type = self.analyze(global, &mut *value, expected)?;
 
                 let is_valid = match operator {
                     UnaryOperator::Not => matches!(value_type.data_type, DataType::Bool),
                     UnaryOperator::Negate => matches!(value_type.data_type, DataType::Float) || value_type.data_type.is_signed_integer(),
+                    UnaryOperator::BitwiseNot => value_type.data_type.is_integer(),
                 };
 
                 if !is_valid {
-                    return Err(CompilerError::new(self.file, 225, "invalid type unary operation")
+                    return Err(CompilerError::new(self.file, 225, "invalid type unary operation", Severity::Error)
                         .highlight(*source_range)
                         .build());
                 }
@@ -595,7 +1007,7 @@ impl AnalysisState {
                 let condition_type = self.analyze(global, condition, Some(&DataType::Bool))?;
 
                 if !self.is_of_type(global, (&condition_type, condition), &SourcedDataType::new(SourceRange::new(0, 0), DataType::Bool))? {
-                    return Err(CompilerError::new(self.file, 203, "condition expects a boolean")
+                    return Err(CompilerError::new(self.file, 203, "condition expects a boolean", Severity::Error)
                         .highlight(condition.source_range)
                             .note(format!("is of type {}", global.to_string(&condition_type.data_type)))
                         .build())
@@ -608,7 +1020,7 @@ impl AnalysisState {
                     let else_type = self.analyze(global, else_part, expected)?;
 
                     if body.last().is_none() || !self.is_of_type(global, (&body_type, body.last_mut().unwrap()), &else_type)? {
-                        return Err(CompilerError::new(self.file, 204, "if expressions branches don't return the same type")
+                        return Err(CompilerError::new(self.file, 204, "if expressions branches don't return the same type", Severity::Error)
                             .highlight(body.last().map_or(*source_range, |x| x.source_range))
                                 .note(format!("is of type {}", global.to_string(&body_type.data_type)))
                             
@@ -624,14 +1036,80 @@ impl AnalysisState {
             },
 
 
+            Expression::Match { scrutinee, arms, default } => {
+                let scrutinee_type = self.analyze(global, scrutinee, None)?;
+
+                if !matches!(scrutinee_type.data_type,
+                    | DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64
+                    | DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64
+                    | DataType::Bool | DataType::Any
+                ) {
+                    return Err(CompilerError::new(self.file, 242, "can only match on integers and bools", Severity::Error)
+                        .highlight(scrutinee.source_range)
+                            .note(format!("is of type {}", global.to_string(&scrutinee_type.data_type)))
+                        .build())
+                }
+
+                let mut errors = vec![];
+                let mut patterns = vec![];
+                let mut arm_type = None;
+
+                for (pattern, body) in arms.iter_mut() {
+                    if let Err(e) = self.analyze_match_pattern(global, pattern, &scrutinee_type, &mut patterns) {
+                        errors.push(e);
+                    }
+
+                    if let Err(e) = self.analyze_match_arm(global, body, expected, &mut arm_type, *source_range) {
+                        errors.push(e);
+                    }
+                }
+
+                if let Some(body) = default {
+                    if let Err(e) = self.analyze_match_arm(global, body, expected, &mut arm_type, *source_range) {
+                        errors.push(e);
+                    }
+                } else if !(patterns.contains(&Data::Bool(true)) && patterns.contains(&Data::Bool(false))) {
+                    errors.push(CompilerError::new(self.file, 245, "match doesn't cover every value", Severity::Error)
+                        .highlight(*source_range)
+                            .note("add a `_` arm to handle the remaining values".to_string())
+                        .build())
+                }
+
+                if !errors.is_empty() {
+                    return Err(errors.combine_into_error())
+                }
+
+                Ok(arm_type.map_or(SourcedDataType::new(*source_range, DataType::Empty), |x| x.0))
+            },
+
+
             Expression::Identifier(identifier) => {
-                match self.variable_stack.find(*identifier) {
+                match self.variable_stack.read(*identifier) {
                     Some(v) => Ok(v),
                     None => {
-                        Err(CompilerError::new(self.file, 205, "variable does not exist")
-                            .highlight(*source_range)
-                            .build()
-                        )
+                        // Constants are inlined as the literal they
+                        // evaluated to, they keep their declared type
+                        if let Some(data) = self.get_constant(global, identifier) {
+                            let data = SourcedData::new(*source_range, data);
+                            let data_type = SourcedDataType::from(&data);
+
+                            *expression = Expression::Data(data);
+                            return Ok(data_type)
+                        }
+
+                        let name = global.symbol_table.get(identifier);
+                        let candidates : Vec<String> = self.variable_stack.names()
+                            .chain(self.constants.keys().copied())
+                            .map(|x| global.symbol_table.get(&x))
+                            .collect();
+
+                        let error = CompilerError::new(self.file, 205, "variable does not exist", Severity::Error)
+                            .highlight(*source_range);
+
+                        Err(match suggestions::closest(&name, candidates.iter().map(String::as_str)) {
+                            Some(v) => error.note(format!("did you mean {v}?")),
+                            None => error,
+                        }.build())
                     },
                 }
             },
@@ -666,12 +1144,22 @@ impl AnalysisState {
                 }
 
                 
-                let (mut function, mut absolute_identifier) = match self.get_function(global, identifier) {
+                let (function, mut absolute_identifier) = match self.get_function(global, identifier) {
                     Some(v) => v,
                     None => {
-                        return Err(CompilerError::new(self.file, 212, "function isn't declared")
+                        let name = global.symbol_table.get(identifier);
+                        let candidates : Vec<String> = self.functions.keys()
+                            .map(|x| global.symbol_table.get(x))
+                            .collect();
+
+                        let note = match suggestions::closest(&name, candidates.iter().map(String::as_str)) {
+                            Some(v) => format!("there's no function named {name}, did you mean {v}?"),
+                            None => format!("there's no function named {name}"),
+                        };
+
+                        return Err(CompilerError::new(self.file, 212, "function isn't declared", Severity::Error)
                             .highlight(*source_range)
-                                .note(format!("there's no function named {}", global.symbol_table.get(identifier)))
+                                .note(note)
                             .build())
                     },
                 };
@@ -681,7 +1169,7 @@ impl AnalysisState {
                 if function.is_template_function {
                     let generic_count = global.template_functions.get(&absolute_identifier).unwrap().generics.len();
                     if generics.len() != generic_count {
-                        return Err(CompilerError::new(self.file, 231, "haven't provided the right amount of generics")
+                        return Err(CompilerError::new(self.file, 231, "haven't provided the right amount of generics", Severity::Error)
                             .highlight(*source_range)
                                 .note(format!("the function {} has {} generic arguments but you've provided {}",
                                     global.symbol_table.get(&absolute_identifier),
@@ -698,9 +1186,8 @@ impl AnalysisState {
                     );
                     
                     absolute_identifier = name;
-                    function = global.functions.get(&name).unwrap();
                 } else if !generics.is_empty() {
-                    return Err(CompilerError::new(self.file, 231, "function has no generic arguments")
+                    return Err(CompilerError::new(self.file, 231, "function has no generic arguments", Severity::Error)
                         .highlight(*source_range)
                             .note(format!("the function {} has no generic arguments", global.symbol_table.get(&absolute_identifier)))
                         .build())
@@ -708,10 +1195,26 @@ impl AnalysisState {
                 
 
                 *identifier = absolute_identifier;
+
+                if global.inferring.contains(&absolute_identifier) {
+                    return Err(CompilerError::new(self.file, 260, "recursive function without a return type", Severity::Error)
+                        .highlight(*source_range)
+                            .note(format!("the return type of {} can't be inferred as it depends on this call", global.symbol_table.get(&absolute_identifier)))
+                            .note("add a return type to the function".to_string())
+                        .build())
+                }
+
+                // Functions without a return type are analyzed
+                // the first time they're called
+                if global.pending_functions.contains_key(&absolute_identifier) {
+                    self.analyze_pending_function(global, absolute_identifier)?;
+                }
+
+                let function = global.functions.get(&absolute_identifier).unwrap();
                 let return_type = function.return_type.clone();
         
                 if function.arguments.len() != arguments.len() {
-                    return Err(CompilerError::new(self.file, 214, "invalid number of arguments")
+                    return Err(CompilerError::new(self.file, 214, "invalid number of arguments", Severity::Error)
                         .highlight(*source_range)
                             .note(format!("expected {} arguments found {}", function.arguments.len(), arguments.len()))
                         .build())
@@ -748,7 +1251,7 @@ impl AnalysisState {
  
 
                         if !is_of_type {
-                            errors.push(CompilerError::new(self.file, 213, "argument is of invalid type")
+                            errors.push(CompilerError::new(self.file, 213, "argument is of invalid type", Severity::Error)
                                 .highlight(argument.source_range)
                                     .note(format!(
                                         "is of type {} while the function expects {}", 
@@ -770,6 +1273,7 @@ impl AnalysisState {
 
             
             Expression::StructureCreation { identifier, fields, identifier_range, generics } => {
+                self.update_generics(generics, global)?;
                 let (mut structure, mut full_name) = self.get_struct(global, identifier_range, identifier, generics)?;
                 let is_template = structure.is_template_structure;
                 if is_template {
@@ -809,7 +1313,7 @@ impl AnalysisState {
                             };
 
                             if !is_same_type {
-                                field_errors.push(CompilerError::new(self.file, 217, "structure field and provided value are not of the same type")
+                                field_errors.push(CompilerError::new(self.file, 217, "structure field and provided value are not of the same type", Severity::Error)
                                     .highlight(v.source_range)
                                         .note(format!("defined here as type {}", global.to_string(&v.data_type)))
 
@@ -827,7 +1331,7 @@ impl AnalysisState {
 
 
                     if !invalid.is_empty() {
-                        field_errors.push(CompilerError::new(self.file, 218, "invalid fields")
+                        field_errors.push(CompilerError::new(self.file, 218, "invalid fields", Severity::Error)
                             .highlight(*source_range)
                                 .note(format!("invalid: {}", invalid.into_iter().map(|x| global.symbol_table.get(&x)).intersperse(", ".to_string()).collect::<String>()))
                             .build())
@@ -835,7 +1339,7 @@ impl AnalysisState {
 
 
                     if !hashmap.is_empty() {
-                        field_errors.push(CompilerError::new(self.file, 219, "missing fields")
+                        field_errors.push(CompilerError::new(self.file, 219, "missing fields", Severity::Error)
                             .highlight(*source_range)
                                 .note(format!("missing: {}", hashmap.into_iter().map(|x| global.symbol_table.get(&x.0)).intersperse(", ".to_string()).collect::<String>()))
                             .build())
@@ -855,6 +1359,120 @@ impl AnalysisState {
             },
 
             
+            Expression::ArrayCreation { elements } => {
+                let expected_element = match expected {
+                    Some(DataType::Array(v)) => Some(&**v),
+                    _ => None,
+                };
+
+                let Some((first, rest)) = elements.split_first_mut() else {
+                    return match expected_element {
+                        Some(v) => Ok(SourcedDataType::new(*source_range, DataType::Array(Box::new(v.clone())))),
+                        None => Err(CompilerError::new(self.file, 234, "can't infer the type of an empty array", Severity::Error)
+                            .highlight(*source_range)
+                                .note("consider giving it a type hint like `[i64]`".to_string())
+                            .build())
+                    }
+                };
+
+                let element_type = self.analyze(global, first, expected_element)?;
+
+                let mut errors = vec![];
+                for element in rest {
+                    let value_type = match self.analyze(global, element, Some(&element_type.data_type)) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            errors.push(e);
+                            continue
+                        },
+                    };
+
+                    let is_of_type = match self.is_of_type(global, (&value_type, element), &element_type) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            errors.push(e);
+                            continue
+                        },
+                    };
+
+                    if !is_of_type {
+                        errors.push(CompilerError::new(self.file, 235, "array elements are of different types", Severity::Error)
+                            .highlight(first.source_range)
+                                .note(format!("the first element is of type {}", global.to_string(&element_type.data_type)))
+
+                            .empty_line()
+
+                            .highlight(element.source_range)
+                                .note(format!("..but this is of type {}", global.to_string(&value_type.data_type)))
+                            .build())
+                    }
+                }
+
+                if !errors.is_empty() {
+                    return Err(errors.combine_into_error())
+                }
+
+                Ok(SourcedDataType::new(*source_range, DataType::Array(Box::new(element_type.data_type))))
+            },
+
+            
+            Expression::Index { value, index } => {
+                let value_type = self.analyze(global, value, None)?;
+                self.analyze_index(global, index)?;
+
+                match value_type.data_type {
+                    DataType::Array(v) => Ok(SourcedDataType::new(*source_range, *v)),
+                    DataType::String => Ok(SourcedDataType::new(*source_range, DataType::Char)),
+                    DataType::Any => Ok(SourcedDataType::new(*source_range, DataType::Any)),
+
+                    _ => Err(CompilerError::new(self.file, 236, "value can't be indexed", Severity::Error)
+                        .highlight(value.source_range)
+                            .note(format!("is of type {} but only arrays and strings can be indexed", global.to_string(&value_type.data_type)))
+                        .build())
+                }
+            },
+
+            
+            Expression::Range { start, end, .. } => {
+                let mut start_type = self.analyze(global, start, expected)?;
+                let end_type = self.analyze(global, end, Some(&start_type.data_type))?;
+
+                // `i..10` infers the literal from `i` but `0..n`
+                // has to infer it from `n` instead
+                if start_type.data_type != end_type.data_type && matches!(start.instruction_kind, InstructionKind::Expression(Expression::Data(_))) {
+                    start_type = self.analyze(global, start, Some(&end_type.data_type))?;
+                }
+
+                if !matches!(start_type.data_type,
+                    | DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64
+                    | DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64
+                    | DataType::Any
+                ) {
+                    return Err(CompilerError::new(self.file, 240, "range bounds must be integers", Severity::Error)
+                        .highlight(start.source_range)
+                            .note(format!("is of type {}", global.to_string(&start_type.data_type)))
+                        .build())
+                }
+
+                if !self.is_of_type(global, (&end_type, end), &start_type)? {
+                    return Err(CompilerError::new(self.file, 241, "range bounds are of different types", Severity::Error)
+                        .highlight(start.source_range)
+                            .note(format!("the start is of type {}", global.to_string(&start_type.data_type)))
+
+                        .empty_line()
+
+                        .highlight(end.source_range)
+                            .note(format!("..but the end is of type {}", global.to_string(&end_type.data_type)))
+                        .build())
+                }
+
+                let InstructionKind::Expression(start) = std::mem::take(&mut **start).instruction_kind else { unreachable!() };
+                *expression = start;
+
+                Ok(SourcedDataType::new(*source_range, start_type.data_type))
+            },
+
+            
             Expression::AccessStructureData { structure, identifier, index_to } => {
                 let structure_type = self.analyze(global, structure, None)?;
                 
@@ -876,7 +1494,7 @@ impl AnalysisState {
                     _ => ()
                 };
 
-                return Err(CompilerError::new(self.file, 220, "structure field doesn't exist")
+                return Err(CompilerError::new(self.file, 220, "structure field doesn't exist", Severity::Error)
                         .highlight(*source_range)
                             .note(format!("is of type {} which doesn't have a field named {}", global.to_string(&structure_type.data_type), global.symbol_table.get(identifier)))
                         .build()
@@ -884,8 +1502,21 @@ impl AnalysisState {
             },
 
             
+            // The wrapper is transparent, the expected type and
+            // any control flow belong to the wrapped expression
             Expression::WithinNamespace { do_within, .. } => {
-                self.analyze(global, do_within, None)
+                self.analyze(global, do_within, expected)
+            },
+
+
+            Expression::LoopIndex => {
+                if self.loop_labels.is_empty() {
+                    return Err(CompilerError::new(self.file, 261, "loop.index outside of loop", Severity::Error)
+                        .highlight(*source_range)
+                        .build())
+                }
+
+                Ok(SourcedDataType::new(*source_range, DataType::I64))
             },
         }
     }
@@ -905,10 +1536,10 @@ impl AnalysisState {
                     },
                 };
                 
-                self.variable_stack.push(*identifier, if let Some(v) = type_hint { v.clone() } else { data_type.clone() });
+                self.variable_stack.declare(*identifier, if let Some(v) = type_hint { v.clone() } else { data_type.clone() }, *source_range);
 
                 if !type_hint.as_ref().map_or(Ok(true), |x| self.is_of_type(global, (&data_type, data), x))? {
-                    return Err(CompilerError::new(self.file, 210, "value differs from type hint")
+                    return Err(CompilerError::new(self.file, 210, "value differs from type hint", Severity::Error)
                         .highlight(data.source_range)
                             .note(format!("is of type {} but the type hint is {}", global.to_string(&data_type.data_type), global.to_string(&type_hint.as_ref().unwrap().data_type)))
                         .build())
@@ -924,7 +1555,7 @@ impl AnalysisState {
                         let value = match self.variable_stack.find(*v) {
                             Some(v) => v,
                             None => {
-                                return Err(CompilerError::new(self.file, 206, "can't update a variable that does not exist")
+                                return Err(CompilerError::new(self.file, 206, "can't update a variable that does not exist", Severity::Error)
                                     .highlight(left.source_range)
                                     .build());
                             },
@@ -933,7 +1564,7 @@ impl AnalysisState {
                         let right_type = self.analyze(global, right, Some(&value.data_type))?;
 
                         if !self.is_of_type(global, (&right_type, right), &value)? {
-                            return Err(CompilerError::new(self.file, 207, "variable is of different type")
+                            return Err(CompilerError::new(self.file, 207, "variable is of different type", Severity::Error)
                                 .highlight(*source_range)
                                     .note(format!("{} is of type {} but the assigned value is of type {}", global.symbol_table.get(v), global.to_string(&value.data_type), global.to_string(&right_type.data_type)))
                                 .build())
@@ -947,43 +1578,69 @@ impl AnalysisState {
             },
 
             
-            Statement::Loop { body } => {
-                self.loop_depth += 1;
+            Statement::Loop { body, label } => {
+                let label = label.map(|x| (x, SourceRange::new(source_range.start, source_range.start + global.symbol_table.get(&x).len())));
+
+                if let Some((label, range)) = label {
+                    let shadowed = self.loop_labels.iter().rev().flatten().find(|x| x.0 == label);
+
+                    if let Some((_, shadowed_range)) = shadowed {
+                        global.warnings.push(CompilerError::new(self.file, 301, "label shadows an outer label", Severity::Warning)
+                            .highlight(*shadowed_range)
+                                .note("first defined here".to_string())
+                            .highlight(range)
+                                .note("`break` and `continue` will refer to this loop".to_string())
+                            .build())
+                    }
+                }
 
-                self.analyze_block(global, body, true, true, None)?;
+                self.loop_labels.push(label);
 
-                self.loop_depth -= 1;
+                let result = self.analyze_block(global, body, true, true, None);
 
+                self.loop_labels.pop();
+
+                result?;
                 Ok(())
             },
             
             
-            Statement::Break => {
-                if self.loop_depth == 0 {
-                    return Err(CompilerError::new(self.file, 208, "break outside of loop")
+            Statement::Break(label) => {
+                if self.loop_labels.is_empty() {
+                    return Err(CompilerError::new(self.file, 208, "break outside of loop", Severity::Error)
                         .highlight(*source_range)
                         .build())
                 }
-                Ok(())
+
+                self.check_label(global, *label, *source_range)
             },
             
             
-            Statement::Continue => {
-                if self.loop_depth == 0 {
-                    return Err(CompilerError::new(self.file, 209, "continue outside of loop")
+            Statement::Continue(label) => {
+                if self.loop_labels.is_empty() {
+                    return Err(CompilerError::new(self.file, 209, "continue outside of loop", Severity::Error)
                         .highlight(*source_range)
                         .build())
                 }
-                Ok(())
-                
+
+                self.check_label(global, *label, *source_range)
             },
 
 
             Statement::Return(v) => {
                 let expected_type = match &self.explicit_return {
                     Some(v) => v.clone(),
+
+                    // The first `return` of a function without
+                    // a return type decides the type of the others
+                    None if self.infers_return => {
+                        let datatype = self.analyze(global, v, None)?;
+                        self.explicit_return = Some(SourcedDataType::new(v.source_range, datatype.data_type));
+                        return Ok(())
+                    },
+
                     None =>
-                    return Err(CompilerError::new(self.file, 221, "return in main scope")
+                    return Err(CompilerError::new(self.file, 221, "return in main scope", Severity::Error)
                         .highlight(*source_range)
                             .note("consider using 'exit(0)'".to_string())
                         .build())
@@ -992,7 +1649,11 @@ impl AnalysisState {
                 let datatype = self.analyze(global, v, Some(&expected_type.data_type))?;
 
                 if !self.is_of_type(global, (&datatype, v), &expected_type)? {
-                    return Err(CompilerError::new(self.file, 222, "invalid return type")
+                    if self.infers_return {
+                        return Err(conflicting_return_types(self.file, global, &expected_type, &SourcedDataType::new(v.source_range, datatype.data_type)))
+                    }
+
+                    return Err(CompilerError::new(self.file, 222, "invalid return type", Severity::Error)
                         .highlight(expected_type.source_range)
                             .note(format!("defined as {}", global.to_string(&expected_type.data_type)))
                         
@@ -1023,7 +1684,7 @@ impl AnalysisState {
                             let right_value = self.analyze(global, right, Some(&field_type.data_type))?;
 
                             if !self.is_of_type(global, (&right_value, right), &field_type)? {
-                                return Err(CompilerError::new(self.file, 207, "variable is of different type")
+                                return Err(CompilerError::new(self.file, 207, "variable is of different type", Severity::Error)
                                     .highlight(*source_range)
                                         .note(format!("{} is of type {} but the assigned value is of type {}", global.symbol_table.get(identifier), global.to_string(&field_type.data_type), global.to_string(&right_value.data_type)))
                                     .build())
@@ -1038,25 +1699,52 @@ impl AnalysisState {
                     _ => ()
                 };
 
-                return Err(CompilerError::new(self.file, 220, "structure field doesn't exist")
+                return Err(CompilerError::new(self.file, 220, "structure field doesn't exist", Severity::Error)
                         .highlight(*source_range)
                             .note(format!("is of type {} which doesn't have a field named {}", global.to_string(&structure_type.data_type), global.symbol_table.get(identifier)))
                         .build()
                 )
             },
+
+
+            Statement::IndexUpdate { value, index, right } => {
+                let value_type = self.analyze(global, value, None)?;
+                self.analyze_index(global, index)?;
+
+                let element_type = match value_type.data_type {
+                    DataType::Array(v) => SourcedDataType::new(value_type.source_range, *v),
+                    DataType::Any => SourcedDataType::new(value_type.source_range, DataType::Any),
+
+                    _ => return Err(CompilerError::new(self.file, 238, "can't assign to an index of this value", Severity::Error)
+                        .highlight(value.source_range)
+                            .note(format!("is of type {} but only arrays can be assigned to", global.to_string(&value_type.data_type)))
+                        .build())
+                };
+
+                let right_value = self.analyze(global, right, Some(&element_type.data_type))?;
+
+                if !self.is_of_type(global, (&right_value, right), &element_type)? {
+                    return Err(CompilerError::new(self.file, 207, "variable is of different type", Severity::Error)
+                        .highlight(*source_range)
+                            .note(format!("the elements are of type {} but the assigned value is of type {}", global.to_string(&element_type.data_type), global.to_string(&right_value.data_type)))
+                        .build())
+                }
+
+                Ok(())
+            },
         } 
     }
 
     
     fn declaration_early_process(&mut self, global: &mut GlobalState, source_range: &SourceRange, declaration: &mut Declaration) -> Result<(), Error> {
         match declaration {
-            Declaration::FunctionDeclaration { name, arguments, return_type, source_range_declaration, generics, body } => {
+            Declaration::FunctionDeclaration { name, arguments, return_type, source_range_declaration, generics, body, infer_return_type } => {
                 let new_name = global.symbol_table.add_combo(self.custom_path, *name);
                 self.functions.insert(*name, (new_name, self.depth));
                 *name = new_name;
                 
                 if global.functions.contains_key(name) {
-                    return Err(CompilerError::new(self.file, 227, "duplicate function definition")
+                    return Err(CompilerError::new(self.file, 227, "duplicate function definition", Severity::Error)
                         .highlight(*source_range_declaration)
                             .note("this function is already defined".to_string())
                         .build())
@@ -1081,6 +1769,7 @@ impl AnalysisState {
                 if !generics.is_empty() {
                     let function = TemplateFunction {
                         return_type: return_type.clone(),
+                        infer_return_type: *infer_return_type,
                         arguments: arguments.clone(),
                         instructions: body.clone(),
                         name: *name,
@@ -1107,7 +1796,7 @@ impl AnalysisState {
                 }
                 
                 if global.functions.contains_key(name) {
-                    return Err(CompilerError::new(self.file, 228, "duplicate struct definition")
+                    return Err(CompilerError::new(self.file, 228, "duplicate struct definition", Severity::Error)
                         .highlight(*source_range)
                             .note("this structure is already defined".to_string())
                         .build())
@@ -1167,24 +1856,12 @@ impl AnalysisState {
 
             
             Declaration::UseFile { file_name } => {
-                let path = global.symbol_table.get(file_name);
-                let mut path = PathBuf::from(path);
-                path.set_extension("az");
-
                 let current_file_path = global.symbol_table.find_root(self.custom_path).0;
                 let current_file_path = PathBuf::from(global.symbol_table.get(&current_file_path));
-                let path_local_to_file = Path::join(current_file_path.parent().unwrap(), &path);
+                let name = global.symbol_table.get(file_name);
 
-                if let Some(v) = global.symbol_table.find(path_local_to_file.to_string_lossy().to_string().as_str()) {
-                    if global.files.contains_key(&v) {
-                        self.available_files.insert(*file_name, v);
-                        *file_name = v;
-                        return Ok(())
-                    }
-                } else {
-                    let new_path = std::env::current_exe().unwrap().parent().unwrap().join("api").join(&path);
-
-                    if let Some(v) = global.symbol_table.find(new_path.to_string_lossy().to_string().as_str()) {
+                for path in use_file_paths(&current_file_path, &name) {
+                    if let Some(v) = global.symbol_table.find(path.to_string_lossy().to_string().as_str()) {
                         if global.files.contains_key(&v) {
                             self.available_files.insert(*file_name, v);
                             *file_name = v;
@@ -1194,22 +1871,16 @@ impl AnalysisState {
                 }
 
 
-                let (file, path) = match fs::read_to_string(&path_local_to_file) {
-                    Ok(v) => (v, path_local_to_file),
-                    Err(_) => {
-                        let new_path = std::env::current_exe().unwrap().parent().unwrap().join("api").join(&path);
-                        match fs::read_to_string(&new_path) {
-                            Ok(v) => (v, new_path),
-                            Err(_) => return Err(CompilerError::new(self.file, 223, "file doesn't exist")
-                                .highlight(*source_range)
-                                    .note(format!("can't find a file named {} at any of the following paths: {}, {}",
-                                        global.symbol_table.get(file_name),
-                                        path_local_to_file.to_string_lossy(),
-                                        new_path.to_string_lossy(),
-                                ))
-                                .build())
-                        }
-                    },
+                let (path, file) = match read_use_file(&current_file_path, &name) {
+                    Ok(v) => v,
+                    Err([path_local_to_file, new_path]) => return Err(CompilerError::new(self.file, 223, "file doesn't exist", Severity::Error)
+                        .highlight(*source_range)
+                            .note(format!("can't find a file named {} at any of the following paths: {}, {}",
+                                name,
+                                path_local_to_file.to_string_lossy(),
+                                new_path.to_string_lossy(),
+                        ))
+                        .build())
                 };
 
                 
@@ -1241,11 +1912,152 @@ impl AnalysisState {
                     }
                 }
             },
+
+
+            Declaration::Const { name, data_type, value } => {
+                let new_name = global.symbol_table.add_combo(self.custom_path, *name);
+
+                if global.constants.contains_key(&new_name) {
+                    return Err(CompilerError::new(self.file, 252, "duplicate constant definition", Severity::Error)
+                        .highlight(*source_range)
+                            .note("this constant is already defined".to_string())
+                        .build())
+                }
+
+                if !self.is_constant_expression(global, value) {
+                    return Err(CompilerError::new(self.file, 250, "constant initializer isn't constant", Severity::Error)
+                        .highlight(value.source_range)
+                            .note("constants can only be made of literals, other constants, casts & operators".to_string())
+                        .build())
+                }
+
+                self.update_type(data_type, global)?;
+
+                let value_type = self.analyze(global, value, Some(&data_type.data_type))?;
+                if !self.is_of_type(global, (&value_type, value), data_type)? {
+                    return Err(CompilerError::new(self.file, 210, "value differs from type hint", Severity::Error)
+                        .highlight(value.source_range)
+                            .note(format!("is of type {} but the type hint is {}", global.to_string(&value_type.data_type), global.to_string(&data_type.data_type)))
+                        .build())
+                }
+
+                let Some(data) = const_eval::evaluate(value) else {
+                    return Err(CompilerError::new(self.file, 251, "constant evaluation failed", Severity::Error)
+                        .highlight(value.source_range)
+                            .note("the evaluation overflowed or divided by zero".to_string())
+                        .build())
+                };
+
+                self.constants.insert(*name, (new_name, self.depth));
+                *name = new_name;
+
+                **value = Instruction {
+                    instruction_kind: InstructionKind::Expression(Expression::Data(SourcedData::new(value.source_range, data.clone()))),
+                    source_range: value.source_range,
+                    result_type: data_type.data_type.clone(),
+                };
+
+                global.constants.insert(*name, data);
+            },
+
+
+            Declaration::Enum { name, variants } => {
+                let new_name = global.symbol_table.add_combo(self.custom_path, *name);
+
+                if global.enums.contains_key(&new_name) || global.structures.contains_key(&new_name) {
+                    return Err(CompilerError::new(self.file, 255, "duplicate enum definition", Severity::Error)
+                        .highlight(*source_range)
+                            .note("a type with this name is already defined".to_string())
+                        .build())
+                }
+
+                let mut errors = vec![];
+                let mut discriminants : Vec<(SymbolIndex, i64, SourceRange)> = Vec::with_capacity(variants.len());
+
+                for (variant, discriminant, range) in variants.iter() {
+                    // Variants without a discriminant follow the previous one
+                    let value = match (discriminant, discriminants.last()) {
+                        (Some(v), _) => *v,
+                        (None, None) => 0,
+                        (None, Some(previous)) => match previous.1.checked_add(1) {
+                            Some(v) => v,
+                            None => {
+                                errors.push(CompilerError::new(self.file, 251, "constant evaluation failed", Severity::Error)
+                                    .highlight(*range)
+                                        .note(format!("the previous variant is {} so this one overflows", previous.1))
+                                    .build());
+                                continue
+                            },
+                        },
+                    };
+
+                    if let Some(existing) = discriminants.iter().find(|x| x.0 == *variant) {
+                        errors.push(CompilerError::new(self.file, 256, "duplicate enum variant", Severity::Error)
+                            .highlight(existing.2)
+                                .note("is first defined here".to_string())
+                            .highlight(*range)
+                                .note("and then defined again here".to_string())
+                            .build());
+                        continue
+                    }
+
+                    if let Some(existing) = discriminants.iter().find(|x| x.1 == value) {
+                        errors.push(CompilerError::new(self.file, 257, "duplicate enum discriminant", Severity::Error)
+                            .highlight(existing.2)
+                                .note(format!("{} is {value}", global.symbol_table.get(&existing.0)))
+                            .highlight(*range)
+                                .note(format!("and so is {}", global.symbol_table.get(variant)))
+                            .build());
+                        continue
+                    }
+
+                    discriminants.push((*variant, value, *range));
+                }
+
+                if !errors.is_empty() {
+                    return Err(errors.combine_into_error())
+                }
+
+                // Variants are constants inside of the enum's
+                // namespace & the enum itself is an alias of `i64`
+                for (variant, value, _) in discriminants.iter() {
+                    let local_name = global.symbol_table.add_combo(*name, *variant);
+                    let variant_name = global.symbol_table.add_combo(new_name, *variant);
+
+                    self.constants.insert(local_name, (variant_name, self.depth));
+                    global.constants.insert(variant_name, Data::I64(*value));
+                }
+
+                self.enums.insert(*name, (new_name, self.depth));
+                *name = new_name;
+
+                global.enums.insert(*name, discriminants.into_iter().map(|x| (x.0, x.1)).collect());
+            },
         };
         Ok(())
     }
 
 
+    /// Whether `instruction` can be evaluated by
+    /// `const_eval::evaluate` once it's analyzed
+    fn is_constant_expression(&self, global: &mut GlobalState, instruction: &Instruction) -> bool {
+        let InstructionKind::Expression(expression) = &instruction.instruction_kind else { return false };
+
+        match expression {
+            Expression::Data(_) => true,
+            Expression::Identifier(v) => self.variable_stack.find(*v).is_none() && self.get_constant(global, v).is_some(),
+            Expression::UnaryOp { value, .. } => self.is_constant_expression(global, value),
+            Expression::BinaryOp { left, right, .. } => self.is_constant_expression(global, left) && self.is_constant_expression(global, right),
+            Expression::AsCast { value, cast_type } => {
+                !matches!(cast_type.data_type, DataType::Struct(..) | DataType::Array(_))
+                    && self.is_constant_expression(global, value)
+            },
+
+            _ => false,
+        }
+    }
+
+
     fn declaration_early_process_stage_2(&mut self, global: &mut GlobalState, instruction: &mut Declaration) -> Result<(), Error> {
         match instruction {
             Declaration::FunctionDeclaration { name, arguments, return_type, generics, .. } => {
@@ -1337,6 +2149,8 @@ impl AnalysisState {
 
             
             Declaration::UseFile { .. } => (),
+            Declaration::Const { .. } => (),
+            Declaration::Enum { .. } => (),
         };
 
         Ok(())
@@ -1386,8 +2200,25 @@ impl AnalysisState {
 
 
     fn update_type(&self, datatype: &mut SourcedDataType, global: &mut GlobalState) -> Result<bool, Error> {
+        // Enums are stored as their discriminant
+        if let DataType::Struct(v, gens) = &datatype.data_type {
+            if gens.is_empty() && self.is_enum(global, v) {
+                datatype.data_type = DataType::I64;
+                return Ok(false)
+            }
+        }
+
         self.is_valid_type(global, datatype)?;
+        if let DataType::Array(v) = &mut datatype.data_type {
+            let mut element = SourcedDataType::new(datatype.source_range, std::mem::replace(&mut **v, DataType::Empty));
+            let result = self.update_type(&mut element, global);
+            **v = element.data_type;
+
+            return result
+        }
+
         if let DataType::Struct(v, gens) = &mut datatype.data_type {
+            self.update_generics(gens, global)?;
             let (structure, id) = self.get_struct(global, &datatype.source_range, v, gens)?;
 
 
@@ -1405,17 +2236,32 @@ impl AnalysisState {
     }
 
     
+    /// Updates the generic arguments of a structure so nested
+    /// generic structures exist before the template is filled
+    /// in with them
+    fn update_generics(&self, generics: &mut Arc<[SourcedDataType]>, global: &mut GlobalState) -> Result<(), Error> {
+        let mut temp = generics.to_vec();
+        for generic in &mut temp {
+            self.update_type(generic, global)?;
+        }
+
+        *generics = temp.into();
+        Ok(())
+    }
+
+    
     fn is_valid_type(&self, global: &mut GlobalState, value: &SourcedDataType) -> Result<(), Error> {
         let v = match &value.data_type {
             DataType::Struct(v, g) => {
                 self.get_struct(global, &value.source_range, v, g)?;
                 true
             },
+            DataType::Array(v) => return self.is_valid_type(global, &SourcedDataType::new(value.source_range, (**v).clone())),
             _ => true
         };
 
         if !v {
-            return Err(CompilerError::new(self.file, 214, "type doesn't exist")
+            return Err(CompilerError::new(self.file, 214, "type doesn't exist", Severity::Error)
                 .highlight(value.source_range)
                     .note(format!("is of type {} which isn't declared", global.to_string(&value.data_type)))
                 .build())
@@ -1425,7 +2271,127 @@ impl AnalysisState {
         Ok(())
     }
 
-    
+
+    /// Analyzes the index of an index expression and
+    /// casts it to an `i64` which is what the runtime
+    /// expects
+    fn analyze_index(&mut self, global: &mut GlobalState, index: &mut Instruction) -> Result<(), Error> {
+        let index_type = self.analyze(global, index, Some(&DataType::I64))?;
+
+        match index_type.data_type {
+            DataType::I64 | DataType::Any => Ok(()),
+
+            | DataType::I8
+            | DataType::I16
+            | DataType::I32
+            | DataType::U8
+            | DataType::U16
+            | DataType::U32
+            | DataType::U64 => {
+                let value = std::mem::take(index);
+
+                *index = Instruction {
+                    source_range: value.source_range,
+                    instruction_kind: InstructionKind::Expression(Expression::AsCast {
+                        value: Box::new(value),
+                        cast_type: SourcedDataType::new(index_type.source_range, DataType::I64)
+                    }),
+                    result_type: DataType::I64,
+                };
+
+                Ok(())
+            },
+
+            _ => Err(CompilerError::new(self.file, 237, "index must be an integer", Severity::Error)
+                .highlight(index.source_range)
+                    .note(format!("is of type {}", global.to_string(&index_type.data_type)))
+                .build())
+        }
+    }
+
+
+    fn analyze_match_pattern(&mut self, global: &mut GlobalState, pattern: &mut Instruction, scrutinee_type: &SourcedDataType, patterns: &mut Vec<Data>) -> Result<(), Error> {
+        let pattern_type = self.analyze(global, pattern, Some(&scrutinee_type.data_type))?;
+
+        // Read the value before `is_of_type`
+        // can wrap the pattern in a cast
+        let InstructionKind::Expression(Expression::Data(data)) = &pattern.instruction_kind else {
+            return Err(CompilerError::new(self.file, 258, "match pattern isn't constant", Severity::Error)
+                .highlight(pattern.source_range)
+                    .note("patterns can only be literals, constants & enum variants".to_string())
+                .build())
+        };
+        let data = data.data.clone();
+
+        if !self.is_of_type(global, (&pattern_type, pattern), scrutinee_type)? {
+            return Err(CompilerError::new(self.file, 243, "match pattern is of a different type", Severity::Error)
+                .highlight(pattern.source_range)
+                    .note(format!("is of type {} but the matched value is of type {}", global.to_string(&pattern_type.data_type), global.to_string(&scrutinee_type.data_type)))
+                .build())
+        }
+
+        if patterns.contains(&data) {
+            return Err(CompilerError::new(self.file, 246, "match pattern is already covered", Severity::Error)
+                .highlight(pattern.source_range)
+                    .note("an earlier arm matches the same value".to_string())
+                .build())
+        }
+
+        patterns.push(data);
+        Ok(())
+    }
+
+
+    fn analyze_match_arm(&mut self, global: &mut GlobalState, body: &mut [Instruction], expected: Option<&DataType>, arm_type: &mut Option<(SourcedDataType, SourceRange)>, source_range: SourceRange) -> Result<(), Error> {
+        // The first arm decides the type of the others
+        let expected = expected.or(arm_type.as_ref().map(|x| &x.0.data_type)).cloned();
+
+        let body_type = self.analyze_block(global, body, true, true, expected.as_ref())?;
+        let range = body.last().map_or(source_range, |x| x.source_range);
+
+        let Some((first_type, first_range)) = arm_type else {
+            *arm_type = Some((body_type, range));
+            return Ok(())
+        };
+
+        let is_of_type = match body.last_mut() {
+            Some(last) => self.is_of_type(global, (&body_type, last), first_type)?,
+            None => body_type.data_type == first_type.data_type,
+        };
+
+        if !is_of_type {
+            return Err(CompilerError::new(self.file, 244, "match arms don't return the same type", Severity::Error)
+                .highlight(*first_range)
+                    .note(format!("is of type {}", global.to_string(&first_type.data_type)))
+
+                .empty_line()
+
+                .highlight(range)
+                    .note(format!("but this returns {}", global.to_string(&body_type.data_type)))
+                .build())
+        }
+
+        Ok(())
+    }
+
+
+    fn check_label(&self, global: &mut GlobalState, label: Option<SymbolIndex>, source_range: SourceRange) -> Result<(), Error> {
+        let label = match label {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        if !self.loop_labels.iter().flatten().any(|x| x.0 == label) {
+            return Err(CompilerError::new(self.file, 233, "label doesn't exist", Severity::Error)
+                .highlight(source_range)
+                    .note(format!("there's no loop labeled '{} in scope", global.symbol_table.get(&label)))
+                .build())
+        }
+
+        Ok(())
+    }
+
+
     fn get_function_detailed<'a>(
             &self,
             symbol_table: &mut SymbolTable,
@@ -1467,6 +2433,180 @@ impl AnalysisState {
     }
 
     
+    /// Finds the user-defined conversion of `from` into `to`,
+    /// that is a `fn as <to>(self): <to>` in an impl block of `from`
+    fn get_conversion(&self, global: &mut GlobalState, from: &DataType, to: &DataType) -> Option<SymbolIndex> {
+        let conversion = conversion_symbol(global.symbol_table, to);
+
+        let identifier = Self::method_symbol(global, from, conversion);
+
+        let (function, absolute_identifier) = self.get_function(global, &identifier)?;
+
+        let returns_target = match (&function.return_type.data_type, to) {
+            (DataType::Struct(v, _), DataType::Struct(v2, _)) => v == v2,
+            (v, v2) => v == v2,
+        };
+
+        if function.is_template_function || function.arguments.len() != 1 || !returns_target {
+            return None
+        }
+
+        Some(absolute_identifier)
+    }
+
+
+    /// Finds the method a struct defines to overload `operator`
+    ///
+    /// `+`, `-`, `*` & `==` map to `add`, `sub`, `mul` & `eq`
+    /// which have to take `self` & another value of the same
+    /// struct. The arithmetic methods have to return the struct
+    /// while `eq` has to return a `bool`, `!=` negates `eq`
+    fn get_operator_overload(&self, global: &mut GlobalState, operator: &BinaryOperator, left: &DataType, right: &DataType) -> Option<(SymbolIndex, SourcedDataType)> {
+        let name = match operator {
+            BinaryOperator::Add      => "add",
+            BinaryOperator::Subtract => "sub",
+            BinaryOperator::Multiply => "mul",
+            BinaryOperator::Equals   => "eq",
+            BinaryOperator::NotEquals => "eq",
+            _ => return None,
+        };
+
+        let same_struct = |x: &DataType, y: &DataType| match (x, y) {
+            (DataType::Struct(v, _), DataType::Struct(v2, _)) => v == v2,
+            _ => false,
+        };
+
+        if !same_struct(left, right) {
+            return None
+        }
+
+        let name = global.symbol_table.add(name.to_string());
+        let identifier = Self::method_symbol(global, left, name);
+
+        let (function, absolute_identifier) = self.get_function(global, &identifier)?;
+
+        let valid_return = match operator {
+            BinaryOperator::Equals | BinaryOperator::NotEquals => function.return_type.data_type == DataType::Bool,
+            _ => same_struct(&function.return_type.data_type, left),
+        };
+
+        if function.is_template_function
+            || function.arguments.len() != 2
+            || !function.arguments.iter().all(|x| same_struct(&x.data_type, left))
+            || !valid_return {
+            return None
+        }
+
+        Some((absolute_identifier, function.return_type.clone()))
+    }
+
+
+    /// Returns the symbol of the method `name` on `data_type`
+    fn method_symbol(global: &mut GlobalState, data_type: &DataType, name: SymbolIndex) -> SymbolIndex {
+        let mut identifier = name;
+
+        let mut temp = data_type.symbol_index(global.symbol_table);
+        let mut pieces = vec![];
+        while let (root, Some(v)) = global.symbol_table.find_root(temp) {
+            temp = v;
+            pieces.push(root);
+        }
+        pieces.push(temp);
+
+        for i in pieces.iter().rev() {
+            identifier = global.symbol_table.add_combo(*i, identifier);
+        }
+
+        identifier
+    }
+
+
+    fn get_constant(&self, global: &mut GlobalState, symbol: &SymbolIndex) -> Option<Data> {
+        if let Some(v) = global.constants.get(symbol) {
+            return Some(v.clone())
+        }
+
+        self.get_constant_detailed(global.symbol_table, &global.files, &global.constants, symbol, true).cloned()
+    }
+
+
+    fn get_constant_detailed<'a>(
+            &self,
+            symbol_table: &mut SymbolTable,
+            files: &HashMap<SymbolIndex, (AnalysisState, Vec<Instruction>, String)>,
+            constants: &'a HashMap<SymbolIndex, Data>,
+            symbol: &SymbolIndex,
+            implicit_complete: bool
+    ) -> Option<&'a Data> {
+        if let Some(v) = self.constants.get(symbol) {
+            return constants.get(&v.0)
+        }
+
+        let (root, root_excluded) = symbol_table.find_root(*symbol);
+
+        if let Some(root_excluded) = root_excluded {
+            if self.available_files.contains_key(&root) {
+                if let Some(v) = files.get(&root)?.0.get_constant_detailed(symbol_table, files, constants, &root_excluded, false) {
+                    return Some(v)
+                }
+            }
+        }
+
+        if !implicit_complete {
+            return None
+        }
+
+        for namespace in self.available_files.iter() {
+            if let Some(v) = files.get(namespace.1)?.0.get_constant_detailed(symbol_table, files, constants, symbol, false) {
+                return Some(v)
+            }
+        }
+
+        None
+    }
+
+    
+    fn is_enum(&self, global: &mut GlobalState, symbol: &SymbolIndex) -> bool {
+        global.enums.contains_key(symbol)
+            || self.is_enum_detailed(global.symbol_table, &global.files, &global.enums, symbol, true)
+    }
+
+
+    fn is_enum_detailed(
+            &self,
+            symbol_table: &mut SymbolTable,
+            files: &HashMap<SymbolIndex, (AnalysisState, Vec<Instruction>, String)>,
+            enums: &HashMap<SymbolIndex, Vec<(SymbolIndex, i64)>>,
+            symbol: &SymbolIndex,
+            implicit_complete: bool
+    ) -> bool {
+        if let Some(v) = self.enums.get(symbol) {
+            return enums.contains_key(&v.0)
+        }
+
+        let (root, root_excluded) = symbol_table.find_root(*symbol);
+
+        if let Some(root_excluded) = root_excluded {
+            if self.available_files.contains_key(&root) {
+                if let Some(file) = files.get(&root) {
+                    if file.0.is_enum_detailed(symbol_table, files, enums, &root_excluded, false) {
+                        return true
+                    }
+                }
+            }
+        }
+
+        if !implicit_complete {
+            return false
+        }
+
+        self.available_files
+            .iter()
+            .filter_map(|namespace| files.get(namespace.1))
+            .any(|file| file.0.is_enum_detailed(symbol_table, files, enums, symbol, false))
+    }
+
+    
     fn get_function<'a>(&'a self, global: &'a mut GlobalState, symbol: &SymbolIndex) -> Option<(&'a Function, SymbolIndex)> {
         if let Some(v) = global.functions.get(symbol) {
             return Some((v, *symbol));
@@ -1484,7 +2624,7 @@ impl AnalysisState {
                         } { v }
                         
                         else {
-                            return Err(CompilerError::new(self.file, 215, "structure isn't declared")
+                            return Err(CompilerError::new(self.file, 215, "structure isn't declared", Severity::Error)
                                 .highlight(*range)
                                     .note(format!("there's no structure named {}", global.symbol_table.get(symbol)))
                                 .build())
@@ -1492,13 +2632,13 @@ impl AnalysisState {
             
 
         if !structure.is_template_structure && v == global.symbol_table.get_name_without_generics(v) && !generics.is_empty() {
-            return Err(CompilerError::new(self.file, 229, "structure has no generic parameters")
+            return Err(CompilerError::new(self.file, 229, "structure has no generic parameters", Severity::Error)
                 .highlight(*range)
                     .note(format!("{} has no generic parameters", global.symbol_table.get(&v)))
                 .build())
             
         } else if structure.is_template_structure && generics.is_empty() {
-            return Err(CompilerError::new(self.file, 230, "structure exists but it has generic parameters")
+            return Err(CompilerError::new(self.file, 230, "structure exists but it has generic parameters", Severity::Error)
                 .highlight(*range)
                     .note("this structure exists but it has generic parameters which you've not provided".to_string())
                 .build())
@@ -1593,6 +2733,7 @@ impl AnalysisState {
                 body: instructions,
                 generics: vec![],
                 source_range_declaration: base.source_range,
+                infer_return_type: base.infer_return_type,
             };
             
             let mut instruction = Instruction {
@@ -1719,8 +2860,15 @@ impl TypeConversionState<'_> {
                 self.convert_type(right);
             },
 
+
+            Statement::IndexUpdate { value, index, right } => {
+                self.convert_type(value);
+                self.convert_type(index);
+                self.convert_type(right);
+            },
+
             
-            Statement::Loop { body } => {
+            Statement::Loop { body, .. } => {
                 body.iter_mut().for_each(|x| self.convert_type(x));
             },
 
@@ -1765,6 +2913,8 @@ impl TypeConversionState<'_> {
 
             
             Declaration::UseFile { .. } => (),
+            Declaration::Const { .. } => (),
+            Declaration::Enum { .. } => (),
         }
     }
 
@@ -1816,6 +2966,27 @@ impl TypeConversionState<'_> {
             },
 
             
+            Expression::ArrayCreation { elements } => self.convert_types(elements),
+            Expression::Index { value, index } => {
+                self.convert_type(value);
+                self.convert_type(index);
+            },
+            Expression::Range { start, end, .. } => {
+                self.convert_type(start);
+                self.convert_type(end);
+            },
+            Expression::Match { scrutinee, arms, default } => {
+                self.convert_type(scrutinee);
+
+                for (pattern, body) in arms {
+                    self.convert_type(pattern);
+                    self.convert_types(body);
+                }
+
+                if let Some(v) = default {
+                    self.convert_types(v);
+                }
+            },
             Expression::AccessStructureData { structure, .. } => self.convert_type(structure),
             Expression::WithinNamespace { do_within, ..  } => {
                 
@@ -1825,11 +2996,17 @@ impl TypeConversionState<'_> {
             
             Expression::Data(_) => (),
             Expression::Identifier(_) => (),
+            Expression::LoopIndex => (),
         }
     }
 
 
     fn convert_data_type(&mut self, datatype: &mut DataType) {
+        if let DataType::Array(v) = datatype {
+            self.convert_data_type(v);
+            return
+        }
+
         if let DataType::Struct(v, generics) = datatype {
             {
                 let mut temp = generics.to_vec();
@@ -1859,3 +3036,171 @@ impl GlobalState<'_> {
     
 }
 
+
+/// Moves the non-generic functions without a return type declared
+/// in `instructions`, including the ones in namespaces & impl blocks,
+/// to `global.pending_functions` leaving an empty instruction behind
+fn take_pending_functions(global: &mut GlobalState, instructions: &mut [Instruction], path: &mut Vec<usize>, pending: &mut Vec<(Vec<usize>, SymbolIndex)>) {
+    for (index, instruction) in instructions.iter_mut().enumerate() {
+        path.push(index);
+
+        match &mut instruction.instruction_kind {
+            InstructionKind::Declaration(Declaration::FunctionDeclaration { name, generics, infer_return_type: true, .. }) if generics.is_empty() => {
+                pending.push((path.clone(), *name));
+                global.pending_functions.insert(*name, std::mem::take(instruction));
+            },
+
+            | InstructionKind::Declaration(Declaration::Namespace { body, .. })
+            | InstructionKind::Declaration(Declaration::ImplBlock { body, .. }) => take_pending_functions(global, body, path, pending),
+
+            _ => (),
+        }
+
+        path.pop();
+    }
+}
+
+
+/// The instruction a function taken by `take_pending_functions` was at
+fn pending_function_slot<'a>(instructions: &'a mut [Instruction], path: &[usize]) -> &'a mut Instruction {
+    let instruction = &mut instructions[path[0]];
+    if path.len() == 1 {
+        return instruction
+    }
+
+    match &mut instruction.instruction_kind {
+        | InstructionKind::Declaration(Declaration::Namespace { body, .. })
+        | InstructionKind::Declaration(Declaration::ImplBlock { body, .. }) => pending_function_slot(body, &path[1..]),
+
+        _ => unreachable!(),
+    }
+}
+
+
+fn conflicting_return_types(file: SymbolIndex, global: &mut GlobalState, first: &SourcedDataType, other: &SourcedDataType) -> Error {
+    CompilerError::new(file, 259, "conflicting return types", Severity::Error)
+        .highlight(first.source_range)
+            .note(format!("the function returns {} here", global.to_string(&first.data_type)))
+
+        .empty_line()
+
+        .highlight(other.source_range)
+            .note(format!("but {} here", global.to_string(&other.data_type)))
+            .note("add a return type to the function to convert the values to it".to_string())
+        .build()
+}
+
+
+/// Maps every non-generic structure declared in `instructions`,
+/// including the ones in namespaces, to the structures its
+/// fields store by value
+fn collect_structure_fields(instructions: &[Instruction], fields: &mut HashMap<SymbolIndex, Vec<SymbolIndex>>) {
+    for instruction in instructions {
+        match &instruction.instruction_kind {
+            InstructionKind::Declaration(Declaration::StructDeclaration { name, fields: struct_fields, generics }) => {
+                if !generics.is_empty() {
+                    continue
+                }
+
+                let by_value = struct_fields
+                    .iter()
+                    .filter_map(|x| if let DataType::Struct(id, _) = &x.1.data_type { Some(*id) } else { None })
+                    .collect();
+
+                fields.insert(*name, by_value);
+            },
+
+            InstructionKind::Declaration(Declaration::Namespace { body, .. }) => collect_structure_fields(body, fields),
+
+            _ => (),
+        }
+    }
+}
+
+
+/// Looks for a path of fields leading from the last structure
+/// in `path` back to the first one, leaving the cycle in `path`
+fn find_structure_cycle(fields: &HashMap<SymbolIndex, Vec<SymbolIndex>>, path: &mut Vec<SymbolIndex>) -> bool {
+    let Some(struct_fields) = fields.get(path.last().unwrap()) else { return false };
+
+    for id in struct_fields {
+        if *id == path[0] {
+            path.push(*id);
+            return true
+        }
+
+        // Cycles which don't go through the first
+        // structure are found when checking their own
+        if path.contains(id) {
+            continue
+        }
+
+        path.push(*id);
+        if find_structure_cycle(fields, path) {
+            return true
+        }
+        path.pop();
+    }
+
+    false
+}
+
+
+/// Whether control flow never continues past `instruction`, that is
+/// it's a `return`, `break` or `continue`, a block containing one or
+/// an `if` whose every branch diverges
+fn diverges(instruction: &Instruction) -> bool {
+    match &instruction.instruction_kind {
+        InstructionKind::Statement(
+            | Statement::Return(_)
+            | Statement::Break(_)
+            | Statement::Continue(_)
+        ) => true,
+
+        InstructionKind::Expression(Expression::Block { body }) => body.iter().any(diverges),
+
+        InstructionKind::Expression(Expression::WithinNamespace { do_within, .. }) => diverges(do_within),
+
+        InstructionKind::Expression(Expression::IfExpression { body, else_part, .. }) => {
+            body.iter().any(diverges) && else_part.as_ref().is_some_and(|x| diverges(x))
+        },
+
+        _ => false,
+    }
+}
+
+
+/// Returns the paths a `using` declaration in `current_file`
+/// can refer to in the order they are checked
+///
+/// The first path is relative to the directory of `current_file`
+/// while the second one is in the `api` directory next to the
+/// executable
+pub fn use_file_paths(current_file: &Path, file_name: &str) -> [PathBuf; 2] {
+    let mut path = PathBuf::from(file_name);
+    path.set_extension("az");
+
+    let path_local_to_file = Path::join(current_file.parent().unwrap(), &path);
+    let path_in_api = std::env::current_exe().unwrap().parent().unwrap().join("api").join(&path);
+
+    [path_local_to_file, path_in_api]
+}
+
+
+/// Reads the file a `using` declaration in `current_file` refers to
+///
+/// # Errors
+/// - If none of the paths from `use_file_paths` can be read,
+///   the error contains the paths that were tried
+pub fn read_use_file(current_file: &Path, file_name: &str) -> Result<(PathBuf, String), [PathBuf; 2]> {
+    let paths = use_file_paths(current_file, file_name);
+
+    for path in &paths {
+        if let Ok(v) = fs::read_to_string(path) {
+            return Ok((path.clone(), v))
+        }
+    }
+
+    Err(paths)
+}
+
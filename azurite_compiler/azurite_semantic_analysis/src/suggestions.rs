@@ -0,0 +1,44 @@
+/// The largest edit distance a name can be
+/// from the unknown one to be suggested
+const MAX_DISTANCE : usize = 2;
+
+
+/// Finds the candidate closest to `name` by Levenshtein distance
+///
+/// Returns `None` if none are within `MAX_DISTANCE` edits or if
+/// the name is so short that every candidate would be as close.
+/// The hidden names the parser generates are never suggested.
+/// Ties go to the alphabetically first one so the suggestion
+/// doesn't depend on the order of a hash map
+pub fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let length = name.chars().count();
+
+    candidates
+        .filter(|x| *x != name && !x.starts_with('#'))
+        .map(|x| (levenshtein(name, x), x))
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE && *distance < length)
+        .min()
+        .map(|x| x.1)
+}
+
+
+/// The amount of single character insertions, deletions
+/// & substitutions it takes to turn `a` into `b`
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b : Vec<char> = b.chars().collect();
+    let mut previous : Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, x) in a.chars().enumerate() {
+        current[0] = i + 1;
+
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != *y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
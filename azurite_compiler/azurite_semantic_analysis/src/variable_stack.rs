@@ -1,10 +1,23 @@
-use common::{SymbolIndex, SourcedDataType};
+use common::{SymbolIndex, SourcedDataType, SourceRange};
 
 #[derive(Debug, PartialEq)]
 pub struct VariableStack {
-    values: Vec<(SymbolIndex, SourcedDataType)>,
+    values: Vec<Variable>,
 }
 
+
+#[derive(Debug, PartialEq)]
+struct Variable {
+    identifier: SymbolIndex,
+    data_type: SourcedDataType,
+
+    /// Where the variable was declared with `var`. Arguments
+    /// don't have one so they're never reported as unused
+    declaration: Option<SourceRange>,
+    is_read: bool,
+}
+
+
 impl VariableStack {
     pub fn new() -> Self {
         Self {
@@ -14,7 +27,15 @@ impl VariableStack {
 
 
     pub(crate) fn find(&self, str: SymbolIndex) -> Option<SourcedDataType> {
-        self.values.iter().rev().find_map(|x| if x.0 == str { Some(x.1.clone()) } else { None })
+        self.values.iter().rev().find_map(|x| if x.identifier == str { Some(x.data_type.clone()) } else { None })
+    }
+
+    /// Same as `find` but marks the variable as read
+    pub(crate) fn read(&mut self, str: SymbolIndex) -> Option<SourcedDataType> {
+        let variable = self.values.iter_mut().rev().find(|x| x.identifier == str)?;
+        variable.is_read = true;
+
+        Some(variable.data_type.clone())
     }
 
     pub(crate) fn pop(&mut self, amount: usize) {
@@ -22,7 +43,26 @@ impl VariableStack {
     }
 
     pub(crate) fn push(&mut self, identifier: SymbolIndex, value: SourcedDataType) {
-        self.values.push((identifier, value));
+        self.values.push(Variable { identifier, data_type: value, declaration: None, is_read: false });
+    }
+
+    pub(crate) fn declare(&mut self, identifier: SymbolIndex, value: SourcedDataType, declaration: SourceRange) {
+        self.values.push(Variable { identifier, data_type: value, declaration: Some(declaration), is_read: false });
+    }
+
+    /// Returns the declared variables above `top`
+    /// which were never read
+    pub(crate) fn unused(&self, top: usize) -> Vec<(SymbolIndex, SourceRange)> {
+        self.values[top..]
+            .iter()
+            .filter(|x| !x.is_read)
+            .filter_map(|x| Some((x.identifier, x.declaration?)))
+            .collect()
+    }
+
+    /// The identifiers of every variable in scope
+    pub(crate) fn names(&self) -> impl Iterator<Item = SymbolIndex> + '_ {
+        self.values.iter().map(|x| x.identifier)
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -1,29 +1,59 @@
-use std::{fmt::Write, rc::Rc, sync::Arc};
+use std::{fmt::Write, rc::Rc, sync::Arc, collections::HashMap};
 
 #[macro_use]
 extern crate istd;
 
+mod tests;
+
 index_map!(GenericMap, GenericIndex, Vec<DataType>);
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct SourceRange {
     pub start: usize,
     pub end: usize,
+
+    /// The positions of `start` & `end`, this is only known
+    /// for ranges built out of the lexer's tokens
+    pub position: Option<(Position, Position)>,
+}
+
+
+/// A 0-based line & the byte offset within it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
 }
 
 
 impl SourceRange {
-    pub fn new(start: usize, end: usize) -> Self { Self { start, end } }
+    pub fn new(start: usize, end: usize) -> Self { Self { start, end, position: None } }
+
+    pub fn with_position(start: usize, end: usize, start_position: Position, end_position: Position) -> Self {
+        Self { start, end, position: Some((start_position, end_position)) }
+    }
 
     pub fn combine(start: SourceRange, end: SourceRange) -> Self {
         Self {
             start: start.start,
             end: end.end,
+            position: start.position.zip(end.position).map(|(start, end)| (start.0, end.1)),
         }
     }
 }
 
 
+// The position is derived from the offsets
+// so they're all that needs to be compared
+impl PartialEq for SourceRange {
+    fn eq(&self, other: &Self) -> bool {
+        self.start == other.start && self.end == other.end
+    }
+}
+
+impl Eq for SourceRange {}
+
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct SourcedDataType {
     pub source_range: SourceRange,
@@ -68,11 +98,13 @@ pub enum DataType {
     
     Float,
     String,
+    Char,
     Bool,
     Empty,
     Any,
     
     Struct(SymbolIndex, Arc<[SourcedDataType]>),
+    Array(Box<DataType>),
 }
 
 
@@ -86,11 +118,22 @@ impl DataType {
         )
     }
 
+
+    pub const fn is_integer(&self) -> bool {
+        self.is_signed_integer() || matches!(self,
+            | Self::U8
+            | Self::U16
+            | Self::U32
+            | Self::U64
+        )
+    }
+
     
     pub fn from(value: &Data) -> Self {
         match value {
             Data::Float(_)  => DataType::Float,
             Data::String(_) => DataType::String,
+            Data::Char(_)   => DataType::Char,
             Data::Bool(_)   => DataType::Bool,
             Data::Empty     => DataType::Empty,
             Data::I8(_)  => DataType::I8,
@@ -109,7 +152,8 @@ impl DataType {
 impl DataType {
     pub fn is_obj(&self) -> bool {
         matches!(self, | DataType::String
-            | DataType::Struct(_, _))
+            | DataType::Struct(_, _)
+            | DataType::Array(_))
     }
     pub fn to_string(&self, symbol_table: &SymbolTable) -> String {
         match self {
@@ -123,6 +167,7 @@ impl DataType {
             DataType::U64          => "u64".to_string(),
             DataType::Float        => "float".to_string(),
             DataType::String       => "str".to_string(),
+            DataType::Char         => "char".to_string(),
             DataType::Bool         => "bool".to_string(),
             DataType::Empty        => "()".to_string(),
             DataType::Any          => "any".to_string(),
@@ -147,6 +192,7 @@ impl DataType {
 
                 string
             }
+            DataType::Array(v) => format!("[{}]", v.to_string(symbol_table)),
         }
     }
 
@@ -163,10 +209,12 @@ impl DataType {
             DataType::U64          => "u64".to_string(),
             DataType::Float        => "float".to_string(),
             DataType::String       => "str".to_string(),
+            DataType::Char         => "char".to_string(),
             DataType::Bool         => "bool".to_string(),
             DataType::Empty        => "()".to_string(),
             DataType::Any          => "any".to_string(),
-            DataType::Struct(v, _) => symbol_table.get(v)
+            DataType::Struct(v, _) => symbol_table.get(v),
+            DataType::Array(v)     => format!("[{}]", v.identifier(symbol_table)),
         }
         
     }
@@ -182,7 +230,13 @@ impl DataType {
 
 
 
-#[derive(Debug, PartialEq, Clone)]
+/// A constant value
+///
+/// Floats are compared & hashed by their bit pattern so `Data`
+/// can be `Eq` and `Hash`. Every NaN is treated as the same
+/// value while `0.0` and `-0.0` are different values since
+/// they don't behave the same, e.g. `1.0 / -0.0`
+#[derive(Debug, Clone)]
 pub enum Data {
     I8    (i8),
     I16   (i16),
@@ -195,16 +249,83 @@ pub enum Data {
 
     Float (f64),
     String(SymbolIndex),
+    Char  (char),
     Bool  (bool),
 
     Empty,
 }
 
+impl Data {
+    /// The bit pattern of a float with
+    /// every NaN turned into the same one
+    fn float_bits(value: f64) -> u64 {
+        if value.is_nan() {
+            f64::NAN.to_bits()
+        } else {
+            value.to_bits()
+        }
+    }
+}
+
+
+impl PartialEq for Data {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Data::I8 (v1), Data::I8 (v2)) => v1 == v2,
+            (Data::I16(v1), Data::I16(v2)) => v1 == v2,
+            (Data::I32(v1), Data::I32(v2)) => v1 == v2,
+            (Data::I64(v1), Data::I64(v2)) => v1 == v2,
+            (Data::U8 (v1), Data::U8 (v2)) => v1 == v2,
+            (Data::U16(v1), Data::U16(v2)) => v1 == v2,
+            (Data::U32(v1), Data::U32(v2)) => v1 == v2,
+            (Data::U64(v1), Data::U64(v2)) => v1 == v2,
+
+            (Data::Float (v1), Data::Float (v2)) => Data::float_bits(*v1) == Data::float_bits(*v2),
+            (Data::String(v1), Data::String(v2)) => v1 == v2,
+            (Data::Char  (v1), Data::Char  (v2)) => v1 == v2,
+            (Data::Bool  (v1), Data::Bool  (v2)) => v1 == v2,
+
+            (Data::Empty, Data::Empty) => true,
+
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Data {}
+
+
+impl std::hash::Hash for Data {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Data::I8 (v) => v.hash(state),
+            Data::I16(v) => v.hash(state),
+            Data::I32(v) => v.hash(state),
+            Data::I64(v) => v.hash(state),
+            Data::U8 (v) => v.hash(state),
+            Data::U16(v) => v.hash(state),
+            Data::U32(v) => v.hash(state),
+            Data::U64(v) => v.hash(state),
+
+            Data::Float (v) => Data::float_bits(*v).hash(state),
+            Data::String(v) => v.hash(state),
+            Data::Char  (v) => v.hash(state),
+            Data::Bool  (v) => v.hash(state),
+
+            Data::Empty => (),
+        }
+    }
+}
+
+
 impl Data {
     pub fn to_string(&self, symbol_table: &SymbolTable) -> String {
         match self {
             Data::Float(v)  => v.to_string(),
             Data::String(v) => symbol_table.get(v),
+            Data::Char(v)   => v.to_string(),
             Data::Bool(v)   => v.to_string(),
             Data::Empty     => "()".to_string(),
             Data::I8 (v)    => v.to_string(),
@@ -223,38 +344,43 @@ impl Data {
 #[derive(Debug, PartialEq)]
 pub struct SymbolTable {
     vec: Vec<SymbolTableValue>,
+
+    /// The index of every value in `vec` so interning
+    /// doesn't have to scan it, indices are still handed
+    /// out in the order the values are first added
+    strings: HashMap<String, SymbolIndex>,
+    combos: HashMap<(SymbolIndex, SymbolIndex), SymbolIndex>,
 }
 
 impl SymbolTable {
-    pub fn new() -> Self { Self { vec: vec![SymbolTableValue::String(GENERIC_START_SYMBOL.to_string()), SymbolTableValue::String(GENERIC_END_SYMBOL.to_string())] } }
+    pub fn new() -> Self {
+        let mut table = Self { vec: vec![], strings: HashMap::new(), combos: HashMap::new() };
+
+        table.add(GENERIC_START_SYMBOL.to_string());
+        table.add(GENERIC_END_SYMBOL.to_string());
+
+        table
+    }
 
     pub fn add(&mut self, string: String) -> SymbolIndex {
-        match self.vec.iter().enumerate().find(|x| match x.1 {
-            SymbolTableValue::String(v) => v == &string,
-            SymbolTableValue::Combo(_, _) => false,
-        }) {
-            Some(v) => SymbolIndex(v.0),
-            None => {
-                self.vec.push(SymbolTableValue::String(string));
-        
-                SymbolIndex(self.vec.len()-1)
-            },
+        if let Some(v) = self.strings.get(&string) {
+            return *v
         }
+
+        let index = SymbolIndex(self.vec.len());
+        self.strings.insert(string.clone(), index);
+        self.vec.push(SymbolTableValue::String(string));
+
+        index
     }
 
 
     pub fn add_combo(&mut self, one: SymbolIndex, two: SymbolIndex) -> SymbolIndex {
-        match self.vec.iter().enumerate().find(|x| match x.1 {
-            SymbolTableValue::String(_) => false,
-            SymbolTableValue::Combo(v1, v2) => v1 == &one && v2 == &two,
-        }) {
-            Some(v) => SymbolIndex(v.0),
-            None => {
-                self.vec.push(SymbolTableValue::Combo(one, two));
-        
-                SymbolIndex(self.vec.len()-1)
-            },
-        }
+        *self.combos.entry((one, two)).or_insert_with(|| {
+            self.vec.push(SymbolTableValue::Combo(one, two));
+
+            SymbolIndex(self.vec.len()-1)
+        })
     }
 
 
@@ -280,21 +406,12 @@ impl SymbolTable {
 
 
     pub fn find_combo(&self, v1: SymbolIndex, v2: SymbolIndex) -> SymbolIndex {
-        let mock = SymbolTableValue::Combo(v1, v2);
-        SymbolIndex(self.vec.iter().enumerate().find(|x| *x.1 == mock).unwrap().0)
+        self.combos[&(v1, v2)]
     }
 
 
     pub fn find(&self, val: &str) -> Option<SymbolIndex> {
-        for i in self.vec.iter().enumerate() {
-            if let SymbolTableValue::String(v) = i.1 {
-                if v.as_str() == val {
-                    return Some(SymbolIndex(i.0))
-                }
-            }
-        }
-
-        None
+        self.strings.get(val).copied()
     }
 
     pub fn add_generics(&mut self, symbol: SymbolIndex, generics: &[SourcedDataType]) -> SymbolIndex {
@@ -325,6 +442,16 @@ impl SymbolTable {
     }
 
 
+    /// Returns the last piece of a path
+    /// i.e `Point` for `geometry::Point`
+    pub fn last_piece(&self, symbol: SymbolIndex) -> SymbolIndex {
+        match &self.vec[symbol.0] {
+            SymbolTableValue::String(_) => symbol,
+            SymbolTableValue::Combo(_, v) => self.last_piece(*v),
+        }
+    }
+
+
     pub fn pretty_print(&self) {
         for i in self.vec.iter().enumerate() {
             println!("{:>w$} | {}", i.0, match i.1 {
@@ -386,6 +513,19 @@ pub fn generic_declaration_suffix(symbol_table: &mut SymbolTable, generics: &[So
 }
 
 
+/// The name of the user-defined conversion into `data_type`
+/// i.e `as Fahrenheit` for both `Fahrenheit` & `temp::Fahrenheit`
+pub fn conversion_symbol(symbol_table: &mut SymbolTable, data_type: &DataType) -> SymbolIndex {
+    let symbol = match data_type {
+        DataType::Struct(v, _) => symbol_table.get_name_without_generics(*v),
+        _ => data_type.symbol_index(symbol_table),
+    };
+
+    let name = format!("as {}", symbol_table.get(&symbol_table.last_piece(symbol)));
+    symbol_table.add(name)
+}
+
+
 pub fn default<T: Default>() -> T {
     T::default()
 }
@@ -0,0 +1,86 @@
+#![allow(unused)]
+use std::collections::HashSet;
+
+use crate::{Data, SymbolTable, GENERIC_START_SYMBOL, GENERIC_END_SYMBOL};
+
+
+#[test]
+fn identical_floats_dedup() {
+    let constants = [Data::Float(1.5), Data::Float(1.5), Data::Float(2.0)]
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    assert_eq!(constants.len(), 2);
+    assert_eq!(Data::Float(1.5), Data::Float(1.5));
+}
+
+
+#[test]
+fn signed_zeroes_are_different() {
+    assert_ne!(Data::Float(0.0), Data::Float(-0.0));
+
+    let constants = [Data::Float(0.0), Data::Float(-0.0)]
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    assert_eq!(constants.len(), 2);
+}
+
+
+#[test]
+fn nans_are_the_same_value() {
+    let other_nan = f64::from_bits(f64::NAN.to_bits() | 1);
+    assert!(other_nan.is_nan());
+
+    assert_eq!(Data::Float(f64::NAN), Data::Float(other_nan));
+
+    let constants = [Data::Float(f64::NAN), Data::Float(other_nan)]
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    assert_eq!(constants.len(), 1);
+}
+
+
+#[test]
+fn variants_are_distinct() {
+    assert_ne!(Data::I32(1), Data::I64(1));
+    assert_ne!(Data::U8(0), Data::Bool(false));
+}
+
+
+#[test]
+fn symbols_are_interned_in_order() {
+    let mut table = SymbolTable::new();
+
+    assert_eq!(table.find(GENERIC_START_SYMBOL), Some(table.add(GENERIC_START_SYMBOL.to_string())));
+    assert_eq!(table.find(GENERIC_END_SYMBOL), Some(table.add(GENERIC_END_SYMBOL.to_string())));
+
+    let a = table.add("a".to_string());
+    let b = table.add("b".to_string());
+    let combo = table.add_combo(a, b);
+    let c = table.add("c".to_string());
+
+    // Indices are handed out one after another
+    // no matter the kind of the value
+    let indices = [a, b, combo, c];
+    assert_eq!(format!("{indices:?}"), "[SymbolIndex(2), SymbolIndex(3), SymbolIndex(4), SymbolIndex(5)]");
+
+    assert_eq!(table.add("a".to_string()), a);
+    assert_eq!(table.add_combo(a, b), combo);
+    assert_eq!(table.find("c"), Some(c));
+    assert_eq!(table.find_combo(a, b), combo);
+    assert_eq!(table.get(&combo), "a::b");
+}
+
+
+#[test]
+fn combos_are_ordered_pairs() {
+    let mut table = SymbolTable::new();
+
+    let a = table.add("a".to_string());
+    let b = table.add("b".to_string());
+
+    assert_ne!(table.add_combo(a, b), table.add_combo(b, a));
+    assert_eq!(table.find("a::b"), None, "combos aren't found as strings");
+}
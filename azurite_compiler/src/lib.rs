@@ -1,48 +1,62 @@
 use std::{collections::HashMap, convert::TryInto};
+#[cfg(not(feature = "afl"))]
 use std::env;
+use std::path::Path;
 
-use azurite_ast_to_ir::ConversionState;
-use azurite_codegen::{CodegenModule, CodeGen};
+use azurite_ast_to_ir::{ConversionState, Structure};
+use azurite_codegen::{CodeGen, Unsupported};
 use azurite_common::{environment, CompilationMetadata};
+pub use azurite_common::Target;
 
 use azurite_lexer::lex;
 use azurite_parser::parse;
 use common::SymbolIndex;
 use azurite_semantic_analysis::{GlobalState, AnalysisState};
-use azurite_errors::Error;
+use azurite_errors::{CompilerError, ErrorBuilder};
 use azurite_parser::ast::Instruction;
 
 pub use common::Data;
 pub use common::SymbolTable;
-pub use azurite_codegen::{bytecode_module::BytecodeModule, c_module::CModule};
+pub use azurite_codegen::{CodegenModule, bytecode_module::BytecodeModule, c_module::CModule, wasm_module::WasmModule};
+pub use azurite_semantic_analysis::dependencies::Dependencies;
+pub use azurite_ast_to_ir::optimizations::OptimizationLevel;
+pub use azurite_errors::explanations::explain;
+pub use azurite_errors::{Error, CombineIntoError, Severity};
 
-type DebugHashmap = HashMap<SymbolIndex, (String, String)>;
-type ReturnValue = Result<(CompilationMetadata, Vec<u8>, Vec<Data>, SymbolTable), Error>;
+pub type DebugHashmap = HashMap<SymbolIndex, (String, String)>;
+type ReturnValue = Result<(CompilationMetadata, Vec<u8>, Vec<Data>, SymbolTable, Vec<u8>, Vec<u8>, Vec<u8>), Error>;
 
-pub fn compile<T: CodegenModule>(file_name: String, data: String) -> (ReturnValue, DebugHashmap) {
+/// Compiles `data` as the root file `file_name`
+///
+/// Alongside the result, returns the warnings produced
+/// during analysis and the source of every file involved
+pub fn compile<T: CodegenModule>(file_name: String, data: String, optimization_level: OptimizationLevel) -> (ReturnValue, Vec<Error>, DebugHashmap) {
     let mut symbol_table = SymbolTable::new();
     let file_name = symbol_table.add(file_name[..file_name.len()-3].to_string());
     
     let tokens = match lex(&data, file_name, &mut symbol_table) {
         Ok(v) => v,
-        Err(e) => return (Err(e), HashMap::from([(file_name, (symbol_table.get(&file_name), data.to_string()))])),
+        Err(e) => return (Err(e), vec![], HashMap::from([(file_name, (symbol_table.get(&file_name), data.to_string()))])),
     };
 
     let mut instructions = match parse(tokens, file_name, &mut symbol_table) {
         Ok(v) => v,
-        Err(e) => return (Err(e), HashMap::from([(file_name, (symbol_table.get(&file_name), data.to_string()))])),
+        Err(e) => return (Err(e), vec![], HashMap::from([(file_name, (symbol_table.get(&file_name), data.to_string()))])),
     };
     
     
     let mut global_state = GlobalState::new(&mut symbol_table);
     
     let mut analysis = AnalysisState::new(file_name);
-    match analysis.start_analysis(&mut global_state, &mut instructions) {
+    let result = analysis.analyze_entry(&mut global_state, &mut instructions);
+    let warnings = std::mem::take(&mut global_state.warnings);
+
+    match result {
         Ok(v) => v,
         Err(e) => {
             let mut temp : DebugHashmap = global_state.files.into_iter().map(|x| (x.0, (symbol_table.get(&x.0), x.1.2))).collect();
             temp.insert(file_name, (symbol_table.get(&file_name), data));
-            return (Err(e), temp)
+            return (Err(e), warnings, temp)
         },
     };
 
@@ -63,28 +77,47 @@ pub fn compile<T: CodegenModule>(file_name: String, data: String) -> (ReturnValu
     let templates = global_state.template_functions.into_iter().flat_map(|x| x.1.generated_funcs).collect();
     let mut ir = ConversionState::new(symbol_table);
 
+    #[cfg(not(feature = "afl"))]
+    {
+        ir.debug_info = env::var(environment::DEBUG_INFO).map_or(false, |x| x == "1");
+    }
+
     ir.generate(file_name, files, templates);
 
-    ir.sort();
+    ir.remove_unreachable_blocks();
 
-    #[cfg(not(features = "afl"))]
-    if env::var(environment::RAW_MODE).unwrap_or("0".to_string()) != *"1" {
-        ir.optimize();
+    if optimization_level != OptimizationLevel::O0 {
+        ir.fold_constants();
     }
 
-    #[cfg(features = "afl")]
-    ir.optimize();
+    ir.sort();
+
+    ir.optimize(optimization_level);
 
     ir.sort();
 
+    if optimization_level != OptimizationLevel::O0 {
+        ir.lower_tail_calls();
+    }
+
+    ir.remove_noops();
+
     let (externs, extern_counter) = ir.take_out_externs();
     let mut functions : Vec<_> = std::mem::take(&mut ir.functions).into_iter().map(|x| x.1).collect();
     functions.sort_unstable_by_key(|x| x.function_index.0);
 
-    #[cfg(not(features = "afl"))]
+    #[cfg(not(feature = "afl"))]
     if env::var(environment::DUMP_IR).unwrap_or("0".to_string()) == *"1" {
+        let filter = env::var(environment::DUMP_IR_FILTER).ok();
+
         let mut string = String::new();
         for f in &functions {
+            if let Some(filter) = &filter {
+                if !f.matches_name(&ir, filter) {
+                    continue
+                }
+            }
+
             f.pretty_print(&ir, &mut string);
         }
         
@@ -97,20 +130,118 @@ pub fn compile<T: CodegenModule>(file_name: String, data: String) -> (ReturnValu
 
     
     let constants = ir.constants;
-    let codegen = CodeGen::<T>::new(ir.structures);
+    let structures = convert_structures_to_bytes(&ir.structures, &ir.symbol_table);
+    let mut codegen = CodeGen::<T>::new(ir.structures);
     let library_count = externs.len().try_into().unwrap();
 
 
     
-    let bytecode = codegen.codegen(&mut ir.symbol_table, externs, functions, &constants);
+    let bytecode = match codegen.codegen(&mut ir.symbol_table, externs, functions, &constants) {
+        Ok(v) => v,
+        Err(Unsupported(feature)) => {
+            let message = format!("{feature} aren't supported by the {} target", T::TARGET.name());
+            let error = CompilerError::new(file_name, 401, &message, Severity::Error).build();
+
+            return (Err(error), warnings, files_data)
+        },
+    };
+    let debug = convert_function_starts_to_bytes(&codegen.function_starts, &ir.symbol_table);
+    let lines = convert_line_table_to_bytes(&codegen.line_table, &ir.symbol_table);
 
 
     let metadata = CompilationMetadata {
         extern_count: extern_counter,
         library_count,
+        target: T::TARGET,
     };
 
-    (Ok((metadata, bytecode, constants, ir.symbol_table)), files_data)
+    (Ok((metadata, bytecode, constants, ir.symbol_table, structures, debug, lines)), warnings, files_data)
+}
+
+
+
+/// Returns every file `file_name` depends on through `using`
+/// declarations without compiling any of them
+pub fn dependencies(file_name: String, data: String) -> (Result<Dependencies, Error>, DebugHashmap) {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add(file_name[..file_name.len()-3].to_string());
+
+    azurite_semantic_analysis::dependencies::resolve_dependencies(file, Path::new(&file_name), data, &mut symbol_table)
+}
+
+
+
+/// Encodes the name & field names of every structure so
+/// the runtime can refer to fields by their name
+///
+/// Each structure is laid out as its type id (u64), its
+/// name and the number of fields (u8) followed by the name
+/// of each field. Names are a u64 length followed by the
+/// utf-8 bytes
+fn convert_structures_to_bytes(structures: &HashMap<SymbolIndex, Structure>, symbol_table: &SymbolTable) -> Vec<u8> {
+    fn write_name(bytes: &mut Vec<u8>, name: &str) {
+        bytes.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    let mut structures : Vec<_> = structures.iter().collect();
+    structures.sort_unstable_by_key(|x| x.1.id);
+
+    let mut bytes = vec![];
+    for (name, structure) in structures {
+        bytes.extend_from_slice(&structure.id.to_le_bytes());
+        write_name(&mut bytes, &symbol_table.get(name));
+
+        bytes.push(structure.field_names.len().try_into().unwrap());
+        for field in &structure.field_names {
+            write_name(&mut bytes, &symbol_table.get(field));
+        }
+    }
+
+    bytes
+}
+
+
+
+/// The debug section the runtime uses to name the functions
+/// in a stack trace, the offset each function starts at
+/// followed by its name
+fn convert_function_starts_to_bytes(function_starts: &[(u32, SymbolIndex)], symbol_table: &SymbolTable) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    for (start, identifier) in function_starts {
+        let name = symbol_table.get(identifier);
+
+        bytes.extend_from_slice(&start.to_le_bytes());
+        bytes.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    bytes
+}
+
+
+
+/// The line table of a debug build, which lets the runtime
+/// point at the source line an error happened at. Empty
+/// unless the build has debug info
+///
+/// Each entry is the offset the line starts at (u32), the
+/// 1-based line (u32) and the name of its file, a u64 length
+/// followed by the utf-8 bytes
+fn convert_line_table_to_bytes(line_table: &[(u32, SymbolIndex, u32)], symbol_table: &SymbolTable) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    for (start, file, line) in line_table {
+        let name = format!("{}.az", symbol_table.get(file));
+
+        bytes.extend_from_slice(&start.to_le_bytes());
+        bytes.extend_from_slice(&(line + 1).to_le_bytes());
+        bytes.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    bytes
 }
 
 
@@ -170,6 +301,10 @@ pub fn convert_constants_to_bytes(constants: Vec<Data>, symbol_table: &SymbolTab
                 constants_bytes.push(10);
                 constants_bytes.append(&mut v.to_le_bytes().into())
             },
+            Data::Char(v) => {
+                constants_bytes.push(11);
+                constants_bytes.append(&mut (v as u32).to_le_bytes().into())
+            },
 
         }
     }
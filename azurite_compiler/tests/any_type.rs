@@ -0,0 +1,40 @@
+mod common;
+
+use azurite_compiler::{BytecodeModule, CModule};
+use self::common::compile_error_for;
+
+
+#[test]
+fn any_isnt_a_user_type() {
+    let error = compile_error_for::<BytecodeModule>("fn describe(value: any): str { \"value\" }");
+
+    assert!(error.is_some(), "only the standard library can use any");
+}
+
+
+#[test]
+fn std_externs_can_take_any() {
+    let source = "
+struct Point { x: i64, y: i64 }
+
+var point = Point { x: 1, y: 2 }
+var string = \"{x},{y}\".format(point)
+";
+
+    assert_eq!(compile_error_for::<BytecodeModule>(source), None);
+}
+
+
+#[test]
+fn c_target_rejects_any() {
+    let source = "
+struct Point { x: i64, y: i64 }
+
+var point = Point { x: 1, y: 2 }
+var string = \"{x},{y}\".format(point)
+";
+
+    let error = compile_error_for::<CModule>(source).expect("C has no type for any");
+    assert!(error.contains("error[401]"));
+    assert!(error.contains("values of any type aren't supported by the c target"));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use azurite_compiler::{BytecodeModule, CModule, OptimizationLevel};
+use self::common::{compile_error, compile_error_at};
+
+
+#[test]
+fn array_literals() {
+    let source = "
+struct Point { x: i64, y: i64 }
+
+var numbers = [1, 2, 3]
+var strings = [\"a\", \"b\",]
+var points : [Point] = [Point { x: 1, y: 2 }]
+var nested = [[1u8], [2u8, 3u8]]
+var empty : [float] = []
+var hinted : [i32] = [1, 2]
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn empty_array_needs_a_hint() {
+    assert!(compile_error("var x = []").unwrap().contains("error[234]"));
+}
+
+
+#[test]
+fn mixed_element_types() {
+    assert!(compile_error("var x = [1, \"two\", 3]").unwrap().contains("error[235]"));
+    assert!(compile_error("var x : [str] = [1, 2]").unwrap().contains("error[210]"));
+}
+
+
+#[test]
+fn long_array_literals_are_rejected() {
+    let compile_error = |source: String| compile_error_at::<BytecodeModule>(&source, OptimizationLevel::O0);
+
+    let elements = vec!["x"; 300].join(", ");
+    let error = compile_error(format!("var x = 5\nvar array = [{elements}]")).unwrap();
+    assert!(error.contains("error[401]"), "{error}");
+
+    let elements = (0..300).map(|x| x.to_string()).collect::<Vec<_>>().join(", ");
+    let error = compile_error(format!("var array = [{elements}]")).unwrap();
+    assert!(error.contains("error[401]"), "{error}");
+
+    let elements = vec!["x"; 255].join(", ");
+    assert_eq!(compile_error(format!("var x = 5\nvar array = [{elements}]")), None);
+}
+
+
+#[test]
+fn index_expressions() {
+    let source = "
+var numbers = [1, 2, 3]
+numbers[0] = numbers[1] + numbers[2u8]
+
+var character = \"abc\"[1]
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn c_target_rejects_arrays() {
+    let error = compile_error_at::<CModule>("var x = [1, 2, 3]", OptimizationLevel::O0).expect("the C runtime has no arrays");
+
+    assert!(error.contains("error[401]"));
+    assert!(error.contains("arrays aren't supported by the c target"));
+}
+
+
+#[test]
+fn invalid_index_expressions() {
+    assert!(compile_error("var x = 5\nvar y = x[0]").unwrap().contains("error[236]"));
+    assert!(compile_error("var x = [1]\nvar y = x[\"zero\"]").unwrap().contains("error[237]"));
+    assert!(compile_error("var x = \"abc\"\nx[0] = 'd'").unwrap().contains("error[238]"));
+    assert!(compile_error("var x = [1]\nx[0] = \"one\"").unwrap().contains("error[207]"));
+}
+
+
+#[test]
+fn arrays_of_the_same_type_can_be_compared() {
+    assert_eq!(compile_error("var x = [1, 2] == [1, 2]\nvar y = [[\"a\"]] != [[\"b\"]]"), None);
+    assert!(compile_error("var x = [1, 2] == [1.0, 2.0]").unwrap().contains("error[202]"));
+}
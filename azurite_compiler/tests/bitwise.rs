@@ -0,0 +1,52 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn integers() {
+    assert_eq!(compile_error("var x = 5 & 3 | 1 ^ 2"), None);
+    assert_eq!(compile_error("var x : u8 = 5u8 & 3u8"), None);
+}
+
+
+#[test]
+fn non_integer_operands() {
+    assert!(compile_error("var x = 5.0 & 3.0").unwrap().contains("error[247]"));
+    assert!(compile_error("var x = true | false").unwrap().contains("error[247]"));
+}
+
+
+#[test]
+fn different_types() {
+    assert!(compile_error("var x = 5u8 ^ 3i32").unwrap().contains("error[247]"));
+}
+
+
+#[test]
+fn shifts() {
+    assert_eq!(compile_error("var x : u8 = 1u8 << 3"), None);
+    assert_eq!(compile_error("var x : i32 = 1i32 >> 3u64"), None);
+}
+
+
+#[test]
+fn non_integer_shifts() {
+    assert!(compile_error("var x = 1.0 << 3").unwrap().contains("error[249]"));
+    assert!(compile_error("var x = 1 >> 3.0").unwrap().contains("error[249]"));
+    assert!(compile_error("var x = true << 1").unwrap().contains("error[249]"));
+}
+
+
+#[test]
+fn bitwise_not() {
+    assert_eq!(compile_error("var x : u16 = ~5u16"), None);
+    assert_eq!(compile_error("var x = ~~5"), None);
+}
+
+
+#[test]
+fn non_integer_bitwise_not() {
+    assert!(compile_error("var x = ~1.0").unwrap().contains("error[225]"));
+    assert!(compile_error("var x = ~true").unwrap().contains("error[225]"));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::collections::HashMap;
+
+use azurite_ast_to_ir::{BlockIndex, BlockTerminator, ConversionState};
+use ::common::SymbolIndex;
+use self::common::lower_to_ir;
+
+
+/// `depth` if/else statements, each nested in the else of the last
+fn nested_if_else(depth: usize) -> String {
+    let mut source = String::from("var x = 0\n");
+
+    for i in 0..depth {
+        source.push_str(&format!("if x == {i} {{\n    x = x + 1\n}} else {{\n"));
+    }
+
+    source.push_str("x = x - 1\n");
+    source.push_str(&"}\n".repeat(depth));
+    source
+}
+
+
+fn terminators(ir: &mut ConversionState, file: SymbolIndex) -> HashMap<BlockIndex, BlockTerminator> {
+    ir.find_function(file).blocks.iter().map(|x| (x.block_index, x.ending.clone())).collect()
+}
+
+
+#[test]
+fn deeply_nested_if_else() {
+    // Every if/else is two levels of nesting which has to
+    // stay within the limit of the parser
+    const DEPTH : usize = 24;
+
+    // Give the compiler the stack size of a main thread
+    // as the default for test threads is much smaller
+    let (mut ir, file) = std::thread::Builder::new()
+        .stack_size(8 * 1024 * 1024)
+        .spawn(|| lower_to_ir(&nested_if_else(DEPTH)))
+        .unwrap()
+        .join()
+        .unwrap();
+    let before = terminators(&mut ir, file);
+
+    // Every target of a terminator has to be a block of the function
+    for ending in before.values() {
+        let targets = match ending {
+            BlockTerminator::Goto(v) => vec![*v],
+            BlockTerminator::SwitchBool { op1, op2, .. } => vec![*op1, *op2],
+            BlockTerminator::Return => vec![],
+        };
+
+        for target in targets {
+            assert!(before.contains_key(&target), "a block jumps to {target:?} which doesn't exist");
+        }
+    }
+
+    let switches = before.values().filter(|x| matches!(x, BlockTerminator::SwitchBool { .. })).count();
+    assert_eq!(switches, DEPTH);
+
+    // Sorting reorders the blocks but mustn't change where they jump to
+    ir.sort();
+    assert_eq!(terminators(&mut ir, file), before);
+}
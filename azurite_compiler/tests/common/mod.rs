@@ -0,0 +1,83 @@
+//! Helpers shared by the integration tests, each test
+//! file only uses some of them
+#![allow(dead_code)]
+
+use ::common::{SymbolIndex, SymbolTable};
+use azurite_ast_to_ir::ConversionState;
+use azurite_compiler::{compile, BytecodeModule, CodegenModule, OptimizationLevel};
+use azurite_lexer::lex;
+use azurite_parser::parse;
+use azurite_semantic_analysis::{GlobalState, AnalysisState};
+
+
+/// Compiles `source` to bytecode and returns the rendered
+/// error, if it failed to compile
+pub fn compile_error(source: &str) -> Option<String> {
+    compile_error_for::<BytecodeModule>(source)
+}
+
+
+/// Compiles `source` with the codegen module `T` and
+/// returns the rendered error, if it failed to compile
+pub fn compile_error_for<T: CodegenModule>(source: &str) -> Option<String> {
+    compile_error_at::<T>(source, OptimizationLevel::O2)
+}
+
+
+/// Compiles `source` with the codegen module `T` at `level`
+/// and returns the rendered error, if it failed to compile
+///
+/// Code generation errors need `O0` when the code causing
+/// them would otherwise be optimized away
+pub fn compile_error_at<T: CodegenModule>(source: &str, level: OptimizationLevel) -> Option<String> {
+    let (result, _, debug_info) = compile::<T>("test.az".to_string(), source.to_string(), level);
+
+    result.err().map(|e| e.build(&debug_info))
+}
+
+
+/// Compiles `source` to bytecode and returns the rendered
+/// warnings
+///
+/// # Panics
+/// - If `source` fails to compile
+pub fn warnings(source: &str) -> Vec<String> {
+    let (result, warnings, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    if let Err(e) = result {
+        panic!("{}", e.build(&debug_info))
+    }
+
+    warnings.into_iter().map(|x| x.build(&debug_info)).collect()
+}
+
+
+/// Lexes, parses, analyzes & lowers `source` to IR without
+/// running any optimizations, returning the IR with the
+/// symbol of the file which is also its root function
+///
+/// # Panics
+/// - If `source` fails to compile
+pub fn lower_to_ir(source: &str) -> (ConversionState, SymbolIndex) {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add("test".to_string());
+
+    let tokens = lex(source, file, &mut symbol_table).unwrap();
+    let mut instructions = parse(tokens, file, &mut symbol_table).unwrap();
+
+    let mut global_state = GlobalState::new(&mut symbol_table);
+    let mut analysis = AnalysisState::new(file);
+    assert!(analysis.start_analysis(&mut global_state, &mut instructions).is_ok());
+
+    let templates = global_state.template_functions.into_iter().flat_map(|x| x.1.generated_funcs).collect();
+    let files = global_state.files
+        .into_iter()
+        .map(|x| (x.0, x.1.1))
+        .chain([(file, instructions)])
+        .collect();
+
+    let mut ir = ConversionState::new(symbol_table);
+    ir.generate(file, files, templates);
+
+    (ir, file)
+}
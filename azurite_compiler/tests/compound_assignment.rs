@@ -0,0 +1,36 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn targets() {
+    let source = "
+struct Point { x: i64, y: i64 }
+
+var x = 5
+x %= 2
+
+var point = Point { x: 1, y: 2 }
+point.y -= 1
+
+var numbers = [1, 2]
+numbers[x] *= 3
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn invalid_target() {
+    assert!(compile_error("5 += 1").unwrap().contains("error[103]"));
+}
+
+
+#[test]
+fn mismatched_types() {
+    assert!(compile_error("var x = 5\nx += 1.0").unwrap().contains("error[201]"));
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use azurite_ast_to_ir::{ConversionState, IR, BlockTerminator};
+use azurite_compiler::OptimizationLevel;
+use ::common::{Data, SymbolIndex};
+use self::common::lower_to_ir;
+
+
+/// Folds the constants of `source` and returns the
+/// instructions of its root function with the constant
+/// every load refers to
+fn folded(source: &str) -> (Vec<IR>, Vec<Data>) {
+    let (mut ir, file) = fold(source);
+
+    let instructions = ir.find_function(file).blocks.iter().flat_map(|x| x.instructions.clone()).collect();
+    (instructions, ir.constants)
+}
+
+
+fn fold(source: &str) -> (ConversionState, SymbolIndex) {
+    let (mut ir, file) = lower_to_ir(source);
+    ir.fold_constants();
+
+    (ir, file)
+}
+
+
+fn is_arithmetic(instruction: &IR) -> bool {
+    matches!(instruction, IR::Add { .. } | IR::Subtract { .. } | IR::Multiply { .. } | IR::Divide { .. } | IR::Modulo { .. })
+}
+
+
+fn loads(instructions: &[IR], constants: &[Data]) -> Vec<Data> {
+    instructions.iter()
+        .filter_map(|x| match x {
+            IR::Load { data, .. } => Some(constants[*data as usize].clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+
+#[test]
+fn arithmetic_on_constants_is_folded() {
+    let (instructions, constants) = folded("var x = 2 + 3 * 4\nx");
+
+    assert!(!instructions.iter().any(is_arithmetic));
+    assert!(loads(&instructions, &constants).contains(&Data::I64(14)));
+}
+
+
+#[test]
+fn folding_wraps_like_the_runtime() {
+    let (instructions, constants) = folded("var x = 255u8 + 1u8\nx");
+
+    assert!(!instructions.iter().any(is_arithmetic));
+    assert!(loads(&instructions, &constants).contains(&Data::U8(0)));
+}
+
+
+#[test]
+fn floats_are_folded() {
+    let (instructions, constants) = folded("var x = 1.5 * 2.0\nx");
+
+    assert!(!instructions.iter().any(is_arithmetic));
+    assert!(loads(&instructions, &constants).contains(&Data::Float(3.0)));
+}
+
+
+#[test]
+fn comparisons_are_folded() {
+    let (instructions, constants) = folded("var x = 2 < 3\nx");
+
+    assert!(!instructions.iter().any(|x| matches!(x, IR::LesserThan { .. })));
+    assert!(loads(&instructions, &constants).contains(&Data::Bool(true)));
+}
+
+
+#[test]
+fn division_by_zero_is_left_alone() {
+    let (instructions, _) = folded("var x = 5 / 0\nvar y = 5 % 0\nx + y");
+
+    assert!(instructions.iter().any(|x| matches!(x, IR::Divide { .. })));
+    assert!(instructions.iter().any(|x| matches!(x, IR::Modulo { .. })));
+}
+
+
+#[test]
+fn reassigned_registers_arent_folded() {
+    let (instructions, _) = folded("fn f(): i64 { 3 }\nvar x = 2\nx = f()\nvar y = x + 1\ny");
+
+    assert!(instructions.iter().any(is_arithmetic));
+}
+
+
+#[test]
+fn branches_on_constants_are_folded() {
+    let (ir, file) = fold("var x = 0\nif true { x = 1 } else { x = 2 }\nx");
+    let root = &ir.functions[&file];
+
+    assert!(!root.blocks.iter().any(|x| matches!(x.ending, BlockTerminator::SwitchBool { .. })));
+
+    let instructions : Vec<_> = root.blocks.iter().flat_map(|x| x.instructions.clone()).collect();
+    let loads = loads(&instructions, &ir.constants);
+    assert!(loads.contains(&Data::I64(1)));
+    assert!(!loads.contains(&Data::I64(2)), "the else branch should be removed");
+}
+
+
+#[test]
+fn registers_of_folded_branches_dont_count() {
+    let stack_size = |source| {
+        let (mut ir, file) = fold(source);
+        ir.sort();
+        ir.optimize(OptimizationLevel::O1);
+        ir.functions[&file].stack_size
+    };
+
+    let small = stack_size("var x = 0\nif true { x = 1 } else { x = 2 }\nx");
+    let large = stack_size("var x = 0\nif true { x = 1 } else { var a = 2\nvar b = a * 3\nvar c = b - a\nx = a + b + c }\nx");
+
+    assert_eq!(small, large);
+}
+
+
+#[test]
+fn widened_literals_are_retyped() {
+    let (instructions, constants) = folded("var x : u64 = 5u8\nx");
+
+    assert!(!instructions.iter().any(|x| matches!(x, IR::CastToU64 { .. })), "{instructions:?}");
+
+    let loads = loads(&instructions, &constants);
+    assert_eq!(loads.iter().filter(|x| **x == Data::U64(5)).count(), 1, "{loads:?}");
+}
+
+
+#[test]
+fn casts_which_keep_the_value_are_folded() {
+    let (instructions, constants) = folded("var x = 7u16 as float\nvar y = 3.0 as u8\nvar z = -5 as i8\nx");
+
+    assert!(!instructions.iter().any(|x| matches!(x, IR::CastToFloat { .. } | IR::CastToU8 { .. } | IR::CastToI8 { .. })), "{instructions:?}");
+
+    let loads = loads(&instructions, &constants);
+    assert!(loads.contains(&Data::Float(7.0)));
+    assert!(loads.contains(&Data::U8(3)));
+    assert!(loads.contains(&Data::I8(-5)));
+}
+
+
+#[test]
+fn casts_which_change_the_value_arent_folded() {
+    let (instructions, constants) = folded("var x = 300 as u8\nvar y = 2.9 as i64\nx");
+
+    assert!(instructions.iter().any(|x| matches!(x, IR::CastToU8 { .. })), "{instructions:?}");
+    assert!(instructions.iter().any(|x| matches!(x, IR::CastToI64 { .. })), "{instructions:?}");
+
+    let loads = loads(&instructions, &constants);
+    assert!(!loads.contains(&Data::U8(44)));
+    assert!(!loads.contains(&Data::I64(2)));
+}
+
+
+#[test]
+fn sign_changing_casts_arent_folded() {
+    let (instructions, constants) = folded("var x = (0 - 1) as u64\nx");
+
+    assert!(instructions.iter().any(|x| matches!(x, IR::CastToU64 { .. })), "{instructions:?}");
+    assert!(!loads(&instructions, &constants).contains(&Data::U64(u64::MAX)));
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn constants() {
+    assert_eq!(compile_error("const PI: float = 3.14159\nvar x = PI * 2.0"), None);
+    assert_eq!(compile_error("const A: i64 = 4\nconst B: i64 = A * A + 1\nvar x : i64 = B"), None);
+    assert_eq!(compile_error("const SMALL: u8 = 10\nvar x : u8 = SMALL"), None);
+    assert_eq!(compile_error("const OK: bool = !(1 > 2)\nvar x : bool = OK"), None);
+}
+
+
+#[test]
+fn constants_in_functions() {
+    assert_eq!(compile_error("const LIMIT: i64 = 10\nfn limit(): i64 { LIMIT }"), None);
+}
+
+
+#[test]
+fn constants_keep_their_type() {
+    assert!(compile_error("const SMALL: u8 = 10\nvar x : i8 = SMALL").unwrap().contains("error[210]"));
+}
+
+
+#[test]
+fn non_constant_initializer() {
+    assert!(compile_error("fn five(): i64 { 5 }\nconst FIVE: i64 = five()").unwrap().contains("error[250]"));
+    assert!(compile_error("var five = 5\nconst FIVE: i64 = five").unwrap().contains("error[250]"));
+}
+
+
+#[test]
+fn failed_evaluation() {
+    assert!(compile_error("const BIG: u8 = 200u8 + 100u8").unwrap().contains("error[251]"));
+    assert!(compile_error("const NAN: i64 = 1 / 0").unwrap().contains("error[251]"));
+}
+
+
+#[test]
+fn duplicate_constant() {
+    assert!(compile_error("const A: i64 = 1\nconst A: i64 = 2").unwrap().contains("error[252]"));
+}
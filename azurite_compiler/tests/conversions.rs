@@ -0,0 +1,39 @@
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+
+#[test]
+fn user_defined_conversion() {
+    let source = "
+struct Meters { value: float }
+struct Feet { value: float }
+
+impl Meters {
+    fn as Feet(self): Feet {
+        Feet { value: self.value * 3.28084 }
+    }
+}
+
+var feet = Meters { value: 2.0 } as Feet
+";
+
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    if let Err(e) = result {
+        panic!("{}", e.build(&debug_info))
+    }
+}
+
+
+#[test]
+fn missing_conversion_errors() {
+    let source = "
+struct Meters { value: float }
+struct Feet { value: float }
+
+var feet = Meters { value: 2.0 } as Feet
+";
+
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    assert!(result.err().unwrap().build(&debug_info).contains("error[226]"));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use azurite_ast_to_ir::IR;
+use azurite_compiler::OptimizationLevel;
+use self::common::lower_to_ir;
+
+
+/// Optimizes `source` and returns the stack size & the
+/// number of copies of its function `f`
+fn optimized(source: &str) -> (u32, usize) {
+    let (mut ir, _) = lower_to_ir(source);
+    ir.sort();
+    ir.optimize(OptimizationLevel::O1);
+
+    let function = ir.functions.values().find(|x| x.matches_name(&ir, "f")).unwrap();
+    let copies = function.blocks.iter()
+        .flat_map(|x| x.instructions.iter())
+        .filter(|x| matches!(x, IR::Copy { .. }))
+        .count();
+
+    (function.stack_size, copies)
+}
+
+
+#[test]
+fn copies_of_variables_are_propagated() {
+    let (direct, _) = optimized("fn f(a: i64): i64 { a + 1 }\nvar x = f(1)");
+    let (copied, copies) = optimized("fn f(a: i64): i64 { var b = a\nvar c = b\nc + 1 }\nvar x = f(1)");
+
+    assert_eq!(copied, direct, "the copies into `b` and `c` shouldn't need registers");
+    assert!(copies <= 1, "only the copy into the return register may be left");
+}
+
+
+#[test]
+fn copies_used_after_a_write_to_the_source_are_kept() {
+    let source = "
+fn f(a: i64): i64 {
+    var b = a
+    a = a + 1
+    a + b
+}
+
+var x = f(1)
+";
+
+    let (_, copies) = optimized(source);
+    assert!(copies >= 1, "`b` must keep the old value of `a`");
+}
@@ -0,0 +1,43 @@
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+
+fn function_names(debug: &[u8]) -> Vec<(u32, String)> {
+    let mut names = vec![];
+    let mut rest = debug;
+
+    while rest.len() >= 12 {
+        let start = u32::from_le_bytes(rest[..4].try_into().unwrap());
+        let length = u64::from_le_bytes(rest[4..12].try_into().unwrap()) as usize;
+        names.push((start, String::from_utf8(rest[12..12+length].to_vec()).unwrap()));
+        rest = &rest[12+length..];
+    }
+
+    names
+}
+
+
+#[test]
+fn debug_section_names_every_function() {
+    let source = r#"
+        fn double(x: i64): i64 {
+            x * 2
+        }
+
+        fn triple(x: i64): i64 {
+            x * 3
+        }
+
+        double(triple(5))
+    "#;
+
+    let (result, _, _) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O0);
+    let (.., debug, _) = result.ok().unwrap();
+    let names = function_names(&debug);
+
+    assert!(names.iter().any(|x| x.1.ends_with("double")), "{names:?}");
+    assert!(names.iter().any(|x| x.1.ends_with("triple")), "{names:?}");
+
+    let mut starts : Vec<_> = names.iter().map(|x| x.0).collect();
+    starts.dedup();
+    assert_eq!(starts.len(), names.len(), "{names:?}");
+}
@@ -0,0 +1,63 @@
+use std::{fs, path::PathBuf};
+
+use azurite_compiler::dependencies;
+
+
+fn create_files(directory: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("{directory}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    for (name, data) in files {
+        fs::write(directory.join(name), data).unwrap();
+    }
+
+    fs::canonicalize(directory).unwrap()
+}
+
+
+#[test]
+fn import_chain() {
+    let directory = create_files("azurite_deps_chain", &[
+        ("a.az", "using b\n"),
+        ("b.az", "using c\n"),
+        ("c.az", "fn message(): str { \"hello\" }\n"),
+    ]);
+
+    let root = directory.join("a.az");
+    let (result, _) = dependencies(root.to_string_lossy().to_string(), fs::read_to_string(&root).unwrap());
+    let result = result.unwrap();
+
+    assert_eq!(result.files, vec![directory.join("b.az"), directory.join("c.az")]);
+    assert!(result.cycles.is_empty());
+}
+
+
+#[test]
+fn cyclic_import_is_not_fatal() {
+    let directory = create_files("azurite_deps_cycle", &[
+        ("a.az", "using b\n"),
+        ("b.az", "using c\n"),
+        ("c.az", "using a\n"),
+    ]);
+
+    let root = directory.join("a.az");
+    let (result, _) = dependencies(root.to_string_lossy().to_string(), fs::read_to_string(&root).unwrap());
+    let result = result.unwrap();
+
+    assert_eq!(result.files, vec![directory.join("b.az"), directory.join("c.az")]);
+    assert_eq!(result.cycles, vec![(directory.join("c.az"), directory.join("a.az"))]);
+}
+
+
+#[test]
+fn missing_import() {
+    let directory = create_files("azurite_deps_missing", &[
+        ("a.az", "using does_not_exist\n"),
+    ]);
+
+    let root = directory.join("a.az");
+    let (result, _) = dependencies(root.to_string_lossy().to_string(), fs::read_to_string(&root).unwrap());
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,40 @@
+use std::env;
+
+use azurite_common::environment;
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+
+#[test]
+fn dump_ir_filter() {
+    let source = "
+fn first(): i64 { 1 }
+fn second(): i64 { 2 }
+fn third(): i64 { 3 }
+
+var x = first() + second() + third()
+";
+
+    let path = env::temp_dir().join(format!("azurite_dump_ir_filter_{}.txt", std::process::id()));
+
+    env::set_var(environment::DUMP_IR, "1");
+    env::set_var(environment::DUMP_IR_FILE, &path);
+    env::set_var(environment::DUMP_IR_FILTER, "second");
+
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O0);
+
+    env::remove_var(environment::DUMP_IR);
+    env::remove_var(environment::DUMP_IR_FILE);
+    env::remove_var(environment::DUMP_IR_FILTER);
+
+    if let Err(e) = result {
+        panic!("{}", e.build(&debug_info))
+    }
+
+    let dump = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let functions = dump.lines().filter(|x| x.starts_with("fn ")).collect::<Vec<_>>();
+
+    assert_eq!(functions.len(), 1, "{dump}");
+    assert!(functions[0].ends_with("::second)"), "{dump}");
+}
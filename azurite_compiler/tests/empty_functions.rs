@@ -0,0 +1,52 @@
+mod common;
+
+use azurite_ast_to_ir::{Function, IR, Variable};
+use self::common::lower_to_ir;
+
+
+/// Lowers `source` without optimising it and
+/// returns the function named `name`
+fn function(source: &str, name: &str) -> Function {
+    let (mut ir, _) = lower_to_ir(source);
+
+    let index = *ir.functions.iter()
+        .find(|x| x.1.matches_name(&ir, name))
+        .unwrap().0;
+
+    ir.functions.remove(&index).unwrap()
+}
+
+
+fn instructions(function: &Function) -> Vec<&IR> {
+    function.blocks.iter().flat_map(|x| x.instructions.iter()).collect()
+}
+
+
+#[test]
+fn empty_bodies_return_unit() {
+    let function = function("fn noop() { }\nnoop()", "noop");
+    let instructions = instructions(&function);
+
+    assert!(instructions.contains(&&IR::Unit { dst: Variable(0) }));
+    assert!(!instructions.iter().any(|x| matches!(x, IR::Copy { dst, src } if dst == src)), "the return value is never copied into itself");
+}
+
+
+#[test]
+fn bodies_of_statements_return_unit() {
+    let function = function("fn assign() { var x = 5 }\nassign()", "assign");
+    let instructions = instructions(&function);
+
+    assert!(instructions.contains(&&IR::Unit { dst: Variable(0) }));
+    assert!(!instructions.iter().any(|x| matches!(x, IR::Copy { dst, src } if dst == src)));
+}
+
+
+#[test]
+fn bodies_with_a_value_still_copy_it() {
+    let function = function("fn five(): i64 { 5 }\nvar x = five()", "five");
+    let instructions = instructions(&function);
+
+    assert!(!instructions.contains(&&IR::Unit { dst: Variable(0) }));
+    assert!(instructions.iter().any(|x| matches!(x, IR::Copy { dst: Variable(0), .. })));
+}
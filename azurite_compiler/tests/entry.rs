@@ -0,0 +1,22 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn integer_entry() {
+    assert_eq!(compile_error("var code = 3\ncode as i32"), None);
+}
+
+
+#[test]
+fn empty_entry() {
+    assert_eq!(compile_error("var code = 3"), None);
+    assert_eq!(compile_error(""), None);
+}
+
+
+#[test]
+fn string_entry() {
+    assert!(compile_error("\"exit\"").unwrap().contains("error[239]"));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn enums() {
+    assert_eq!(compile_error("enum Color { Red, Green, Blue }\nvar x : Color = Color::Green"), None);
+    assert_eq!(compile_error("enum Code { Ok = 200, Error = -1, }\nvar x : i64 = Code::Error"), None);
+    assert_eq!(compile_error("enum Empty {}"), None);
+}
+
+
+#[test]
+fn enums_in_functions() {
+    assert_eq!(compile_error("enum Color { Red, Green }\nfn is_red(color: Color): bool { color == Color::Red }"), None);
+}
+
+
+#[test]
+fn match_on_variants() {
+    let source = "
+enum Color { Red, Green, Blue }
+var color = Color::Blue
+var name = match color {
+    Color::Red => \"red\",
+    Color::Green => \"green\",
+    _ => \"other\",
+}";
+
+    assert_eq!(compile_error(source), None);
+    assert!(compile_error("enum Color { Red, Green }\nvar x = match 1 { Color::Red => 1, Color::Red => 2, _ => 3 }").unwrap().contains("error[246]"));
+}
+
+
+#[test]
+fn non_constant_match_pattern() {
+    assert!(compile_error("var y = 1\nvar x = match 1 { y => 1, _ => 2 }").unwrap().contains("error[258]"));
+}
+
+
+#[test]
+fn unknown_variant() {
+    assert!(compile_error("enum Color { Red }\nvar x = Color::Purple").unwrap().contains("error[205]"));
+}
+
+
+#[test]
+fn duplicate_enum() {
+    assert!(compile_error("enum Color { Red }\nenum Color { Blue }").unwrap().contains("error[255]"));
+}
+
+
+#[test]
+fn duplicate_variant() {
+    assert!(compile_error("enum Color { Red, Green, Red }").unwrap().contains("error[256]"));
+}
+
+
+#[test]
+fn duplicate_discriminant() {
+    assert!(compile_error("enum Color { Red = 1, Green = 1 }").unwrap().contains("error[257]"));
+    assert!(compile_error("enum Color { Red = 1, Green = 0, Blue }").unwrap().contains("error[257]"));
+}
@@ -0,0 +1,28 @@
+use azurite_compiler::explain;
+
+
+#[test]
+fn known_code_is_explained() {
+    let explanation = explain("211");
+
+    assert!(explanation.starts_with("error[211]: Function return value differs"));
+    assert!(explanation.contains("the functions body returns a value that is not the functions return type"));
+    assert!(explanation.contains("for example:"));
+    assert!(explanation.contains("to fix it,"));
+}
+
+
+#[test]
+fn prefixed_codes() {
+    assert_eq!(explain("E0211"), explain("211"));
+    assert!(explain("W302").starts_with("warning[302]"));
+    assert!(explain("1").starts_with("error[001]"));
+    assert!(explain("401").starts_with("error[401]"));
+}
+
+
+#[test]
+fn unknown_code() {
+    assert!(explain("999").contains("no such error code"));
+    assert!(explain("abc").contains("no such error code"));
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use self::common::warnings;
+
+
+#[test]
+fn float_equality_warns() {
+    let equals = warnings("assert(1.0 == 1.0)");
+
+    assert_eq!(equals.len(), 1);
+    assert!(equals[0].contains("warning[303]"));
+
+    assert!(warnings("assert(1.0 != 2.0)")[0].contains("warning[303]"));
+}
+
+
+#[test]
+fn other_comparisons_dont_warn() {
+    assert!(warnings("assert(1 == 1)").is_empty());
+    assert!(warnings("assert(1.0 < 2.0)").is_empty());
+}
+
+
+#[test]
+fn approx_eq_is_available() {
+    assert!(warnings("assert(approx_eq(0.1 + 0.2, 0.3, 0.000001))").is_empty());
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn ranges() {
+    assert_eq!(compile_error("for i in 0..10 { var x = i }"), None);
+    assert_eq!(compile_error("for i in 0..=10 { var x = i }"), None);
+}
+
+
+#[test]
+fn inferred_bounds() {
+    assert_eq!(compile_error("var n = 10u8\nfor i in 0..n { var x : u8 = i }"), None);
+    assert_eq!(compile_error("var n = 0u8\nfor i in n..10 { var x : u8 = i }"), None);
+}
+
+
+#[test]
+fn loop_variable_scope() {
+    assert!(compile_error("for i in 0..10 { }\nvar x = i").is_some());
+}
+
+
+#[test]
+fn non_integer_bounds() {
+    assert!(compile_error("for i in 0.5..10.0 { }").unwrap().contains("error[240]"));
+}
+
+
+#[test]
+fn different_bound_types() {
+    assert!(compile_error("for i in 0u8..10i32 { }").unwrap().contains("error[241]"));
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use azurite_codegen::{CodeGen, bytecode_module::BytecodeModule};
+use azurite_common::Bytecode;
+use azurite_compiler::OptimizationLevel;
+use self::common::lower_to_ir;
+
+
+/// Lowers `source` and returns the bytecode of
+/// the function named `name`
+fn function_bytecode(source: &str, name: &str) -> Vec<u8> {
+    let (mut ir, _) = lower_to_ir(source);
+    ir.sort();
+    ir.optimize(OptimizationLevel::O0);
+
+    let index = *ir.functions.iter()
+        .find(|x| x.1.matches_name(&ir, name))
+        .unwrap().0;
+
+    let function = ir.functions.remove(&index).unwrap();
+
+    let mut codegen = CodeGen::<BytecodeModule>::new(ir.structures);
+    codegen.codegen(&mut ir.symbol_table, Default::default(), vec![function], &ir.constants).unwrap()
+}
+
+
+#[test]
+fn functions_without_locals_dont_push_or_pop() {
+    let bytecode = function_bytecode("fn nothing() { }\nnothing()", "nothing");
+
+    assert!(!bytecode.contains(&Bytecode::Push.as_u8()));
+    assert!(!bytecode.contains(&Bytecode::Pop.as_u8()));
+    assert_eq!(bytecode.last(), Some(&Bytecode::Return.as_u8()));
+}
+
+
+#[test]
+fn functions_with_locals_still_push_and_pop() {
+    let bytecode = function_bytecode("fn twice(): i64 { var a = 5\na + a }\nvar x = twice()", "twice");
+
+    assert_eq!(bytecode.first(), Some(&Bytecode::Push.as_u8()));
+    assert!(bytecode.contains(&Bytecode::Pop.as_u8()));
+}
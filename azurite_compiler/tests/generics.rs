@@ -0,0 +1,110 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn generic_type_hints() {
+    let source = "
+struct Pair[A, B] { first: A, second: B }
+
+var inner : Pair[str, bool] = Pair[str, bool] { first: \"a\", second: true }
+var outer : Pair[i64, Pair[str, bool]] = Pair[i64, Pair[str, bool]] { first: 1, second: inner }
+var trailing : Pair[u8, u8,] = Pair[u8, u8] { first: 1, second: 2 }
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn empty_generic_arguments() {
+    assert!(compile_error("struct Pair[A, B] { first: A, second: B }\nvar x : Pair[] = 5").unwrap().contains("error[108]"));
+}
+
+
+#[test]
+fn missing_comma() {
+    assert!(compile_error("struct Pair[A, B] { first: A, second: B }\nvar x : Pair[i64 str] = 5").unwrap().contains("error[102]"));
+}
+
+
+#[test]
+fn generic_declarations() {
+    let source = "
+fn identity[T](x: T): T { x }
+struct Pair[A, B,] { first: A, second: B }
+
+var x : i64 = identity[i64](5)
+var y : Pair[str, bool] = Pair[str, bool] { first: identity[str](\"a\"), second: true }
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn generics_shadow_structures() {
+    let source = "
+struct T { field: bool }
+
+fn identity[T](x: T): T { x }
+
+var x : i64 = identity[i64](5)
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn empty_generic_parameters() {
+    assert!(compile_error("fn foo[]() { }").unwrap().contains("error[108]"));
+    assert!(compile_error("struct Pair[] { }").unwrap().contains("error[108]"));
+}
+
+
+#[test]
+fn duplicate_generic_parameters() {
+    assert!(compile_error("struct Pair[T, T] { first: T }").unwrap().contains("error[109]"));
+}
+
+
+#[test]
+fn call_site_generics() {
+    let source = "
+struct Box { value: i64 }
+
+impl Box {
+    fn with[T](self, x: T): T { x }
+}
+
+fn identity[T](x: T): T { x }
+
+var x : str = identity[str](\"a\")
+var y : bool = Box { value: 5 }.with[bool](true)
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn indexing_isnt_generics() {
+    assert_eq!(compile_error("var i = 1\nvar arr = [1, 2, 3]\nvar x : i64 = arr[i]"), None);
+    assert_eq!(compile_error("struct Pair { first: [i64] }\nvar i = 1\nvar x : i64 = Pair { first: [1, 2] }.first[i]"), None);
+}
+
+
+#[test]
+fn empty_call_site_generics() {
+    assert!(compile_error("fn identity[T](x: T): T { x }\nvar x = identity[](5)").unwrap().contains("error[108]"));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use azurite_ast_to_ir::IR;
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+use self::common::lower_to_ir;
+
+
+/// Optimizes `source` at `O2` with the given inline
+/// threshold and returns the number of functions
+/// and calls left
+fn optimized(source: &str, threshold: usize) -> (usize, usize) {
+    let (mut ir, _) = lower_to_ir(source);
+    ir.inline_threshold = threshold;
+    ir.remove_unreachable_blocks();
+    ir.sort();
+    ir.optimize(OptimizationLevel::O2);
+
+    let calls = ir.functions.values()
+        .flat_map(|x| x.blocks.iter())
+        .flat_map(|x| x.instructions.iter())
+        .filter(|x| matches!(x, IR::Call { .. }))
+        .count();
+
+    (ir.functions.len(), calls)
+}
+
+
+const SOURCE : &str = "
+fn double(x: i64): i64 { x * 2 }
+
+var y = double(4)
+var z = double(y)
+";
+
+
+#[test]
+fn small_functions_are_inlined() {
+    let (functions, calls) = optimized(SOURCE, 16);
+
+    assert_eq!(calls, 0);
+    assert_eq!(functions, 1, "every function should be inlined into the root");
+}
+
+
+#[test]
+fn threshold_limits_inlining() {
+    let (functions, calls) = optimized(SOURCE, 0);
+
+    assert!(calls > 0);
+    assert!(functions > 1);
+}
+
+
+#[test]
+fn recursive_functions_arent_inlined() {
+    let source = "
+fn count(n: i64): i64 {
+    if n > 0 {
+        return count(n - 1)
+    }
+    n
+}
+
+var x = count(3)
+";
+
+    let (_, calls) = optimized(source, usize::MAX);
+    assert!(calls > 0);
+}
+
+
+#[test]
+fn functions_with_branches_are_inlined() {
+    let source = "
+fn abs(x: i64): i64 {
+    if x < 0 {
+        return 0 - x
+    }
+    x
+}
+
+var x = abs(0 - 3)
+var y = abs(x)
+";
+
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+    if let Err(e) = result {
+        panic!("{}", e.build(&debug_info));
+    }
+
+    let (_, calls) = optimized(source, 16);
+    assert_eq!(calls, 0);
+}
@@ -0,0 +1,31 @@
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel, CombineIntoError};
+
+
+#[test]
+fn errors_as_json() {
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), "var x = y".to_string(), OptimizationLevel::O2);
+
+    let json = result.err().unwrap().build_json(&debug_info);
+
+    assert!(json.starts_with("[{") && json.ends_with("}]"), "{json}");
+    assert!(json.contains("\"severity\":\"error\""), "{json}");
+    assert!(json.contains("\"id\":205"), "{json}");
+    assert!(json.contains("\"message\":\"variable does not exist\""), "{json}");
+    assert!(json.contains("\"start\":8,"), "{json}");
+    assert!(json.contains("\"note\":null"), "{json}");
+    assert!(!json.contains('\x1b'), "no colour codes in {json}");
+}
+
+
+#[test]
+fn warnings_as_json() {
+    let (result, warnings, debug_info) = compile::<BytecodeModule>("test.az".to_string(), "var x = 5\nvar y = 6".to_string(), OptimizationLevel::O2);
+    assert!(result.is_ok());
+
+    let json = warnings.combine_into_error().build_json(&debug_info);
+
+    assert_eq!(json.matches("\"severity\":\"warning\"").count(), 2, "{json}");
+    assert_eq!(json.matches("\"id\":302").count(), 2, "{json}");
+    assert!(json.contains("\"note\":\"x is never read"), "{json}");
+    assert!(json.contains("},{"), "the diagnostics are separate objects in {json}");
+}
@@ -0,0 +1,59 @@
+use std::env;
+
+use azurite_common::environment;
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+
+fn lines(table: &[u8]) -> Vec<(u32, u32, String)> {
+    let mut lines = vec![];
+    let mut rest = table;
+
+    while rest.len() >= 16 {
+        let start = u32::from_le_bytes(rest[..4].try_into().unwrap());
+        let line = u32::from_le_bytes(rest[4..8].try_into().unwrap());
+        let length = u64::from_le_bytes(rest[8..16].try_into().unwrap()) as usize;
+        lines.push((start, line, String::from_utf8(rest[16..16+length].to_vec()).unwrap()));
+        rest = &rest[16+length..];
+    }
+
+    lines
+}
+
+
+fn compile_lines(source: &str) -> Vec<u8> {
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O0);
+
+    match result {
+        Ok((.., lines)) => lines,
+        Err(e) => panic!("{}", e.build(&debug_info)),
+    }
+}
+
+
+// Both cases are in one test as the flag is
+// read from the environment of the process
+#[test]
+fn line_table_only_in_debug_builds() {
+    let source = "fn divide(a: i64, b: i64): i64 {
+    a / b
+}
+
+var x = divide(10, 2)
+var y = divide(x, 0)
+";
+
+    assert!(compile_lines(source).is_empty());
+
+    env::set_var(environment::DEBUG_INFO, "1");
+    let table = compile_lines(source);
+    env::remove_var(environment::DEBUG_INFO);
+
+    let lines = lines(&table);
+
+    // The standard library has lines of its own
+    let numbers : Vec<_> = lines.iter().filter(|x| x.2 == "test.az").map(|x| x.1).collect();
+
+    assert!(lines.iter().all(|x| x.2 == "test.az" || x.2 == "std.az"), "{lines:?}");
+    assert!(numbers.contains(&2) && numbers.contains(&5) && numbers.contains(&6), "{lines:?}");
+    assert!(lines.windows(2).all(|x| x[0].0 < x[1].0), "{lines:?}");
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn literals_in_range() {
+    assert_eq!(compile_error("var x : u8 = 255"), None);
+    assert_eq!(compile_error("var x : i8 = -128"), None);
+    assert_eq!(compile_error("var x : u16 = 65535"), None);
+}
+
+
+#[test]
+fn literal_out_of_range() {
+    let error = compile_error("var x : u8 = 300").unwrap();
+
+    assert!(error.contains("error[253]"));
+    assert!(error.contains("literal 300 does not fit in 'u8'"));
+
+    assert!(compile_error("var x : i8 = 128").unwrap().contains("error[253]"));
+    assert!(compile_error("var x : i8 = -129").unwrap().contains("error[253]"));
+    assert!(compile_error("var x : u32 = -1").unwrap().contains("error[253]"));
+}
+
+
+#[test]
+fn out_of_range_operand() {
+    assert!(compile_error("var x = 10u8\nvar y = x + 256").unwrap().contains("error[253]"));
+}
+
+
+#[test]
+fn type_mismatch_isnt_a_range_error() {
+    assert!(compile_error("var x : str = 300").unwrap().contains("error[210]"));
+    assert!(compile_error("var x : u8 = 300u16").unwrap().contains("error[210]"));
+}
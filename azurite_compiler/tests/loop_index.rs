@@ -0,0 +1,69 @@
+mod common;
+
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+use self::common::compile_error;
+
+
+#[test]
+fn counter_in_every_kind_of_loop() {
+    let source = "
+var total = 0
+
+loop {
+    if loop.index == 3 { break }
+    total = total + loop.index
+}
+
+while loop.index < 3 {
+    total = total + loop.index
+}
+
+for i in 0..3 {
+    loop {
+        if loop.index == i { break }
+        total = total + loop.index * 2
+    }
+}
+";
+
+    for level in [OptimizationLevel::O0, OptimizationLevel::O2] {
+        let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), level);
+
+        if let Err(e) = result {
+            panic!("{}", e.build(&debug_info))
+        }
+    }
+}
+
+
+#[test]
+fn counter_outside_of_loop() {
+    assert!(compile_error("var x = loop.index").unwrap().contains("error[261]"));
+}
+
+
+#[test]
+fn counter_is_not_visible_in_called_functions() {
+    let source = "
+fn inner(): i64 { loop.index }
+
+loop {
+    inner()
+    break
+}
+";
+
+    assert!(compile_error(source).unwrap().contains("error[261]"));
+}
+
+
+#[test]
+fn counter_is_read_only() {
+    assert!(compile_error("loop { loop.index = 5 }").unwrap().contains("error[103]"));
+}
+
+
+#[test]
+fn unknown_loop_property() {
+    assert!(compile_error("loop { var x = loop.count }").unwrap().contains("error[111]"));
+}
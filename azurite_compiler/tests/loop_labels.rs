@@ -0,0 +1,83 @@
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+
+#[test]
+fn shadowed_label_warns() {
+    let source = "
+'a: loop {
+    'a: loop {
+        break 'a
+    }
+    break 'a
+}
+";
+
+    let (result, warnings, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    assert!(result.is_ok());
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings.into_iter().next().unwrap().build(&debug_info).contains("warning[301]"));
+}
+
+
+#[test]
+fn distinct_labels_dont_warn() {
+    let source = "
+'a: loop {
+    'b: loop {
+        break 'a
+    }
+}
+";
+
+    let (result, warnings, _) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    assert!(result.is_ok());
+    assert!(warnings.is_empty());
+}
+
+
+#[test]
+fn unknown_label() {
+    let source = "
+'a: loop {
+    break 'b
+}
+";
+
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    assert!(result.err().unwrap().build(&debug_info).contains("error[233]"));
+}
+
+
+#[test]
+fn namespaced_calls_inside_loops() {
+    let source = "
+struct Counter { n: i64 }
+
+impl Counter {
+    fn new(n: i64): Counter { Counter { n: n } }
+}
+
+var i = 0
+'outer: loop {
+    while true {
+        var counter = Counter::new(i)
+        i = i + 1
+
+        if counter.n == 2 { continue 'outer }
+        var next = Counter::new(i)
+        if next.n > 5 { break 'outer }
+    }
+}
+";
+
+    let (result, warnings, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    if let Err(e) = result {
+        panic!("{}", e.build(&debug_info))
+    }
+
+    assert!(warnings.is_empty());
+}
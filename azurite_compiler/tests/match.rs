@@ -0,0 +1,55 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn valid_matches() {
+    let source = "
+var x = 5
+var name = match x { 1 => \"one\", 2 => \"two\", _ => \"other\" }
+var flag = match x == 5 { true => 1, false => 0 }
+match x { 5 => { x = 6 }, _ => {} }
+";
+
+    if let Some(e) = compile_error(source) {
+        panic!("{e}")
+    }
+}
+
+
+#[test]
+fn invalid_scrutinee() {
+    assert!(compile_error("match \"a\" { _ => 0 }").unwrap().contains("error[242]"));
+}
+
+
+#[test]
+fn pattern_of_a_different_type() {
+    assert!(compile_error("match 5 { true => 0, _ => 1 }").unwrap().contains("error[243]"));
+}
+
+
+#[test]
+fn arms_of_different_types() {
+    assert!(compile_error("match 5 { 1 => 0, _ => \"a\" }").unwrap().contains("error[244]"));
+}
+
+
+#[test]
+fn missing_default_arm() {
+    assert!(compile_error("match 5 { 1 => 0, 2 => 1 }").unwrap().contains("error[245]"));
+    assert!(compile_error("match true { true => 0 }").unwrap().contains("error[245]"));
+}
+
+
+#[test]
+fn duplicate_pattern() {
+    assert!(compile_error("match 5 { 1 => 0, 1 => 1, _ => 2 }").unwrap().contains("error[246]"));
+}
+
+
+#[test]
+fn default_arm_has_to_be_last() {
+    assert!(compile_error("match 5 { _ => 0, 1 => 1 }").unwrap().contains("error[102]"));
+}
@@ -0,0 +1,45 @@
+mod common;
+
+
+fn compile_error(source: String) -> Option<String> {
+    // Give the compiler the stack size of a main thread
+    // as the default for test threads is much smaller
+    std::thread::Builder::new()
+        .stack_size(8 * 1024 * 1024)
+        .spawn(move || common::compile_error(&source))
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+
+#[test]
+fn nested_parentheses() {
+    let source = format!("var x = {}1{}", "(".repeat(10_000), ")".repeat(10_000));
+
+    assert!(compile_error(source).unwrap().contains("error[110]"));
+}
+
+
+#[test]
+fn long_operator_chain() {
+    let source = format!("var x = 1{}", " + 1".repeat(10_000));
+
+    assert!(compile_error(source).unwrap().contains("error[110]"));
+}
+
+
+#[test]
+fn nested_unary_operators() {
+    let source = format!("var x = {}true", "!".repeat(10_000));
+
+    assert!(compile_error(source).unwrap().contains("error[110]"));
+}
+
+
+#[test]
+fn reasonable_nesting() {
+    let source = format!("var x = {}1{}{}", "(".repeat(32), ")".repeat(32), " + 1".repeat(32));
+
+    assert_eq!(compile_error(source), None);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::env;
+
+use azurite_ast_to_ir::IR;
+use azurite_codegen::{CodeGen, bytecode_module::BytecodeModule};
+use azurite_common::environment;
+use azurite_compiler::OptimizationLevel;
+use self::common::lower_to_ir;
+
+
+const SOURCE : &str = "
+fn add(a: i64, b: i64): i64 {
+    var c = a
+    var d = b
+    c + d
+}
+
+var x = add(1, 2)
+var y = x
+y = y + 1
+";
+
+
+/// Lowers `SOURCE` the same way `compile` does and
+/// returns the number of noops left and the bytecode
+fn lower(level: OptimizationLevel, remove_noops: bool) -> (usize, Vec<u8>) {
+    let (mut ir, _) = lower_to_ir(SOURCE);
+    ir.sort();
+    ir.optimize(level);
+    ir.sort();
+
+    if remove_noops {
+        ir.remove_noops();
+    }
+
+    let noops = ir.functions.values()
+        .flat_map(|x| x.blocks.iter())
+        .flat_map(|x| x.instructions.iter())
+        .filter(|x| matches!(x, IR::Noop))
+        .count();
+
+    let (externs, _) = ir.take_out_externs();
+    let mut functions : Vec<_> = std::mem::take(&mut ir.functions).into_values().collect();
+    functions.sort_unstable_by_key(|x| x.function_index.0);
+
+    let mut codegen = CodeGen::<BytecodeModule>::new(ir.structures);
+    (noops, codegen.codegen(&mut ir.symbol_table, externs, functions, &ir.constants).unwrap())
+}
+
+
+#[test]
+fn noops_are_removed_without_changing_the_bytecode() {
+    // The externs of the standard library aren't numbered in
+    // the same order every compilation, so leave it out to be
+    // able to compare the bytecode of two compilations
+    env::set_var(environment::NO_STD, "1");
+
+    for level in [OptimizationLevel::O0, OptimizationLevel::O1, OptimizationLevel::O2] {
+        let (noops, with_noops) = lower(level, false);
+        let (remaining, without_noops) = lower(level, true);
+
+        assert!(noops > 0);
+        assert_eq!(remaining, 0);
+        assert_eq!(with_noops, without_noops);
+    }
+}
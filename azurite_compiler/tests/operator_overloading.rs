@@ -0,0 +1,87 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn overloaded_add() {
+    let source = "
+struct Vec2 { x: float, y: float }
+
+impl Vec2 {
+    fn add(self, other: Vec2): Vec2 {
+        Vec2 { x: self.x + other.x, y: self.y + other.y }
+    }
+}
+
+var sum : Vec2 = Vec2 { x: 1.0, y: 2.0 } + Vec2 { x: 3.0, y: 4.0 }
+";
+
+    assert_eq!(compile_error(source), None);
+}
+
+
+#[test]
+fn overloaded_eq() {
+    let source = "
+struct Vec2 { x: float, y: float }
+
+impl Vec2 {
+    fn eq(self, other: Vec2): bool {
+        self.x == other.x
+    }
+}
+
+var same : bool = Vec2 { x: 1.0, y: 2.0 } == Vec2 { x: 1.0, y: 3.0 }
+";
+
+    assert_eq!(compile_error(source), None);
+}
+
+
+#[test]
+fn overloaded_not_eq() {
+    let source = "
+struct Vec2 { x: float, y: float }
+
+impl Vec2 {
+    fn eq(self, other: Vec2): bool {
+        self.x == other.x
+    }
+}
+
+var different : bool = Vec2 { x: 1.0, y: 2.0 } != Vec2 { x: 1.0, y: 3.0 }
+";
+
+    assert_eq!(compile_error(source), None);
+}
+
+
+#[test]
+fn missing_overload() {
+    let source = "
+struct Vec2 { x: float, y: float }
+
+var sum = Vec2 { x: 1.0, y: 2.0 } - Vec2 { x: 3.0, y: 4.0 }
+";
+
+    assert!(compile_error(source).unwrap().contains("error[201]"));
+}
+
+
+#[test]
+fn invalid_overload_signature() {
+    let source = "
+struct Vec2 { x: float, y: float }
+
+impl Vec2 {
+    fn add(self, other: float): Vec2 {
+        Vec2 { x: self.x + other, y: self.y + other }
+    }
+}
+
+var sum = Vec2 { x: 1.0, y: 2.0 } + Vec2 { x: 3.0, y: 4.0 }
+";
+
+    assert!(compile_error(source).unwrap().contains("error[201]"));
+}
@@ -0,0 +1,81 @@
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+
+const SOURCE : &str = "
+fn unused(): i64 { 5 }
+
+fn add(a: i64, b: i64): i64 {
+    var c = a
+    var d = b
+    c + d
+}
+
+var x = add(1, 2)
+var y = x
+if y > 2 {
+    y = y + 1
+}
+";
+
+
+fn bytecode_size(level: OptimizationLevel) -> usize {
+    source_size(SOURCE, level)
+}
+
+
+fn source_size(source: &str, level: OptimizationLevel) -> usize {
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), level);
+
+    match result {
+        Ok(v) => v.1.len(),
+        Err(e) => panic!("{}", e.build(&debug_info)),
+    }
+}
+
+
+#[test]
+fn levels_shrink_the_output() {
+    let o0 = bytecode_size(OptimizationLevel::O0);
+    let o1 = bytecode_size(OptimizationLevel::O1);
+    let o2 = bytecode_size(OptimizationLevel::O2);
+
+    assert!(o0 > o1, "-O1 should optimize the IR ({o0} <= {o1})");
+    assert!(o1 > o2, "-O2 should apply more passes than -O1 ({o1} <= {o2})");
+}
+
+
+#[test]
+fn default_level_is_o2() {
+    assert_eq!(OptimizationLevel::default(), OptimizationLevel::O2);
+    assert_eq!(bytecode_size(OptimizationLevel::default()), bytecode_size(OptimizationLevel::O2));
+}
+
+
+#[test]
+fn levels_from_numbers() {
+    assert_eq!(OptimizationLevel::from_level(0), Some(OptimizationLevel::O0));
+    assert_eq!(OptimizationLevel::from_level(1), Some(OptimizationLevel::O1));
+    assert_eq!(OptimizationLevel::from_level(2), Some(OptimizationLevel::O2));
+    assert_eq!(OptimizationLevel::from_level(3), None);
+}
+
+
+#[test]
+fn unreachable_functions_are_removed() {
+    let used = "
+fn used(): i64 { 5 }
+var x = used()
+";
+
+    // The dead functions only call each other, so they're
+    // referenced but never reachable from the root
+    let with_dead = "
+fn used(): i64 { 5 }
+fn ping(n: i64): i64 { if n > 0 { pong(n - 1) } else { n } }
+fn pong(n: i64): i64 { if n > 0 { ping(n - 1) } else { n } }
+var x = used()
+";
+
+    assert_eq!(source_size(used, OptimizationLevel::O2), source_size(with_dead, OptimizationLevel::O2));
+    assert!(source_size(used, OptimizationLevel::O0) < source_size(with_dead, OptimizationLevel::O0));
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn self_recursive_structure() {
+    let error = compile_error("struct Node { value: i64, next: Node }").unwrap();
+    assert!(error.contains("error[254]"));
+    assert!(error.contains("'Node' -> 'Node'"));
+}
+
+
+#[test]
+fn mutually_recursive_structures() {
+    let error = compile_error("struct A { b: B }\nstruct B { a: A }").unwrap();
+    assert!(error.contains("error[254]"));
+    assert!(error.contains("'A' -> 'B' -> 'A'"));
+    assert_eq!(error.matches("error[254]").count(), 1);
+}
+
+
+#[test]
+fn arrays_break_the_cycle() {
+    assert_eq!(compile_error("struct Node { value: i64, children: [Node] }"), None);
+}
+
+
+#[test]
+fn nested_structures() {
+    assert_eq!(compile_error("struct Inner { value: i64 }\nstruct Outer { a: Inner, b: Inner }"), None);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use self::common::compile_error;
+
+
+#[test]
+fn inferred_from_the_tail() {
+    assert_eq!(compile_error("fn five() { 5 }\nvar x : i64 = five()"), None);
+    assert_eq!(compile_error("fn nothing() {}\nnothing()"), None);
+    assert!(compile_error("fn five() { 5 }\nvar x : str = five()").unwrap().contains("error[210]"));
+}
+
+
+#[test]
+fn inferred_from_returns() {
+    assert_eq!(compile_error("fn sign(x: i64) {\n    if x < 0 { return -1 }\n    1\n}\nvar s : i64 = sign(5)"), None);
+    assert_eq!(compile_error("fn name(x: bool) {\n    if x { return \"yes\" }\n    return \"no\"\n}\nvar s : str = name(true)"), None);
+}
+
+
+#[test]
+fn called_before_the_declaration() {
+    assert_eq!(compile_error("var x : str = name()\nfn name() { \"azurite\" }"), None);
+    assert_eq!(compile_error("fn a() { b() + 1 }\nfn b() { 2 }\nvar x : i64 = a()"), None);
+}
+
+
+#[test]
+fn inferred_methods() {
+    let source = "
+struct Point { x: i64 }
+
+impl Point {
+    fn get_x(self) { self.x }
+}
+
+var x : i64 = Point { x: 5 }.get_x()
+";
+
+    assert_eq!(compile_error(source), None);
+}
+
+
+#[test]
+fn conflicting_return_types() {
+    assert!(compile_error("fn f(x: i64) {\n    if x < 0 { return 1 }\n    \"positive\"\n}").unwrap().contains("error[259]"));
+    assert!(compile_error("fn f(x: i64) {\n    if x < 0 { return 1 }\n    return \"positive\"\n}").unwrap().contains("error[259]"));
+}
+
+
+#[test]
+fn recursion_needs_a_return_type() {
+    assert!(compile_error("fn fact(n: i64) {\n    if n == 0 { return 1 }\n    n * fact(n - 1)\n}").unwrap().contains("error[260]"));
+    assert!(compile_error("fn even(n: i64) {\n    if n == 0 { return true }\n    odd(n - 1)\n}\nfn odd(n: i64) {\n    if n == 0 { return false }\n    even(n - 1)\n}").unwrap().contains("error[260]"));
+    assert_eq!(compile_error("fn fact(n: i64): i64 {\n    if n == 0 { return 1 }\n    n * fact(n - 1)\n}"), None);
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel, Severity};
+use azurite_errors::{CompilerError, ErrorBuilder};
+use common::{SymbolTable, SourceRange};
+
+
+#[test]
+fn warnings_dont_fail_compilation() {
+    let (result, warnings, _) = compile::<BytecodeModule>("test.az".to_string(), "var x = 5".to_string(), OptimizationLevel::O2);
+
+    assert!(result.is_ok());
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity(), Some(Severity::Warning));
+    assert!(!warnings[0].is_fatal());
+}
+
+
+#[test]
+fn errors_are_fatal() {
+    let (result, _, _) = compile::<BytecodeModule>("test.az".to_string(), "var x = y".to_string(), OptimizationLevel::O2);
+
+    let error = result.err().unwrap();
+    assert_eq!(error.severity(), Some(Severity::Error));
+    assert!(error.is_fatal());
+}
+
+
+#[test]
+fn severity_names_the_title() {
+    let mut symbol_table = SymbolTable::new();
+    let file = symbol_table.add("test".to_string());
+    let files = HashMap::from([(file, ("test".to_string(), "var x = 5".to_string()))]);
+
+    for (severity, title) in [(Severity::Error, "error[001]"), (Severity::Warning, "warning[001]"), (Severity::Note, "note[001]")] {
+        let error = CompilerError::new(file, 1, "message", severity)
+            .highlight(SourceRange::new(4, 4))
+            .build();
+
+        assert_eq!(error.severity(), Some(severity));
+        assert!(error.build(&files).contains(title));
+    }
+}
@@ -0,0 +1,40 @@
+use azurite_compiler::{compile, BytecodeModule, OptimizationLevel};
+
+
+fn compile_error(source: &str) -> String {
+    let (result, _, debug_info) = compile::<BytecodeModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O2);
+
+    result.err().unwrap().build(&debug_info)
+}
+
+
+#[test]
+fn suggests_close_variables() {
+    let error = compile_error("var counter = 5\nvar x = countr + 1");
+
+    assert!(error.contains("error[205]"), "{error}");
+    assert!(error.contains("did you mean counter?"), "{error}");
+}
+
+
+#[test]
+fn suggests_close_functions() {
+    let error = compile_error("fn double(x: i64): i64 { x * 2 }\nvar x = dubble(5)");
+
+    assert!(error.contains("error[212]"), "{error}");
+    assert!(error.contains("did you mean double?"), "{error}");
+}
+
+
+#[test]
+fn distant_names_arent_suggested() {
+    let error = compile_error("var counter = 5\nvar x = total + 1");
+
+    assert!(error.contains("error[205]"), "{error}");
+    assert!(!error.contains("did you mean"), "{error}");
+
+    let error = compile_error("fn double(x: i64): i64 { x * 2 }\nvar x = triple(5)");
+
+    assert!(error.contains("error[212]"), "{error}");
+    assert!(!error.contains("did you mean"), "{error}");
+}
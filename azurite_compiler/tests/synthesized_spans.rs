@@ -0,0 +1,41 @@
+mod common;
+
+use self::common::compile_error;
+
+
+/// The columns of every highlight in the error
+fn highlighted_columns(error: &str) -> Vec<usize> {
+    error.lines()
+        .filter(|x| x.contains("-->"))
+        .map(|x| x.rsplit(':').next().unwrap().trim().parse().unwrap())
+        .collect()
+}
+
+
+#[test]
+fn logical_and_highlights_its_right_hand_side() {
+    let error = compile_error("var x = true && 5").unwrap();
+    assert!(error.contains("error[204]"));
+
+    let columns = highlighted_columns(&error);
+    assert!(columns.contains(&16));
+    assert!(!columns.contains(&8));
+}
+
+
+#[test]
+fn logical_or_highlights_its_right_hand_side() {
+    let error = compile_error("var x = false || 5").unwrap();
+    assert!(error.contains("error[204]"));
+
+    let columns = highlighted_columns(&error);
+    assert!(columns.contains(&17));
+    assert!(!columns.contains(&8));
+}
+
+
+#[test]
+fn logical_operators_still_compile() {
+    assert_eq!(compile_error("var x = true && false || true"), None);
+    assert_eq!(compile_error("var i = 0\nwhile i < 10 && i != 5 { i += 1 }\nfor j in 0..i { }"), None);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use azurite_ast_to_ir::IR;
+use azurite_compiler::OptimizationLevel;
+use self::common::lower_to_ir;
+
+
+/// Lowers `source` at `level` and returns the number of
+/// calls & tail calls in the function named `name`, tail
+/// calls are only lowered above `O0` like the compiler
+fn calls(source: &str, name: &str, level: OptimizationLevel) -> (usize, usize) {
+    let (mut ir, _) = lower_to_ir(source);
+    ir.remove_unreachable_blocks();
+    ir.sort();
+    ir.optimize(level);
+    ir.sort();
+
+    if level != OptimizationLevel::O0 {
+        ir.lower_tail_calls();
+    }
+
+    let function = ir.functions.values()
+        .find(|x| x.matches_name(&ir, name))
+        .unwrap();
+
+    let instructions = function.blocks.iter().flat_map(|x| x.instructions.iter());
+
+    (
+        instructions.clone().filter(|x| matches!(x, IR::Call { .. })).count(),
+        instructions.filter(|x| matches!(x, IR::TailCall { .. })).count(),
+    )
+}
+
+
+const LEVELS : [OptimizationLevel; 2] = [OptimizationLevel::O1, OptimizationLevel::O2];
+
+
+#[test]
+fn returned_calls_become_tail_calls() {
+    let source = "
+fn sum_to(n: i64, total: i64): i64 {
+    if n == 0 {
+        return total
+    }
+
+    sum_to(n - 1, total + n)
+}
+
+var x = sum_to(10, 0)
+";
+
+    for level in LEVELS {
+        assert_eq!(calls(source, "sum_to", level), (0, 1), "at {level:?}");
+    }
+}
+
+
+#[test]
+fn calls_in_if_expressions_become_tail_calls() {
+    let source = "
+fn is_even(n: i64): bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+fn is_odd(n: i64): bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+var x = is_even(10)
+";
+
+    for level in LEVELS {
+        assert_eq!(calls(source, "is_even", level), (0, 1), "at {level:?}");
+    }
+}
+
+
+#[test]
+fn calls_are_not_lowered_at_o0() {
+    let source = "
+fn sum_to(n: i64, total: i64): i64 {
+    if n == 0 {
+        return total
+    }
+
+    sum_to(n - 1, total + n)
+}
+
+var x = sum_to(10, 0)
+";
+
+    assert_eq!(calls(source, "sum_to", OptimizationLevel::O0), (1, 0));
+}
+
+
+#[test]
+fn calls_whose_result_is_used_stay_calls() {
+    let source = "
+fn sum_to(n: i64): i64 {
+    if n == 0 {
+        return 0
+    }
+
+    n + sum_to(n - 1)
+}
+
+var x = sum_to(10)
+";
+
+    for level in LEVELS {
+        assert_eq!(calls(source, "sum_to", level), (1, 0), "at {level:?}");
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use azurite_compiler::{compile, BytecodeModule, CModule, WasmModule, OptimizationLevel, Target};
+use self::common::compile_error_at;
+
+
+#[test]
+fn bytecode_target() {
+    let (result, _, _) = compile::<BytecodeModule>("test.az".to_string(), "var x = 5".to_string(), OptimizationLevel::O2);
+    let (metadata, ..) = result.ok().unwrap();
+
+    assert_eq!(metadata.target, Target::Bytecode);
+}
+
+
+#[test]
+fn c_target() {
+    let (result, _, _) = compile::<CModule>("test.az".to_string(), "var x = 5".to_string(), OptimizationLevel::O2);
+    let (metadata, ..) = result.ok().unwrap();
+
+    assert_eq!(metadata.target, Target::C);
+}
+
+
+#[test]
+fn wasm_target() {
+    let (result, _, _) = compile::<WasmModule>("test.az".to_string(), "var x = 5".to_string(), OptimizationLevel::O2);
+    let (metadata, ..) = result.ok().unwrap();
+
+    assert_eq!(metadata.target, Target::Wasm);
+}
+
+
+#[test]
+fn wasm_lowers_arithmetic_and_externs() {
+    let source = r#"
+        extern "host" {
+            fn log(i64): i64
+        }
+
+        fn add(a: i64, b: i64): i64 {
+            log(a + b)
+        }
+
+        add(1, 2)
+    "#;
+
+    let (result, _, _) = compile::<WasmModule>("test.az".to_string(), source.to_string(), OptimizationLevel::O0);
+    let (_, module, ..) = result.ok().unwrap();
+    let module = String::from_utf8(module).unwrap();
+
+    assert!(module.starts_with("(module"));
+    assert!(module.contains("(export \"main\")"));
+    assert!(module.contains("(i64.add "));
+
+    // The externs of the standard library are imported too
+    // so the index of `log` isn't known up front
+    let import = module.lines()
+        .find(|x| x.contains("(import \"host\" \"log\""))
+        .unwrap();
+    let index = import.split("(func ").nth(1).unwrap().split(' ').next().unwrap();
+
+    assert!(import.ends_with(&format!("(func {index} (param i64) (result i64)))")));
+    assert!(module.contains(&format!("(call {index} ")));
+}
+
+
+#[test]
+fn wasm_target_rejects_allocations() {
+    let sources = [
+        (r#"var x = "hello""#, "strings"),
+        ("var x = [1, 2, 3]", "arrays"),
+        ("struct Point { x: i64, y: i64 }\nvar p = Point { x: 1, y: 2 }", "structures"),
+    ];
+
+    for (source, feature) in sources {
+        let error = compile_error_at::<WasmModule>(source, OptimizationLevel::O0).expect("the wasm target has no heap");
+
+        assert!(error.contains("error[401]"), "{error}");
+        assert!(error.contains(&format!("{feature} aren't supported by the wasm target")), "{error}");
+    }
+}
+
+
+#[test]
+fn target_round_trips() {
+    for target in [Target::Bytecode, Target::C, Target::Wasm] {
+        let metadata = azurite_common::CompilationMetadata { extern_count: 1, library_count: 2, target };
+        let metadata = azurite_common::CompilationMetadata::from_bytes(metadata.to_bytes()).unwrap();
+
+        assert_eq!(metadata.target, target);
+        assert_eq!(Target::from_name(target.name()), Some(target));
+    }
+}
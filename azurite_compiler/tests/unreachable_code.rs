@@ -0,0 +1,51 @@
+mod common;
+
+use self::common::warnings;
+
+
+#[test]
+fn code_after_return() {
+    let warnings = warnings("fn f(): i64 {\n    return 5\n    6\n}");
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("warning[304]"));
+}
+
+
+#[test]
+fn code_after_break_and_continue() {
+    assert!(warnings("loop {\n    break\n    assert(true)\n}")[0].contains("warning[304]"));
+    assert!(warnings("var _x = 0\nloop {\n    if _x > 5 { break }\n    _x = _x + 1\n    continue\n    assert(true)\n}")[0].contains("warning[304]"));
+}
+
+
+#[test]
+fn diverging_if() {
+    let source = "
+fn sign(x: i64): i64 {
+    if x < 0 { return -1 } else { return 1 }
+    0
+}
+";
+
+    assert!(warnings(source)[0].contains("warning[304]"));
+}
+
+
+#[test]
+fn partially_diverging_if() {
+    let source = "
+fn clamp(x: i64): i64 {
+    if x < 0 { return 0 }
+    x
+}
+";
+
+    assert!(warnings(source).is_empty());
+}
+
+
+#[test]
+fn trailing_return() {
+    assert!(warnings("fn f() {\n    assert(true)\n    return ()\n}").is_empty());
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use self::common::warnings;
+
+
+#[test]
+fn unused_variable_warns() {
+    let warnings = warnings("var x = 5");
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("warning[302]"));
+}
+
+
+#[test]
+fn read_variables_dont_warn() {
+    assert!(warnings("var x = 5\nvar y = x + 1\ny").is_empty());
+    assert!(warnings("fn double(x: i64): i64 { var y = x * 2\n y }").is_empty());
+}
+
+
+#[test]
+fn only_written_variables_warn() {
+    assert_eq!(warnings("var x = 5\nx = 6").len(), 1);
+}
+
+
+#[test]
+fn underscore_silences_the_warning() {
+    assert!(warnings("var _x = 5").is_empty());
+}
+
+
+#[test]
+fn unused_variable_in_a_block() {
+    let warnings = warnings("{\n    var unused = 5\n}");
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("warning[302]"));
+}
+
+
+#[test]
+fn unused_arguments_dont_warn() {
+    assert!(warnings("fn ignore(x: i64) { }").is_empty());
+}
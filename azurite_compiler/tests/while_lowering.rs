@@ -0,0 +1,76 @@
+mod common;
+
+use azurite_ast_to_ir::{BlockIndex, BlockTerminator};
+use self::common::lower_to_ir;
+
+
+/// Returns the number of blocks the root function of
+/// `source` is lowered into, before any optimizations
+fn block_count(source: &str) -> usize {
+    let (mut ir, file) = lower_to_ir(source);
+    ir.find_function(file).blocks.len()
+}
+
+
+#[test]
+fn while_jumps_straight_to_the_exit() {
+    let desugared = block_count("
+var i = 0
+while i < 10 {
+    i = i + 1
+}
+");
+
+    // The extra statement in the else keeps the naive lowering
+    let naive = block_count("
+var i = 0
+loop {
+    if i < 10 {
+        i = i + 1
+    } else {
+        i = i
+        break
+    }
+}
+");
+
+    assert!(desugared < naive, "{desugared} blocks for while, {naive} blocks for the naive lowering");
+}
+
+
+#[test]
+fn placeholders_are_patched_before_removing_unreachable_blocks() {
+    let source = "
+var i = 0
+'outer: while i < 10 {
+    while true {
+        i = i + 1
+        if i == 5 { continue 'outer }
+        break
+    }
+}
+";
+
+    let (mut ir, file) = lower_to_ir(source);
+    let before = ir.find_function(file).blocks.len();
+
+    ir.remove_unreachable_blocks();
+
+    let function = ir.find_function(file);
+    let blocks : Vec<BlockIndex> = function.blocks.iter().map(|x| x.block_index).collect();
+
+    for block in &function.blocks {
+        let targets = match block.ending {
+            BlockTerminator::Goto(v) => vec![v],
+            BlockTerminator::SwitchBool { op1, op2, .. } => vec![op1, op2],
+            BlockTerminator::Return => vec![],
+        };
+
+        for target in targets {
+            assert_ne!(target, BlockIndex(u32::MAX), "a placeholder terminator was left behind");
+            assert!(blocks.contains(&target), "a reachable block jumps to a removed one");
+        }
+    }
+
+    assert!(function.blocks.len() < before, "the blocks after `break` & `continue` are unreachable");
+}
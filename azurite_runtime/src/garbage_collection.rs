@@ -2,7 +2,7 @@ use std::{sync::atomic::AtomicU64, time::Instant};
 
 use rayon::prelude::{IntoParallelRefMutIterator, IndexedParallelIterator, ParallelIterator};
 
-use crate::{VM, Object, object_map::{ObjectMap, ObjectData, ObjectIndex}};
+use crate::{VM, VMData, RawVMData, Object, object_map::{ObjectMap, ObjectData, ObjectIndex}};
 
 impl VM<'_> {
     pub fn run_garbage_collection(&mut self) {
@@ -19,6 +19,60 @@ impl VM<'_> {
     }
 
 
+    /// Runs a garbage collection and then compacts the object map
+    ///
+    /// Every live object is moved to the front of the map, in the
+    /// same order, and every reference to it on the stack, in the
+    /// constants & within other objects is rewritten.
+    ///
+    /// Indices held outside of the VM are invalidated by this so
+    /// the compaction is skipped while `call_function` is running
+    pub fn run_major_garbage_collection(&mut self) {
+        self.run_garbage_collection();
+
+        if self.reentries.is_empty() {
+            self.compact();
+        }
+    }
+
+
+    fn compact(&mut self) {
+        let space = self.objects.raw().len();
+        if space == 0 {
+            return
+        }
+
+        let mut forward = Vec::with_capacity(space);
+        let mut live = 0;
+
+        // Every slot before `live` holds a live object that has
+        // already been moved so the slots between `live` and
+        // `index` are all free
+        let objects = self.objects.raw_mut();
+        for index in 0..space {
+            if matches!(objects[index].data, ObjectData::Free { .. }) {
+                forward.push(ObjectIndex::new(index as u64));
+                continue
+            }
+
+            objects.swap(live, index);
+            forward.push(ObjectIndex::new(live as u64));
+            live += 1;
+        }
+
+        objects[..live].iter_mut().for_each(|x| x.relocate(&forward));
+
+        for (index, object) in objects.iter_mut().enumerate().skip(live) {
+            object.data = ObjectData::Free { next: ObjectIndex::new(((index + 1) % space) as u64) };
+        }
+
+        self.objects.free = ObjectIndex::new((live % space) as u64);
+
+        self.stack.values[..self.stack.top].iter_mut().for_each(|x| x.relocate(&forward));
+        self.constants.iter_mut().for_each(|x| x.relocate(&forward));
+    }
+
+
     fn mark(&mut self) {
         for object in 0..self.stack.top {
             let val = self.stack.values[object];
@@ -58,6 +112,8 @@ impl VM<'_> {
                     // We don't need to add up the inner-objects as all objects are in
                     // the object map so eventually we will also add that objects size
                     ObjectData::Struct(v) => std::mem::size_of::<Object>() + std::mem::size_of_val(v.fields()),
+                    ObjectData::List(v) => std::mem::size_of::<Object>() + std::mem::size_of_val(v.as_slice()),
+                    ObjectData::Map(v) => std::mem::size_of::<Object>() + v.len() * std::mem::size_of::<(VMData, VMData)>(),
 
                     // If the object is free, it is technically still occupying space
                     // in the VM but that is not considered as "used" memory so it
@@ -78,9 +134,39 @@ impl Object {
 
         match &self.data {
             ObjectData::Struct(v) => v.fields().iter().filter(|x| x.is_object()).for_each(|x| objects.get(x.as_object()).mark(mark_as, objects)),
+            ObjectData::List(v) => v.iter().filter(|x| x.is_object()).for_each(|x| objects.get(x.as_object()).mark(mark_as, objects)),
+            ObjectData::Map(v) => v.entries()
+                .flat_map(|(key, value)| [key, value])
+                .filter(|x| x.is_object())
+                .for_each(|x| objects.get(x.as_object()).mark(mark_as, objects)),
             
             | ObjectData::String(_)
             | ObjectData::Free { .. } => (),
         }
     }
+
+
+    fn relocate(&mut self, forward: &[ObjectIndex]) {
+        match &mut self.data {
+            ObjectData::Struct(v) => v.fields_mut().iter_mut().for_each(|x| x.relocate(forward)),
+            ObjectData::List(v) => v.iter_mut().for_each(|x| x.relocate(forward)),
+            ObjectData::Map(v) => v.entries_mut()
+                .flat_map(|(key, value)| [key, value])
+                .for_each(|x| x.relocate(forward)),
+
+            | ObjectData::String(_)
+            | ObjectData::Free { .. } => (),
+        }
+    }
+}
+
+
+impl VMData {
+    fn relocate(&mut self, forward: &[ObjectIndex]) {
+        // Only the index moves, strings, lists & maps
+        // keep their reserved tags
+        if self.is_object() {
+            *self = VMData::new(self.tag, RawVMData { as_object: forward[self.as_object().index as usize] });
+        }
+    }
 }
@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+
+use crate::{VM, VMData, FatalError, ObjectIndex, object_map::ObjectData};
+
+
+const INITIAL_BUCKETS : usize = 8;
+
+
+/// A hash table of runtime values
+///
+/// Keys are hashed with `VM::hash` and compared with
+/// `VM::structural_eq`, so primitives, strings and
+/// structures made out of them can be used as keys.
+/// Collisions are handled by chaining the entries
+/// within a bucket
+#[derive(Debug, Clone)]
+pub struct Map {
+    buckets: Vec<Vec<(VMData, VMData)>>,
+    len: usize,
+}
+
+
+impl Map {
+    pub fn new() -> Self {
+        Self {
+            buckets: vec![vec![]; INITIAL_BUCKETS],
+            len: 0,
+        }
+    }
+
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+
+    /// Returns every key-value pair in an unspecified order
+    pub fn entries(&self) -> impl Iterator<Item = &(VMData, VMData)> {
+        self.buckets.iter().flatten()
+    }
+
+
+    /// Returns every key-value pair mutably, the keys
+    /// must keep hashing to the same value
+    pub(crate) fn entries_mut(&mut self) -> impl Iterator<Item = &mut (VMData, VMData)> {
+        self.buckets.iter_mut().flatten()
+    }
+
+
+    #[inline]
+    fn bucket(&self, hash: i64) -> usize {
+        (hash as u64 % self.buckets.len() as u64) as usize
+    }
+}
+
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl VM<'_> {
+    /// Inserts a key-value pair into the map at `map`
+    /// returning the previous value of the key
+    ///
+    /// # Errors
+    /// - If the value at `map` isn't a map
+    /// - If the key can't be hashed
+    pub fn map_insert(&mut self, map: ObjectIndex, key: VMData, value: VMData) -> Result<Option<VMData>, FatalError> {
+        let hash = self.key_hash(key)?;
+
+        if let Some((bucket, index)) = self.map_find(map, key, hash)? {
+            let entry = &mut self.objects.get_mut(map).map_mut()?.buckets[bucket][index];
+            return Ok(Some(std::mem::replace(&mut entry.1, value)))
+        }
+
+        let current = self.objects.get(map).map()?;
+        if current.len >= current.buckets.len() * 2 {
+            self.map_grow(map)?;
+        }
+
+        let map = self.objects.get_mut(map).map_mut()?;
+        let bucket = map.bucket(hash);
+
+        map.buckets[bucket].push((key, value));
+        map.len += 1;
+
+        Ok(None)
+    }
+
+
+    /// Returns the value of the key in the map at `map`
+    ///
+    /// # Errors
+    /// - If the value at `map` isn't a map
+    /// - If the key can't be hashed
+    pub fn map_get(&self, map: ObjectIndex, key: VMData) -> Result<Option<VMData>, FatalError> {
+        let hash = self.key_hash(key)?;
+
+        let Some((bucket, index)) = self.map_find(map, key, hash)? else { return Ok(None) };
+
+        Ok(Some(self.objects.get(map).map()?.buckets[bucket][index].1))
+    }
+
+
+    /// Removes the key from the map at `map` returning
+    /// its value
+    ///
+    /// # Errors
+    /// - If the value at `map` isn't a map
+    /// - If the key can't be hashed
+    pub fn map_remove(&mut self, map: ObjectIndex, key: VMData) -> Result<Option<VMData>, FatalError> {
+        let hash = self.key_hash(key)?;
+
+        let Some((bucket, index)) = self.map_find(map, key, hash)? else { return Ok(None) };
+
+        let map = self.objects.get_mut(map).map_mut()?;
+        map.len -= 1;
+
+        Ok(Some(map.buckets[bucket].swap_remove(index).1))
+    }
+
+
+    /// Renders the map at `map` as `{key: value, ..}`
+    ///
+    /// The entries are sorted by their key so the output
+    /// doesn't depend on the insertion order or the layout
+    /// of the buckets
+    ///
+    /// # Errors
+    /// - If the value at `map` isn't a map
+    pub fn map_to_string(&self, map: ObjectIndex) -> Result<String, FatalError> {
+        Ok(self.render_map(self.objects.get(map).map()?))
+    }
+
+
+    fn render_map(&self, map: &Map) -> String {
+        let mut entries = map.entries().collect::<Vec<_>>();
+        entries.sort_by(|x, y| self.key_cmp(x.0, y.0));
+
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| format!("{}: {}", self.render(*key), self.render(*value)))
+            .collect::<Vec<_>>();
+
+        format!("{{{}}}", entries.join(", "))
+    }
+
+
+    /// Orders keys of the same type by their value and
+    /// keys of differing types by their tag, structures
+    /// of the same type are ordered field by field
+    fn key_cmp(&self, k1: VMData, k2: VMData) -> Ordering {
+        if k1.tag != k2.tag {
+            return k1.tag.cmp(&k2.tag)
+        }
+
+        match k1.tag {
+            VMData::TAG_I8    => k1.as_i8().cmp(&k2.as_i8()),
+            VMData::TAG_I16   => k1.as_i16().cmp(&k2.as_i16()),
+            VMData::TAG_I32   => k1.as_i32().cmp(&k2.as_i32()),
+            VMData::TAG_I64   => k1.as_i64().cmp(&k2.as_i64()),
+            VMData::TAG_U8    => k1.as_u8().cmp(&k2.as_u8()),
+            VMData::TAG_U16   => k1.as_u16().cmp(&k2.as_u16()),
+            VMData::TAG_U32   => k1.as_u32().cmp(&k2.as_u32()),
+            VMData::TAG_U64   => k1.as_u64().cmp(&k2.as_u64()),
+            VMData::TAG_FLOAT => k1.as_float().total_cmp(&k2.as_float()),
+            VMData::TAG_BOOL  => k1.as_bool().cmp(&k2.as_bool()),
+            VMData::TAG_CHAR  => k1.as_char().cmp(&k2.as_char()),
+            VMData::TAG_STR   => self.objects.get(k1.as_object()).string().cmp(self.objects.get(k2.as_object()).string()),
+
+            _ if k1.is_object() => {
+                let (ObjectData::Struct(s1), ObjectData::Struct(s2)) = (&self.objects.get(k1.as_object()).data, &self.objects.get(k2.as_object()).data)
+                else { return Ordering::Equal };
+
+                s1.fields().iter()
+                    .zip(s2.fields())
+                    .map(|(f1, f2)| self.key_cmp(*f1, *f2))
+                    .find(|x| x.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            },
+
+            _ => Ordering::Equal,
+        }
+    }
+
+
+    fn render(&self, value: VMData) -> String {
+        if !value.is_object() {
+            return value.to_string()
+        }
+
+        match &self.objects.get(value.as_object()).data {
+            ObjectData::String(v) => format!("{v:?}"),
+            ObjectData::Map(v) => self.render_map(v),
+            ObjectData::List(v) => format!("[{}]", v.iter().map(|x| self.render(*x)).collect::<Vec<_>>().join(", ")),
+
+            _ => value.to_string(),
+        }
+    }
+
+
+    fn key_hash(&self, key: VMData) -> Result<i64, FatalError> {
+        self.hash(key).ok_or_else(|| FatalError::new(String::from("the key can't be hashed")))
+    }
+
+
+    fn map_find(&self, map: ObjectIndex, key: VMData, hash: i64) -> Result<Option<(usize, usize)>, FatalError> {
+        let map = self.objects.get(map).map()?;
+        let bucket = map.bucket(hash);
+
+        Ok(map.buckets[bucket]
+            .iter()
+            .position(|x| self.structural_eq(x.0, key))
+            .map(|index| (bucket, index)))
+    }
+
+
+    fn map_grow(&mut self, map: ObjectIndex) -> Result<(), FatalError> {
+        let entries = std::mem::take(&mut self.objects.get_mut(map).map_mut()?.buckets)
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (self.hash(key).unwrap(), key, value))
+            .collect::<Vec<_>>();
+
+        let bucket_count = (entries.len() / 2).max(INITIAL_BUCKETS) * 2;
+
+        let map = self.objects.get_mut(map).map_mut()?;
+        map.buckets = vec![vec![]; bucket_count];
+
+        for (hash, key, value) in entries {
+            let bucket = map.bucket(hash);
+            map.buckets[bucket].push((key, value));
+        }
+
+        Ok(())
+    }
+}
@@ -5,14 +5,17 @@
 mod object_map;
 mod runtime;
 mod garbage_collection;
+mod hash_map;
 
 use azurite_archiver::{Packed, Data};
-use azurite_common::CompilationMetadata;
+use azurite_common::{CompilationMetadata, Target};
 use colored::Colorize;
 use libloading::Library;
 use libloading::Symbol;
 use object_map::ObjectData;
 use object_map::ObjectMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::env;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -27,6 +30,11 @@ use std::{time::Instant, ops::FromResidual, convert::Infallible, ffi::CString, m
 pub use object_map::Object;
 pub use object_map::ObjectIndex;
 pub use object_map::Structure;
+pub use hash_map::Map;
+
+
+#[cfg(test)]
+mod tests;
 
 
 const _: () = assert!(size_of::<VMData>() <= 16);
@@ -46,18 +54,89 @@ type ExternFunctionRaw = unsafe extern "C" fn(&mut VM) -> Status;
 /// # Panics
 /// - If the 'Packed' value is not correct
 pub fn run_packed(packed: Packed) -> Result<(), &'static str> {
-    let mut files : Vec<Data> = packed.into();
+    let (metadata, bytecode, constants, structures, debug, lines) = unpack(packed)?;
 
-    let Some(constants) = files.pop() else { return Err("the file isn't a valid azurite file") };
-    let Some(bytecode)  = files.pop() else { return Err("the file isn't a valid azurite file") };
-    let Some(metadata)  = files.pop() else { return Err("the file isn't a valid azurite file") };
-    let Ok(metadata)    = metadata.0.try_into() else { return Err("the file isn't a valid azurite file")};
-    let metadata        = CompilationMetadata::from_bytes(metadata);
+    run(metadata, &bytecode, constants, structures, (&debug, &lines));
+    Ok(())
+}
 
-    assert!(files.is_empty());
 
-    run(metadata, &bytecode.0, constants.0);
-    Ok(())
+/// Runs a 'Packed' file without printing anything
+/// and returns the top-of-stack result (register 0)
+/// so the output can be consumed by a host program
+///
+/// # Errors
+/// - `Status::Err` if the 'Packed' value isn't a valid azurite file
+/// - `Status::Err` if the 'Packed' value wasn't compiled for the bytecode target
+/// - `Status::Err` if the program fails at runtime
+/// - `Status::Exit` with the exit code if the program exits
+///
+/// # Panics
+/// - If the 'Packed' value is not correct
+pub fn run_packed_returning(packed: Packed) -> Result<VMData, Status> {
+    run_packed_bounded(packed, None)
+}
+
+
+/// Same as `run_packed_returning` but the program is
+/// stopped with an error once it has executed `budget`
+/// instructions, which bounds the time an untrusted
+/// program can run for
+///
+/// # Errors
+/// - `Status::Err` if the 'Packed' value isn't a valid azurite file
+/// - `Status::Err` if the 'Packed' value wasn't compiled for the bytecode target
+/// - `Status::Err` if the program fails at runtime
+/// - `Status::Err` if the program exceeds the instruction budget
+/// - `Status::Exit` with the exit code if the program exits
+///
+/// # Panics
+/// - If the 'Packed' value is not correct
+pub fn run_packed_with_budget(packed: Packed, budget: u64) -> Result<VMData, Status> {
+    run_packed_bounded(packed, Some(budget))
+}
+
+
+fn run_packed_bounded(packed: Packed, budget: Option<u64>) -> Result<VMData, Status> {
+    let (metadata, bytecode, constants, structures, debug, lines) = unpack(packed).map_err(Status::err)?;
+
+    let mut vm = create_vm(metadata, &bytecode, structures, (&debug, &lines));
+    vm.set_instruction_budget(budget);
+    bytes_to_constants(&mut vm, constants).map_err(Status::Err)?;
+
+    match vm.run() {
+        Status::Ok => Ok(vm.stack.reg(0)),
+        status => Err(status),
+    }
+}
+
+
+/// Splits a 'Packed' file into its metadata, bytecode,
+/// constants, structure, debug and line table sections
+#[allow(clippy::type_complexity)]
+fn unpack(packed: Packed) -> Result<(CompilationMetadata, Vec<u8>, Vec<u8>, HashMap<u64, StructureLayout>, Vec<u8>, Vec<u8>), &'static str> {
+    const INVALID : &str = "the file isn't a valid azurite file";
+    const OTHER_VERSION : &str = "the file was compiled by a different version of azurite, recompile it";
+
+    let mut files = Vec::<Data>::from(packed).into_iter();
+
+    let Some(metadata) = files.next() else { return Err(INVALID) };
+    let Ok(metadata) = <[u8; CompilationMetadata::SIZE]>::try_from(metadata.0) else { return Err(OTHER_VERSION) };
+
+    if metadata[0] != CompilationMetadata::FORMAT_VERSION {
+        return Err(OTHER_VERSION)
+    }
+
+    let Some(metadata) = CompilationMetadata::from_bytes(metadata) else { return Err(INVALID) };
+
+    if metadata.target != Target::Bytecode {
+        return Err("the file was compiled for the C target and can't be run by the VM")
+    }
+
+    let Ok([bytecode, constants, structures, debug, lines]) = <[Data; 5]>::try_from(files.collect::<Vec<_>>()) else { return Err(INVALID) };
+    let Some(structures) = bytes_to_structures(&structures.0) else { return Err(INVALID) };
+
+    Ok((metadata, bytecode.0, constants.0, structures, debug.0, lines.0))
 }
 
 
@@ -69,14 +148,37 @@ pub struct VM<'a> {
 
     callstack: Vec<Code<'a>>,
     current: Code<'a>,
+
+    /// The callstack depths at which `call_function`
+    /// entered the interpreter, innermost last
+    reentries: Vec<usize>,
     libraries: Vec<Library>,
     externs: Vec<ExternFunctionRaw>,
     metadata: CompilationMetadata,
+    structures: HashMap<u64, StructureLayout>,
+
+    /// Taken when the VM is created, monotonic
+    /// time is measured relative to this
+    start: Instant,
+
+    /// The number of instructions left to execute
+    /// before the program is stopped, unbounded
+    /// if `None`
+    instruction_budget: Option<u64>,
 
     debug: VMDebugInfo,
 }
 
 
+/// The name of a structure type and
+/// the names of its fields in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructureLayout {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+
 impl VM<'_> {
     pub fn create_object(&mut self, object: Object) -> Result<ObjectIndex, FatalError> {
         match self.objects.put(object) {
@@ -90,16 +192,169 @@ impl VM<'_> {
             },
         }
     }
+
+
+    /// Returns the time elapsed since the VM was created
+    /// using a monotonic clock which is unaffected by
+    /// changes to the system clock
+    #[must_use]
+    pub fn monotonic_elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+
+    /// Limits the number of instructions the VM executes
+    /// before it stops with an error, `None` removes the
+    /// limit
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.instruction_budget = budget;
+    }
+
+
+    /// Returns the layout of the structure type `id`
+    #[must_use]
+    pub fn structure_layout(&self, id: u64) -> Option<&StructureLayout> {
+        self.structures.get(&id)
+    }
+
+
+    /// Compares two values by their contents
+    ///
+    /// Unlike `VMData`'s `PartialEq`, which compares objects by
+    /// their index in the object map, this compares strings by
+    /// their text and lists & structures element-wise. Values
+    /// with differing tags, which includes structures of
+    /// different types, are never equal
+    ///
+    /// Objects which contain themselves are compared by
+    /// assuming a pair of objects which is already being
+    /// compared is equal, so the comparison terminates
+    #[must_use]
+    pub fn structural_eq(&self, v1: VMData, v2: VMData) -> bool {
+        self.structural_eq_visiting(v1, v2, &mut HashSet::new())
+    }
+
+
+    /// `visiting` holds the pairs of object indices
+    /// which are being compared further up
+    fn structural_eq_visiting(&self, v1: VMData, v2: VMData, visiting: &mut HashSet<(u64, u64)>) -> bool {
+        if v1.tag != v2.tag {
+            return false
+        }
+
+        if !v1.is_object() {
+            return v1 == v2
+        }
+
+        if v1.as_object() == v2.as_object() {
+            return true
+        }
+
+        if !visiting.insert((v1.as_object().index, v2.as_object().index)) {
+            return true
+        }
+
+        let (o1, o2) = (self.objects.get(v1.as_object()), self.objects.get(v2.as_object()));
+        match (&o1.data, &o2.data) {
+            (ObjectData::String(s1), ObjectData::String(s2)) => s1 == s2,
+
+            (ObjectData::List(l1), ObjectData::List(l2)) => {
+                l1.len() == l2.len()
+                    && l1.iter().zip(l2.iter()).all(|(x, y)| self.structural_eq_visiting(*x, *y, visiting))
+            },
+
+            (ObjectData::Struct(s1), ObjectData::Struct(s2)) => {
+                s1.fields().len() == s2.fields().len()
+                    && s1.fields().iter().zip(s2.fields().iter()).all(|(x, y)| self.structural_eq_visiting(*x, *y, visiting))
+            },
+
+            _ => false,
+        }
+    }
+
+
+    /// Hashes a value with 64-bit FNV-1a
+    ///
+    /// Primitives hash their little-endian bytes (floats by
+    /// their bit pattern with `-0.0` as `0.0`, characters by
+    /// their code point, booleans as a single byte, unit as
+    /// no bytes at all) and strings hash their utf-8 bytes.
+    /// The result is reproducible across runs and platforms
+    /// so it can be relied upon by user code
+    ///
+    /// Structures hash their type and the hashes of their
+    /// fields in order, which agrees with `structural_eq`
+    ///
+    /// Returns `None` for NaN, lists, maps & structures which
+    /// contain any of them
+    #[must_use]
+    pub fn hash(&self, value: VMData) -> Option<i64> {
+        let hash = match value.tag {
+            VMData::TAG_UNIT  => fnv1a(&[]),
+            VMData::TAG_I8    => fnv1a(&value.as_i8().to_le_bytes()),
+            VMData::TAG_I16   => fnv1a(&value.as_i16().to_le_bytes()),
+            VMData::TAG_I32   => fnv1a(&value.as_i32().to_le_bytes()),
+            VMData::TAG_I64   => fnv1a(&value.as_i64().to_le_bytes()),
+            VMData::TAG_U8    => fnv1a(&value.as_u8().to_le_bytes()),
+            VMData::TAG_U16   => fnv1a(&value.as_u16().to_le_bytes()),
+            VMData::TAG_U32   => fnv1a(&value.as_u32().to_le_bytes()),
+            VMData::TAG_U64   => fnv1a(&value.as_u64().to_le_bytes()),
+            VMData::TAG_FLOAT => {
+                // `-0.0 == 0.0` so both have to hash the same while
+                // NaN isn't equal to anything, not even itself
+                let float = value.as_float();
+                if float.is_nan() {
+                    return None
+                }
+
+                fnv1a(&(float + 0.0).to_bits().to_le_bytes())
+            },
+            VMData::TAG_BOOL  => fnv1a(&[u8::from(value.as_bool())]),
+            VMData::TAG_CHAR  => fnv1a(&u32::from(value.as_char()).to_le_bytes()),
+            VMData::TAG_STR   => fnv1a(self.objects.get(value.as_object()).string().as_bytes()),
+
+            _ if value.is_object() => {
+                let ObjectData::Struct(structure) = &self.objects.get(value.as_object()).data else { return None };
+
+                let mut bytes = value.tag.to_le_bytes().to_vec();
+                for field in structure.fields() {
+                    bytes.extend(self.hash(*field)?.to_le_bytes());
+                }
+
+                fnv1a(&bytes)
+            },
+
+            _ => return None,
+        };
+
+        Some(hash as i64)
+    }
+}
+
+
+/// 64-bit FNV-1a
+///
+/// See <http://www.isthe.com/chongo/tech/comp/fnv/>
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS : u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME : u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(PRIME))
 }
 
 
+/// The number of values the stack starts with
 const STACK_SIZE : usize = 16 * 1024 / size_of::<VMData>();
 
+/// The number of values the stack can grow up to
+/// before reporting a stack overflow
+const MAX_STACK_SIZE : usize = 64 * 1024 * 1024 / size_of::<VMData>();
+
 
 #[derive(Debug)]
 #[repr(C)]
 pub struct Stack {
-    values: [VMData; STACK_SIZE],
+    values: Vec<VMData>,
     stack_offset: usize,
     top: usize,
 }
@@ -109,7 +364,7 @@ pub struct Stack {
 impl Stack {
     fn new() -> Self {
         Self {
-            values: [VMData::new_unit(); STACK_SIZE],
+            values: vec![VMData::new_unit(); STACK_SIZE],
             stack_offset: 0,
             top: 1,
         }
@@ -156,9 +411,24 @@ impl Stack {
     fn push(&mut self, amount: usize) -> Status {
         self.top += amount;
         if self.top >= self.values.len() {
+            return self.grow()
+        }
+
+        Status::Ok
+    }
+
+
+    /// Reallocates the stack so it can hold `top`
+    /// values, up to `MAX_STACK_SIZE`
+    #[cold]
+    fn grow(&mut self) -> Status {
+        if self.top >= MAX_STACK_SIZE {
             return Status::Err(FatalError::new(String::from("stack overflow")))
         }
 
+        let len = (self.top + 1).next_power_of_two().min(MAX_STACK_SIZE);
+        self.values.resize(len, VMData::new_unit());
+
         Status::Ok
     }
 
@@ -240,6 +510,14 @@ impl<'a> Code<'a> {
     fn goto(&mut self, at: usize) {
         self.pointer = at;
     }
+
+
+    /// The bytecode offset of the instruction being executed.
+    /// The pointer is already past its opcode so this is the
+    /// last byte read, which is still within the instruction
+    pub(crate) fn position(&self) -> usize {
+        self.pointer.saturating_sub(1)
+    }
 }
 
 
@@ -272,6 +550,9 @@ impl VMData {
     pub const TAG_FLOAT: u64 = 9;
     pub const TAG_BOOL: u64 = 10;
     pub const TAG_STR: u64 = 11;
+    pub const TAG_LIST: u64 = 12;
+    pub const TAG_MAP: u64 = 13;
+    pub const TAG_CHAR: u64 = 14;
 
 
     pub fn new(tag: u64, data: RawVMData) -> Self {
@@ -304,6 +585,16 @@ impl VMData {
     }
 
 
+    pub fn new_list(val: ObjectIndex) -> Self {
+        Self::new(Self::TAG_LIST, RawVMData { as_object: val })
+    }
+
+
+    pub fn new_map(val: ObjectIndex) -> Self {
+        Self::new(Self::TAG_MAP, RawVMData { as_object: val })
+    }
+
+
     def_new_vmdata_func!(new_i8, as_i8, i8, TAG_I8);
     def_new_vmdata_func!(new_i16, as_i16, i16, TAG_I16);
     def_new_vmdata_func!(new_i32, as_i32, i32, TAG_I32);
@@ -314,6 +605,7 @@ impl VMData {
     def_new_vmdata_func!(new_u64, as_u64, u64, TAG_U64);
     def_new_vmdata_func!(new_float, as_float, f64, TAG_FLOAT);
     def_new_vmdata_func!(new_bool, as_bool, bool, TAG_BOOL);
+    def_new_vmdata_func!(new_char, as_char, char, TAG_CHAR);
 }
 
 
@@ -335,6 +627,10 @@ impl PartialEq for VMData {
             Self::TAG_FLOAT => self.as_float() == other.as_float(),
             Self::TAG_UNIT => true,
             Self::TAG_BOOL => self.as_bool() == other.as_bool(),
+            Self::TAG_CHAR => self.as_char() == other.as_char(),
+            Self::TAG_STR  => self.as_object() == other.as_object(),
+            Self::TAG_LIST => self.as_object() == other.as_object(),
+            Self::TAG_MAP  => self.as_object() == other.as_object(),
             _ if self.tag > 256 => self.as_object() == other.as_object(),
             _ => panic!("reserved"),
         }
@@ -358,6 +654,7 @@ impl Debug for VMData {
                 Self::TAG_U64 => "u64",
                 Self::TAG_FLOAT => "float",
                 Self::TAG_BOOL => "bool",
+                Self::TAG_CHAR => "char",
                 
                 _ if self.is_object() => "obj",
                 _ => "res"
@@ -374,6 +671,7 @@ impl Debug for VMData {
                 Self::TAG_U64 => self.as_u64().to_string(),
                 Self::TAG_FLOAT => self.as_float().to_string(),
                 Self::TAG_BOOL => self.as_bool().to_string(),
+                Self::TAG_CHAR => self.as_char().to_string(),
 
                 _ if self.is_object() => self.as_object().to_string(),
                 _ => "reserved".to_string(),
@@ -397,6 +695,7 @@ impl Display for VMData {
             Self::TAG_U64 => self.as_u64().to_string(),
             Self::TAG_FLOAT => self.as_float().to_string(),
             Self::TAG_BOOL => self.as_bool().to_string(),
+            Self::TAG_CHAR => self.as_char().to_string(),
             
             _ if self.is_object() => self.as_object().to_string(),
             _ => "reserved".to_string(),
@@ -420,6 +719,7 @@ pub union RawVMData {
     as_u64: u64,
     as_float: f64,
     as_bool: bool,
+    as_char: char,
     as_object: ObjectIndex,
 }
 
@@ -459,12 +759,13 @@ impl VMData {
 
     enum_variant_function!(as_float, is_float, TAG_FLOAT, f64);
     enum_variant_function!(as_bool, is_bool, TAG_BOOL, bool);
+    enum_variant_function!(as_char, is_char, TAG_CHAR, char);
 
 
     #[inline(always)]
     #[must_use]
     pub fn is_object(self) -> bool {
-        self.tag > 256 || self.tag == Self::TAG_STR
+        self.tag > 256 || self.tag == Self::TAG_STR || self.tag == Self::TAG_LIST || self.tag == Self::TAG_MAP
     }
 
     pub fn as_object(self) -> ObjectIndex {
@@ -551,26 +852,45 @@ impl FatalError {
 }
 
 
-fn run(metadata: CompilationMetadata, bytecode: &[u8], constants: Vec<u8>) {
-    let mut vm = VM {
+/// `debug` is the function starts & the line table
+fn create_vm<'a>(metadata: CompilationMetadata, bytecode: &'a [u8], structures: HashMap<u64, StructureLayout>, (debug, lines): (&[u8], &[u8])) -> VM<'a> {
+    let lines = bytes_to_line_table(lines);
+
+    VM {
         constants: Vec::new(),
         stack: Stack::new(),
         objects: ObjectMap::new((8 * 1000 * 1000) / size_of::<Object>()),
         
         callstack: Vec::with_capacity(128),
         current: Code::new(bytecode, 0, 0),
+        reentries: Vec::new(),
         libraries: Vec::with_capacity(metadata.library_count as usize),
         externs: Vec::with_capacity(metadata.extern_count as usize),
         
-        debug: Default::default(),
+        debug: VMDebugInfo {
+            functions: bytes_to_function_starts(debug),
+            is_debug_build: !lines.is_empty(),
+            lines,
+            ..Default::default()
+        },
+        structures,
+        start: Instant::now(),
+        instruction_budget: None,
         metadata,
-    };
+    }
+}
+
+
+fn run(metadata: CompilationMetadata, bytecode: &[u8], constants: Vec<u8>, structures: HashMap<u64, StructureLayout>, debug: (&[u8], &[u8])) {
+    let mut vm = create_vm(metadata, bytecode, structures, debug);
 
     if let Err(e) = bytes_to_constants(&mut vm, constants) {
         println!(
             "{}",
             format!("panicked at '{}'", e.read_message().to_string_lossy()).bright_red()
         );
+
+        return
     }
 
     let start = Instant::now();
@@ -596,11 +916,20 @@ fn run(metadata: CompilationMetadata, bytecode: &[u8], constants: Vec<u8>) {
     });
 
 
+    // Unset only writes a log when the runtime panics,
+    // `1` always writes one & `0` never does
+    let panic_log = env::var(azurite_common::environment::PANIC_LOG).ok();
+
     if v.is_err() {
         println!("a panic occurred in the runtime while running this program");
+
+        if panic_log.as_deref() == Some("0") {
+            return
+        }
+
         vm.clear_poison();
         let vm = vm.into_inner().unwrap();
-        let log = generate_panic_log(&vm, false);
+        let log = generate_panic_log(&vm, false, PanicLogLimits::from_env());
         let mut write_to_stdout = true;
         if let Ok(current_dir) = env::current_dir() {
             let path = current_dir.join("panic_log.txt");
@@ -623,12 +952,21 @@ fn run(metadata: CompilationMetadata, bytecode: &[u8], constants: Vec<u8>) {
     
     let vm = vm.into_inner().unwrap();
 
+    if let Ok(Status::Err(e)) = v {
+        println!(
+            "{}",
+            format!("panicked at '{}'", e.read_message().to_string_lossy()).bright_red()
+        );
+
+        print!("{}", vm.format_stack_trace());
+    }
+
     let end = start.elapsed();
     println!("it took {}ms {}ns, result {}", end.as_millis(), end.as_nanos(), vm.stack.reg(0));
 
 
-    if env::var(azurite_common::environment::PANIC_LOG).unwrap_or("0".to_string()) == "1" {
-        let log = generate_panic_log(&vm, true);
+    if panic_log.as_deref() == Some("1") {
+        let log = generate_panic_log(&vm, true, PanicLogLimits::from_env());
         let mut write_to_stdout = true;
         if let Ok(current_dir) = env::current_dir() {
             let path = current_dir.join("panic_log.txt");
@@ -652,40 +990,130 @@ fn run(metadata: CompilationMetadata, bytecode: &[u8], constants: Vec<u8>) {
 }
 
 
+/// Reads the structure layouts written by
+/// `azurite_compiler::convert_structures_to_bytes`,
+/// `None` if the section is malformed
+fn bytes_to_structures(data: &[u8]) -> Option<HashMap<u64, StructureLayout>> {
+    fn read_name(iter: &mut std::slice::Iter<u8>) -> Option<String> {
+        let length = u64::from_le_bytes(iter.next_chunk::<8>().ok()?.map(|x| *x));
+        let length = usize::try_from(length).ok().filter(|x| *x <= iter.len())?;
+        let bytes = iter.take(length).copied().collect();
+
+        String::from_utf8(bytes).ok()
+    }
+
+    let mut structures = HashMap::new();
+    let mut iter = data.iter();
+
+    while !iter.as_slice().is_empty() {
+        let id = u64::from_le_bytes(iter.next_chunk::<8>().ok()?.map(|x| *x));
+        let name = read_name(&mut iter)?;
+
+        let field_count = *iter.next()?;
+        let fields = (0..field_count).map(|_| read_name(&mut iter)).collect::<Option<_>>()?;
+
+        structures.insert(id, StructureLayout { name, fields });
+    }
+
+    Some(structures)
+}
+
+
+/// Reads the debug section written by
+/// `azurite_compiler::convert_function_starts_to_bytes`
+fn bytes_to_function_starts(data: &[u8]) -> Vec<(usize, String)> {
+    let mut functions = vec![];
+    let mut iter = data.iter();
+
+    while let Ok(start) = iter.next_chunk::<4>() {
+        let start = u32::from_le_bytes(start.map(|x| *x));
+
+        let Ok(length) = iter.next_chunk::<8>() else { break };
+        let length = u64::from_le_bytes(length.map(|x| *x));
+        let name : Vec<u8> = iter.by_ref().take(length as usize).copied().collect();
+
+        functions.push((start as usize, String::from_utf8_lossy(&name).into_owned()));
+    }
+
+    functions.sort_by_key(|x| x.0);
+    functions
+}
+
+
+/// Reads the line table written by
+/// `azurite_compiler::convert_line_table_to_bytes`
+fn bytes_to_line_table(data: &[u8]) -> Vec<(usize, String, u32)> {
+    let mut lines = vec![];
+    let mut iter = data.iter();
+
+    while let Ok(start) = iter.next_chunk::<4>() {
+        let start = u32::from_le_bytes(start.map(|x| *x));
+
+        let Ok(line) = iter.next_chunk::<4>() else { break };
+        let line = u32::from_le_bytes(line.map(|x| *x));
+
+        let Ok(length) = iter.next_chunk::<8>() else { break };
+        let length = u64::from_le_bytes(length.map(|x| *x));
+        let file : Vec<u8> = iter.by_ref().take(length as usize).copied().collect();
+
+        lines.push((start as usize, String::from_utf8_lossy(&file).into_owned(), line));
+    }
+
+    lines.sort_by_key(|x| x.0);
+    lines
+}
+
+
+/// Reads the constants written by the compiler
+///
+/// # Errors
+/// - If the data is truncated or holds a value which
+///   isn't valid for its type, like an invalid `char`
 fn bytes_to_constants(vm: &mut VM, data: Vec<u8>) -> Result<(), FatalError> {
     let mut constants_iter = data.into_iter();
+    let corrupt = |what: &str| FatalError::new(format!("the constants are corrupt, {what}"));
+
+    macro_rules! chunk {
+        ($n: literal) => {
+            constants_iter.next_chunk::<$n>().map_err(|_| corrupt("a value is truncated"))?
+        }
+    }
 
     while let Some(datatype) = constants_iter.next() {
         let constant = match datatype {
-            0 => VMData::new_float(f64::from_le_bytes(constants_iter.next_chunk::<8>().unwrap())),
+            0 => VMData::new_float(f64::from_le_bytes(chunk!(8))),
 
-            1 => VMData::new_bool(constants_iter.next().unwrap() == 1),
+            1 => VMData::new_bool(chunk!(1)[0] == 1),
 
             2 => {
-                let length = u64::from_le_bytes(constants_iter.next_chunk::<8>().unwrap());
+                let length = u64::from_le_bytes(chunk!(8));
 
-                let mut vec = Vec::with_capacity(length as usize);
-                for _ in 0..length {
-                    vec.push(constants_iter.next().unwrap());
+                let vec : Vec<u8> = constants_iter.by_ref().take(length as usize).collect();
+                if vec.len() as u64 != length {
+                    return Err(corrupt("a string is truncated"))
                 }
 
-                let object = String::from_utf8(vec).unwrap();
+                let object = String::from_utf8(vec).map_err(|_| corrupt("a string isn't valid utf-8"))?;
                 
                 let index = vm.create_object(Object::new(object))?;
 
-                VMData::new_object(11, index)
+                VMData::new_string(index)
             }
 
-            3  => VMData::new_i8 (i8 ::from_le_bytes(constants_iter.next_chunk::<1>().unwrap())),
-            4  => VMData::new_i16(i16::from_le_bytes(constants_iter.next_chunk::<2>().unwrap())),
-            5  => VMData::new_i32(i32::from_le_bytes(constants_iter.next_chunk::<4>().unwrap())),
-            6  => VMData::new_i64(i64::from_le_bytes(constants_iter.next_chunk::<8>().unwrap())),
-            7  => VMData::new_u8 (u8 ::from_le_bytes(constants_iter.next_chunk::<1>().unwrap())),
-            8  => VMData::new_u16(u16::from_le_bytes(constants_iter.next_chunk::<2>().unwrap())),
-            9  => VMData::new_u32(u32::from_le_bytes(constants_iter.next_chunk::<4>().unwrap())),
-            10 => VMData::new_u64(u64::from_le_bytes(constants_iter.next_chunk::<8>().unwrap())),
+            3  => VMData::new_i8 (i8 ::from_le_bytes(chunk!(1))),
+            4  => VMData::new_i16(i16::from_le_bytes(chunk!(2))),
+            5  => VMData::new_i32(i32::from_le_bytes(chunk!(4))),
+            6  => VMData::new_i64(i64::from_le_bytes(chunk!(8))),
+            7  => VMData::new_u8 (u8 ::from_le_bytes(chunk!(1))),
+            8  => VMData::new_u16(u16::from_le_bytes(chunk!(2))),
+            9  => VMData::new_u32(u32::from_le_bytes(chunk!(4))),
+            10 => VMData::new_u64(u64::from_le_bytes(chunk!(8))),
+            11 => {
+                let value = u32::from_le_bytes(chunk!(4));
+                VMData::new_char(char::from_u32(value).ok_or_else(|| corrupt(&format!("{value:#x} isn't a valid char")))?)
+            },
 
-            _ => unreachable!()
+            _ => return Err(corrupt(&format!("{datatype} isn't a constant type"))),
         };
 
         vm.constants.push(constant);
@@ -698,6 +1126,19 @@ struct VMDebugInfo {
     last_gc_time: SystemTime,
     last_gc_duration: Duration,
     total_gc_count: u64,
+
+    /// The bytecode offset each function starts
+    /// at with its name, sorted by the offset
+    functions: Vec<(usize, String)>,
+
+    /// The bytecode offset each source line starts at with
+    /// its file & line, sorted by the offset. Only debug
+    /// builds have one
+    lines: Vec<(usize, String, u32)>,
+
+    /// Whether the program was compiled with `--debug`,
+    /// which enables extra runtime checks
+    is_debug_build: bool,
 }
 
 
@@ -707,12 +1148,152 @@ impl Default for VMDebugInfo {
             last_gc_time: SystemTime::now(),
             last_gc_duration: Duration::ZERO,
             total_gc_count: 0,
+            functions: Vec::new(),
+            lines: Vec::new(),
+            is_debug_build: false,
+        }
+    }
+}
+
+
+impl<'a> VM<'a> {
+    /// The name of the function the bytecode
+    /// offset is in, if there's debug info for it
+    fn function_at(&self, offset: usize) -> Option<&str> {
+        let functions = &self.debug.functions;
+        let index = functions.partition_point(|x| x.0 <= offset).checked_sub(1)?;
+
+        Some(&functions[index].1)
+    }
+
+
+    /// The file & line the bytecode offset is on, like
+    /// `main.az:42`, if the build has a line table. A line
+    /// from before the start of the offset's function
+    /// belongs to another function so it's not used
+    fn location_at(&self, offset: usize) -> Option<String> {
+        let lines = &self.debug.lines;
+        let index = lines.partition_point(|x| x.0 <= offset).checked_sub(1)?;
+        let (start, file, line) = &lines[index];
+
+        let functions = &self.debug.functions;
+        let function = functions.partition_point(|x| x.0 <= offset).checked_sub(1);
+        if function.is_some_and(|x| functions[x].0 > *start) {
+            return None
+        }
+
+        Some(format!("{file}:{line}"))
+    }
+
+
+    /// The source location of the instruction being
+    /// executed, if the build has a line table
+    pub(crate) fn location(&self) -> Option<String> {
+        self.location_at(self.current.position())
+    }
+
+
+    /// The frames on the callstack, innermost first
+    fn frames(&self) -> impl Iterator<Item = &Code<'a>> {
+        std::iter::once(&self.current).chain(self.callstack.iter().rev())
+    }
+
+
+    /// The functions on the callstack, innermost first. The
+    /// pointer of a frame is past the instruction it's at so
+    /// its position is used to stay in the same function
+    pub(crate) fn stack_trace(&self) -> Vec<&str> {
+        self.frames()
+            .map(|x| self.function_at(x.position()).unwrap_or("<unknown>"))
+            .collect()
+    }
+
+
+    /// Formats `stack_trace` with the location of each frame
+    /// if there's a line table. Consecutive frames at the same
+    /// place, like the ones of a recursion which overflowed
+    /// the stack, are collapsed into one line
+    pub(crate) fn format_stack_trace(&self) -> String {
+        let mut string = String::new();
+
+        if self.debug.functions.is_empty() {
+            return string
+        }
+
+        let _ = writeln!(string, "stack trace:");
+
+        let trace : Vec<_> = self.stack_trace()
+            .into_iter()
+            .zip(self.frames().map(|x| self.location_at(x.position())))
+            .collect();
+
+        let mut index = 0;
+        while index < trace.len() {
+            let (name, location) = &trace[index];
+            let repeats = trace[index..].iter().take_while(|x| **x == trace[index]).count();
+
+            let _ = write!(string, "\t{index}: {name}");
+
+            if let Some(location) = location {
+                let _ = write!(string, " at {location}");
+            }
+
+            if repeats != 1 {
+                let _ = write!(string, " ({repeats} frames)");
+            }
+
+            let _ = writeln!(string);
+
+            index += repeats;
+        }
+
+        string
+    }
+}
+
+
+/// How much of the VM state a panic log includes
+///
+/// Each limit can be set through its environment variable,
+/// anything past a limit is left out with a note saying
+/// how much was omitted
+#[derive(Debug, Clone, Copy)]
+struct PanicLogLimits {
+    max_objects: usize,
+    max_stack_values: usize,
+    max_bytecode: usize,
+}
+
+
+impl PanicLogLimits {
+    fn from_env() -> Self {
+        let default = Self::default();
+
+        let limit = |name: &str, default: usize| {
+            env::var(name).ok().and_then(|x| x.parse().ok()).unwrap_or(default)
+        };
+
+        Self {
+            max_objects: limit(azurite_common::environment::PANIC_LOG_MAX_OBJECTS, default.max_objects),
+            max_stack_values: limit(azurite_common::environment::PANIC_LOG_MAX_STACK, default.max_stack_values),
+            max_bytecode: limit(azurite_common::environment::PANIC_LOG_MAX_BYTECODE, default.max_bytecode),
+        }
+    }
+}
+
+
+impl Default for PanicLogLimits {
+    fn default() -> Self {
+        Self {
+            max_objects: 1024,
+            max_stack_values: 1024,
+            max_bytecode: 4096,
         }
     }
 }
 
 
-fn generate_panic_log(vm: &VM, forced: bool) -> String {
+fn generate_panic_log(vm: &VM, forced: bool, limits: PanicLogLimits) -> String {
     let mut string = String::new();
 
     if !forced {
@@ -726,6 +1307,11 @@ fn generate_panic_log(vm: &VM, forced: bool) -> String {
         let _ = writeln!(string, "line: {}", panic_info.0.1);
         let _ = writeln!(string, "column: {}", panic_info.0.2);
         let _ = writeln!(string, "message: {}", &panic_info.1);
+
+        if let Some(location) = vm.location() {
+            let _ = writeln!(string, "source: {location}");
+        }
+
         let _ = writeln!(string);
     }
     
@@ -768,6 +1354,8 @@ fn generate_panic_log(vm: &VM, forced: bool) -> String {
 
     let _ = writeln!(string, "\tobjects:");
     let _ = writeln!(string, "\t\t-- default objects are excluded --");
+    let mut listed_objects = 0;
+    let mut omitted_objects = 0;
     for object in vm.objects.raw().iter().enumerate() {
         if let ObjectData::Free { next } = object.1.data {
             if next == ObjectIndex::new((object.0 as u64 + 1) % vm.objects.raw().len() as u64) {
@@ -775,9 +1363,19 @@ fn generate_panic_log(vm: &VM, forced: bool) -> String {
             }
         }
 
+        if listed_objects == limits.max_objects {
+            omitted_objects += 1;
+            continue
+        }
+
+        listed_objects += 1;
         let _ = writeln!(string, "\t\t{} - live: {} data: {:?}", object.0, object.1.liveliness_status.take(), object.1.data);
     }
 
+    if omitted_objects != 0 {
+        let _ = writeln!(string, "\t\t-- {omitted_objects} more objects omitted, see {} --", azurite_common::environment::PANIC_LOG_MAX_OBJECTS);
+    }
+
     let _ = writeln!(string);
 
     
@@ -787,19 +1385,24 @@ fn generate_panic_log(vm: &VM, forced: bool) -> String {
     let _ = writeln!(string, "\ttop: {}", vm.stack.top);
 
     let _ = writeln!(string, "\tvalues:");
-    for stack_val in vm.stack.values.iter().take(vm.stack.top).enumerate().rev() {
+    for stack_val in vm.stack.values.iter().take(vm.stack.top).enumerate().rev().take(limits.max_stack_values) {
         let _ = writeln!(string, "\t\t{:>w$} - {:?}", stack_val.0, stack_val.1, w = vm.stack.top);
     }
 
+    if vm.stack.top > limits.max_stack_values {
+        let _ = writeln!(string, "\t\t-- {} more values omitted, see {} --", vm.stack.top - limits.max_stack_values, azurite_common::environment::PANIC_LOG_MAX_STACK);
+    }
+
     let _ = writeln!(string);
 
     let _ = writeln!(string, "callstack:");
-    let _ = writeln!(string, "\tcurrent - ip: {} ret: {} saved stack offset: {}", vm.current.pointer, vm.current.return_to, vm.current.offset);
+    let at = |code: &Code| vm.location_at(code.position()).map_or(String::new(), |x| format!(" at {x}"));
+    let _ = writeln!(string, "\tcurrent - ip: {} ret: {} saved stack offset: {}{}", vm.current.pointer, vm.current.return_to, vm.current.offset, at(&vm.current));
 
     {
         let w = vm.callstack.len().to_string().len();
         for (index, c) in vm.callstack.iter().enumerate().rev() {
-            let _ = writeln!(string, "\t{index:>w$} - ip: {} ret: {} saved stack offset: {}", c.pointer, c.return_to, c.offset);
+            let _ = writeln!(string, "\t{index:>w$} - ip: {} ret: {} saved stack offset: {}{}", c.pointer, c.return_to, c.offset, at(c));
         }
     }
 
@@ -819,7 +1422,23 @@ fn generate_panic_log(vm: &VM, forced: bool) -> String {
     let _ = writeln!(string);
 
     let _ = writeln!(string, "bytecode:");
-    let _ = writeln!(string, "{:?}", vm.current.code.to_vec());
+    {
+        // Only the bytes around the instruction pointer
+        // are kept when the bytecode is over the limit
+        let code = vm.current.code;
+        let max = limits.max_bytecode.min(code.len());
+        let start = vm.current.pointer.saturating_sub(max / 2).min(code.len() - max);
+
+        if max != code.len() {
+            let _ = writeln!(
+                string,
+                "\t-- {} bytes omitted, showing {start}..{}, see {} --",
+                code.len() - max, start + max, azurite_common::environment::PANIC_LOG_MAX_BYTECODE,
+            );
+        }
+
+        let _ = writeln!(string, "{:?}", &code[start..start + max]);
+    }
    
 
     string
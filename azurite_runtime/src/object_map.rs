@@ -1,6 +1,6 @@
 use std::{cell::Cell, fmt::Display};
 
-use crate::VMData;
+use crate::{VMData, FatalError, hash_map::Map};
 
 pub(crate) use self::lock::ObjectData;
 
@@ -39,7 +39,7 @@ impl Display for ObjectIndex {
 
 
 pub(crate) mod lock {
-    use super::{Structure, ObjectIndex};
+    use super::{Structure, ObjectIndex, VMData, Map};
 
     /// Runtime union of objects
     // TODO: Convert to an arena allocator maybe?
@@ -48,6 +48,8 @@ pub(crate) mod lock {
     pub enum ObjectData {
         Struct(Structure),
         String(String),
+        List(Vec<VMData>),
+        Map(Map),
 
         /// Internal value to keep track
         /// of the free objects.
@@ -67,6 +69,20 @@ pub(crate) mod lock {
             ObjectData::String(val)
         }
     }
+
+
+    impl From<Vec<VMData>> for ObjectData {
+        fn from(val: Vec<VMData>) -> Self {
+            ObjectData::List(val)
+        }
+    }
+
+
+    impl From<Map> for ObjectData {
+        fn from(val: Map) -> Self {
+            ObjectData::Map(val)
+        }
+    }
 }
 
 
@@ -127,6 +143,62 @@ impl Object {
             _ => unreachable!()
         }
     }
+
+
+    /// Returns a reference to a list
+    /// 
+    /// # Errors
+    /// - If the union type is not a list, a program
+    ///   can get here by building a structure of the
+    ///   same type as the list, like `List {}`
+    #[inline]
+    pub fn list(&self) -> Result<&Vec<VMData>, FatalError> {
+        match &self.data {
+            ObjectData::List(v) => Ok(v),
+            _ => Err(FatalError::new(String::from("the value isn't a list"))),
+        }
+    }
+
+    
+    /// Returns a mutable reference to a list
+    /// 
+    /// # Errors
+    /// - If the union type is not a list
+    #[inline]
+    pub fn list_mut(&mut self) -> Result<&mut Vec<VMData>, FatalError> {
+        match &mut self.data {
+            ObjectData::List(v) => Ok(v),
+            _ => Err(FatalError::new(String::from("the value isn't a list"))),
+        }
+    }
+
+
+    /// Returns a reference to a map
+    /// 
+    /// # Errors
+    /// - If the union type is not a map, a program
+    ///   can get here by building a structure of the
+    ///   same type as the map, like `HashMap {}`
+    #[inline]
+    pub fn map(&self) -> Result<&Map, FatalError> {
+        match &self.data {
+            ObjectData::Map(v) => Ok(v),
+            _ => Err(FatalError::new(String::from("the value isn't a map"))),
+        }
+    }
+
+    
+    /// Returns a mutable reference to a map
+    /// 
+    /// # Errors
+    /// - If the union type is not a map
+    #[inline]
+    pub fn map_mut(&mut self) -> Result<&mut Map, FatalError> {
+        match &mut self.data {
+            ObjectData::Map(v) => Ok(v),
+            _ => Err(FatalError::new(String::from("the value isn't a map"))),
+        }
+    }
 }
 
 impl ObjectMap {
@@ -1,14 +1,156 @@
 pub use azurite_common::{consts, Bytecode};
-use colored::Colorize;
 use libloading::Library;
 
-use crate::{object_map::{Object, Structure}, Code, FatalError, Status, VMData, VM, ExternFunction};
-use std::ops::{Add, Mul, Sub};
+use crate::{object_map::{Object, ObjectIndex, Structure}, Code, FatalError, Status, VMData, VM, ExternFunction};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Mul, Sub};
 
 impl VM<'_> {
+    pub(crate) fn run(&mut self) -> Status {
+        let result = match self.execute() {
+            // Point at the source line in debug builds
+            Status::Err(e) => match self.location() {
+                Some(location) => Status::Err(FatalError::new(format!("{} at {location}", e.read_message().to_string_lossy()))),
+                None => Status::Err(e),
+            },
+
+            v => v,
+        };
+
+        self.externs.clear();
+        let libraries = std::mem::take(&mut self.libraries);
+        for library in libraries {
+            unsafe {
+                let shutdown: ExternFunction = match library.get(b"_shutdown") {
+                    Ok(v) => v,
+                    Err(_) => continue,
+                };
+
+                shutdown(self);
+            }
+        }
+
+
+        result
+    }
+
+
+    /// Calls the function at the bytecode offset `goto` with
+    /// `args` and runs it until it returns. This re-enters the
+    /// interpreter so externs can call back into Azurite code
+    ///
+    /// Objects which are only held by the extern aren't on the
+    /// stack, so they may be collected while the function runs
+    ///
+    /// # Errors
+    /// - The status to return from the extern if the function
+    ///   panics or exits
+    pub fn call_function(&mut self, goto: u32, args: &[VMData]) -> Result<VMData, Status> {
+        let arg_count = args.len();
+
+        if let Status::Err(e) = self.stack.push(arg_count + 1) {
+            return Err(Status::Err(e));
+        }
+
+        let temp = self.stack.top - arg_count - self.stack.stack_offset;
+        for (v, arg) in args.iter().enumerate() {
+            self.stack.set_reg(convert_usize_to_u8(temp + v), *arg);
+        }
+
+        // The return value is written to the
+        // extern's own return register
+        let mut code = Code::new(self.current.code, self.stack.top - arg_count - 1, 0);
+        code.goto(goto as usize);
+
+        self.reentries.push(self.callstack.len());
+        self.callstack.push(std::mem::replace(&mut self.current, code));
+
+        self.stack.set_stack_offset(self.current.offset);
+
+        let result = self.execute();
+        self.reentries.pop();
+
+        match result {
+            Status::Ok => Ok(self.stack.reg(0)),
+            _ => Err(result),
+        }
+    }
+
+
+    /// Calls the function at the bytecode offset `function` with
+    /// every element of the list at `list` and returns a new list
+    /// of the results
+    ///
+    /// The new list is kept in the return register of the extern
+    /// while the function runs so the results aren't collected
+    ///
+    /// # Errors
+    /// - If the value at `list` isn't a list
+    /// - If `function` is outside of the bytecode
+    /// - The status to return from the extern if the function
+    ///   panics or exits
+    pub fn list_map(&mut self, list: ObjectIndex, function: u32) -> Result<ObjectIndex, Status> {
+        self.list_with_callback(list, function, |_, _, result| Ok(Some(result)))
+    }
+
+
+    /// Calls the function at the bytecode offset `function` with
+    /// every element of the list at `list` and returns a new list
+    /// of the elements it returned `true` for
+    ///
+    /// # Errors
+    /// - If the value at `list` isn't a list
+    /// - If `function` is outside of the bytecode
+    /// - If the function returns something other than a `bool`
+    /// - The status to return from the extern if the function
+    ///   panics or exits
+    pub fn list_filter(&mut self, list: ObjectIndex, function: u32) -> Result<ObjectIndex, Status> {
+        self.list_with_callback(list, function, |index, element, result| {
+            if result.tag() != VMData::TAG_BOOL {
+                return Err(FatalError::new(format!("the predicate returned a non-bool value for the element at index {index}")))
+            }
+
+            Ok(result.as_bool().then_some(element))
+        })
+    }
+
+
+    /// Calls `function` with every element of `list`, pushing
+    /// whatever `keep` returns into a new list
+    ///
+    /// The elements are read one at a time so the function
+    /// can change the list while it's being iterated
+    fn list_with_callback(
+        &mut self,
+        list: ObjectIndex,
+        function: u32,
+        keep: impl Fn(usize, VMData, VMData) -> Result<Option<VMData>, FatalError>,
+    ) -> Result<ObjectIndex, Status> {
+        if function as usize >= self.current.code.len() {
+            return Err(Status::err(format!("there's no function at offset {function}")))
+        }
+
+        let result = self.create_object(Object::new(Vec::<VMData>::new())).map_err(Status::Err)?;
+
+        let mut index = 0;
+        while let Some(element) = self.objects.get(list).list().map_err(Status::Err)?.get(index).copied() {
+            self.stack.set_reg(0, VMData::new_list(result));
+            let value = self.call_function(function, &[element])?;
+
+            if let Some(value) = keep(index, element, value).map_err(Status::Err)? {
+                self.objects.get_mut(result).list_mut().map_err(Status::Err)?.push(value);
+            }
+
+            index += 1;
+        }
+
+        self.stack.set_reg(0, VMData::new_list(result));
+        Ok(result)
+    }
+
+
     #[allow(clippy::too_many_lines)]
     #[inline(never)]
-    pub(crate) fn run(&mut self) -> Status {
+    fn execute(&mut self) -> Status {
         macro_rules! all_integer_types {
             ($f: ident) => {
                 (
@@ -25,32 +167,54 @@ impl VM<'_> {
         }
 
         
+        // Casts between integers reinterpret the bits. In programs
+        // compiled with `--debug` a cast which flips the sign of
+        // the value, like `-1 as u64`, is an error instead
         macro_rules! cast_to {
             ($t: ty, $variant: ident) => { {
                 let dst = self.current.next();
                 let val = self.current.next();
 
                 let reg = self.stack.reg(val);
-                let v = match reg.tag {
-                    VMData::TAG_I8    => reg.as_i8() as $t,
-                    VMData::TAG_I16   => reg.as_i16() as $t,
-                    VMData::TAG_I32   => reg.as_i32() as $t,
-                    VMData::TAG_I64   => reg.as_i64() as $t,
-                    VMData::TAG_U8    => reg.as_u8() as $t,
-                    VMData::TAG_U16   => reg.as_u16() as $t,
-                    VMData::TAG_U32   => reg.as_u32() as $t,
-                    VMData::TAG_U64   => reg.as_u64() as $t,
-                    VMData::TAG_FLOAT => reg.as_float() as $t,
+                let (v, integer) = match reg.tag {
+                    VMData::TAG_I8    => (reg.as_i8()  as $t, Some(reg.as_i8()  as i128)),
+                    VMData::TAG_I16   => (reg.as_i16() as $t, Some(reg.as_i16() as i128)),
+                    VMData::TAG_I32   => (reg.as_i32() as $t, Some(reg.as_i32() as i128)),
+                    VMData::TAG_I64   => (reg.as_i64() as $t, Some(reg.as_i64() as i128)),
+                    VMData::TAG_U8    => (reg.as_u8()  as $t, Some(reg.as_u8()  as i128)),
+                    VMData::TAG_U16   => (reg.as_u16() as $t, Some(reg.as_u16() as i128)),
+                    VMData::TAG_U32   => (reg.as_u32() as $t, Some(reg.as_u32() as i128)),
+                    VMData::TAG_U64   => (reg.as_u64() as $t, Some(reg.as_u64() as i128)),
+                    VMData::TAG_FLOAT => (reg.as_float() as $t, None),
 
                     _ => unreachable!(),
                 };
 
+                if self.debug.is_debug_build {
+                    if let Some(integer) = integer {
+                        if (integer < 0) != ((v as i128) < 0) {
+                            break Status::Err(FatalError::new(format!(
+                                "casting {integer} to {} changes its sign, the result would be {v}",
+                                stringify!($t),
+                            )));
+                        }
+                    }
+                }
+
                 self.stack.set_reg(dst, VMData::$variant(v));
             } }
         }
 
 
         let result: Status = 'global: loop {
+            if let Some(budget) = &mut self.instruction_budget {
+                if *budget == 0 {
+                    break Status::err("instruction budget exceeded")
+                }
+
+                *budget -= 1;
+            }
+
             let value = self.current.next();
             // println!("{:?}", Bytecode::from_u8(value).unwrap());
 
@@ -163,6 +327,14 @@ impl VM<'_> {
                 ),
 
                 
+                consts::BitAnd => self.integer_operation(all_integer_types!(bitand)),
+                consts::BitOr  => self.integer_operation(all_integer_types!(bitor)),
+                consts::BitXor => self.integer_operation(all_integer_types!(bitxor)),
+
+                consts::ShiftLeft  => self.shift_operation(all_integer_types!(wrapping_shl)),
+                consts::ShiftRight => self.shift_operation(all_integer_types!(wrapping_shr)),
+
+                
                 consts::Divide => {
                     macro_rules! integer_division {
                         ($v: ident, $v1: expr, $v2: expr) => {
@@ -210,7 +382,7 @@ impl VM<'_> {
                 consts::Equals => {
                     let vals = self.current.next_n::<3>();
 
-                    let value = self.stack.reg(vals[1]) == self.stack.reg(vals[2]);
+                    let value = self.structural_eq(self.stack.reg(vals[1]), self.stack.reg(vals[2]));
                     self.stack.set_reg(vals[0], VMData::new_bool(value));
                 }
 
@@ -218,7 +390,7 @@ impl VM<'_> {
                 consts::NotEquals => {
                     let vals = self.current.next_n::<3>();
 
-                    let value = self.stack.reg(vals[1]) != self.stack.reg(vals[2]);
+                    let value = !self.structural_eq(self.stack.reg(vals[1]), self.stack.reg(vals[2]));
                     self.stack.set_reg(vals[0], VMData::new_bool(value));
                 }
 
@@ -265,6 +437,11 @@ impl VM<'_> {
 
                     self.stack.set_reg(ret_reg, ret_val);
                     self.stack.pop(1);
+
+                    // Returning from a function called by an extern
+                    if self.reentries.last() == Some(&self.callstack.len()) {
+                        break Status::Ok;
+                    }
                 }
 
 
@@ -292,6 +469,36 @@ impl VM<'_> {
                 }
 
 
+                consts::TailCall => {
+                    let goto = self.current.u32();
+                    let arg_count = self.current.next() as usize;
+
+                    // The arguments are moved above the top of the
+                    // stack first as they can be any of the registers
+                    // they're about to be copied into
+                    let top = self.stack.top;
+                    if let Status::Err(e) = self.stack.push(arg_count) {
+                        break Status::Err(e);
+                    }
+
+                    let temp = top - self.stack.stack_offset;
+                    for v in 0..arg_count {
+                        let reg = self.stack.reg(self.current.next());
+                        self.stack.set_reg(convert_usize_to_u8(temp + v), reg);
+                    }
+
+                    for v in 0..arg_count {
+                        let reg = self.stack.reg(convert_usize_to_u8(temp + v));
+                        self.stack.set_reg(convert_usize_to_u8(v + 1), reg);
+                    }
+
+                    // Leaves the return register & the arguments,
+                    // the callee pushes its own locals
+                    self.stack.pop(temp - 1);
+                    self.current.goto(goto as usize);
+                }
+
+
                 consts::ExtCall => {
                     let index = self.current.u32();
                     let dst = self.current.next();
@@ -366,6 +573,75 @@ impl VM<'_> {
                 }
 
 
+                consts::MakeArray => {
+                    let dst = self.current.next();
+                    let amount = self.current.next();
+
+                    let vec = (0..amount)
+                        .map(|_| self.stack.reg(self.current.next()))
+                        .collect::<Vec<_>>();
+
+                    let index = match self.create_object(Object::new(vec)) {
+                        Ok(v) => v,
+                        Err(e) => break Status::Err(e),
+                    };
+                    
+                    self.stack.set_reg(dst, VMData::new_list(index));
+                }
+
+
+                consts::IndexGet => {
+                    let dst = self.current.next();
+                    let val = self.stack.reg(self.current.next());
+                    let index = self.stack.reg(self.current.next()).as_i64();
+
+                    let object = self.objects.get(val.as_object());
+
+                    let element = if val.tag == VMData::TAG_STR {
+                        let string = object.string();
+                        let character = usize::try_from(index).ok().and_then(|x| string.chars().nth(x));
+
+                        let Some(character) = character else {
+                            break Status::err(format!("index {index} is out of bounds for a string of {} characters", string.chars().count()))
+                        };
+
+                        VMData::new_char(character)
+                    } else {
+                        let list = match object.list() {
+                            Ok(v) => v,
+                            Err(e) => break Status::Err(e),
+                        };
+
+                        let Some(element) = usize::try_from(index).ok().and_then(|x| list.get(x)) else {
+                            break Status::err(format!("index {index} is out of bounds for an array of length {}", list.len()))
+                        };
+
+                        *element
+                    };
+
+                    self.stack.set_reg(dst, element);
+                }
+
+
+                consts::IndexSet => {
+                    let dst = self.stack.reg(self.current.next());
+                    let data = self.stack.reg(self.current.next());
+                    let index = self.stack.reg(self.current.next()).as_i64();
+
+                    let list = match self.objects.get_mut(dst.as_object()).list_mut() {
+                        Ok(v) => v,
+                        Err(e) => break Status::Err(e),
+                    };
+                    let length = list.len();
+
+                    let Some(element) = usize::try_from(index).ok().and_then(|x| list.get_mut(x)) else {
+                        break Status::err(format!("index {index} is out of bounds for an array of length {length}"))
+                    };
+
+                    *element = data;
+                }
+
+
                 consts::AccStruct => {
                     let dst = self.current.next();
                     let struct_at = self.current.next();
@@ -412,6 +688,26 @@ impl VM<'_> {
                 }
 
 
+                consts::UnaryBitNot => {
+                    let dst = self.current.next();
+                    let val = self.current.next();
+
+                    let reg = self.stack.reg(val);
+                    match reg.tag {
+                        VMData::TAG_I8  => self.stack.set_reg(dst, VMData::new_i8(!reg.as_i8())),
+                        VMData::TAG_I16 => self.stack.set_reg(dst, VMData::new_i16(!reg.as_i16())),
+                        VMData::TAG_I32 => self.stack.set_reg(dst, VMData::new_i32(!reg.as_i32())),
+                        VMData::TAG_I64 => self.stack.set_reg(dst, VMData::new_i64(!reg.as_i64())),
+                        VMData::TAG_U8  => self.stack.set_reg(dst, VMData::new_u8(!reg.as_u8())),
+                        VMData::TAG_U16 => self.stack.set_reg(dst, VMData::new_u16(!reg.as_u16())),
+                        VMData::TAG_U32 => self.stack.set_reg(dst, VMData::new_u32(!reg.as_u32())),
+                        VMData::TAG_U64 => self.stack.set_reg(dst, VMData::new_u64(!reg.as_u64())),
+
+                        _ => unreachable!(),
+                    }
+                }
+
+
                 consts::UnaryNot => {
                     let dst = self.current.next();
                     let val = self.current.next();
@@ -435,29 +731,6 @@ impl VM<'_> {
             };
         };
 
-
-        self.externs.clear();
-        let libraries = std::mem::take(&mut self.libraries);
-        for library in libraries {
-            unsafe {
-                let shutdown: ExternFunction = match library.get(b"_shutdown") {
-                    Ok(v) => v,
-                    Err(_) => continue,
-                };
-
-                shutdown(self);
-            }
-        }
-
-        
-        if let Status::Err(e) = &result {
-            println!(
-                "{}",
-                format!("panicked at '{}'", e.read_message().to_string_lossy()).bright_red()
-            );
-        }
-
-
         result
     }
 }
@@ -536,6 +809,116 @@ impl<'a> VM<'a> {
         self.stack.set_reg(dst, val);
     }
 
+    /// Like `arithmetic_operation` but for operations
+    /// which are only defined on integers
+    #[inline(always)]
+    #[allow(clippy::type_complexity)]
+    fn integer_operation(
+        &mut self,
+        (
+            i8_func ,
+            i16_func,
+            i32_func,
+            i64_func,
+            u8_func ,
+            u16_func,
+            u32_func,
+            u64_func,
+        ): (
+            fn(i8 , i8 ) -> i8 ,
+            fn(i16, i16) -> i16,
+            fn(i32, i32) -> i32,
+            fn(i64, i64) -> i64,
+            fn(u8 , u8 ) -> u8 ,
+            fn(u16, u16) -> u16,
+            fn(u32, u32) -> u32,
+            fn(u64, u64) -> u64,
+        ),
+    ) {
+        let [dst, v1, v2] = self.current.next_n::<3>();
+
+        let v1 = self.stack.reg(v1);
+        let v2 = self.stack.reg(v2);
+        let val = match (v1.tag(), v2.tag()) {
+            (VMData::TAG_I8 , VMData::TAG_I8 ) => VMData::new_i8(i8_func(v1.as_i8(), v2.as_i8())),
+            (VMData::TAG_I16, VMData::TAG_I16) => VMData::new_i16(i16_func(v1.as_i16(), v2.as_i16())),
+            (VMData::TAG_I32, VMData::TAG_I32) => VMData::new_i32(i32_func(v1.as_i32(), v2.as_i32())),
+            (VMData::TAG_I64, VMData::TAG_I64) => VMData::new_i64(i64_func(v1.as_i64(), v2.as_i64())),
+            (VMData::TAG_U8 , VMData::TAG_U8 ) => VMData::new_u8(u8_func(v1.as_u8(), v2.as_u8())),
+            (VMData::TAG_U16, VMData::TAG_U16) => VMData::new_u16(u16_func(v1.as_u16(), v2.as_u16())),
+            (VMData::TAG_U32, VMData::TAG_U32) => VMData::new_u32(u32_func(v1.as_u32(), v2.as_u32())),
+            (VMData::TAG_U64, VMData::TAG_U64) => VMData::new_u64(u64_func(v1.as_u64(), v2.as_u64())),
+
+            _ => panic!("unreachable in integer operation: v1={v1}, v2={v2}"),
+        };
+
+        self.stack.set_reg(dst, val);
+    }
+
+    /// Shifts the left operand by the right one, the
+    /// right operand can be any integer and is masked
+    /// to the bit width of the left one
+    #[inline(always)]
+    #[allow(clippy::type_complexity)]
+    fn shift_operation(
+        &mut self,
+        (
+            i8_func ,
+            i16_func,
+            i32_func,
+            i64_func,
+            u8_func ,
+            u16_func,
+            u32_func,
+            u64_func,
+        ): (
+            fn(i8 , u32) -> i8 ,
+            fn(i16, u32) -> i16,
+            fn(i32, u32) -> i32,
+            fn(i64, u32) -> i64,
+            fn(u8 , u32) -> u8 ,
+            fn(u16, u32) -> u16,
+            fn(u32, u32) -> u32,
+            fn(u64, u32) -> u64,
+        ),
+    ) {
+        let [dst, v1, v2] = self.current.next_n::<3>();
+
+        let v1 = self.stack.reg(v1);
+        let v2 = self.stack.reg(v2);
+
+        // Truncating is fine as only the lowest 6 bits
+        // are ever used after masking
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let amount = match v2.tag() {
+            VMData::TAG_I8  => v2.as_i8() as u32,
+            VMData::TAG_I16 => v2.as_i16() as u32,
+            VMData::TAG_I32 => v2.as_i32() as u32,
+            VMData::TAG_I64 => v2.as_i64() as u32,
+            VMData::TAG_U8  => u32::from(v2.as_u8()),
+            VMData::TAG_U16 => u32::from(v2.as_u16()),
+            VMData::TAG_U32 => v2.as_u32(),
+            VMData::TAG_U64 => v2.as_u64() as u32,
+
+            _ => panic!("unreachable in shift operation: v2={v2}"),
+        };
+
+        let val = match v1.tag() {
+            VMData::TAG_I8  => VMData::new_i8(i8_func(v1.as_i8(), amount)),
+            VMData::TAG_I16 => VMData::new_i16(i16_func(v1.as_i16(), amount)),
+            VMData::TAG_I32 => VMData::new_i32(i32_func(v1.as_i32(), amount)),
+            VMData::TAG_I64 => VMData::new_i64(i64_func(v1.as_i64(), amount)),
+            VMData::TAG_U8  => VMData::new_u8(u8_func(v1.as_u8(), amount)),
+            VMData::TAG_U16 => VMData::new_u16(u16_func(v1.as_u16(), amount)),
+            VMData::TAG_U32 => VMData::new_u32(u32_func(v1.as_u32(), amount)),
+            VMData::TAG_U64 => VMData::new_u64(u64_func(v1.as_u64(), amount)),
+
+            _ => panic!("unreachable in shift operation: v1={v1}"),
+        };
+
+        self.stack.set_reg(dst, val);
+    }
+
     #[inline(always)]
     #[allow(clippy::too_many_arguments)]
     fn comparisson_operation(
@@ -571,6 +954,7 @@ impl<'a> VM<'a> {
             VMData::TAG_U64 => VMData::new_bool(u64_func(&v1.as_u64(), &v2.as_u64())),
 
             VMData::TAG_FLOAT => VMData::new_bool(float_func(&v1.as_float(), &v2.as_float())),
+            VMData::TAG_CHAR  => VMData::new_bool(u32_func(&u32::from(v1.as_char()), &u32::from(v2.as_char()))),
 
             _ => unreachable!(),
         };
@@ -0,0 +1,1092 @@
+#![allow(unused)]
+use std::collections::HashMap;
+
+use azurite_common::{Bytecode, CompilationMetadata, Target};
+
+use crate::{VM, VMData, Stack, Status, STACK_SIZE, MAX_STACK_SIZE, Code, Object, Structure, StructureLayout, Map, PanicLogLimits, generate_panic_log, bytes_to_constants, bytes_to_function_starts, bytes_to_line_table, object_map::{ObjectMap, ObjectData}};
+
+
+fn vm() -> VM<'static> {
+    VM {
+        constants: Vec::new(),
+        stack: Stack::new(),
+        objects: ObjectMap::new(64),
+
+        callstack: Vec::new(),
+        current: Code::new(&[], 0, 0),
+        reentries: Vec::new(),
+        libraries: Vec::new(),
+        externs: Vec::new(),
+        metadata: CompilationMetadata { extern_count: 0, library_count: 0, target: Target::Bytecode },
+        structures: HashMap::new(),
+        start: std::time::Instant::now(),
+        instruction_budget: None,
+
+        debug: Default::default(),
+    }
+}
+
+
+fn structure(vm: &mut VM, id: u64, fields: Vec<VMData>) -> VMData {
+    let index = vm.create_object(Object::new(Structure::new(fields))).unwrap();
+    VMData::new_object(id, index)
+}
+
+
+#[test]
+fn different_struct_types_are_not_equal() {
+    let mut vm = vm();
+
+    let first  = structure(&mut vm, 257, vec![VMData::new_i64(1)]);
+    let second = structure(&mut vm, 258, vec![VMData::new_i64(1)]);
+
+    assert!(first != second);
+    assert!(!vm.structural_eq(first, second));
+}
+
+
+#[test]
+fn same_struct_types_compare_structurally() {
+    let mut vm = vm();
+
+    let first  = structure(&mut vm, 257, vec![VMData::new_i64(1), VMData::new_bool(true)]);
+    let second = structure(&mut vm, 257, vec![VMData::new_i64(1), VMData::new_bool(true)]);
+    let third  = structure(&mut vm, 257, vec![VMData::new_i64(2), VMData::new_bool(true)]);
+
+    assert!(first != second, "`PartialEq` compares objects by identity");
+    assert!(vm.structural_eq(first, first));
+    assert!(vm.structural_eq(first, second));
+    assert!(!vm.structural_eq(first, third));
+}
+
+
+#[test]
+fn nested_structures_and_strings() {
+    let mut vm = vm();
+
+    let s1 = VMData::new_string(vm.create_object(Object::new(String::from("hello"))).unwrap());
+    let s2 = VMData::new_string(vm.create_object(Object::new(String::from("hello"))).unwrap());
+    let s3 = VMData::new_string(vm.create_object(Object::new(String::from("world"))).unwrap());
+
+    assert!(vm.structural_eq(s1, s2));
+    assert!(!vm.structural_eq(s1, s3));
+
+    let first  = structure(&mut vm, 257, vec![s1]);
+    let second = structure(&mut vm, 257, vec![s2]);
+    let third  = structure(&mut vm, 257, vec![s3]);
+
+    let first  = structure(&mut vm, 258, vec![first]);
+    let second = structure(&mut vm, 258, vec![second]);
+    let third  = structure(&mut vm, 258, vec![third]);
+
+    assert!(vm.structural_eq(first, second));
+    assert!(!vm.structural_eq(first, third));
+}
+
+
+fn list(vm: &mut VM, elements: Vec<VMData>) -> VMData {
+    VMData::new_list(vm.create_object(Object::new(elements)).unwrap())
+}
+
+
+#[test]
+fn lists_compare_element_wise() {
+    let mut vm = vm();
+
+    let first  = list(&mut vm, vec![VMData::new_i64(1), VMData::new_i64(2)]);
+    let second = list(&mut vm, vec![VMData::new_i64(1), VMData::new_i64(2)]);
+    let longer = list(&mut vm, vec![VMData::new_i64(1), VMData::new_i64(2), VMData::new_i64(3)]);
+    let other  = list(&mut vm, vec![VMData::new_i64(1), VMData::new_i64(3)]);
+
+    assert!(vm.structural_eq(first, second));
+    assert!(!vm.structural_eq(first, longer));
+    assert!(!vm.structural_eq(longer, first));
+    assert!(!vm.structural_eq(first, other));
+}
+
+
+#[test]
+fn nested_lists_compare_recursively() {
+    let mut vm = vm();
+
+    let s1 = VMData::new_string(vm.create_object(Object::new(String::from("a"))).unwrap());
+    let s2 = VMData::new_string(vm.create_object(Object::new(String::from("a"))).unwrap());
+    let s3 = VMData::new_string(vm.create_object(Object::new(String::from("b"))).unwrap());
+
+    let inner1 = list(&mut vm, vec![s1]);
+    let inner2 = list(&mut vm, vec![s2]);
+    let inner3 = list(&mut vm, vec![s3]);
+
+    let first  = list(&mut vm, vec![inner1]);
+    let second = list(&mut vm, vec![inner2]);
+    let third  = list(&mut vm, vec![inner3]);
+
+    assert!(vm.structural_eq(first, second));
+    assert!(!vm.structural_eq(first, third));
+}
+
+
+#[test]
+fn self_referential_lists_compare() {
+    let mut vm = vm();
+
+    let first  = list(&mut vm, vec![]);
+    let second = list(&mut vm, vec![]);
+    let longer = list(&mut vm, vec![VMData::new_i64(1)]);
+
+    vm.objects.get_mut(first.as_object()).list_mut().unwrap().push(first);
+    vm.objects.get_mut(second.as_object()).list_mut().unwrap().push(second);
+    vm.objects.get_mut(longer.as_object()).list_mut().unwrap().insert(0, longer);
+
+    assert!(vm.structural_eq(first, second));
+    assert!(!vm.structural_eq(first, longer));
+}
+
+
+#[test]
+fn structures_arent_lists() {
+    let mut vm = vm();
+
+    let value = structure(&mut vm, 257, vec![]);
+    let object = vm.objects.get_mut(value.as_object());
+
+    assert!(object.list().is_err());
+    assert!(object.list_mut().is_err());
+}
+
+
+#[test]
+fn hashes_are_fnv1a() {
+    let mut vm = vm();
+
+    let empty = VMData::new_string(vm.create_object(Object::new(String::new())).unwrap());
+    let hello = VMData::new_string(vm.create_object(Object::new(String::from("hello"))).unwrap());
+
+    assert_eq!(vm.hash(empty), Some(0xcbf2_9ce4_8422_2325_u64 as i64));
+    assert_eq!(vm.hash(hello), Some(0xa430_d846_80aa_bd0b_u64 as i64));
+
+    assert_eq!(vm.hash(VMData::new_i64(42)), Some(-55_488_592_825_689_361));
+    assert_eq!(vm.hash(VMData::new_i64(-1)), Some(-8_289_690_350_564_177_859));
+    assert_eq!(vm.hash(VMData::new_bool(true)), Some(-5_808_592_057_526_012_372));
+    assert_eq!(vm.hash(VMData::new_float(1.5)), Some(-6_154_756_914_075_763_584));
+}
+
+
+#[test]
+fn hashes_strings_by_contents() {
+    let mut vm = vm();
+
+    let first  = VMData::new_string(vm.create_object(Object::new(String::from("azurite"))).unwrap());
+    let second = VMData::new_string(vm.create_object(Object::new(String::from("azurite"))).unwrap());
+
+    assert!(first != second);
+    assert_eq!(vm.hash(first), vm.hash(second));
+}
+
+
+#[test]
+fn float_hashes_agree_with_equality() {
+    let vm = vm();
+
+    assert_eq!(vm.hash(VMData::new_float(0.0)), vm.hash(VMData::new_float(-0.0)));
+    assert_eq!(vm.hash(VMData::new_float(f64::NAN)), None);
+}
+
+
+#[test]
+fn structures_are_hashed_by_fields() {
+    let mut vm = vm();
+
+    let value = structure(&mut vm, 257, vec![VMData::new_i64(1)]);
+    let same_fields = structure(&mut vm, 257, vec![VMData::new_i64(1)]);
+    let other_fields = structure(&mut vm, 257, vec![VMData::new_i64(2)]);
+
+    assert!(vm.hash(value).is_some());
+    assert_eq!(vm.hash(value), vm.hash(same_fields));
+    assert_ne!(vm.hash(value), vm.hash(other_fields));
+}
+
+
+fn string(vm: &mut VM, string: &str) -> VMData {
+    VMData::new_string(vm.create_object(Object::new(string.to_string())).unwrap())
+}
+
+
+#[test]
+fn map_insert_and_get() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    for i in 0..100 {
+        assert_eq!(vm.map_insert(map, VMData::new_i64(i), VMData::new_i64(i * 2)).unwrap(), None);
+    }
+
+    let key = string(&mut vm, "key");
+    vm.map_insert(map, key, VMData::new_bool(true)).unwrap();
+
+    assert_eq!(vm.objects.get(map).map().unwrap().len(), 101);
+
+    for i in 0..100 {
+        assert_eq!(vm.map_get(map, VMData::new_i64(i)).unwrap(), Some(VMData::new_i64(i * 2)));
+    }
+
+    let same_key = string(&mut vm, "key");
+    assert_eq!(vm.map_get(map, same_key).unwrap(), Some(VMData::new_bool(true)), "strings are looked up by contents");
+
+    assert_eq!(vm.map_get(map, VMData::new_i64(100)).unwrap(), None);
+    assert_eq!(vm.map_get(map, VMData::new_i32(1)).unwrap(), None, "keys of different types are distinct");
+}
+
+
+#[test]
+fn map_replace_and_remove() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    vm.map_insert(map, VMData::new_u8(1), VMData::new_u8(1)).unwrap();
+    assert_eq!(vm.map_insert(map, VMData::new_u8(1), VMData::new_u8(2)).unwrap(), Some(VMData::new_u8(1)));
+    assert_eq!(vm.objects.get(map).map().unwrap().len(), 1);
+
+    assert_eq!(vm.map_remove(map, VMData::new_u8(1)).unwrap(), Some(VMData::new_u8(2)));
+    assert_eq!(vm.map_remove(map, VMData::new_u8(1)).unwrap(), None);
+    assert!(vm.objects.get(map).map().unwrap().is_empty());
+}
+
+
+#[test]
+fn structures_arent_maps() {
+    let mut vm = vm();
+
+    let value = structure(&mut vm, 257, vec![]);
+
+    assert!(vm.objects.get(value.as_object()).map().is_err());
+    assert!(vm.map_insert(value.as_object(), VMData::new_i64(0), VMData::new_i64(0)).is_err());
+    assert!(vm.map_get(value.as_object(), VMData::new_i64(0)).is_err());
+    assert!(vm.map_to_string(value.as_object()).is_err());
+}
+
+
+#[test]
+fn map_to_string_is_sorted() {
+    let mut vm = vm();
+    let mut rendered = vec![];
+
+    for keys in [["b", "c", "a"], ["a", "b", "c"], ["c", "a", "b"]] {
+        let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+        for key in keys {
+            let (k, v) = (string(&mut vm, key), string(&mut vm, &key.repeat(2)));
+            vm.map_insert(map, k, v).unwrap();
+        }
+
+        rendered.push(vm.map_to_string(map).unwrap());
+    }
+
+    assert_eq!(rendered[0], r#"{"a": "aa", "b": "bb", "c": "cc"}"#);
+    assert!(rendered.iter().all(|x| *x == rendered[0]));
+
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+    for i in [10, -1, 9] {
+        vm.map_insert(map, VMData::new_i64(i), VMData::new_bool(i > 0)).unwrap();
+    }
+
+    assert_eq!(vm.map_to_string(map).unwrap(), "{-1: false, 9: true, 10: true}", "integers are sorted numerically");
+}
+
+
+#[test]
+fn map_to_string_sorts_structures_by_fields() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    for (x, y) in [(2, 1), (1, 2), (1, 1)] {
+        let key = structure(&mut vm, 257, vec![VMData::new_i64(x), VMData::new_i64(y)]);
+        vm.map_insert(map, key, VMData::new_i64(x * 10 + y)).unwrap();
+    }
+
+    // Structures render as their object index so only
+    // the order of the values is stable
+    let rendered = vm.map_to_string(map).unwrap();
+    let values = rendered
+        .trim_matches(|x| x == '{' || x == '}')
+        .split(", ")
+        .map(|x| x.split(": ").nth(1).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(values, ["11", "12", "21"]);
+}
+
+
+#[test]
+fn map_rejects_unhashable_keys() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    let inner = vm.create_object(Object::new(Map::new())).unwrap();
+    let key = structure(&mut vm, 257, vec![VMData::new_map(inner)]);
+    assert!(vm.map_insert(map, key, VMData::new_unit()).is_err(), "maps compare by identity");
+
+    let list = vm.create_object(Object::new(vec![VMData::new_i64(1)])).unwrap();
+    assert!(vm.map_insert(map, VMData::new_list(list), VMData::new_unit()).is_err());
+}
+
+
+#[test]
+fn structures_as_map_keys() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    let name = string(&mut vm, "origin");
+    let key = structure(&mut vm, 257, vec![VMData::new_i64(0), name]);
+    vm.map_insert(map, key, VMData::new_i64(42)).unwrap();
+
+    let other_name = string(&mut vm, "origin");
+    let equal_key = structure(&mut vm, 257, vec![VMData::new_i64(0), other_name]);
+    assert_ne!(key.as_object(), equal_key.as_object());
+
+    assert_eq!(vm.hash(key), vm.hash(equal_key));
+    assert_eq!(vm.map_get(map, equal_key).unwrap(), Some(VMData::new_i64(42)));
+
+    let different_field = structure(&mut vm, 257, vec![VMData::new_i64(1), name]);
+    let different_type = structure(&mut vm, 258, vec![VMData::new_i64(0), name]);
+    assert_eq!(vm.map_get(map, different_field).unwrap(), None);
+    assert_eq!(vm.map_get(map, different_type).unwrap(), None, "structures of another type aren't equal");
+}
+
+
+#[test]
+fn nested_structures_hash_recursively() {
+    let mut vm = vm();
+
+    let inner1 = structure(&mut vm, 257, vec![VMData::new_bool(true)]);
+    let inner2 = structure(&mut vm, 257, vec![VMData::new_bool(true)]);
+    let outer1 = structure(&mut vm, 258, vec![inner1, VMData::new_char('a')]);
+    let outer2 = structure(&mut vm, 258, vec![inner2, VMData::new_char('a')]);
+
+    assert!(vm.hash(outer1).is_some());
+    assert_eq!(vm.hash(outer1), vm.hash(outer2));
+}
+
+
+#[test]
+fn unhashable_map_keys() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    let nested = VMData::new_map(vm.create_object(Object::new(Map::new())).unwrap());
+    let list = VMData::new_list(vm.create_object(Object::new(vec![VMData::new_i64(1)])).unwrap());
+    let containing_map = structure(&mut vm, 257, vec![VMData::new_i64(0), nested]);
+
+    for key in [nested, list, containing_map] {
+        assert_eq!(vm.hash(key), None);
+        assert!(vm.map_insert(map, key, VMData::new_i64(0)).is_err());
+        assert!(vm.map_get(map, key).is_err());
+        assert!(vm.map_remove(map, key).is_err());
+    }
+
+    assert!(vm.objects.get(map).map().unwrap().is_empty());
+}
+
+
+#[test]
+fn float_map_keys() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    vm.map_insert(map, VMData::new_float(0.0), VMData::new_i64(1)).unwrap();
+
+    assert_eq!(vm.hash(VMData::new_float(0.0)), vm.hash(VMData::new_float(-0.0)));
+    assert_eq!(vm.map_get(map, VMData::new_float(-0.0)).unwrap(), Some(VMData::new_i64(1)), "-0.0 is equal to 0.0");
+    assert_eq!(vm.map_insert(map, VMData::new_float(-0.0), VMData::new_i64(2)).unwrap(), Some(VMData::new_i64(1)));
+    assert_eq!(vm.objects.get(map).map().unwrap().len(), 1);
+
+    assert_eq!(vm.hash(VMData::new_float(f64::NAN)), None);
+    assert!(vm.map_insert(map, VMData::new_float(f64::NAN), VMData::new_i64(3)).is_err(), "NaN can never be found again");
+    assert_eq!(vm.objects.get(map).map().unwrap().len(), 1);
+}
+
+
+#[test]
+fn map_entries_survive_garbage_collection() {
+    let mut vm = vm();
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+
+    let key = string(&mut vm, "key");
+    let value = string(&mut vm, "value");
+    let garbage = string(&mut vm, "garbage");
+
+    vm.map_insert(map, key, value).unwrap();
+    vm.stack.values[0] = VMData::new_map(map);
+
+    vm.run_garbage_collection();
+
+    assert!(matches!(vm.objects.get(garbage.as_object()).data, ObjectData::Free { .. }));
+    assert_eq!(vm.objects.get(key.as_object()).string(), "key");
+    assert_eq!(vm.objects.get(value.as_object()).string(), "value");
+}
+
+
+#[test]
+fn compaction_moves_live_objects_to_the_front() {
+    let mut vm = vm();
+
+    let mut garbage = vec![];
+    for i in 0..8 {
+        garbage.push(string(&mut vm, &format!("garbage {i}")));
+    }
+
+    let key = string(&mut vm, "key");
+    let value = string(&mut vm, "value");
+    garbage.push(string(&mut vm, "more garbage"));
+
+    let map = vm.create_object(Object::new(Map::new())).unwrap();
+    vm.map_insert(map, key, value).unwrap();
+
+    let list = vm.create_object(Object::new(vec![VMData::new_map(map), value])).unwrap();
+    let element = structure(&mut vm, 257, vec![VMData::new_list(list)]);
+
+    vm.stack.values[0] = element;
+    vm.constants.push(value);
+
+    vm.run_major_garbage_collection();
+
+    let live = vm.objects.raw().iter().take_while(|x| !matches!(x.data, ObjectData::Free { .. })).count();
+    assert_eq!(live, 5);
+    assert!(vm.objects.raw()[live..].iter().all(|x| matches!(x.data, ObjectData::Free { .. })));
+
+    let element = vm.stack.values[0];
+    assert!((element.as_object().index as usize) < live);
+
+    let list = vm.objects.get(element.as_object()).structure().fields()[0];
+    let map = vm.objects.get(list.as_object()).list().unwrap()[0];
+    let value = vm.objects.get(list.as_object()).list().unwrap()[1];
+
+    assert_eq!(vm.objects.get(value.as_object()).string(), "value");
+    assert_eq!(vm.objects.get(vm.constants[0].as_object()).string(), "value");
+
+    let lookup = string(&mut vm, "key");
+    let stored = vm.map_get(map.as_object(), lookup).unwrap().unwrap();
+    assert_eq!(vm.objects.get(stored.as_object()).string(), "value");
+
+    // The free slots right after the live objects are used first
+    assert_eq!(lookup.as_object().index as usize, live);
+}
+
+
+#[test]
+fn compaction_keeps_builtin_object_tags() {
+    let mut vm = vm();
+
+    let _garbage = string(&mut vm, "garbage");
+    let value = string(&mut vm, "value");
+    let list = VMData::new_list(vm.create_object(Object::new(vec![value])).unwrap());
+    let map = VMData::new_map(vm.create_object(Object::new(Map::new())).unwrap());
+    vm.map_insert(map.as_object(), value, list).unwrap();
+
+    assert!(matches!(vm.stack.push(2), Status::Ok));
+    vm.stack.values[0] = value;
+    vm.stack.values[1] = list;
+    vm.stack.values[2] = map;
+
+    vm.run_major_garbage_collection();
+
+    let (value, list, map) = (vm.stack.values[0], vm.stack.values[1], vm.stack.values[2]);
+    assert_eq!(value.tag(), VMData::TAG_STR);
+    assert_eq!(list.tag(), VMData::TAG_LIST);
+    assert_eq!(map.tag(), VMData::TAG_MAP);
+    assert_eq!(value.as_object().index, 0);
+
+    let element = vm.objects.get(list.as_object()).list().unwrap()[0];
+    assert_eq!(element.tag(), VMData::TAG_STR);
+    assert_eq!(vm.objects.get(element.as_object()).string(), "value");
+
+    let stored = vm.map_get(map.as_object(), value).unwrap().unwrap();
+    assert_eq!(stored.tag(), VMData::TAG_LIST);
+    assert_eq!(stored.as_object(), list.as_object());
+}
+
+
+#[test]
+fn compaction_is_skipped_when_reentered() {
+    let mut vm = vm();
+
+    let _garbage = string(&mut vm, "garbage");
+    let kept = string(&mut vm, "kept");
+    vm.stack.values[0] = kept;
+
+    vm.reentries.push(0);
+    vm.run_major_garbage_collection();
+
+    assert_eq!(vm.stack.values[0].as_object(), kept.as_object());
+}
+
+
+#[test]
+fn structure_layouts_from_bytes() {
+    fn name(bytes: &mut Vec<u8>, name: &str) {
+        bytes.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    let mut bytes = vec![];
+
+    bytes.extend_from_slice(&257_u64.to_le_bytes());
+    name(&mut bytes, "Point");
+    bytes.push(2);
+    name(&mut bytes, "x");
+    name(&mut bytes, "y");
+
+    let first = bytes.len();
+
+    bytes.extend_from_slice(&258_u64.to_le_bytes());
+    name(&mut bytes, "Empty");
+    bytes.push(0);
+
+    let structures = crate::bytes_to_structures(&bytes).unwrap();
+
+    assert_eq!(structures.len(), 2);
+    assert_eq!(structures[&257], StructureLayout { name: "Point".to_string(), fields: vec!["x".to_string(), "y".to_string()] });
+    assert_eq!(structures[&258], StructureLayout { name: "Empty".to_string(), fields: vec![] });
+
+    for length in (1..bytes.len()).filter(|x| *x != first) {
+        assert_eq!(crate::bytes_to_structures(&bytes[..length]), None, "truncated to {length} bytes");
+    }
+
+    let mut invalid_utf8 = 259_u64.to_le_bytes().to_vec();
+    invalid_utf8.extend_from_slice(&1_u64.to_le_bytes());
+    invalid_utf8.extend_from_slice(&[0xff, 0]);
+    assert_eq!(crate::bytes_to_structures(&invalid_utf8), None);
+
+    let mut too_long = 259_u64.to_le_bytes().to_vec();
+    too_long.extend_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(crate::bytes_to_structures(&too_long), None);
+}
+
+
+#[test]
+fn monotonic_clock_advances() {
+    let vm = vm();
+
+    let start = vm.monotonic_elapsed();
+
+    let mut counter = 0_u64;
+    for i in 0..100_000 {
+        counter = std::hint::black_box(counter.wrapping_add(i));
+    }
+
+    let end = vm.monotonic_elapsed();
+
+    assert!(end > start);
+}
+
+
+#[test]
+fn stack_grows_past_its_initial_size() {
+    let mut stack = Stack::new();
+
+    // Frames of 200 registers, like a chain of calls
+    // to functions with many locals, until the frames
+    // take up more space than the initial stack
+    let frames = STACK_SIZE / 200 + 2;
+    for frame in 0..frames {
+        let offset = stack.top;
+        assert!(matches!(stack.push(200), Status::Ok));
+
+        stack.set_stack_offset(offset);
+        for reg in 0..200 {
+            stack.set_reg(reg, VMData::new_i64((frame * 200 + reg as usize) as i64));
+        }
+    }
+
+    assert!(stack.top > STACK_SIZE);
+
+    stack.set_stack_offset(1);
+    assert_eq!(stack.reg(0), VMData::new_i64(0));
+    assert_eq!(stack.reg(199), VMData::new_i64(199));
+
+    stack.set_stack_offset(1 + (frames - 1) * 200);
+    assert_eq!(stack.reg(199), VMData::new_i64((frames * 200 - 1) as i64));
+}
+
+
+#[test]
+fn stack_overflows_at_its_maximum_size() {
+    let mut stack = Stack::new();
+
+    assert!(matches!(stack.push(MAX_STACK_SIZE / 2), Status::Ok));
+    assert!(matches!(stack.push(MAX_STACK_SIZE / 2), Status::Err(_)));
+}
+
+
+#[test]
+fn call_function_reenters_the_interpreter() {
+    // The code at 0 is never reached, at 1 is
+    // a function which doubles its argument
+    let code = vec![
+        Bytecode::Return.as_u8(),
+        Bytecode::Add.as_u8(), 0, 1, 1,
+        Bytecode::Return.as_u8(),
+    ];
+
+    let mut vm = vm();
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+
+    let result = vm.call_function(1, &[VMData::new_i64(21)]);
+
+    assert!(matches!(result, Ok(v) if v == VMData::new_i64(42)));
+    assert!(vm.callstack.is_empty());
+    assert!(vm.reentries.is_empty());
+    assert_eq!(vm.current.pointer, 0, "the caller continues where it left off");
+}
+
+
+/// A list of the integers in `values`, kept alive by the
+/// first argument register like a list passed to an extern
+fn integer_list(vm: &mut VM, values: &[i64]) -> crate::ObjectIndex {
+    let list = vm.create_object(Object::new(values.iter().map(|x| VMData::new_i64(*x)).collect::<Vec<_>>())).unwrap();
+
+    assert!(matches!(vm.stack.push(1), Status::Ok));
+    vm.stack.set_reg(1, VMData::new_list(list));
+
+    list
+}
+
+
+#[test]
+fn list_map_doubles_every_element() {
+    // The function at 1 doubles its argument
+    let code = vec![
+        Bytecode::Return.as_u8(),
+        Bytecode::Add.as_u8(), 0, 1, 1,
+        Bytecode::Return.as_u8(),
+    ];
+
+    let mut vm = vm();
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+
+    let list = integer_list(&mut vm, &[1, 2, 3]);
+    let mapped = vm.list_map(list, 1).unwrap();
+
+    assert_eq!(vm.objects.get(mapped).list().unwrap(), &vec![VMData::new_i64(2), VMData::new_i64(4), VMData::new_i64(6)]);
+    assert_eq!(vm.objects.get(list).list().unwrap().len(), 3, "the original list is unchanged");
+    assert_eq!(vm.stack.reg(0), VMData::new_list(mapped));
+}
+
+
+#[test]
+fn list_filter_keeps_matching_elements() {
+    // The function at 1 returns whether its argument is positive
+    let code = vec![
+        Bytecode::Return.as_u8(),
+        Bytecode::Push.as_u8(), 1,
+        Bytecode::LoadConst.as_u8(), 2, 0,
+        Bytecode::GreaterThan.as_u8(), 0, 1, 2,
+        Bytecode::Pop.as_u8(), 1,
+        Bytecode::Return.as_u8(),
+    ];
+
+    let mut vm = vm();
+    vm.constants = vec![VMData::new_i64(0)];
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+
+    let list = integer_list(&mut vm, &[-2, 5, 0, 7, -1]);
+    let filtered = vm.list_filter(list, 1).unwrap();
+
+    assert_eq!(vm.objects.get(filtered).list().unwrap(), &vec![VMData::new_i64(5), VMData::new_i64(7)]);
+}
+
+
+#[test]
+fn list_filter_requires_a_bool() {
+    // The function at 1 returns its argument
+    let code = vec![
+        Bytecode::Return.as_u8(),
+        Bytecode::Copy.as_u8(), 0, 1,
+        Bytecode::Return.as_u8(),
+    ];
+
+    let mut vm = vm();
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+
+    let list = integer_list(&mut vm, &[1]);
+
+    assert!(matches!(vm.list_filter(list, 1), Err(Status::Err(_))));
+    assert!(matches!(vm.list_map(list, 100), Err(Status::Err(_))), "there's no function at 100");
+}
+
+
+#[test]
+fn frames_without_push_or_pop_are_balanced() {
+    // The root calls the function at 8 which loads a
+    // constant without pushing or popping a frame
+    let code = vec![
+        Bytecode::Call.as_u8(), 8, 0, 0, 0, 0, 0,
+        Bytecode::Return.as_u8(),
+        Bytecode::LoadConst.as_u8(), 0, 0,
+        Bytecode::Return.as_u8(),
+    ];
+
+    let mut vm = vm();
+    vm.constants = vec![VMData::new_i64(42)];
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+
+    assert!(matches!(vm.run(), Status::Ok));
+    assert_eq!(vm.stack.top, 1);
+    assert_eq!(vm.stack.reg(0), VMData::new_i64(42));
+}
+
+
+#[test]
+fn tail_calls_reuse_the_frame() {
+    // The function at 1 tail calls the function at 9
+    // with its arguments swapped, which subtracts them
+    let code = vec![
+        Bytecode::Return.as_u8(),
+        Bytecode::TailCall.as_u8(), 9, 0, 0, 0, 2, 2, 1,
+        Bytecode::Subtract.as_u8(), 0, 1, 2,
+        Bytecode::Pop.as_u8(), 2,
+        Bytecode::Return.as_u8(),
+    ];
+
+    let mut vm = vm();
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+
+    let result = vm.call_function(1, &[VMData::new_i64(1), VMData::new_i64(10)]);
+
+    assert!(matches!(result, Ok(v) if v == VMData::new_i64(9)));
+    assert!(vm.callstack.is_empty());
+    assert_eq!(vm.stack.top, 1, "the frame of the tail call is popped like the caller's");
+}
+
+
+#[test]
+fn panic_log_is_truncated_to_its_limits() {
+    let mut vm = vm();
+    vm.objects = ObjectMap::new(256);
+
+    for i in 0..200 {
+        let value = string(&mut vm, &i.to_string());
+        vm.stack.set_reg(0, value);
+    }
+
+    assert!(matches!(vm.stack.push(500), Status::Ok));
+    vm.current = Code::new(Box::leak(vec![0; 100].into_boxed_slice()), 0, 0);
+
+    let limits = PanicLogLimits { max_objects: 10, max_stack_values: 20, max_bytecode: 0 };
+    let log = generate_panic_log(&vm, true, limits);
+
+    assert_eq!(log.lines().filter(|x| x.contains(" - live: ")).count(), 10);
+    assert!(log.contains("190 more objects omitted"));
+
+    assert!(log.contains(&format!("{} more values omitted", vm.stack.top - 20)));
+    assert!(log.contains("100 bytes omitted"));
+
+
+    let log = generate_panic_log(&vm, true, PanicLogLimits::default());
+
+    assert_eq!(log.lines().filter(|x| x.contains(" - live: ")).count(), 200);
+    assert!(!log.contains("omitted"));
+}
+
+
+#[test]
+fn panic_log_caps_the_bytecode_around_the_pointer() {
+    let mut vm = vm();
+    vm.current = Code::new(Box::leak(vec![0; 10_000].into_boxed_slice()), 0, 0);
+    vm.current.pointer = 6000;
+
+    let log = generate_panic_log(&vm, true, PanicLogLimits::default());
+    assert!(log.contains("5904 bytes omitted, showing 3952..8048"), "{log}");
+
+    vm.current.pointer = 9999;
+
+    let log = generate_panic_log(&vm, true, PanicLogLimits::default());
+    assert!(log.contains("showing 5904..10000"), "the window doesn't run past the end");
+}
+
+
+fn cast_to_u64(value: VMData, is_debug_build: bool) -> Result<VMData, Status> {
+    // The function at 1 casts its argument
+    let code = vec![
+        Bytecode::Return.as_u8(),
+        Bytecode::CastToU64.as_u8(), 0, 1,
+        Bytecode::Return.as_u8(),
+    ];
+
+    let mut vm = vm();
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+    vm.debug.is_debug_build = is_debug_build;
+
+    vm.call_function(1, &[value])
+}
+
+
+#[test]
+fn sign_preserving_casts_are_allowed() {
+    for is_debug_build in [true, false] {
+        assert!(matches!(cast_to_u64(VMData::new_i64(42), is_debug_build), Ok(v) if v == VMData::new_u64(42)));
+        assert!(matches!(cast_to_u64(VMData::new_float(-1.5), is_debug_build), Ok(v) if v == VMData::new_u64(0)), "floats saturate");
+    }
+}
+
+
+#[test]
+fn sign_changing_cast_is_an_error_in_debug_builds() {
+    let Err(Status::Err(error)) = cast_to_u64(VMData::new_i64(-1), true) else { panic!("the cast succeeded") };
+    assert!(error.read_message().to_str().unwrap().contains("changes its sign"));
+}
+
+
+#[test]
+fn sign_changing_cast_reinterprets_without_debug_info() {
+    assert!(matches!(cast_to_u64(VMData::new_i64(-1), false), Ok(v) if v == VMData::new_u64(u64::MAX)));
+}
+
+
+#[test]
+fn instruction_budget_stops_infinite_loops() {
+    let mut vm = vm();
+
+    let code = vec![
+        Bytecode::Jump.as_u8(), 0, 0, 0, 0,
+    ];
+
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+    vm.set_instruction_budget(Some(1000));
+
+    let Status::Err(e) = vm.run() else { panic!("the loop should've been stopped") };
+
+    assert_eq!(e.read_message().to_string_lossy(), "instruction budget exceeded");
+    assert_eq!(vm.instruction_budget, Some(0));
+}
+
+
+#[test]
+fn programs_within_the_budget_finish() {
+    let mut vm = vm();
+
+    let code = vec![
+        Bytecode::Return.as_u8(),
+    ];
+
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+    vm.set_instruction_budget(Some(1));
+
+    assert!(matches!(vm.run(), Status::Ok));
+}
+
+
+fn function_starts(functions: &[(u32, &str)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (start, name) in functions {
+        bytes.extend_from_slice(&start.to_le_bytes());
+        bytes.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    bytes
+}
+
+
+#[test]
+fn function_starts_are_read_in_order() {
+    let functions = bytes_to_function_starts(&function_starts(&[(20, "main::fib"), (0, "main")]));
+
+    assert_eq!(functions, vec![(0, "main".to_string()), (20, "main::fib".to_string())]);
+    assert!(bytes_to_function_starts(&[]).is_empty());
+}
+
+
+#[test]
+fn stack_trace_names_each_frame() {
+    let mut vm = vm();
+    vm.debug.functions = bytes_to_function_starts(&function_starts(&[(0, "main"), (10, "main::outer"), (20, "main::inner")]));
+
+    let code : &'static [u8] = Box::leak(vec![0; 30].into_boxed_slice());
+
+    // Pointers are past the instruction each frame is at
+    let frame = |pointer| {
+        let mut code = Code::new(code, 0, 0);
+        code.goto(pointer);
+        code
+    };
+
+    vm.callstack.push(frame(5));
+    vm.callstack.push(frame(15));
+    vm.current = frame(21);
+
+    assert_eq!(vm.stack_trace(), vec!["main::inner", "main::outer", "main"]);
+
+    let trace = vm.format_stack_trace();
+    assert!(trace.contains("0: main::inner"), "{trace}");
+    assert!(trace.contains("2: main"), "{trace}");
+}
+
+
+#[test]
+fn recursive_frames_are_collapsed() {
+    let mut vm = vm();
+    vm.debug.functions = bytes_to_function_starts(&function_starts(&[(0, "main"), (10, "main::fib")]));
+
+    let code : &'static [u8] = Box::leak(vec![0; 20].into_boxed_slice());
+    let frame = |pointer| {
+        let mut code = Code::new(code, 0, 0);
+        code.goto(pointer);
+        code
+    };
+
+    vm.callstack.push(frame(5));
+    for _ in 0..1000 {
+        vm.callstack.push(frame(15));
+    }
+    vm.current = frame(12);
+
+    let trace = vm.format_stack_trace();
+    assert!(trace.contains("0: main::fib (1001 frames)"), "{trace}");
+    assert!(trace.contains("1001: main"), "{trace}");
+    assert_eq!(trace.lines().count(), 3, "{trace}");
+}
+
+
+#[test]
+fn no_stack_trace_without_debug_info() {
+    let vm = vm();
+
+    assert!(vm.format_stack_trace().is_empty());
+}
+
+
+fn line_table(lines: &[(u32, &str, u32)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (start, file, line) in lines {
+        bytes.extend_from_slice(&start.to_le_bytes());
+        bytes.extend_from_slice(&line.to_le_bytes());
+        bytes.extend_from_slice(&(file.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(file.as_bytes());
+    }
+
+    bytes
+}
+
+
+#[test]
+fn line_table_is_read_in_order() {
+    let lines = bytes_to_line_table(&line_table(&[(12, "main.az", 4), (0, "main.az", 2)]));
+
+    assert_eq!(lines, vec![(0, "main.az".to_string(), 2), (12, "main.az".to_string(), 4)]);
+    assert!(bytes_to_line_table(&[]).is_empty());
+}
+
+
+#[test]
+fn location_of_the_current_instruction() {
+    let mut vm = vm();
+    vm.debug.functions = bytes_to_function_starts(&function_starts(&[(0, "main"), (10, "main::divide")]));
+    vm.debug.lines = bytes_to_line_table(&line_table(&[(2, "main.az", 7), (10, "main.az", 2), (14, "main.az", 3)]));
+
+    let code : &'static [u8] = Box::leak(vec![0; 20].into_boxed_slice());
+    let frame = |pointer| {
+        let mut code = Code::new(code, 0, 0);
+        code.goto(pointer);
+        code
+    };
+
+    // Past the operands of an instruction starting at 14
+    vm.current = frame(17);
+    assert_eq!(vm.current.position(), 16);
+    assert_eq!(vm.location().as_deref(), Some("main.az:3"));
+
+    vm.current = frame(12);
+    assert_eq!(vm.location().as_deref(), Some("main.az:2"));
+
+    // Before the first line of `main`
+    vm.current = frame(1);
+    assert_eq!(vm.location(), None);
+}
+
+
+#[test]
+fn line_of_another_function_is_not_used() {
+    let mut vm = vm();
+    vm.debug.functions = bytes_to_function_starts(&function_starts(&[(0, "main::outer"), (10, "main::template")]));
+    vm.debug.lines = bytes_to_line_table(&line_table(&[(0, "main.az", 5)]));
+
+    let code : &'static [u8] = Box::leak(vec![0; 20].into_boxed_slice());
+    let mut current = Code::new(code, 0, 0);
+    current.goto(13);
+    vm.current = current;
+
+    assert_eq!(vm.location(), None);
+}
+
+
+#[test]
+fn stack_trace_has_locations() {
+    let mut vm = vm();
+    vm.debug.functions = bytes_to_function_starts(&function_starts(&[(0, "main"), (10, "main::inner")]));
+    vm.debug.lines = bytes_to_line_table(&line_table(&[(0, "main.az", 6), (10, "main.az", 2)]));
+
+    let code : &'static [u8] = Box::leak(vec![0; 20].into_boxed_slice());
+    let frame = |pointer| {
+        let mut code = Code::new(code, 0, 0);
+        code.goto(pointer);
+        code
+    };
+
+    vm.callstack.push(frame(5));
+    vm.current = frame(12);
+
+    let trace = vm.format_stack_trace();
+    assert!(trace.contains("0: main::inner at main.az:2"), "{trace}");
+    assert!(trace.contains("1: main at main.az:6"), "{trace}");
+}
+
+
+#[test]
+fn errors_point_at_the_line() {
+    let mut vm = vm();
+    vm.debug.functions = bytes_to_function_starts(&function_starts(&[(0, "main")]));
+    vm.debug.lines = bytes_to_line_table(&line_table(&[(0, "main.az", 42)]));
+
+    let code = vec![
+        Bytecode::Jump.as_u8(), 0, 0, 0, 0,
+    ];
+
+    vm.current = Code::new(Box::leak(code.into_boxed_slice()), 0, 0);
+    vm.set_instruction_budget(Some(10));
+
+    let Status::Err(e) = vm.run() else { panic!("the loop should've been stopped") };
+
+    assert_eq!(e.read_message().to_string_lossy(), "instruction budget exceeded at main.az:42");
+}
+
+
+#[test]
+fn corrupt_constants_are_errors() {
+    let mut valid = vec![11];
+    valid.extend(u32::from('a').to_le_bytes());
+    valid.push(2);
+    valid.extend(2u64.to_le_bytes());
+    valid.extend(b"hi");
+
+    let mut decoded = vm();
+    assert!(bytes_to_constants(&mut decoded, valid).is_ok());
+    assert_eq!(decoded.constants[0], VMData::new_char('a'));
+
+    let mut invalid_char = vec![11];
+    invalid_char.extend(0xd800u32.to_le_bytes());
+
+    let mut invalid_utf8 = vec![2];
+    invalid_utf8.extend(1u64.to_le_bytes());
+    invalid_utf8.push(0xff);
+
+    let mut truncated_string = vec![2];
+    truncated_string.extend(u64::MAX.to_le_bytes());
+
+    for data in [invalid_char, invalid_utf8, truncated_string, vec![6, 1, 2], vec![12]] {
+        let mut vm = vm();
+        let error = bytes_to_constants(&mut vm, data.clone()).expect_err(&format!("{data:?} was accepted"));
+
+        assert!(error.read_message().to_string_lossy().starts_with("the constants are corrupt"));
+    }
+}
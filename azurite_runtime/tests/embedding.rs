@@ -0,0 +1,108 @@
+use azurite_archiver::{Packed, Data};
+use azurite_common::{Bytecode, CompilationMetadata, Target};
+use azurite_runtime::{run_packed_returning, run_packed_with_budget, Status, VMData};
+
+
+/// Packs a program without any structures, debug info or line table
+fn pack(metadata: CompilationMetadata, bytecode: Vec<u8>, constants: Vec<u8>) -> Packed {
+    Packed::new()
+        .with(Data(Vec::from(metadata.to_bytes())))
+        .with(Data(bytecode))
+        .with(Data(constants))
+        .with(Data(vec![]))
+        .with(Data(vec![]))
+        .with(Data(vec![]))
+}
+
+
+#[test]
+fn returns_the_result() {
+    let metadata = CompilationMetadata { extern_count: 0, library_count: 0, target: Target::Bytecode };
+
+    let bytecode = vec![
+        Bytecode::LoadConst.as_u8(), 0, 0,
+        Bytecode::Return.as_u8(),
+    ];
+
+    // an i64 constant
+    let mut constants = vec![6];
+    constants.extend(42i64.to_le_bytes());
+
+    let packed = pack(metadata, bytecode, constants);
+
+    let result = run_packed_returning(packed);
+
+    assert!(matches!(result, Ok(v) if v == VMData::new_i64(42)));
+}
+
+
+#[test]
+fn invalid_file() {
+    assert!(run_packed_returning(Packed::new()).is_err());
+}
+
+
+#[test]
+fn other_format_versions_are_rejected() {
+    let metadata = CompilationMetadata { extern_count: 0, library_count: 0, target: Target::Bytecode };
+    let bytecode = vec![Bytecode::Return.as_u8()];
+
+    let mut newer = metadata.to_bytes();
+    newer[0] = CompilationMetadata::FORMAT_VERSION + 1;
+
+    let packed = Packed::new()
+        .with(Data(Vec::from(newer)))
+        .with(Data(bytecode.clone()))
+        .with(Data(vec![]))
+        .with(Data(vec![]))
+        .with(Data(vec![]))
+        .with(Data(vec![]));
+
+    let Err(Status::Err(e)) = run_packed_returning(packed) else { panic!("a file of another version was run") };
+    assert!(e.read_message().to_string_lossy().contains("different version"));
+
+    // Files from before the version was written only
+    // have the extern & library counts as their metadata
+    let older = Packed::new()
+        .with(Data(vec![0; 8]))
+        .with(Data(bytecode))
+        .with(Data(vec![]))
+        .with(Data(vec![]));
+
+    let Err(Status::Err(e)) = run_packed_returning(older) else { panic!("a file of another version was run") };
+    assert!(e.read_message().to_string_lossy().contains("different version"));
+}
+
+
+#[test]
+fn missing_sections_are_rejected() {
+    let metadata = CompilationMetadata { extern_count: 0, library_count: 0, target: Target::Bytecode };
+
+    let packed = Packed::new()
+        .with(Data(Vec::from(metadata.to_bytes())))
+        .with(Data(vec![Bytecode::Return.as_u8()]))
+        .with(Data(vec![]));
+
+    assert!(run_packed_returning(packed).is_err());
+}
+
+
+#[test]
+fn c_target_is_rejected() {
+    let metadata = CompilationMetadata { extern_count: 0, library_count: 0, target: Target::C };
+
+    let packed = pack(metadata, vec![Bytecode::Return.as_u8()], vec![]);
+
+    assert!(run_packed_returning(packed).is_err());
+}
+
+
+#[test]
+fn instruction_budget() {
+    let metadata = CompilationMetadata { extern_count: 0, library_count: 0, target: Target::Bytecode };
+
+    let packed = pack(metadata, vec![Bytecode::Jump.as_u8(), 0, 0, 0, 0], vec![]);
+
+    let Err(Status::Err(e)) = run_packed_with_budget(packed, 10_000) else { panic!("an infinite loop finished") };
+    assert_eq!(e.read_message().to_string_lossy(), "instruction budget exceeded");
+}
@@ -1,14 +1,82 @@
-use rand::{thread_rng, Rng};
+use std::cell::RefCell;
+
+use rand::{thread_rng, Rng, RngCore, SeedableRng, rngs::StdRng};
 use azurite_runtime::{VM, VMData, Status};
 
+
+#[cfg(test)]
+mod tests;
+
+
+thread_local! {
+    /// Set by `rng_seed`, the functions below use
+    /// this instead of `thread_rng` once it is
+    static SEEDED : RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+
 #[no_mangle]
 pub extern "C" fn randi(vm: &mut VM) -> Status {
-    vm.stack.set_reg(0, VMData::new_i64(thread_rng().gen()));
+    vm.stack.set_reg(0, VMData::new_i64(with_rng(|x| x.gen())));
     Status::Ok
 }
 
 #[no_mangle]
 pub extern "C" fn randf(vm: &mut VM) -> Status {
-    vm.stack.set_reg(0, VMData::new_float(thread_rng().gen()));
+    vm.stack.set_reg(0, VMData::new_float(with_rng(|x| x.gen())));
+    Status::Ok
+}
+
+
+/*
+    Seeds the generator so the following
+    numbers are the same on every run
+*/
+#[no_mangle]
+pub extern "C" fn rng_seed(vm: &mut VM) -> Status {
+    seed(vm.stack.reg(1).as_u64());
+    Status::Ok
+}
+
+
+/*
+    Returns an integer in `min..max`, the
+    maximum itself is never returned
+*/
+#[no_mangle]
+pub extern "C" fn rng_range(vm: &mut VM) -> Status {
+    let min = vm.stack.reg(1).as_i64();
+    let max = vm.stack.reg(2).as_i64();
+
+    let value = match range(min, max) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    vm.stack.set_reg(0, VMData::new_i64(value));
     Status::Ok
-}
\ No newline at end of file
+}
+
+
+fn seed(seed: u64) {
+    SEEDED.with(|x| *x.borrow_mut() = Some(StdRng::seed_from_u64(seed)));
+}
+
+
+fn range(min: i64, max: i64) -> Result<i64, String> {
+    if min >= max {
+        return Err(format!("the range {min}..{max} is empty"))
+    }
+
+    Ok(with_rng(|x| x.gen_range(min..max)))
+}
+
+
+/// Calls `function` with the seeded generator if
+/// there is one and `thread_rng` otherwise
+fn with_rng<T>(function: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    SEEDED.with(|x| match x.borrow_mut().as_mut() {
+        Some(rng) => function(rng),
+        None => function(&mut thread_rng()),
+    })
+}
@@ -0,0 +1,51 @@
+use crate::{seed, range, with_rng, SEEDED};
+
+use rand::Rng;
+
+
+fn sequence() -> Vec<i64> {
+    (0..16).map(|_| with_rng(|x| x.gen())).collect()
+}
+
+
+#[test]
+fn seeded_sequences_repeat() {
+    seed(1234);
+    let first = sequence();
+
+    seed(1234);
+    assert_eq!(sequence(), first);
+
+    seed(4321);
+    assert_ne!(sequence(), first, "another seed gives other numbers");
+}
+
+
+#[test]
+fn ranges_stay_in_bounds() {
+    seed(7);
+
+    for _ in 0..1000 {
+        let value = range(-3, 5).unwrap();
+        assert!((-3..5).contains(&value));
+    }
+
+    assert_eq!(range(9, 10), Ok(9), "a range of one value");
+}
+
+
+#[test]
+fn empty_ranges_are_errors() {
+    assert!(range(5, 5).is_err());
+    assert!(range(5, 4).is_err());
+}
+
+
+#[test]
+fn unseeded_generator_uses_entropy() {
+    SEEDED.with(|x| *x.borrow_mut() = None);
+
+    // Not a strict guarantee but 16 equal
+    // random 64-bit integers won't happen
+    assert_ne!(sequence(), sequence());
+}
@@ -1,6 +1,10 @@
 use std::io::Write;
 
-use azurite_runtime::{VM, Object, VMData, FatalError, Status, ObjectIndex, Structure};
+use azurite_runtime::{VM, Object, VMData, FatalError, Status, ObjectIndex, Structure, Map};
+
+
+#[cfg(test)]
+mod tests;
 
 
 #[no_mangle]
@@ -51,9 +55,24 @@ pub extern "C" fn duration_now_nanos(vm: &mut VM) -> Status {
 }
 
 
+/*
+    Nanoseconds since the VM started, measured with
+    a monotonic clock. Unlike `duration_now_*` this
+    isn't affected by changes to the system clock
+*/
+#[no_mangle]
+pub extern "C" fn clock_monotonic_nanos(vm: &mut VM) -> Status {
+    let nanos = vm.monotonic_elapsed().as_nanos();
+
+    vm.stack.set_reg(0, VMData::new_u64(nanos as u64));
+
+    Status::Ok
+}
+
+
 #[no_mangle]
 pub extern "C" fn force_gc(vm: &mut VM) -> Status {
-    vm.run_garbage_collection();
+    vm.run_major_garbage_collection();
     Status::Ok
 }
 
@@ -165,6 +184,244 @@ pub extern "C" fn float_to_str(vm: &mut VM) -> Status {
 }
 
 
+/*
+    Returns whether the difference between the
+    two floats is at most the epsilon, equal
+    infinities are always approximately equal
+*/
+#[no_mangle]
+pub extern "C" fn approx_eq(vm: &mut VM) -> Status {
+    let a = vm.stack.reg(1).as_float();
+    let b = vm.stack.reg(2).as_float();
+    let epsilon = vm.stack.reg(3).as_float();
+
+    vm.stack.set_reg(0, VMData::new_bool(floats_approx_eq(a, b, epsilon)));
+
+    Status::Ok
+}
+
+
+fn floats_approx_eq(a: f64, b: f64, epsilon: f64) -> bool {
+    a == b || (a - b).abs() <= epsilon
+}
+
+
+/*
+    The float functions take the value in register 1
+    and, for the binary ones, the other operand in
+    register 2
+*/
+#[no_mangle]
+pub extern "C" fn float_sqrt(vm: &mut VM) -> Status {
+    float_unary(vm, sqrt)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_abs(vm: &mut VM) -> Status {
+    float_unary(vm, f64::abs)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_floor(vm: &mut VM) -> Status {
+    float_unary(vm, f64::floor)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_ceil(vm: &mut VM) -> Status {
+    float_unary(vm, f64::ceil)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_round(vm: &mut VM) -> Status {
+    float_unary(vm, f64::round)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_sin(vm: &mut VM) -> Status {
+    float_unary(vm, f64::sin)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_cos(vm: &mut VM) -> Status {
+    float_unary(vm, f64::cos)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_tan(vm: &mut VM) -> Status {
+    float_unary(vm, f64::tan)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_log(vm: &mut VM) -> Status {
+    float_unary(vm, f64::ln)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_log2(vm: &mut VM) -> Status {
+    float_unary(vm, f64::log2)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_pow(vm: &mut VM) -> Status {
+    float_binary(vm, f64::powf)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_min(vm: &mut VM) -> Status {
+    float_binary(vm, f64::min)
+}
+
+
+#[no_mangle]
+pub extern "C" fn float_max(vm: &mut VM) -> Status {
+    float_binary(vm, f64::max)
+}
+
+
+/*
+    Integer powers wrap on overflow the same way
+    the runtime's integer arithmetic does
+*/
+#[no_mangle]
+pub extern "C" fn int_pow(vm: &mut VM) -> Status {
+    let base = vm.stack.reg(1).as_i64();
+    let exponent = vm.stack.reg(2).as_i64();
+
+    let result = match integer_pow(base, exponent) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    vm.stack.set_reg(0, VMData::new_i64(result));
+
+    Status::Ok
+}
+
+
+/*
+    Both are always non-negative, an error is
+    returned if the result doesn't fit in an i64
+    like `gcd(i64::MIN, 0)` instead of wrapping
+*/
+#[no_mangle]
+pub extern "C" fn int_gcd(vm: &mut VM) -> Status {
+    integer_binary(vm, gcd)
+}
+
+
+#[no_mangle]
+pub extern "C" fn int_lcm(vm: &mut VM) -> Status {
+    integer_binary(vm, lcm)
+}
+
+
+fn integer_binary(vm: &mut VM, function: fn(i64, i64) -> Result<i64, String>) -> Status {
+    let value = vm.stack.reg(1).as_i64();
+    let other = vm.stack.reg(2).as_i64();
+
+    let result = match function(value, other) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    vm.stack.set_reg(0, VMData::new_i64(result));
+
+    Status::Ok
+}
+
+
+fn float_unary(vm: &mut VM, function: fn(f64) -> f64) -> Status {
+    let value = vm.stack.reg(1).as_float();
+    vm.stack.set_reg(0, VMData::new_float(function(value)));
+
+    Status::Ok
+}
+
+
+fn float_binary(vm: &mut VM, function: fn(f64, f64) -> f64) -> Status {
+    let value = vm.stack.reg(1).as_float();
+    let other = vm.stack.reg(2).as_float();
+    vm.stack.set_reg(0, VMData::new_float(function(value, other)));
+
+    Status::Ok
+}
+
+
+/// The square root of a negative number is always
+/// the same NaN regardless of the platform
+fn sqrt(value: f64) -> f64 {
+    if value < 0.0 {
+        return f64::NAN
+    }
+
+    value.sqrt()
+}
+
+
+fn integer_pow(base: i64, exponent: i64) -> Result<i64, String> {
+    let Ok(mut exponent) = u64::try_from(exponent) else {
+        return Err(format!("can't raise an integer to a negative power ({exponent})"))
+    };
+
+    // `i64::wrapping_pow` only takes a `u32`
+    // exponent so square and multiply by hand
+    let mut base = base;
+    let mut result : i64 = 1;
+
+    while exponent != 0 {
+        if exponent & 1 == 1 {
+            result = result.wrapping_mul(base);
+        }
+
+        base = base.wrapping_mul(base);
+        exponent >>= 1;
+    }
+
+    Ok(result)
+}
+
+
+/// Euclid's algorithm on the magnitudes so
+/// `i64::MIN` doesn't overflow when negated
+fn unsigned_gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+
+fn gcd(a: i64, b: i64) -> Result<i64, String> {
+    let result = unsigned_gcd(a.unsigned_abs(), b.unsigned_abs());
+
+    i64::try_from(result).map_err(|_| format!("the gcd of {a} and {b} doesn't fit in an integer"))
+}
+
+
+fn lcm(a: i64, b: i64) -> Result<i64, String> {
+    if a == 0 || b == 0 {
+        return Ok(0)
+    }
+
+    let gcd = unsigned_gcd(a.unsigned_abs(), b.unsigned_abs());
+    let result = u128::from(a.unsigned_abs() / gcd) * u128::from(b.unsigned_abs());
+
+    i64::try_from(result).map_err(|_| format!("the lcm of {a} and {b} doesn't fit in an integer"))
+}
+
+
 #[no_mangle]
 pub extern "C" fn bool_to_str(vm: &mut VM) -> Status {
     let boolean = vm.stack.reg(1).as_bool();
@@ -176,6 +433,17 @@ pub extern "C" fn bool_to_str(vm: &mut VM) -> Status {
 }
 
 
+#[no_mangle]
+pub extern "C" fn char_to_str(vm: &mut VM) -> Status {
+    let character = vm.stack.reg(1).as_char();
+
+    let object = register_string(vm, character.to_string())?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
 #[no_mangle]
 pub extern "C" fn to_string_float(vm: &mut VM) -> Status {
     let float = vm.stack.reg(1).as_float();
@@ -212,21 +480,1235 @@ pub extern "C" fn string_append(vm: &mut VM) -> Status {
 }
 
 
+/*
+    Strings are case folded by upper-casing and then
+    lower-casing them which, unlike only lower-casing,
+    also folds characters that expand when upper-cased
+    such as 'ß' into "ss"
+*/
 #[no_mangle]
-pub extern "C" fn parse_str_as_int(vm: &mut VM) -> Status {
+pub extern "C" fn string_eq_ignore_case(vm: &mut VM) -> Status {
     let string = vm.stack.reg(1).as_object();
-    let string = vm.objects.get(string).string().trim();
+    let string = vm.objects.get(string).string();
 
-    let Ok(number) = string.parse() else {
-        return Status::err("failed to parse string as int");
+    let other = vm.stack.reg(2).as_object();
+    let other = vm.objects.get(other).string();
+
+    let is_equal = string == other || fold_case(string) == fold_case(other);
+    vm.stack.set_reg(0, VMData::new_bool(is_equal));
+
+    Status::Ok
+}
+
+
+fn fold_case(string: &str) -> String {
+    string.to_uppercase().to_lowercase()
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_to_title_case(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = title_case(vm.objects.get(string).string());
+
+    let object = register_string(vm, string)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+/// Upper-cases the first character of every whitespace
+/// separated word and lower-cases the rest, the whitespace
+/// itself is kept as is. Punctuation within a word doesn't
+/// start a new word so "it's" becomes "It's"
+fn title_case(string: &str) -> String {
+    let mut result = String::with_capacity(string.len());
+    let mut is_word_start = true;
+
+    for c in string.chars() {
+        if c.is_whitespace() {
+            result.push(c);
+            is_word_start = true;
+        } else if is_word_start {
+            result.extend(c.to_uppercase());
+            is_word_start = false;
+        } else {
+            result.extend(c.to_lowercase());
+        }
+    }
+
+    result
+}
+
+
+/*
+    Pads the string with the fill character up to the
+    width in characters. The alignment is the `Align`
+    enum of the API file, centered strings put the odd
+    character of padding on the right
+*/
+#[no_mangle]
+pub extern "C" fn string_pad(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let width = vm.stack.reg(2).as_i64();
+    let align = vm.stack.reg(3).as_i64();
+    let fill = vm.stack.reg(4).as_char();
+
+    let Some(align) = Align::from_discriminant(align) else {
+        return Status::err(format!("{align} isn't a valid alignment"))
     };
 
-    vm.stack.set_reg(0, VMData::new_i64(number));
+    let padded = pad(vm.objects.get(string).string(), width, align, fill);
+
+    let object = register_string(vm, padded)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
 
     Status::Ok
 }
 
 
-fn register_string(vm: &mut VM, string: String) -> core::result::Result<ObjectIndex, FatalError> {
-    vm.create_object(Object::new(string))
+/// Mirrors `Align` in the API file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+
+impl Align {
+    fn from_discriminant(value: i64) -> Option<Self> {
+        match value {
+            0 => Some(Self::Left),
+            1 => Some(Self::Right),
+            2 => Some(Self::Center),
+            _ => None,
+        }
+    }
+}
+
+
+/// Strings which are already at least `width`
+/// characters long are returned unchanged
+fn pad(string: &str, width: i64, align: Align, fill: char) -> String {
+    let length = string.chars().count();
+    let padding = usize::try_from(width).unwrap_or(0).saturating_sub(length);
+
+    let (left, right) = match align {
+        Align::Left   => (0, padding),
+        Align::Right  => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+
+    let mut padded = String::with_capacity(string.len() + padding * fill.len_utf8());
+    padded.extend(std::iter::repeat(fill).take(left));
+    padded.push_str(string);
+    padded.extend(std::iter::repeat(fill).take(right));
+
+    padded
+}
+
+
+/*
+    The trimming functions all use Rust's definition
+    of whitespace which is the unicode `White_Space`
+    property, so non-breaking spaces and the like are
+    trimmed too
+*/
+#[no_mangle]
+pub extern "C" fn string_trim(vm: &mut VM) -> Status {
+    trim_with(vm, str::trim)
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_trim_start(vm: &mut VM) -> Status {
+    trim_with(vm, str::trim_start)
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_trim_end(vm: &mut VM) -> Status {
+    trim_with(vm, str::trim_end)
+}
+
+
+/*
+    Reverses by unicode scalar values instead
+    of bytes so multi-byte characters stay intact
+*/
+#[no_mangle]
+pub extern "C" fn string_reverse(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let reversed = vm.objects.get(string).string().chars().rev().collect::<String>();
+
+    let object = register_string(vm, reversed)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+fn trim_with(vm: &mut VM, trim: fn(&str) -> &str) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let trimmed = trim(vm.objects.get(string).string()).to_string();
+
+    let object = register_string(vm, trimmed)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+/*
+    An empty pattern matches nowhere, so replacing
+    it returns the string unchanged instead of
+    inserting the replacement between every character
+*/
+#[no_mangle]
+pub extern "C" fn string_replace(vm: &mut VM) -> Status {
+    replace_with(vm, usize::MAX)
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_replace_first(vm: &mut VM) -> Status {
+    replace_with(vm, 1)
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_replace_n(vm: &mut VM) -> Status {
+    let count = vm.stack.reg(4).as_i64();
+    let Ok(count) = usize::try_from(count) else {
+        return Status::err(format!("can't replace a negative amount ({count}) of occurrences"))
+    };
+
+    replace_with(vm, count)
+}
+
+
+fn replace_with(vm: &mut VM, count: usize) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let from = vm.stack.reg(2).as_object();
+    let from = vm.objects.get(from).string();
+
+    let to = vm.stack.reg(3).as_object();
+    let to = vm.objects.get(to).string();
+
+    let replaced = if from.is_empty() {
+        string.to_string()
+    } else {
+        string.replacen(from, to, count)
+    };
+
+    let object = register_string(vm, replaced)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+/*
+    Returns the character indices at which each
+    occurrence of the pattern starts
+
+    Occurrences don't overlap, the search continues
+    after the end of each match so "aaaa" contains
+    "aa" twice, at 0 and 2
+*/
+#[no_mangle]
+pub extern "C" fn string_find_all(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let pattern = vm.stack.reg(2).as_object();
+    let pattern = vm.objects.get(pattern).string();
+
+    let mut indices = vec![];
+
+    if !pattern.is_empty() {
+        let mut last_byte = 0;
+        let mut chars = 0;
+
+        for (byte, _) in string.match_indices(pattern) {
+            chars += string[last_byte..byte].chars().count();
+            last_byte = byte;
+
+            indices.push(VMData::new_i64(chars as i64));
+        }
+    }
+
+    let list = register_list(vm, indices)?;
+    vm.stack.set_reg(0, VMData::new_list(list));
+
+    Status::Ok
+}
+
+
+/*
+    Counts the occurrences the same way `string_find_all`
+    finds them, but an empty pattern is an error rather
+    than a zero since counting it is always a mistake
+*/
+#[no_mangle]
+pub extern "C" fn string_count(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let pattern = vm.stack.reg(2).as_object();
+    let pattern = vm.objects.get(pattern).string();
+
+    let count = match count_matches(string, pattern) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    vm.stack.set_reg(0, VMData::new_i64(count as i64));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_length(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let length = vm.objects.get(string).string().chars().count();
+
+    vm.stack.set_reg(0, VMData::new_i64(length as i64));
+
+    Status::Ok
+}
+
+
+/*
+    Returns the characters from `start` up to but
+    not including `end`. Both are character indices
+    so a substring can't split a multi-byte character
+*/
+#[no_mangle]
+pub extern "C" fn string_substring(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let start = vm.stack.reg(2).as_i64();
+    let end = vm.stack.reg(3).as_i64();
+
+    let substring = match substring(string, start, end) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    let object = register_string(vm, substring)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+/*
+    Both return a new string and take character
+    indices like `string_substring`
+*/
+#[no_mangle]
+pub extern "C" fn string_insert(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let index = vm.stack.reg(2).as_i64();
+
+    let inserted = vm.stack.reg(3).as_object();
+    let inserted = vm.objects.get(inserted).string();
+
+    let result = match insert(string, index, inserted) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    let object = register_string(vm, result)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_remove_range(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let start = vm.stack.reg(2).as_i64();
+    let end = vm.stack.reg(3).as_i64();
+
+    let result = match remove_range(string, start, end) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    let object = register_string(vm, result)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_contains(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let pattern = vm.stack.reg(2).as_object();
+    let pattern = vm.objects.get(pattern).string();
+
+    vm.stack.set_reg(0, VMData::new_bool(string.contains(pattern)));
+
+    Status::Ok
+}
+
+
+/*
+    Returns the character index of the first
+    occurrence of the pattern or -1 if there
+    is none
+*/
+#[no_mangle]
+pub extern "C" fn string_index_of(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let pattern = vm.stack.reg(2).as_object();
+    let pattern = vm.objects.get(pattern).string();
+
+    let index = index_of(string, pattern).map_or(-1, |x| x as i64);
+    vm.stack.set_reg(0, VMData::new_i64(index));
+
+    Status::Ok
+}
+
+
+/*
+    An empty separator matches nowhere, the same as
+    an empty pattern does for `string_replace`, so
+    the whole string is the only part
+*/
+#[no_mangle]
+pub extern "C" fn string_split(vm: &mut VM) -> Status {
+    // Same as `split_lines`, the list has to be
+    // reachable before the parts are allocated
+    let list = register_list(vm, vec![])?;
+    vm.stack.set_reg(0, VMData::new_list(list));
+
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let separator = vm.stack.reg(2).as_object();
+    let separator = vm.objects.get(separator).string();
+
+    let parts = split(string, separator);
+
+    for part in parts {
+        let part = VMData::new_string(register_string(vm, part)?);
+        vm.objects.get_mut(list).list_mut()?.push(part);
+    }
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_to_upper(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let upper = vm.objects.get(string).string().to_uppercase();
+
+    let object = register_string(vm, upper)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_to_lower(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let lower = vm.objects.get(string).string().to_lowercase();
+
+    let object = register_string(vm, lower)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+fn substring(string: &str, start: i64, end: i64) -> Result<String, String> {
+    let length = string.chars().count();
+
+    let (Ok(start), Ok(end)) = (usize::try_from(start), usize::try_from(end)) else {
+        return Err(format!("the range {start}..{end} is out of bounds for a string of {length} characters"))
+    };
+
+    if start > end || end > length {
+        return Err(format!("the range {start}..{end} is out of bounds for a string of {length} characters"))
+    }
+
+    Ok(string.chars().skip(start).take(end - start).collect())
+}
+
+
+/// The byte offset of the character at `index`, the
+/// length of the string being the index past the end
+fn byte_offset(string: &str, index: usize) -> usize {
+    string.char_indices().nth(index).map_or(string.len(), |x| x.0)
+}
+
+
+fn insert(string: &str, index: i64, inserted: &str) -> Result<String, String> {
+    let length = string.chars().count();
+
+    let Some(index) = usize::try_from(index).ok().filter(|x| *x <= length) else {
+        return Err(format!("the index {index} is out of bounds for a string of {length} characters"))
+    };
+
+    let offset = byte_offset(string, index);
+
+    let mut result = String::with_capacity(string.len() + inserted.len());
+    result.push_str(&string[..offset]);
+    result.push_str(inserted);
+    result.push_str(&string[offset..]);
+
+    Ok(result)
+}
+
+
+fn remove_range(string: &str, start: i64, end: i64) -> Result<String, String> {
+    let length = string.chars().count();
+
+    let (Ok(start), Ok(end)) = (usize::try_from(start), usize::try_from(end)) else {
+        return Err(format!("the range {start}..{end} is out of bounds for a string of {length} characters"))
+    };
+
+    if start > end || end > length {
+        return Err(format!("the range {start}..{end} is out of bounds for a string of {length} characters"))
+    }
+
+    let mut result = string.to_string();
+    result.replace_range(byte_offset(string, start)..byte_offset(string, end), "");
+
+    Ok(result)
+}
+
+
+fn index_of(string: &str, pattern: &str) -> Option<usize> {
+    let byte = string.find(pattern)?;
+    Some(string[..byte].chars().count())
+}
+
+
+fn count_matches(string: &str, pattern: &str) -> Result<usize, String> {
+    if pattern.is_empty() {
+        return Err("can't count the occurrences of an empty pattern".to_string())
+    }
+
+    let mut count = 0;
+    let mut rest = string;
+
+    while let Some(char) = rest.chars().next() {
+        if rest.starts_with(pattern) {
+            count += 1;
+            rest = &rest[pattern.len()..];
+        } else {
+            rest = &rest[char.len_utf8()..];
+        }
+    }
+
+    Ok(count)
+}
+
+
+fn split(string: &str, separator: &str) -> Vec<String> {
+    if separator.is_empty() {
+        return vec![string.to_string()]
+    }
+
+    string.split(separator).map(str::to_string).collect()
+}
+
+
+/*
+    Returns the character at the given index
+
+    The index counts characters, not bytes, so
+    multi-byte characters take up a single index.
+    A negative index counts from the end, `-1`
+    being the last character
+*/
+#[no_mangle]
+pub extern "C" fn str_char_at(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let index = vm.stack.reg(2).as_i64();
+
+    let Some(character) = char_at(string, index) else {
+        return Status::err(format!("index {index} is out of bounds for a string of {} characters", string.chars().count()))
+    };
+
+    vm.stack.set_reg(0, VMData::new_char(character));
+
+    Status::Ok
+}
+
+
+/*
+    Same as `str_char_at` but returns the
+    character as a string of length one
+*/
+#[no_mangle]
+pub extern "C" fn string_char_at_str(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let index = vm.stack.reg(2).as_i64();
+
+    let Some(character) = char_at(string, index) else {
+        return Status::err(format!("index {index} is out of bounds for a string of {} characters", string.chars().count()))
+    };
+
+    let object = register_string(vm, character.to_string())?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+fn char_at(string: &str, index: i64) -> Option<char> {
+    let index = if index < 0 {
+        i64::try_from(string.chars().count()).ok()? + index
+    } else {
+        index
+    };
+
+    string.chars().nth(usize::try_from(index).ok()?)
+}
+
+
+#[no_mangle]
+pub extern "C" fn parse_str_as_int(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string().trim();
+
+    let Ok(number) = string.parse() else {
+        return Status::err("failed to parse string as int");
+    };
+
+    vm.stack.set_reg(0, VMData::new_i64(number));
+
+    Status::Ok
+}
+
+
+/*
+    Only the exact words `true` & `false` are
+    accepted, numbers like `1` & `0` aren't
+*/
+#[no_mangle]
+pub extern "C" fn parse_bool(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let string = vm.objects.get(string).string();
+
+    let Some(value) = str_as_bool(string) else {
+        return Status::err("failed to parse string as bool");
+    };
+
+    vm.stack.set_reg(0, VMData::new_bool(value));
+
+    Status::Ok
+}
+
+
+fn str_as_bool(string: &str) -> Option<bool> {
+    match string.trim() {
+        "true"  => Some(true),
+        "false" => Some(false),
+        _       => None,
+    }
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_split_lines(vm: &mut VM) -> Status {
+    // The list is stored in the return register before
+    // the lines are allocated so a garbage collection
+    // triggered by `register_string` can see it
+    let list = register_list(vm, vec![])?;
+    vm.stack.set_reg(0, VMData::new_list(list));
+
+    let string = vm.stack.reg(1).as_object();
+    let lines = vm.objects.get(string).string()
+        .lines()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    for line in lines {
+        let line = VMData::new_string(register_string(vm, line)?);
+        vm.objects.get_mut(list).list_mut()?.push(line);
+    }
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_parse_csv_line(vm: &mut VM) -> Status {
+    // Same as `split_lines`, the list has to be
+    // reachable before the fields are allocated
+    let list = register_list(vm, vec![])?;
+    vm.stack.set_reg(0, VMData::new_list(list));
+
+    let string = vm.stack.reg(1).as_object();
+    let fields = parse_csv_line(vm.objects.get(string).string());
+
+    for field in fields {
+        let field = VMData::new_string(register_string(vm, field)?);
+        vm.objects.get_mut(list).list_mut()?.push(field);
+    }
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_line_count(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let count = line_count(vm.objects.get(string).string());
+
+    vm.stack.set_reg(0, VMData::new_i64(count as i64));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn string_word_count(vm: &mut VM) -> Status {
+    let string = vm.stack.reg(1).as_object();
+    let count = word_count(vm.objects.get(string).string());
+
+    vm.stack.set_reg(0, VMData::new_i64(count as i64));
+
+    Status::Ok
+}
+
+
+/// Counts lines the same way `split_lines` splits
+/// them so a trailing line ending doesn't start an
+/// extra, empty line
+fn line_count(string: &str) -> usize {
+    string.lines().count()
+}
+
+
+/// Splits a line of comma separated values. Commas inside
+/// a quoted field don't separate it and `""` inside one is
+/// an escaped quote, the surrounding quotes are removed
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut is_quoted = false;
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if is_quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            },
+
+            '"' => is_quoted = !is_quoted,
+
+            ',' if !is_quoted => fields.push(std::mem::take(&mut field)),
+
+            _ => field.push(c),
+        }
+    }
+
+    fields.push(field);
+    fields
+}
+
+
+/// A word is a run of characters which aren't
+/// unicode whitespace
+fn word_count(string: &str) -> usize {
+    string.split_whitespace().count()
+}
+
+
+
+/*
+    The bytes are given as a `[u8]`. The strict
+    version errors on invalid utf-8 while the lossy
+    one replaces every invalid sequence with U+FFFD
+*/
+#[no_mangle]
+pub extern "C" fn bytes_to_string(vm: &mut VM) -> Status {
+    let bytes = list_bytes(vm, 1)?;
+
+    let string = match utf8_strict(bytes) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    let object = register_string(vm, string)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn bytes_to_string_lossy(vm: &mut VM) -> Status {
+    let bytes = list_bytes(vm, 1)?;
+
+    let object = register_string(vm, utf8_lossy(&bytes))?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn is_valid_utf8(vm: &mut VM) -> Status {
+    let bytes = list_bytes(vm, 1)?;
+
+    vm.stack.set_reg(0, VMData::new_bool(std::str::from_utf8(&bytes).is_ok()));
+
+    Status::Ok
+}
+
+
+fn list_bytes(vm: &VM, reg: u8) -> Result<Vec<u8>, FatalError> {
+    let list = vm.stack.reg(reg).as_object();
+    Ok(vm.objects.get(list).list()?.iter().map(|x| x.as_u8()).collect())
+}
+
+
+fn utf8_strict(bytes: Vec<u8>) -> Result<String, String> {
+    String::from_utf8(bytes).map_err(|e| {
+        let error = e.utf8_error();
+        format!("the bytes aren't valid utf-8, the first invalid sequence starts at index {}", error.valid_up_to())
+    })
+}
+
+
+fn utf8_lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[no_mangle]
+pub extern "C" fn list_join(vm: &mut VM) -> Status {
+    let list = vm.stack.reg(1).as_object();
+    let separator = vm.stack.reg(2).as_object();
+
+    let string = {
+        let separator = vm.objects.get(separator).string();
+        let list = vm.objects.get(list).list()?;
+
+        if let Some(index) = list.iter().position(|x| x.tag() != VMData::TAG_STR) {
+            return Status::err(format!("the element at index {index} isn't a string"))
+        }
+
+        let strings = list
+            .iter()
+            .map(|x| vm.objects.get(x.as_object()).string().as_str())
+            .collect::<Vec<_>>();
+
+        strings.join(separator)
+    };
+
+    let object = register_string(vm, string)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn list_len(vm: &mut VM) -> Status {
+    let list = vm.stack.reg(1).as_object();
+    let len = vm.objects.get(list).list()?.len();
+
+    vm.stack.set_reg(0, VMData::new_i64(len as i64));
+
+    Status::Ok
+}
+
+
+/*
+    Until functions can be passed as values the
+    function is given as its bytecode offset
+*/
+#[no_mangle]
+pub extern "C" fn list_map(vm: &mut VM) -> Status {
+    let list = vm.stack.reg(1).as_object();
+    let Ok(function) = u32::try_from(vm.stack.reg(2).as_i64()) else {
+        return Status::err("the function offset is out of bounds")
+    };
+
+    match vm.list_map(list, function) {
+        Ok(_) => Status::Ok,
+        Err(e) => e,
+    }
+}
+
+
+#[no_mangle]
+pub extern "C" fn list_filter(vm: &mut VM) -> Status {
+    let list = vm.stack.reg(1).as_object();
+    let Ok(function) = u32::try_from(vm.stack.reg(2).as_i64()) else {
+        return Status::err("the function offset is out of bounds")
+    };
+
+    match vm.list_filter(list, function) {
+        Ok(_) => Status::Ok,
+        Err(e) => e,
+    }
+}
+
+
+#[no_mangle]
+pub extern "C" fn list_new(vm: &mut VM) -> Status {
+    let list = register_list(vm, vec![])?;
+    vm.stack.set_reg(0, VMData::new_list(list));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn list_push(vm: &mut VM) -> Status {
+    let list = vm.stack.reg(1).as_object();
+    let value = vm.stack.reg(2);
+
+    vm.objects.get_mut(list).list_mut()?.push(value);
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn list_get(vm: &mut VM) -> Status {
+    let list = vm.objects.get(vm.stack.reg(1).as_object()).list()?;
+
+    let index = match list_index(vm.stack.reg(2).as_i64(), list.len()) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    vm.stack.set_reg(0, list[index]);
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn list_set(vm: &mut VM) -> Status {
+    let value = vm.stack.reg(3);
+    let list = vm.objects.get_mut(vm.stack.reg(1).as_object()).list_mut()?;
+
+    let index = match list_index(vm.stack.reg(2).as_i64(), list.len()) {
+        Ok(v) => v,
+        Err(e) => return Status::err(e),
+    };
+
+    list[index] = value;
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn list_pop(vm: &mut VM) -> Status {
+    let list = vm.objects.get_mut(vm.stack.reg(1).as_object()).list_mut()?;
+
+    let Some(value) = list.pop() else {
+        return Status::err("can't pop from an empty list")
+    };
+
+    vm.stack.set_reg(0, value);
+
+    Status::Ok
+}
+
+
+/// Bounds checks an index given by the program, negative
+/// indices count from the end like in `str_char_at`
+fn list_index(index: i64, len: usize) -> Result<usize, String> {
+    let position = if index < 0 {
+        i64::try_from(len).ok().map(|len| len + index)
+    } else {
+        Some(index)
+    };
+
+    position
+        .and_then(|x| usize::try_from(x).ok())
+        .filter(|x| *x < len)
+        .ok_or_else(|| format!("index {index} is out of bounds for a list of length {len}"))
+}
+
+
+#[no_mangle]
+pub extern "C" fn hash(vm: &mut VM) -> Status {
+    let value = vm.stack.reg(1);
+
+    let Some(hash) = vm.hash(value) else {
+        return Status::err("the value can't be hashed")
+    };
+
+    vm.stack.set_reg(0, VMData::new_i64(hash));
+
+    Status::Ok
+}
+
+
+/*
+    Replaces every `{field}` in the template with the
+    value of that field in the given structure. `{{`
+    and `}}` are written as `{` and `}`
+*/
+#[no_mangle]
+pub extern "C" fn format_struct(vm: &mut VM) -> Status {
+    let template = vm.stack.reg(1).as_object();
+    let value = vm.stack.reg(2);
+
+    let Some(layout) = vm.structure_layout(value.tag()) else {
+        return Status::err("the value given to `format` isn't a structure")
+    };
+
+    let fields = vm.objects.get(value.as_object()).structure().fields();
+    let template = vm.objects.get(template).string();
+
+    let mut string = String::with_capacity(template.len());
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                string.push('{');
+            },
+
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                string.push('}');
+            },
+
+            '{' => {
+                let Some((name, rest)) = chars.as_str().split_once('}') else {
+                    return Status::err("unterminated placeholder in the format string")
+                };
+
+                let Some(index) = layout.fields.iter().position(|x| x == name) else {
+                    return Status::err(format!("{} has no field named '{name}'", layout.name))
+                };
+
+                let field = fields[index];
+                if field.tag() == VMData::TAG_STR {
+                    string.push_str(vm.objects.get(field.as_object()).string());
+                } else if field.is_object() {
+                    return Status::err(format!("the field '{name}' can't be formatted"))
+                } else {
+                    string.push_str(&field.to_string());
+                }
+
+                chars = rest.chars();
+            },
+
+            _ => string.push(c),
+        }
+    }
+
+    let object = register_string(vm, string)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+/*
+    The file functions never stop the program, a
+    failure is reported through their return value
+    so the program can recover from it
+
+    Reading returns a list which holds the contents
+    of the file if it could be read and is empty
+    otherwise, the API file turns it into a `Maybe`
+*/
+#[no_mangle]
+pub extern "C" fn file_read_to_string(vm: &mut VM) -> Status {
+    let list = register_list(vm, vec![])?;
+    vm.stack.set_reg(0, VMData::new_list(list));
+
+    let path = vm.stack.reg(1).as_object();
+    let contents = read_file(vm.objects.get(path).string());
+
+    if let Some(contents) = contents {
+        let contents = VMData::new_string(register_string(vm, contents)?);
+        vm.objects.get_mut(list).list_mut()?.push(contents);
+    }
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn file_write(vm: &mut VM) -> Status {
+    file_write_with(vm, false)
+}
+
+
+#[no_mangle]
+pub extern "C" fn file_append(vm: &mut VM) -> Status {
+    file_write_with(vm, true)
+}
+
+
+#[no_mangle]
+pub extern "C" fn file_exists(vm: &mut VM) -> Status {
+    let path = vm.stack.reg(1).as_object();
+    let exists = std::path::Path::new(vm.objects.get(path).string()).is_file();
+
+    vm.stack.set_reg(0, VMData::new_bool(exists));
+
+    Status::Ok
+}
+
+
+fn file_write_with(vm: &mut VM, append: bool) -> Status {
+    let path = vm.stack.reg(1).as_object();
+    let contents = vm.stack.reg(2).as_object();
+
+    let is_ok = write_file(
+        vm.objects.get(path).string(),
+        vm.objects.get(contents).string(),
+        append,
+    );
+
+    vm.stack.set_reg(0, VMData::new_bool(is_ok));
+
+    Status::Ok
+}
+
+
+fn read_file(path: &str) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+
+/// Writes `contents` to the file at `path`, creating it
+/// if it doesn't exist. The file is truncated first
+/// unless `append` is set
+fn write_file(path: &str, contents: &str, append: bool) -> bool {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path);
+
+    file.and_then(|mut x| x.write_all(contents.as_bytes())).is_ok()
+}
+
+
+#[no_mangle]
+pub extern "C" fn map_new(vm: &mut VM) -> Status {
+    let map = vm.create_object(Object::new(Map::new()))?;
+    vm.stack.set_reg(0, VMData::new_map(map));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn map_insert(vm: &mut VM) -> Status {
+    let map = vm.stack.reg(1).as_object();
+    let key = vm.stack.reg(2);
+    let value = vm.stack.reg(3);
+
+    vm.map_insert(map, key, value)?;
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn map_get(vm: &mut VM) -> Status {
+    let map = vm.stack.reg(1).as_object();
+    let key = vm.stack.reg(2);
+
+    let Some(value) = vm.map_get(map, key)? else {
+        return Status::err("the key doesn't exist in the map")
+    };
+
+    vm.stack.set_reg(0, value);
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn map_remove(vm: &mut VM) -> Status {
+    let map = vm.stack.reg(1).as_object();
+    let key = vm.stack.reg(2);
+
+    let removed = vm.map_remove(map, key)?;
+    vm.stack.set_reg(0, VMData::new_bool(removed.is_some()));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn map_contains(vm: &mut VM) -> Status {
+    let map = vm.stack.reg(1).as_object();
+    let key = vm.stack.reg(2);
+
+    let contains = vm.map_get(map, key)?.is_some();
+    vm.stack.set_reg(0, VMData::new_bool(contains));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn map_len(vm: &mut VM) -> Status {
+    let map = vm.stack.reg(1).as_object();
+    let len = vm.objects.get(map).map()?.len();
+
+    vm.stack.set_reg(0, VMData::new_i64(len as i64));
+
+    Status::Ok
+}
+
+
+#[no_mangle]
+pub extern "C" fn map_to_str(vm: &mut VM) -> Status {
+    let map = vm.stack.reg(1).as_object();
+    let string = vm.map_to_string(map)?;
+
+    let object = register_string(vm, string)?;
+    vm.stack.set_reg(0, VMData::new_string(object));
+
+    Status::Ok
+}
+
+
+fn register_string(vm: &mut VM, string: String) -> core::result::Result<ObjectIndex, FatalError> {
+    vm.create_object(Object::new(string))
+}
+
+
+fn register_list(vm: &mut VM, list: Vec<VMData>) -> core::result::Result<ObjectIndex, FatalError> {
+    vm.create_object(Object::new(list))
 }
\ No newline at end of file
@@ -0,0 +1,345 @@
+use crate::{str_as_bool, char_at, floats_approx_eq, line_count, word_count, parse_csv_line, list_index, title_case, substring, insert, remove_range, index_of, count_matches, split, sqrt, integer_pow, gcd, lcm, read_file, write_file, pad, Align, utf8_strict, utf8_lossy};
+
+
+#[test]
+fn parse_bool() {
+    assert_eq!(str_as_bool("true"), Some(true));
+    assert_eq!(str_as_bool("false"), Some(false));
+    assert_eq!(str_as_bool(" true\n"), Some(true), "surrounding whitespace is ignored");
+}
+
+
+#[test]
+fn parse_invalid_bool() {
+    assert_eq!(str_as_bool("1"), None);
+    assert_eq!(str_as_bool("0"), None);
+    assert_eq!(str_as_bool("True"), None);
+    assert_eq!(str_as_bool("yes"), None);
+    assert_eq!(str_as_bool(""), None);
+}
+
+
+#[test]
+fn char_at_negative_index() {
+    assert_eq!(char_at("hello", -1), Some('o'));
+    assert_eq!(char_at("hello", -5), Some('h'));
+    assert_eq!(char_at("café", -1), Some('é'), "counts characters, not bytes");
+}
+
+
+#[test]
+fn char_at_out_of_bounds() {
+    assert_eq!(char_at("hello", 5), None);
+    assert_eq!(char_at("hello", -6), None);
+    assert_eq!(char_at("", -1), None);
+    assert_eq!(char_at("hello", i64::MIN), None);
+}
+
+
+#[test]
+fn approx_eq() {
+    assert!(floats_approx_eq(0.1 + 0.2, 0.3, 1e-9));
+    assert!(floats_approx_eq(f64::INFINITY, f64::INFINITY, 0.0));
+    assert!(!floats_approx_eq(1.0, 1.1, 0.05));
+    assert!(!floats_approx_eq(f64::NAN, f64::NAN, 1.0));
+}
+
+
+#[test]
+fn line_count_matches_split_lines() {
+    assert_eq!(line_count("first\nsecond\r\nthird"), 3);
+    assert_eq!(line_count("first\nsecond\n"), 2, "a trailing line ending doesn't add a line");
+    assert_eq!(line_count("\nsecond"), 2, "a leading line ending adds an empty line");
+    assert_eq!(line_count(""), 0);
+}
+
+
+#[test]
+fn word_count_ignores_surrounding_whitespace() {
+    assert_eq!(word_count("the quick\nbrown  fox"), 4);
+    assert_eq!(word_count("  leading and trailing\t\n"), 3);
+    assert_eq!(word_count(" \n\t "), 0);
+    assert_eq!(word_count(""), 0);
+}
+
+
+#[test]
+fn substrings_use_character_indices() {
+    assert_eq!(substring("hello", 1, 4), Ok("ell".to_string()));
+    assert_eq!(substring("café au lait", 3, 6), Ok("é a".to_string()));
+    assert_eq!(substring("hello", 0, 5), Ok("hello".to_string()));
+    assert_eq!(substring("hello", 2, 2), Ok(String::new()), "an empty range");
+}
+
+
+#[test]
+fn substrings_out_of_bounds() {
+    assert!(substring("hello", 0, 6).is_err());
+    assert!(substring("hello", 3, 2).is_err(), "start after end");
+    assert!(substring("hello", -1, 2).is_err());
+    assert!(substring("é", 0, 2).is_err(), "a multi-byte character is one index");
+}
+
+
+#[test]
+fn insert_at_character_index() {
+    assert_eq!(insert("held", 3, "lo wor"), Ok("hello word".to_string()));
+    assert_eq!(insert("café", 3, "f"), Ok("caffé".to_string()), "before a multi-byte character");
+    assert_eq!(insert("café", 4, "s"), Ok("cafés".to_string()), "at the end");
+    assert_eq!(insert("", 0, "é"), Ok("é".to_string()));
+}
+
+
+#[test]
+fn insert_out_of_bounds() {
+    assert!(insert("café", 5, "x").is_err());
+    assert!(insert("café", -1, "x").is_err());
+}
+
+
+#[test]
+fn remove_character_range() {
+    assert_eq!(remove_range("hello world", 5, 11), Ok("hello".to_string()));
+    assert_eq!(remove_range("naïve café", 2, 8), Ok("nafé".to_string()), "multi-byte characters are one index");
+    assert_eq!(remove_range("hello", 2, 2), Ok("hello".to_string()), "an empty range");
+}
+
+
+#[test]
+fn remove_range_out_of_bounds() {
+    assert!(remove_range("café", 0, 5).is_err());
+    assert!(remove_range("café", 3, 2).is_err(), "start after end");
+    assert!(remove_range("café", -1, 2).is_err());
+}
+
+
+#[test]
+fn index_of_counts_characters() {
+    assert_eq!(index_of("hello", "l"), Some(2));
+    assert_eq!(index_of("café bar", "bar"), Some(5));
+    assert_eq!(index_of("hello", "x"), None);
+    assert_eq!(index_of("hello", ""), Some(0));
+}
+
+
+#[test]
+fn count_matches_non_overlapping() {
+    assert_eq!(count_matches("hello", "x"), Ok(0));
+    assert_eq!(count_matches("hello", "ell"), Ok(1));
+    assert_eq!(count_matches("a-b-c-d", "-"), Ok(3));
+    assert_eq!(count_matches("aaaa", "aa"), Ok(2), "overlapping occurrences aren't counted");
+    assert_eq!(count_matches("aaa", "aa"), Ok(1));
+    assert_eq!(count_matches("café-é", "é"), Ok(2), "multi-byte pattern");
+    assert_eq!(count_matches("", "a"), Ok(0));
+}
+
+
+#[test]
+fn count_matches_empty_pattern() {
+    assert!(count_matches("abc", "").is_err());
+    assert!(count_matches("", "").is_err());
+}
+
+
+#[test]
+fn split_on_separator() {
+    assert_eq!(split("a,b,,c", ","), ["a", "b", "", "c"]);
+    assert_eq!(split("a::b", "::"), ["a", "b"]);
+    assert_eq!(split("abc", ""), ["abc"], "an empty separator matches nowhere");
+    assert_eq!(split("", ","), [""]);
+}
+
+
+#[test]
+fn pad_alignments() {
+    assert_eq!(pad("ab", 5, Align::Left, '.'), "ab...");
+    assert_eq!(pad("ab", 5, Align::Right, '.'), "...ab");
+    assert_eq!(pad("ab", 6, Align::Center, '.'), "..ab..");
+    assert_eq!(pad("ab", 5, Align::Center, '.'), ".ab..", "the odd character goes on the right");
+}
+
+
+#[test]
+fn pad_counts_characters() {
+    assert_eq!(pad("é", 3, Align::Right, ' '), "  é");
+    assert_eq!(pad("x", 3, Align::Left, '─'), "x──", "multi-byte fill characters");
+}
+
+
+#[test]
+fn pad_long_strings_unchanged() {
+    assert_eq!(pad("hello", 3, Align::Center, '.'), "hello");
+    assert_eq!(pad("hello", 5, Align::Left, '.'), "hello");
+    assert_eq!(pad("hello", -1, Align::Right, '.'), "hello");
+}
+
+
+#[test]
+fn title_case_words() {
+    assert_eq!(title_case("hello world"), "Hello World");
+    assert_eq!(title_case("hELLO wORLD"), "Hello World", "the rest of a word is lower-cased");
+    assert_eq!(title_case("  two\tspaced\nwords "), "  Two\tSpaced\nWords ", "whitespace is kept");
+    assert_eq!(title_case(""), "");
+}
+
+
+#[test]
+fn title_case_punctuation() {
+    assert_eq!(title_case("hello, world!"), "Hello, World!");
+    assert_eq!(title_case("it's o'CLOCK"), "It's O'clock", "punctuation doesn't start a word");
+    assert_eq!(title_case("(quoted) text"), "(quoted) Text");
+}
+
+
+#[test]
+fn title_case_unicode() {
+    assert_eq!(title_case("élan ÜBER ωμέγα"), "Élan Über Ωμέγα");
+}
+
+
+#[test]
+fn csv_fields() {
+    assert_eq!(parse_csv_line("a,b,c"), ["a", "b", "c"]);
+    assert_eq!(parse_csv_line("a,,c,"), ["a", "", "c", ""], "empty fields are kept");
+    assert_eq!(parse_csv_line(""), [""]);
+}
+
+
+#[test]
+fn csv_quoted_fields() {
+    assert_eq!(parse_csv_line(r#""a,b",c"#), ["a,b", "c"], "commas inside quotes don't separate");
+    assert_eq!(parse_csv_line(r#""say ""hi""",x"#), [r#"say "hi""#, "x"]);
+    assert_eq!(parse_csv_line(r#""""#), [""], "an empty quoted field");
+    assert_eq!(parse_csv_line(r#""a"",b""#), [r#"a",b"#], "an escaped quote doesn't end the field");
+}
+
+
+#[test]
+fn valid_utf8_bytes() {
+    assert_eq!(utf8_strict("café".as_bytes().to_vec()), Ok(String::from("café")));
+    assert_eq!(utf8_lossy("café".as_bytes()), "café");
+}
+
+
+#[test]
+fn invalid_utf8_bytes() {
+    let bytes = vec![b'h', b'i', 0xff, b'!'];
+
+    assert!(utf8_strict(bytes.clone()).unwrap_err().contains("index 2"));
+    assert_eq!(utf8_lossy(&bytes), "hi\u{fffd}!");
+    assert_eq!(utf8_lossy(&[0xe2, 0x82]), "\u{fffd}", "a truncated sequence is replaced once");
+}
+
+
+#[test]
+fn list_indices_are_bounds_checked() {
+    assert_eq!(list_index(0, 3), Ok(0));
+    assert_eq!(list_index(2, 3), Ok(2));
+    assert_eq!(list_index(3, 3), Err("index 3 is out of bounds for a list of length 3".to_string()));
+    assert_eq!(list_index(-1, 3), Ok(2), "negative indices count from the end");
+    assert_eq!(list_index(-3, 3), Ok(0));
+    assert_eq!(list_index(-4, 3), Err("index -4 is out of bounds for a list of length 3".to_string()));
+    assert_eq!(list_index(i64::MIN, 3), Err(format!("index {} is out of bounds for a list of length 3", i64::MIN)));
+    assert!(list_index(0, 0).is_err(), "an empty list has no valid index");
+    assert!(list_index(-1, 0).is_err());
+}
+
+
+#[test]
+fn sqrt_of_negatives_is_nan() {
+    assert_eq!(sqrt(16.0), 4.0);
+    assert_eq!(sqrt(0.0), 0.0);
+    assert!(sqrt(-1.0).is_nan());
+    assert!(sqrt(f64::NEG_INFINITY).is_nan());
+    assert_eq!(sqrt(-1.0).to_bits(), sqrt(-4.0).to_bits(), "the same NaN every time");
+}
+
+
+#[test]
+fn integer_powers() {
+    assert_eq!(integer_pow(2, 10), Ok(1024));
+    assert_eq!(integer_pow(-3, 3), Ok(-27));
+    assert_eq!(integer_pow(7, 0), Ok(1));
+    assert_eq!(integer_pow(0, 0), Ok(1));
+    assert!(integer_pow(2, -1).is_err());
+}
+
+
+#[test]
+fn integer_powers_wrap() {
+    assert_eq!(integer_pow(2, 63), Ok(i64::MIN));
+    assert_eq!(integer_pow(2, 64), Ok(0));
+    assert_eq!(integer_pow(3, 41), Ok(3i64.wrapping_pow(41)));
+    assert_eq!(integer_pow(-1, i64::MAX), Ok(-1), "exponents past u32::MAX");
+}
+
+
+#[test]
+fn greatest_common_divisor() {
+    assert_eq!(gcd(12, 18), Ok(6));
+    assert_eq!(gcd(-12, 18), Ok(6));
+    assert_eq!(gcd(7, 0), Ok(7));
+    assert_eq!(gcd(0, 0), Ok(0));
+    assert_eq!(gcd(i64::MIN, 6), Ok(2));
+    assert!(gcd(i64::MIN, 0).is_err());
+}
+
+
+#[test]
+fn least_common_multiple() {
+    assert_eq!(lcm(4, 6), Ok(12));
+    assert_eq!(lcm(-4, 6), Ok(12));
+    assert_eq!(lcm(0, 6), Ok(0));
+    assert_eq!(lcm(i64::MAX, 1), Ok(i64::MAX));
+}
+
+
+#[test]
+fn least_common_multiple_overflow() {
+    assert!(lcm(i64::MAX, 2).is_err());
+    assert!(lcm(i64::MIN, 3).is_err());
+    assert!(lcm(i64::MAX, i64::MAX - 1).is_err(), "consecutive values are coprime");
+}
+
+
+fn temp_file(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("azurite_std_{}_{name}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    path.to_string_lossy().into_owned()
+}
+
+
+#[test]
+fn write_then_read() {
+    let path = temp_file("write_then_read");
+
+    assert!(write_file(&path, "first", false));
+    assert_eq!(read_file(&path), Some("first".to_string()));
+
+    assert!(write_file(&path, "second", false));
+    assert_eq!(read_file(&path), Some("second".to_string()), "writing replaces the contents");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+
+#[test]
+fn append_to_file() {
+    let path = temp_file("append_to_file");
+
+    assert!(write_file(&path, "a", true), "appending creates the file");
+    assert!(write_file(&path, "b", true));
+    assert_eq!(read_file(&path), Some("ab".to_string()));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+
+#[test]
+fn file_errors_are_recoverable() {
+    let path = temp_file("missing");
+
+    assert_eq!(read_file(&path), None);
+    assert!(!write_file(&format!("{path}/nested/file"), "x", false));
+}
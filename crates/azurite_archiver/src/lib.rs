@@ -78,11 +78,15 @@ impl Packed {
     }
 
 
+    /// Returns `None` if the bytes aren't a valid archive,
+    /// sizes are checked against the bytes which are left
+    /// before anything is allocated so a corrupt size
+    /// can't request an enormous allocation
     pub fn from_bytes(data: &[u8]) -> Option<Packed> {
-        let data = &data[MAGIC_TEXT.as_bytes().len()..];
+        let data = data.get(MAGIC_TEXT.as_bytes().len()..)?;
         let mut dec = ZlibDecoder::new(data);
         let mut data : Vec<u8> = Vec::new();
-        dec.read_to_end(&mut data).unwrap();
+        dec.read_to_end(&mut data).ok()?;
 
         let mut iterator = data.iter();
 
@@ -90,20 +94,23 @@ impl Packed {
 
         let mut lookup_table : Vec<_>;
         {
-            let lookup_table_size = take_u64(&mut iterator)?;
-            lookup_table = Vec::with_capacity(lookup_table_size as usize);
+            let lookup_table_size = usize::try_from(take_u64(&mut iterator)?).ok()?;
+            if lookup_table_size.checked_mul(8)? > iterator.as_slice().len() {
+                return None
+            }
+
+            lookup_table = Vec::with_capacity(lookup_table_size);
 
             for _ in 0..lookup_table_size {
-                lookup_table.push(take_u64(&mut iterator)?);
+                lookup_table.push(usize::try_from(take_u64(&mut iterator)?).ok()?);
             }
         }
 
         let mut data_table = Vec::with_capacity(lookup_table.len());
         for size in lookup_table {
-            let mut data = Vec::with_capacity(size as usize);
-            for _ in 0..size {
-                data.push(*iterator.next()?);
-            }
+            let remaining = iterator.as_slice();
+            let data = remaining.get(..size)?.to_vec();
+            iterator = remaining[size..].iter();
 
             data_table.push(Data(data))
         }
@@ -162,3 +169,6 @@ impl Data {
     }
 }
 
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use flate2::{write::ZlibEncoder, Compression};
+
+use crate::{Packed, Data, MAGIC_TEXT};
+
+fn forge(body: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body).unwrap();
+
+    let mut bytes = MAGIC_TEXT.as_bytes().to_vec();
+    bytes.append(&mut encoder.finish().unwrap());
+    bytes
+}
+
+#[test]
+fn round_trip() {
+    let packed = Packed::new()
+        .with(Data(vec![1, 2, 3]))
+        .with(Data(vec![]))
+        .with(Data(vec![4, 5]));
+
+    let bytes = packed.clone().as_bytes();
+    assert_eq!(Packed::from_bytes(&bytes), Some(packed));
+}
+
+#[test]
+fn oversized_entry_size() {
+    let mut body = vec![];
+    body.extend_from_slice(&0u64.to_le_bytes());        // version hash
+    body.extend_from_slice(&1u64.to_le_bytes());        // lookup table size
+    body.extend_from_slice(&u64::MAX.to_le_bytes());    // forged entry size
+    body.extend_from_slice(&[1, 2, 3, 4]);
+
+    assert_eq!(Packed::from_bytes(&forge(&body)), None);
+}
+
+#[test]
+fn oversized_lookup_table() {
+    let mut body = vec![];
+    body.extend_from_slice(&0u64.to_le_bytes());
+    body.extend_from_slice(&(u64::MAX / 4).to_le_bytes());
+    body.extend_from_slice(&8u64.to_le_bytes());
+
+    assert_eq!(Packed::from_bytes(&forge(&body)), None);
+}
+
+#[test]
+fn truncated_input() {
+    assert_eq!(Packed::from_bytes(b"GONNA"), None);
+    assert_eq!(Packed::from_bytes(MAGIC_TEXT.as_bytes()), None);
+}